@@ -1,3 +1,6 @@
+// These helpers take `&PathBuf`, which clippy flags
+#![allow(clippy::ptr_arg)]
+
 use std::env;
 use std::fs;
 use std::path::PathBuf;
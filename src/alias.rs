@@ -0,0 +1,93 @@
+//! Command aliases from `claw.yaml`, expanded before the command line is
+//! parsed.
+//!
+//! ```yaml
+//! aliases:
+//!   rv: "review --context src --"
+//! ```
+//!
+//! makes `claw rv --lang rust` run `claw review --context src -- --lang rust`.
+//! Only the first argument is expanded, and the expansion is not expanded
+//! again. Built-in subcommands can't be redefined.
+
+use anyhow::{Result, bail};
+use clap::CommandFactory;
+use std::collections::BTreeMap;
+
+use crate::cli::Cli;
+
+/// Returns whether `name` is a built-in subcommand, which aliases can't shadow.
+pub fn is_builtin(name: &str) -> bool {
+    Cli::command()
+        .get_subcommands()
+        .any(|command| command.get_name() == name || command.get_all_aliases().any(|a| a == name))
+}
+
+/// Splits an alias definition into arguments, with shell quoting.
+pub fn split(name: &str, definition: &str) -> Result<Vec<String>> {
+    match shlex::split(definition) {
+        Some(words) if !words.is_empty() => Ok(words),
+        Some(_) => bail!("Alias '{}' is empty", name),
+        None => bail!("Alias '{}' has unbalanced quotes: {}", name, definition),
+    }
+}
+
+/// Replaces the first argument after the program name with its alias
+/// definition, if it names one. `args` includes the program name.
+pub fn expand(args: Vec<String>, aliases: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    let Some(definition) = args.get(1).and_then(|first| aliases.get(first)) else {
+        return Ok(args);
+    };
+    let name = &args[1];
+    if is_builtin(name) {
+        return Ok(args);
+    }
+    let mut expanded = vec![args[0].clone()];
+    expanded.extend(split(name, definition)?);
+    expanded.extend(args.into_iter().skip(2));
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_expand() {
+        let aliases = BTreeMap::from([
+            ("rv".to_string(), "review --context src --".to_string()),
+            ("t".to_string(), "dry-run 'my goal'".to_string()),
+            ("list".to_string(), "stats".to_string()),
+        ]);
+        assert_eq!(
+            expand(words("claw rv --lang rust"), &aliases).unwrap(),
+            words("claw review --context src -- --lang rust")
+        );
+        assert_eq!(
+            expand(words("claw t"), &aliases).unwrap(),
+            vec!["claw", "dry-run", "my goal"]
+        );
+
+        // Built-ins win, and only the first argument is expanded
+        assert_eq!(
+            expand(words("claw list"), &aliases).unwrap(),
+            words("claw list")
+        );
+        assert_eq!(
+            expand(words("claw review rv"), &aliases).unwrap(),
+            words("claw review rv")
+        );
+    }
+
+    #[test]
+    fn test_split_rejects_bad_definitions() {
+        assert!(split("x", "").is_err());
+        assert!(split("x", "review 'unclosed").is_err());
+        assert!(is_builtin("dry-run"));
+        assert!(!is_builtin("review"));
+    }
+}
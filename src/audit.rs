@@ -0,0 +1,118 @@
+//! Append-only audit log of prompts sent to LLMs.
+//!
+//! A repository policy with `audit_log: <path>` makes claw append one JSON
+//! line per prompt it sends: who sent it, when, for which goal, to which
+//! receivers, and SHA-256 hashes of the prompt and of each context file in
+//! it. Unlike the run history, the log lives where the policy says (e.g. a
+//! shared directory), records only hashes, and is never rewritten. A prompt
+//! is sent only once its line is written, so the log can't miss one.
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::digest;
+use crate::history::{self, ContextFileRecord};
+use crate::version;
+
+/// One prompt sent to an LLM, as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The local user, from `$USER` (or `$USERNAME`).
+    pub user: String,
+    pub cwd: PathBuf,
+    pub goal: String,
+    /// The ID of the run in `claw history`, when it's recorded.
+    pub run_id: String,
+    /// Every receiver the prompt may go to: the configured one and its
+    /// fallbacks, or each `fan_out` profile.
+    pub receivers: Vec<String>,
+    /// SHA-256 of the prompt as sent, in hex.
+    pub prompt_sha256: String,
+    pub prompt_bytes: usize,
+    pub context_files: Vec<ContextFileRecord>,
+    pub claw_version: String,
+}
+
+impl AuditRecord {
+    /// Describes sending `prompt` for `goal` now, in the current directory.
+    pub fn new(
+        goal: &str,
+        run_id: &str,
+        receivers: Vec<String>,
+        prompt: &str,
+        context_files: Vec<ContextFileRecord>,
+    ) -> Self {
+        AuditRecord {
+            timestamp: history::now_secs(),
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+            cwd: std::env::current_dir().unwrap_or_default(),
+            goal: goal.to_string(),
+            run_id: run_id.to_string(),
+            receivers,
+            prompt_sha256: digest::sha256_hex(prompt.as_bytes()),
+            prompt_bytes: prompt.len(),
+            context_files,
+            claw_version: version::CURRENT.to_string(),
+        }
+    }
+}
+
+/// Appends `record` to the log at `path` as one line, creating the file
+/// and its directory if needed.
+pub fn append(path: &Path, record: &AuditRecord) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(record).context("Failed to serialize audit record")?;
+    line.push('\n');
+
+    // A single write in append mode, so concurrent runs don't interleave
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write audit log {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_adds_one_line_per_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("logs/audit.jsonl");
+        let record = AuditRecord::new(
+            "review",
+            "1700000000-review",
+            vec!["claude".to_string()],
+            "hello",
+            Vec::new(),
+        );
+        append(&path, &record).unwrap();
+        append(&path, &record).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed: AuditRecord = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(
+            parsed.prompt_sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+}
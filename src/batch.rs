@@ -0,0 +1,271 @@
+//! Batch runs: many goals from one plan.
+//!
+//! `claw batch plan.yaml` runs a list of goal invocations, a few at a time,
+//! and ends with a table of what succeeded and what failed:
+//!
+//! ```yaml
+//! jobs: 2
+//! runs:
+//!   - goal: review
+//!     args: { scope: api }
+//!     context: [src/api]
+//!     output: reports/review-api.md
+//!   - goal: dependency-audit
+//!     output: reports/deps.md
+//! ```
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::matrix;
+
+/// The contents of a batch plan file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPlan {
+    /// Number of runs at the same time (default: 1).
+    #[serde(default)]
+    jobs: Option<usize>,
+    runs: Vec<RawRun>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRun {
+    goal: String,
+    #[serde(default)]
+    args: BTreeMap<String, Value>,
+    #[serde(default)]
+    context: Vec<PathBuf>,
+    #[serde(default)]
+    output: Option<PathBuf>,
+}
+
+/// A validated batch plan.
+#[derive(Debug)]
+pub struct BatchPlan {
+    pub jobs: Option<usize>,
+    pub runs: Vec<BatchRun>,
+}
+
+/// One goal invocation in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRun {
+    pub goal: String,
+    pub params: BTreeMap<String, String>,
+    pub context: Vec<PathBuf>,
+    /// Where the response is written (default: `<goal>.md`).
+    pub output: PathBuf,
+}
+
+impl BatchRun {
+    /// Returns the run's parameters as template arguments, e.g. `--scope=api`.
+    pub fn template_args(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|(key, value)| format!("--{}={}", key, value))
+            .collect()
+    }
+
+    /// The parameters as `scope=api, format=md`, or `-` if there are none.
+    pub fn args_label(&self) -> String {
+        if self.params.is_empty() {
+            return "-".to_string();
+        }
+        self.params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Loads and validates a batch plan. Relative context and output paths are
+/// kept as written, so they resolve from the current directory.
+pub fn load_plan(path: &Path) -> Result<BatchPlan> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read batch plan {}", path.display()))?;
+    let raw: RawPlan = serde_yaml::from_str(&content).with_context(|| {
+        format!(
+            "Batch plan {} must be a mapping with a `runs` list of {{goal, args, context, output}}",
+            path.display()
+        )
+    })?;
+    if raw.runs.is_empty() {
+        anyhow::bail!("Batch plan {} contains no runs", path.display());
+    }
+
+    let mut outputs = HashSet::new();
+    let runs = raw
+        .runs
+        .into_iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let params = run
+                .args
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = matrix::scalar_to_string(&value).with_context(|| {
+                        format!(
+                            "Run {} of {}: parameter '{}' must be a string, number or boolean",
+                            i + 1,
+                            path.display(),
+                            key
+                        )
+                    })?;
+                    Ok((key, value))
+                })
+                .collect::<Result<_>>()?;
+            let output = run
+                .output
+                .unwrap_or_else(|| PathBuf::from(format!("{}.md", run.goal)));
+            if !outputs.insert(output.clone()) {
+                anyhow::bail!(
+                    "Run {} of {} writes to {}, like an earlier run. Give each run its own `output`.",
+                    i + 1,
+                    path.display(),
+                    output.display()
+                );
+            }
+            Ok(BatchRun {
+                goal: run.goal,
+                params,
+                context: run.context,
+                output,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(BatchPlan {
+        jobs: raw.jobs,
+        runs,
+    })
+}
+
+/// How one run of a batch ended, for the summary table.
+pub enum RunResult {
+    Succeeded,
+    Failed(String),
+    NotRun,
+}
+
+/// Formats the summary table printed after a batch.
+pub fn format_summary(runs: &[BatchRun], results: &[(RunResult, Duration)]) -> String {
+    let rows: Vec<[String; 5]> = runs
+        .iter()
+        .zip(results)
+        .map(|(run, (result, duration))| {
+            let (status, detail) = match result {
+                RunResult::Succeeded => ("✓", run.output.display().to_string()),
+                RunResult::Failed(error) => {
+                    ("✗", error.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+                RunResult::NotRun => ("-", "not run".to_string()),
+            };
+            [
+                status.to_string(),
+                run.goal.clone(),
+                run.args_label(),
+                format!("{:.1}s", duration.as_secs_f64()),
+                detail,
+            ]
+        })
+        .collect();
+
+    let header = ["", "GOAL", "ARGS", "TIME", "OUTPUT / ERROR"];
+    let widths: Vec<usize> = (0..4)
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .chain([header[col].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: [&str; 5]| {
+        format!(
+            "  {:<w0$}  {:<w1$}  {:<w2$}  {:>w3$}  {}\n",
+            cells[0],
+            cells[1],
+            cells[2],
+            cells[3],
+            cells[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        )
+    };
+
+    let mut table = line(header);
+    for row in &rows {
+        table.push_str(&line([&row[0], &row[1], &row[2], &row[3], &row[4]]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_plan_defaults_and_duplicate_outputs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("plan.yaml");
+        fs::write(
+            &path,
+            "jobs: 2\nruns:\n  - goal: review\n    args: { scope: api, depth: 2 }\n    context: [src]\n    output: out/review.md\n  - goal: audit\n",
+        )
+        .unwrap();
+        let plan = load_plan(&path).unwrap();
+        assert_eq!(plan.jobs, Some(2));
+        assert_eq!(
+            plan.runs[0].template_args(),
+            vec!["--depth=2", "--scope=api"]
+        );
+        assert_eq!(plan.runs[0].context, vec![PathBuf::from("src")]);
+        assert_eq!(plan.runs[1].output, PathBuf::from("audit.md"));
+        assert_eq!(plan.runs[1].args_label(), "-");
+
+        fs::write(&path, "runs:\n  - goal: audit\n  - goal: audit\n").unwrap();
+        assert!(
+            load_plan(&path)
+                .unwrap_err()
+                .to_string()
+                .contains("like an earlier run")
+        );
+        fs::write(&path, "runs:\n  - goal: audit\n    ouptut: a.md\n").unwrap();
+        assert!(load_plan(&path).is_err());
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let run = |goal: &str| BatchRun {
+            goal: goal.to_string(),
+            params: BTreeMap::new(),
+            context: Vec::new(),
+            output: PathBuf::from(format!("{}.md", goal)),
+        };
+        let table = format_summary(
+            &[run("review"), run("audit")],
+            &[
+                (RunResult::Succeeded, Duration::from_millis(12_340)),
+                (
+                    RunResult::Failed("The LLM exited\n  with status 1".to_string()),
+                    Duration::from_millis(400),
+                ),
+            ],
+        );
+        assert_eq!(
+            table,
+            "     GOAL    ARGS   TIME  OUTPUT / ERROR\n\
+             \x20 ✓  review  -     12.3s  review.md\n\
+             \x20 ✗  audit   -      0.4s  The LLM exited with status 1\n"
+        );
+    }
+}
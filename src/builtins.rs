@@ -0,0 +1,266 @@
+//! Built-in context sections.
+//!
+//! `context_builtin:` in `prompt.yaml` names common environment details that
+//! claw gathers itself instead of through `sh -c` context scripts. They work
+//! the same on every platform and are available in the prompt as
+//! `{{ Context.<name> }}`, just like script outputs. The git sections are
+//! read from the repository with gix, so they don't need `git` installed. Only `failing_tests`,
+//! which runs the project's tests, and `toolchains` with a `toolchain_report`
+//! set, which runs the tools it lists, need the trust context scripts need.
+
+use anyhow::{Context as AnyhowContext, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{ClawConfig, ContextBuiltin};
+use crate::failing_tests;
+use crate::git_context;
+
+impl ContextBuiltin {
+    /// The name the section is available under in `Context`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContextBuiltin::GitStatus => "git_status",
+            ContextBuiltin::GitDiffStaged => "git_diff_staged",
+            ContextBuiltin::OsInfo => "os_info",
+            ContextBuiltin::RustToolchain => "rust_toolchain",
+            ContextBuiltin::Toolchains => "toolchains",
+            ContextBuiltin::FailingTests => "failing_tests",
+        }
+    }
+}
+
+/// Tools in the toolchain report when `toolchain_report` isn't set.
+pub const DEFAULT_TOOLCHAIN_REPORT: &[&str] = &[
+    "rustc", "cargo", "go", "node", "npm", "python3", "java", "gcc", "clang", "make", "cmake",
+    "docker", "git",
+];
+
+/// How long a tool in the toolchain report gets to print its version.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Gathers each built-in section, keyed by its name, with the settings
+/// from `claw.yaml` (`toolchain_report`, `test_command`).
+pub fn collect(
+    builtins: &[ContextBuiltin],
+    claw_config: &ClawConfig,
+) -> Result<HashMap<String, String>> {
+    let default_tools: Vec<String>;
+    let tools = match claw_config.toolchain_report.as_deref() {
+        Some(tools) => tools,
+        None => {
+            default_tools = DEFAULT_TOOLCHAIN_REPORT.iter().map(|t| t.to_string()).collect();
+            &default_tools
+        }
+    };
+    let cwd = std::env::current_dir()?;
+    builtins
+        .iter()
+        .map(|builtin| {
+            let output = gather(*builtin, &cwd, tools, claw_config).with_context(|| {
+                format!("Failed to gather built-in context '{}'", builtin.name())
+            })?;
+            Ok((builtin.name().to_string(), output))
+        })
+        .collect()
+}
+
+fn gather(
+    builtin: ContextBuiltin,
+    dir: &Path,
+    tools: &[String],
+    claw_config: &ClawConfig,
+) -> Result<String> {
+    match builtin {
+        ContextBuiltin::GitStatus => git_context::short_status(dir),
+        ContextBuiltin::GitDiffStaged => git_context::staged_diff(dir),
+        ContextBuiltin::OsInfo => Ok(os_info()),
+        ContextBuiltin::RustToolchain => rust_toolchain(dir),
+        ContextBuiltin::Toolchains => Ok(toolchain_report(dir, tools)),
+        ContextBuiltin::FailingTests => failing_tests::report(
+            dir,
+            claw_config.test_command.as_deref(),
+            claw_config.max_file_size_kb.unwrap_or(1024),
+        ),
+    }
+}
+
+/// Runs a tool directly (no shell) and returns its trimmed stdout.
+fn run_tool(dir: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let executable =
+        which::which(program).with_context(|| format!("'{}' not found in your PATH", program))?;
+    let output = Command::new(executable)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{} {}` failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Describes the operating system from compile-time constants and, where
+/// available, `/etc/os-release`.
+fn os_info() -> String {
+    let mut lines = vec![
+        format!("os: {}", std::env::consts::OS),
+        format!("family: {}", std::env::consts::FAMILY),
+        format!("arch: {}", std::env::consts::ARCH),
+    ];
+    let release = fs::read_to_string("/etc/os-release").unwrap_or_default();
+    if let Some(name) = release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+    {
+        lines.push(format!("distribution: {}", name.trim_matches('"')));
+    }
+    lines.join("\n")
+}
+
+/// Reports the active `rustc` and `cargo` versions and the project's
+/// toolchain file, if any.
+fn rust_toolchain(dir: &Path) -> Result<String> {
+    let mut lines = vec![
+        run_tool(dir, "rustc", &["--version"])?,
+        run_tool(dir, "cargo", &["--version"])?,
+    ];
+    for file in ["rust-toolchain.toml", "rust-toolchain"] {
+        if let Ok(content) = fs::read_to_string(dir.join(file)) {
+            lines.push(format!("{}:\n{}", file, content.trim()));
+            break;
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Reports one line per tool with its version, or why there is none. The
+/// tools are asked in parallel, so the report takes as long as the slowest.
+fn toolchain_report(dir: &Path, tools: &[String]) -> String {
+    let lines: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = tools
+            .iter()
+            .map(|entry| scope.spawn(move || tool_version(dir, entry)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(tools)
+            .map(|(handle, entry)| {
+                let name = entry.split_whitespace().next().unwrap_or_default();
+                let version = handle
+                    .join()
+                    .unwrap_or_else(|_| "version check failed".to_string());
+                format!("{}: {}", name, version)
+            })
+            .collect()
+    });
+    lines.join("\n")
+}
+
+/// Runs `<tool> --version` (or the entry's own arguments) and returns the
+/// first line it prints.
+fn tool_version(dir: &Path, entry: &str) -> String {
+    let Some(words) = shlex::split(entry) else {
+        return "invalid entry".to_string();
+    };
+    let Some((program, args)) = words.split_first() else {
+        return "empty entry".to_string();
+    };
+    let args: Vec<&str> = if args.is_empty() {
+        // A few tools don't take --version
+        match program.as_str() {
+            "go" => vec!["version"],
+            "java" => vec!["-version"],
+            _ => vec!["--version"],
+        }
+    } else {
+        args.iter().map(String::as_str).collect()
+    };
+    let Ok(executable) = which::which(program) else {
+        return "not installed".to_string();
+    };
+
+    let child = Command::new(executable)
+        .args(&args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let Ok(mut child) = child else {
+        return "failed to run".to_string();
+    };
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return "timed out".to_string();
+            }
+        }
+    }
+    let Ok(output) = child.wait_with_output() else {
+        return "failed to run".to_string();
+    };
+
+    // Some tools (java, older pythons) print their version on stderr
+    [&output.stdout, &output.stderr]
+        .iter()
+        .find_map(|stream| {
+            String::from_utf8_lossy(stream)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "no version output".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_info_reports_platform() {
+        let info = os_info();
+        assert!(info.contains(&format!("os: {}", std::env::consts::OS)));
+        assert!(info.contains(&format!("arch: {}", std::env::consts::ARCH)));
+    }
+
+    #[test]
+    fn test_rust_toolchain_includes_toolchain_file() {
+        // The repository pins its toolchain
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let output = rust_toolchain(dir).unwrap();
+        assert!(output.starts_with("rustc "));
+        assert!(output.contains("rust-toolchain.toml:"));
+    }
+
+    #[test]
+    fn test_toolchain_report_lists_each_tool() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let tools = [
+            "cargo".to_string(),
+            "claw-no-such-tool".to_string(),
+            "sh -c 'true'".to_string(),
+        ];
+        let report = toolchain_report(dir, &tools);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("cargo: cargo "));
+        assert_eq!(lines[1], "claw-no-such-tool: not installed");
+        assert_eq!(lines[2], "sh: no version output");
+    }
+}
@@ -0,0 +1,214 @@
+//! Tracks the example goals bundled with claw and copied into the global
+//! config directory, so `claw upgrade-goals` can refresh them.
+//!
+//! When claw copies its bundled goals, it records the SHA-256 of each file
+//! in `.bundled-goals.json` next to `claw.yaml`. A later release's goals can
+//! then replace installed files still matching their record, while files
+//! the user edited (or that were installed before the record existed) are
+//! left alone, and goals the user deleted stay deleted.
+
+use anyhow::{Context as AnyhowContext, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::digest;
+
+/// The record of installed bundled files, in the global config directory.
+const MANIFEST: &str = ".bundled-goals.json";
+
+/// How an installed copy of a bundled goal file compares to this release's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The same as the bundled file.
+    Current,
+    /// Not installed: added in this release.
+    New,
+    /// Unchanged since it was installed, and changed in this release.
+    Outdated,
+    /// Edited since it was installed, or installed before claw kept records.
+    Customized,
+    /// Installed once and since deleted.
+    Removed,
+}
+
+/// One file of a bundled goal, e.g. `goals/pr-notes/prompt.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundledFile {
+    /// The path relative to the assets and config directories.
+    pub relative: String,
+    pub bundled: PathBuf,
+    pub installed: PathBuf,
+    pub status: Status,
+}
+
+/// Compares every bundled goal file in `assets_dir` with its copy in
+/// `config_dir`, sorted by path.
+pub fn compare(assets_dir: &Path, config_dir: &Path) -> Result<Vec<BundledFile>> {
+    let manifest = load_manifest(config_dir)?;
+    let mut files = Vec::new();
+    for relative in goal_files(assets_dir)? {
+        let bundled = assets_dir.join(&relative);
+        let installed = config_dir.join(&relative);
+        let recorded = manifest.get(&relative);
+        let status = if !installed.exists() {
+            if recorded.is_some() {
+                Status::Removed
+            } else {
+                Status::New
+            }
+        } else {
+            let installed_hash = hash_file(&installed)?;
+            if installed_hash == hash_file(&bundled)? {
+                Status::Current
+            } else if recorded == Some(&installed_hash) {
+                Status::Outdated
+            } else {
+                Status::Customized
+            }
+        };
+        files.push(BundledFile {
+            relative,
+            bundled,
+            installed,
+            status,
+        });
+    }
+    Ok(files)
+}
+
+/// Copies a bundled file over its installed copy and records it.
+pub fn install(config_dir: &Path, file: &BundledFile) -> Result<()> {
+    if let Some(parent) = file.installed.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::copy(&file.bundled, &file.installed)
+        .with_context(|| format!("Failed to write {}", file.installed.display()))?;
+    let mut manifest = load_manifest(config_dir)?;
+    manifest.insert(file.relative.clone(), hash_file(&file.bundled)?);
+    save_manifest(config_dir, &manifest)
+}
+
+/// Records the bundled goal files whose installed copies match them, e.g.
+/// right after the first-time setup copied them.
+pub fn record_current(assets_dir: &Path, config_dir: &Path) -> Result<()> {
+    let mut manifest = load_manifest(config_dir)?;
+    for file in compare(assets_dir, config_dir)? {
+        if file.status == Status::Current {
+            manifest.insert(file.relative, hash_file(&file.bundled)?);
+        }
+    }
+    save_manifest(config_dir, &manifest)
+}
+
+/// The files under `goals/` in the assets directory, relative to it and
+/// with `/` separators.
+fn goal_files(assets_dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(assets_dir.join("goals")).sort_by_file_name() {
+        let entry = entry.context("Failed to read the bundled goals")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(assets_dir)?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        files.push(parts.join("/"));
+    }
+    Ok(files)
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(digest::sha256_hex(&bytes))
+}
+
+fn load_manifest(config_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let path = config_dir.join(MANIFEST);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save_manifest(config_dir: &Path, manifest: &BTreeMap<String, String>) -> Result<()> {
+    let path = config_dir.join(MANIFEST);
+    let content = serde_json::to_string_pretty(manifest)?;
+    fs::write(&path, content + "\n").with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_compare_tells_outdated_from_customized() {
+        let assets = TempDir::new().unwrap();
+        let config = TempDir::new().unwrap();
+        for goal in ["kept", "edited", "old", "deleted", "same"] {
+            write(&assets.path().join(format!("goals/{}/prompt.yaml", goal)), "v1");
+        }
+        fs_extra::dir::copy(
+            assets.path().join("goals"),
+            config.path(),
+            &fs_extra::dir::CopyOptions::new(),
+        )
+        .unwrap();
+        record_current(assets.path(), config.path()).unwrap();
+
+        // A new release changes some goals and adds one
+        for goal in ["edited", "old", "deleted"] {
+            write(&assets.path().join(format!("goals/{}/prompt.yaml", goal)), "v2");
+        }
+        write(&assets.path().join("goals/added/prompt.yaml"), "v2");
+        // The user edits one and deletes another
+        write(&config.path().join("goals/edited/prompt.yaml"), "mine");
+        fs::remove_dir_all(config.path().join("goals/deleted")).unwrap();
+        // and has a file from before claw kept records
+        write(&config.path().join("goals/same/prompt.yaml"), "v0");
+
+        let statuses: Vec<(String, Status)> = compare(assets.path(), config.path())
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.relative, file.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("goals/added/prompt.yaml".to_string(), Status::New),
+                ("goals/deleted/prompt.yaml".to_string(), Status::Removed),
+                ("goals/edited/prompt.yaml".to_string(), Status::Customized),
+                ("goals/kept/prompt.yaml".to_string(), Status::Current),
+                ("goals/old/prompt.yaml".to_string(), Status::Outdated),
+                ("goals/same/prompt.yaml".to_string(), Status::Customized),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_records_the_new_version() {
+        let assets = TempDir::new().unwrap();
+        let config = TempDir::new().unwrap();
+        write(&assets.path().join("goals/notes/prompt.yaml"), "v2");
+
+        let file = compare(assets.path(), config.path()).unwrap().remove(0);
+        assert_eq!(file.status, Status::New);
+        install(config.path(), &file).unwrap();
+
+        let file = compare(assets.path(), config.path()).unwrap().remove(0);
+        assert_eq!(file.status, Status::Current);
+        assert!(load_manifest(config.path()).unwrap().contains_key("goals/notes/prompt.yaml"));
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{ArgGroup, Args, Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::ReceiverType;
 
 /// A goal-driven, context-aware wrapper for Large Language Model (LLM) CLIs.
 #[derive(Parser, Debug)]
@@ -8,6 +10,39 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Subcommands>,
 
+    /// Use the named profile from claw.yaml (default: $CLAW_PROFILE).
+    #[arg(long = "profile", value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Send prompts to this LLM command for this run instead of claw.yaml's
+    /// `llm_command`. Implies `--receiver generic` unless one is given.
+    #[arg(long = "llm", value_name = "COMMAND", global = true)]
+    pub llm: Option<String>,
+
+    /// Use this receiver for this run instead of claw.yaml's `receiver_type`.
+    #[arg(long = "receiver", value_name = "TYPE", global = true)]
+    pub receiver: Option<ReceiverArg>,
+
+    /// Pass the prompt with this argument template for this run instead of
+    /// claw.yaml's `prompt_arg_template`, e.g. `"-p {{prompt}}"`.
+    #[arg(
+        long = "prompt-template",
+        value_name = "TEMPLATE",
+        global = true,
+        allow_hyphen_values = true
+    )]
+    pub prompt_template: Option<String>,
+
+    /// Keep stdout machine-clean: it carries only the prompt (dry-run) or the
+    /// response, and claw never stops to ask questions or page output.
+    #[arg(long = "porcelain", global = true)]
+    pub porcelain: bool,
+
+    /// Don't create the global config directory or print the welcome banner
+    /// on first run (default: $CLAW_NO_FIRST_RUN_SETUP).
+    #[arg(long = "no-setup", global = true)]
+    pub no_setup: bool,
+
     #[command(flatten)]
     pub run_args: RunArgs,
 }
@@ -15,7 +50,9 @@ pub struct Cli {
 /// Common arguments shared between run and dry-run commands.
 #[derive(Args, Debug)]
 pub struct CommonGoalArgs {
-    /// Files or directories to include as context.
+    /// Files or directories to include as context. A directory can take its
+    /// own options after a colon: `src:depth=2`, `docs:depth=0,ext=md`,
+    /// `tests:exclude=fixtures`.
     #[arg(short = 'c', long = "context", num_args = 0..)]
     pub context: Vec<std::path::PathBuf>,
 
@@ -23,6 +60,63 @@ pub struct CommonGoalArgs {
     #[arg(short = 'd', long = "recurse_depth")]
     pub recurse_depth: Option<usize>,
 
+    /// List the files deeper than --recurse_depth in the directory tree
+    /// without including their contents.
+    #[arg(long = "list-beyond-depth", requires = "recurse_depth")]
+    pub list_beyond_depth: bool,
+
+    /// Include files ignored by `.gitignore` (and hidden files) when scanning
+    /// context directories, even if the goal respects them.
+    #[arg(long = "no-gitignore")]
+    pub no_gitignore: bool,
+
+    /// Leave the Notes section describing claw's context limits out of the
+    /// prompt; only the directory tree and files are included.
+    #[arg(long = "quiet-context")]
+    pub quiet_context: bool,
+
+    /// Run every context script, ignoring outputs cached by
+    /// `script_cache_ttl_secs` or a script's `cache_ttl_secs`.
+    #[arg(long = "no-script-cache")]
+    pub no_script_cache: bool,
+
+    /// Don't run context scripts; each one's output is replaced by a
+    /// `<<script: NAME>>` placeholder, to inspect the prompt's structure.
+    #[arg(long = "no-scripts")]
+    pub no_scripts: bool,
+
+    /// Number the context files and ask the model to cite them as [n]; the
+    /// run records which files the response cited.
+    #[arg(long = "cite-context")]
+    pub cite_context: bool,
+
+    /// Add a condensed map of the working directory (files and the key
+    /// symbols regular expressions find in them) to the prompt, from
+    /// `claw index` if it was run here.
+    #[arg(long = "repo-map")]
+    pub repo_map: bool,
+
+    /// Add the TODO, FIXME and HACK comments found under this path, with
+    /// their locations and surrounding lines. Can be given more than once.
+    #[arg(long = "context-todos", value_name = "PATH")]
+    pub context_todos: Vec<std::path::PathBuf>,
+
+    /// Add the files changed in git as context: `branch:<ref>` (since the
+    /// branch forked from <ref>), `<from>..<to>`, `staged` or `working`. In a terminal, a
+    /// checklist with each file's diffstat lets you leave some out.
+    #[arg(long = "context-git", value_name = "SCOPE")]
+    pub context_git: Option<String>,
+
+    /// Add the changes themselves as unified diffs, one fenced block per
+    /// file: `branch:<ref>`, `<from>..<to>`, `staged` or `working`.
+    #[arg(long = "context-diff", value_name = "SCOPE")]
+    pub context_diff: Option<String>,
+
+    /// Add the last N commands of your shell history, with anything that
+    /// looks like a secret redacted, e.g. for "why did this fail" goals.
+    #[arg(long = "context-shell-history", value_name = "N")]
+    pub context_shell_history: Option<usize>,
+
     /// Arbitrary arguments for the prompt template, e.g., --lang=Python or --lang Python.
     /// All arguments after the goal name are collected here.
     #[arg(last = true)]
@@ -41,6 +135,70 @@ pub struct RunArgs {
     #[arg(short = 'e', long = "explain")]
     pub explain: bool,
 
+    /// Run example N from the goal's `examples` (numbered as in --explain).
+    /// Parameters and context given on the command line are added to it.
+    #[arg(long = "example", value_name = "N", conflicts_with = "matrix")]
+    pub example: Option<usize>,
+
+    /// Copy everything the LLM writes to stdout into this file as it streams.
+    /// Note: the LLM's stdout becomes a pipe, so some CLIs may disable
+    /// interactive features.
+    #[arg(long = "tee", visible_alias = "save-response", value_name = "FILE")]
+    pub tee: Option<std::path::PathBuf>,
+
+    /// Fail if the captured response is empty or only whitespace, so CI jobs
+    /// can gate on meaningful output.
+    #[arg(long = "fail-on-empty-response")]
+    pub fail_on_empty_response: bool,
+
+    /// After the run, write a JSON summary of it (exit code, duration, prompt
+    /// size, receiver, script timings) to FILE, or to stderr without one.
+    #[arg(
+        long = "json-status",
+        value_name = "FILE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "-",
+        conflicts_with = "matrix"
+    )]
+    pub json_status: Option<std::path::PathBuf>,
+
+    /// Show the run in a split-pane view: claw's phases on the left, the LLM
+    /// output streaming on the right, with keys to cancel, save or copy.
+    #[arg(long = "live", conflicts_with = "matrix")]
+    pub live: bool,
+
+    /// Stream the response as usual, then open it in $PAGER (default:
+    /// less -R) if it doesn't fit on the screen.
+    /// Note: the LLM's stdout becomes a pipe, as with --tee.
+    #[arg(long = "page", conflicts_with_all = ["matrix", "live"])]
+    pub page: bool,
+
+    /// Send the prompt to each of these profiles at once and show their
+    /// responses side by side, e.g. `--fan-out claude,ollama`. With --tee,
+    /// each response is also saved as `<tee stem>.<profile>.md`.
+    #[arg(
+        long = "fan-out",
+        value_name = "PROFILES",
+        value_delimiter = ',',
+        conflicts_with_all = ["matrix", "live"]
+    )]
+    pub fan_out: Option<Vec<String>>,
+
+    /// Run the goal once per parameter map in this YAML list, writing each
+    /// response to `<goal>-<values>.md`.
+    #[arg(long = "matrix", value_name = "FILE", conflicts_with = "tee")]
+    pub matrix: Option<std::path::PathBuf>,
+
+    /// Directory for matrix output files (default: current directory).
+    #[arg(long = "matrix-dir", value_name = "DIR", requires = "matrix")]
+    pub matrix_dir: Option<std::path::PathBuf>,
+
+    /// Number of matrix entries to run at the same time. With more than one,
+    /// LLM output goes only to the output files.
+    #[arg(short = 'j', long = "jobs", value_name = "N", default_value_t = 1, requires = "matrix")]
+    pub jobs: usize,
+
     #[command(flatten)]
     pub common: CommonGoalArgs,
 }
@@ -60,6 +218,11 @@ pub enum Subcommands {
         /// Force creation of the goal in the global ~/.config/claw directory.
         #[arg(long)]
         global: bool,
+
+        /// Continue an unfinished session for this goal, saved in
+        /// ~/.config/claw/drafts/ when the previous one ended early.
+        #[arg(long, conflicts_with = "location")]
+        resume: bool,
     },
     /// List all available goals with their descriptions and parameters.
     #[command(group(ArgGroup::new("filter").args(["local", "global"])))]
@@ -71,20 +234,304 @@ pub enum Subcommands {
         /// Show only global goals from ~/.config/claw directory.
         #[arg(long)]
         global: bool,
+
+        /// Show the goals whose name, description, prompt or parameter names
+        /// contain TERM (ignoring case), with the matching lines.
+        #[arg(long, value_name = "TERM")]
+        search: Option<String>,
+
+        /// Show only the goals of this installed collection.
+        #[arg(long, value_name = "NAME")]
+        collection: Option<String>,
     },
     /// Execute the underlying LLM CLI directly without any modifications.
     Pass,
+    /// Send a one-line prompt through the receiver to check that the LLM
+    /// is reachable and answers, and how long it takes.
+    Ping,
+    /// Check goal prompts for common style problems.
+    PromptLint {
+        /// Name of the goal to lint. Lints all goals if omitted.
+        goal_name: Option<String>,
+    },
     /// Render a goal's prompt without executing the LLM.
     DryRun {
         /// Name of the goal to render.
-        #[arg(required = true)]
-        goal_name: String,
+        #[arg(required_unless_present = "check")]
+        goal_name: Option<String>,
 
         /// Optional file path to write the rendered prompt.
         #[arg(short = 'o', long = "output")]
         output: Option<std::path::PathBuf>,
 
+        /// Don't print the prompt; exit non-zero if rendering would fail
+        /// (missing parameters, script syntax errors, template errors). Checks
+        /// every goal if none is given. Context scripts are not run.
+        #[arg(long, conflicts_with_all = ["output", "pretty", "provenance"])]
+        check: bool,
+
+        /// Syntax-highlight the prompt and page it through $PAGER (TTY only).
+        #[arg(long, conflicts_with = "output")]
+        pretty: bool,
+
+        /// Also write a JSON map of where each byte range of the prompt came
+        /// from, next to the output file (`<output>.provenance.json`).
+        #[arg(long, requires = "output")]
+        provenance: bool,
+
         #[command(flatten)]
         common: CommonGoalArgs,
     },
+    /// Start a conversation from a goal's prompt: after each response, type a
+    /// follow-up to continue it, or /exit to end.
+    Session {
+        /// Name of the goal to start from.
+        goal_name: String,
+
+        /// Save the conversation as markdown to this file when it ends.
+        #[arg(long, value_name = "FILE")]
+        save: Option<std::path::PathBuf>,
+
+        #[command(flatten)]
+        common: CommonGoalArgs,
+    },
+    /// Trust this repository's local .claw directory to run shell commands.
+    Trust {
+        /// Stop trusting it instead.
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Print the JSON Schema for claw.yaml or prompt.yaml, for editor completion.
+    Schema {
+        /// Which file to describe.
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+    /// Install, remove or show git hooks that run goals.
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+    /// Install, enable, disable or list goal collections: directories of
+    /// related goals with a `collection.yaml` manifest.
+    Collection {
+        #[command(subcommand)]
+        action: CollectionAction,
+    },
+    /// Run the goal invocations listed in a YAML plan, a few at a time, and
+    /// print a table of the results.
+    Batch {
+        /// The plan: a `runs` list of {goal, args, context, output}.
+        #[arg(value_name = "PLAN")]
+        plan: std::path::PathBuf,
+
+        /// Number of runs at the same time (default: the plan's `jobs`, or 1).
+        /// With more than one, LLM output goes only to the output files.
+        #[arg(short = 'j', long = "jobs", value_name = "N")]
+        jobs: Option<usize>,
+    },
+    /// Compare each goal's rendered prompts with the snapshots in its
+    /// `__snapshots__/` directory, using the cases in its `tests.yaml`.
+    Test {
+        /// Name of the goal to test. Tests every goal with a tests.yaml or
+        /// snapshots if omitted.
+        goal_name: Option<String>,
+
+        /// Write the rendered prompts as the new snapshots instead of failing
+        /// when they differ.
+        #[arg(long = "update-snapshots")]
+        update_snapshots: bool,
+    },
+    /// Index the current directory's files and their symbols, found with
+    /// regular expressions, for `--repo-map` (experimental).
+    Index,
+    /// Pack a recorded run (prompt, goal definition, context manifest and
+    /// response) into a zip archive to share or audit.
+    Bundle {
+        /// The run's ID, its history file name without `.json`
+        /// (e.g. `1718000000-review`), or `last` for the most recent run.
+        #[arg(value_name = "RUN_ID")]
+        run_id: String,
+
+        /// Where to write the archive (default: `<RUN_ID>.zip`).
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// List the command aliases defined in claw.yaml.
+    Alias,
+    /// Refresh the example goals bundled with claw in ~/.config/claw after
+    /// an upgrade. Goals you changed are kept.
+    UpgradeGoals {
+        /// Apply every update without asking.
+        #[arg(short = 'y', long = "yes")]
+        yes: bool,
+    },
+    /// Show how a local goal differs from the global goal it shadows.
+    DiffGoal {
+        /// Name of the goal to compare.
+        goal_name: String,
+    },
+    /// Write a markdown page for each goal (description, parameters,
+    /// examples, scripts and the start of the prompt) and an index of them.
+    Docs {
+        /// Goals to document (default: every goal).
+        goal_names: Vec<String>,
+
+        /// The directory to write the pages to.
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "DIR",
+            default_value = "docs/goals"
+        )]
+        output: std::path::PathBuf,
+    },
+    /// Diagnose the last shell command that failed with the triage goal.
+    #[command(group(
+        ArgGroup::new("mode")
+            .required(true)
+            .args(["last", "install_hook", "uninstall_hook"])
+    ))]
+    Fix {
+        /// Triage the last command recorded by the shell hook.
+        #[arg(long)]
+        last: bool,
+
+        /// Add the hook that records each command to the shell's startup file.
+        #[arg(long, value_enum, value_name = "SHELL")]
+        install_hook: Option<Shell>,
+
+        /// Remove the hook from the shell's startup file.
+        #[arg(long, value_enum, value_name = "SHELL")]
+        uninstall_hook: Option<Shell>,
+
+        /// The goal to run instead of `triage_goal` from claw.yaml.
+        #[arg(long)]
+        goal: Option<String>,
+
+        /// Run the failed command again to capture its output without asking.
+        #[arg(short = 'y', long = "yes", conflicts_with = "no_rerun")]
+        yes: bool,
+
+        /// Don't run the failed command again; triage its line and status only.
+        #[arg(long = "no-rerun")]
+        no_rerun: bool,
+    },
+    /// Run the context scripts marked `prefetch: true` in the background,
+    /// each on its own interval, so renders here use their latest output
+    /// instead of waiting for them.
+    Daemon {
+        /// Run the scripts that are due once, then exit (e.g. from cron).
+        #[arg(long = "once")]
+        once: bool,
+    },
+    /// Show how often goals were run, or how a goal's parameters are used.
+    Stats {
+        /// Show which of this goal's parameters were supplied, defaulted or never used.
+        #[arg(long)]
+        goal: Option<String>,
+    },
+    /// Print a shell completion script, e.g. `claw completions bash > ~/.local/share/bash-completion/completions/claw`.
+    Completions {
+        /// The shell to generate the script for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Completion protocol used by the scripts from `claw completions`.
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Words after `claw`; the last one is the word being completed.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+}
+
+/// Actions of `claw hooks`.
+#[derive(Subcommand, Debug)]
+pub enum HooksAction {
+    /// Write hook scripts that run goals, e.g.
+    /// `claw hooks install commit-msg=tidy-message pre-push=review`.
+    Install {
+        /// Hook and goal pairs. commit-msg goals get --message_file, pre-push
+        /// goals get --remote and --push_range.
+        #[arg(required = true, value_name = "HOOK=GOAL")]
+        hooks: Vec<String>,
+
+        /// Replace existing hooks that weren't installed by claw.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove hooks installed by claw: the named ones, or all of them.
+    Uninstall {
+        #[arg(value_name = "HOOK")]
+        hooks: Vec<String>,
+    },
+    /// Show the repository's git hooks and the goals they run.
+    Status,
+}
+
+/// Actions of `claw collection`.
+#[derive(Subcommand, Debug)]
+pub enum CollectionAction {
+    /// Install the collection in DIR, replacing an installed version of it.
+    #[command(group(ArgGroup::new("location").args(["local", "global"])))]
+    Install {
+        /// The collection's directory, with `collection.yaml` and `goals/`.
+        #[arg(value_name = "DIR")]
+        path: std::path::PathBuf,
+
+        /// Install into the local .claw/ directory.
+        #[arg(long)]
+        local: bool,
+
+        /// Install into the global ~/.config/claw directory.
+        #[arg(long)]
+        global: bool,
+    },
+    /// Remove an installed collection and its goals.
+    Uninstall { name: String },
+    /// Make an installed collection's goals available again.
+    Enable { name: String },
+    /// Hide an installed collection's goals without removing them.
+    Disable { name: String },
+    /// Show the installed collections with their versions and goals.
+    List,
+}
+
+/// Configuration files `claw schema` can describe.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SchemaKind {
+    /// `claw.yaml`
+    Claw,
+    /// A goal's `prompt.yaml`
+    Prompt,
+}
+
+/// Receiver types accepted by `--receiver`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReceiverArg {
+    /// Runs the LLM command (`llm_command` or `--llm`).
+    Generic,
+    /// Runs `claude`.
+    ClaudeCli,
+    /// Pastes into the tmux pane from `tmux_target`.
+    Tmux,
+}
+
+impl From<ReceiverArg> for ReceiverType {
+    fn from(receiver: ReceiverArg) -> Self {
+        match receiver {
+            ReceiverArg::Generic => ReceiverType::Generic,
+            ReceiverArg::ClaudeCli => ReceiverType::ClaudeCli,
+            ReceiverArg::Tmux => ReceiverType::Tmux,
+        }
+    }
+}
+
+/// Shells supported by `claw completions` and `claw fix --install-hook`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
 }
@@ -0,0 +1,280 @@
+//! Goal collections: related goals installed, enabled and listed together.
+//!
+//! A collection is a directory with a `collection.yaml` manifest and its
+//! goals laid out like `.claw/`:
+//!
+//! ```text
+//! security-pack/
+//!   collection.yaml      # name, description, version
+//!   goals/
+//!     threat-model/prompt.yaml
+//!     secrets-scan/prompt.yaml
+//! ```
+//!
+//! `claw collection install` copies it to `<config dir>/collections/<name>/`.
+//! Goals of enabled collections are found after the config directory's own
+//! goals, so a team can roll out a new version of its goals in one step.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+/// Directory in a config directory holding installed collections.
+pub const COLLECTIONS_DIR: &str = "collections";
+
+/// The manifest at the root of a collection.
+pub const MANIFEST_FILE: &str = "collection.yaml";
+
+/// Marker file in an installed collection whose goals are turned off.
+const DISABLED_MARKER: &str = ".disabled";
+
+/// The contents of a `collection.yaml`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CollectionManifest {
+    /// Names the collection, and its directory once installed.
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The collection's version, e.g. `1.2.0`, shown by `claw collection list`.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// A collection installed in a config directory.
+#[derive(Debug, Clone)]
+pub struct InstalledCollection {
+    pub manifest: CollectionManifest,
+    pub directory: PathBuf,
+    pub enabled: bool,
+    /// Names of the collection's goals, sorted.
+    pub goals: Vec<String>,
+}
+
+/// Reads and checks the manifest of the collection in `directory`.
+pub fn load_manifest(directory: &Path) -> Result<CollectionManifest> {
+    let path = directory.join(MANIFEST_FILE);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("No collection manifest at {}", path.display()))?;
+    let manifest: CollectionManifest = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if manifest.name.is_empty()
+        || !manifest
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid collection name '{}' in {}: use letters, digits, '-' and '_'",
+            manifest.name,
+            path.display()
+        );
+    }
+    Ok(manifest)
+}
+
+/// Returns the names of the goals in a collection directory, sorted.
+fn goal_names(directory: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(directory.join("goals"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Lists the collections installed in a config directory, by name.
+/// Directories without a valid manifest are skipped.
+pub fn installed(base_dir: &Path) -> Vec<InstalledCollection> {
+    let mut collections: Vec<InstalledCollection> = fs::read_dir(base_dir.join(COLLECTIONS_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let directory = entry.path();
+            let manifest = load_manifest(&directory).ok()?;
+            Some(InstalledCollection {
+                manifest,
+                enabled: !directory.join(DISABLED_MARKER).exists(),
+                goals: goal_names(&directory),
+                directory,
+            })
+        })
+        .collect();
+    collections.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    collections
+}
+
+/// Returns the installed collection named `name`, if any.
+pub fn find(base_dir: &Path, name: &str) -> Option<InstalledCollection> {
+    installed(base_dir)
+        .into_iter()
+        .find(|collection| collection.manifest.name == name)
+}
+
+/// Installs the collection in `source` into a config directory, replacing
+/// an installed version of it. A disabled collection stays disabled.
+/// Returns the version it replaced, if any, and the installed collection.
+pub fn install(
+    source: &Path,
+    base_dir: &Path,
+) -> Result<(Option<InstalledCollection>, InstalledCollection)> {
+    let manifest = load_manifest(source)?;
+    let goals = goal_names(source);
+    if goals.is_empty() {
+        bail!(
+            "Collection '{}' has no goals; put them in {}",
+            manifest.name,
+            source.join("goals").display()
+        );
+    }
+    for goal in &goals {
+        if config::load_goal_config(source, goal)?.is_none() {
+            bail!(
+                "{} has no {} or {}",
+                source.join("goals").join(goal).display(),
+                config::PROMPT_FILE,
+                config::PROMPT_MARKDOWN_FILE
+            );
+        }
+    }
+
+    let target = base_dir.join(COLLECTIONS_DIR).join(&manifest.name);
+    let previous = find(base_dir, &manifest.name);
+    if let (Ok(source), Ok(target)) = (source.canonicalize(), target.canonicalize())
+        && source == target
+    {
+        bail!(
+            "Collection '{}' is already installed from here",
+            manifest.name
+        );
+    }
+    if target.exists() {
+        fs::remove_dir_all(&target)
+            .with_context(|| format!("Failed to remove {}", target.display()))?;
+    }
+    fs::create_dir_all(&target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    let mut options = fs_extra::dir::CopyOptions::new();
+    options.content_only = true;
+    fs_extra::dir::copy(source, &target, &options).with_context(|| {
+        format!(
+            "Failed to copy {} to {}",
+            source.display(),
+            target.display()
+        )
+    })?;
+
+    let _ = fs::remove_file(target.join(DISABLED_MARKER));
+    if previous.as_ref().is_some_and(|p| !p.enabled) {
+        set_enabled(base_dir, &manifest.name, false)?;
+    }
+    let installed = find(base_dir, &manifest.name)
+        .with_context(|| format!("Failed to read back collection '{}'", manifest.name))?;
+    Ok((previous, installed))
+}
+
+/// Turns an installed collection's goals on or off.
+pub fn set_enabled(base_dir: &Path, name: &str, enabled: bool) -> Result<()> {
+    let collection =
+        find(base_dir, name).with_context(|| format!("Collection '{}' is not installed", name))?;
+    let marker = collection.directory.join(DISABLED_MARKER);
+    if enabled {
+        if marker.exists() {
+            fs::remove_file(&marker)
+                .with_context(|| format!("Failed to remove {}", marker.display()))?;
+        }
+    } else {
+        fs::write(&marker, "").with_context(|| format!("Failed to write {}", marker.display()))?;
+    }
+    Ok(())
+}
+
+/// Removes an installed collection and its goals.
+pub fn uninstall(base_dir: &Path, name: &str) -> Result<()> {
+    let collection =
+        find(base_dir, name).with_context(|| format!("Collection '{}' is not installed", name))?;
+    fs::remove_dir_all(&collection.directory)
+        .with_context(|| format!("Failed to remove {}", collection.directory.display()))
+}
+
+/// The directories a config directory's goals are loaded from, in lookup
+/// order: its own `goals/`, then each enabled collection's, by name. Each
+/// comes with the collection it belongs to.
+pub fn goal_roots(base_dir: &Path) -> Vec<(PathBuf, Option<String>)> {
+    std::iter::once((base_dir.to_path_buf(), None))
+        .chain(
+            installed(base_dir)
+                .into_iter()
+                .filter(|collection| collection.enabled)
+                .map(|collection| (collection.directory, Some(collection.manifest.name))),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_collection(dir: &Path, version: &str) {
+        fs::create_dir_all(dir.join("goals/threat-model")).unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            format!("name: security-pack\nversion: \"{}\"\n", version),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("goals/threat-model/prompt.yaml"),
+            "name: Threat Model\nprompt: Model the threats.\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_install_disable_and_upgrade() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("src");
+        let base_dir = temp_dir.path().join(".claw");
+        write_collection(&source, "1.0.0");
+
+        let (previous, installed) = install(&source, &base_dir).unwrap();
+        assert!(previous.is_none());
+        assert!(installed.enabled);
+        assert_eq!(installed.goals, vec!["threat-model"]);
+        assert_eq!(goal_roots(&base_dir).len(), 2);
+
+        set_enabled(&base_dir, "security-pack", false).unwrap();
+        assert_eq!(goal_roots(&base_dir), vec![(base_dir.clone(), None)]);
+
+        // Upgrading keeps the collection disabled
+        write_collection(&source, "1.1.0");
+        let (previous, installed) = install(&source, &base_dir).unwrap();
+        assert_eq!(previous.unwrap().manifest.version.as_deref(), Some("1.0.0"));
+        assert_eq!(installed.manifest.version.as_deref(), Some("1.1.0"));
+        assert!(!installed.enabled);
+
+        uninstall(&base_dir, "security-pack").unwrap();
+        assert!(super::installed(&base_dir).is_empty());
+        assert!(set_enabled(&base_dir, "security-pack", true).is_err());
+    }
+
+    #[test]
+    fn test_install_rejects_bad_collections() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("src");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join(MANIFEST_FILE), "name: ../escape\n").unwrap();
+        assert!(load_manifest(&source).is_err());
+
+        fs::write(source.join(MANIFEST_FILE), "name: empty\n").unwrap();
+        let error = install(&source, temp_dir.path()).unwrap_err();
+        assert!(error.to_string().contains("has no goals"));
+    }
+}
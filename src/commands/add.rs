@@ -1,52 +1,222 @@
 use crate::config::{self, ClawConfig};
+use crate::history;
 use crate::runner;
-use anyhow::{Context, Result};
+use crate::script_cache;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tera::Context as TeraContext;
 use tera::Tera;
 
+/// An unfinished `claw add` session, stored as `draft.json` in the goal's
+/// drafts directory next to the agent's notes and working `prompt.yaml`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Draft {
+    goal: String,
+    /// The config directory the goal is being created in (`.claw` or
+    /// `~/.config/claw`).
+    base_dir: PathBuf,
+    /// Unix timestamp (seconds) when the first session started.
+    started_at: u64,
+    /// Unix timestamp (seconds) when the last session ended.
+    updated_at: u64,
+}
+
+/// Returns `~/.config/claw/drafts/<name>`, where an unfinished session of
+/// `claw add <name>` is kept.
+fn draft_dir(name: &str) -> Result<PathBuf> {
+    config::ConfigPaths::new()?
+        .global
+        .map(|dir| dir.join("drafts").join(name))
+        .context("No global config directory to store drafts in")
+}
+
+fn load_draft(dir: &Path) -> Option<Draft> {
+    let json = fs::read_to_string(dir.join("draft.json")).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn save_draft(dir: &Path, draft: &Draft) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create drafts directory {}", dir.display()))?;
+    let json = serde_json::to_string_pretty(draft).context("Failed to serialize draft")?;
+    fs::write(dir.join("draft.json"), json)
+        .with_context(|| format!("Failed to write draft in {}", dir.display()))
+}
+
+/// Lists the names of the goals with an unfinished draft.
+fn draft_names(drafts_root: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(drafts_root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join("draft.json").exists())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Describes what an earlier session left behind, for the meta-prompt.
+fn resume_summary(draft_dir: &Path, save_path: &Path) -> String {
+    let mut summary = String::new();
+    for (title, path) in [
+        ("Session notes", draft_dir.join("notes.md")),
+        ("Draft prompt.yaml", draft_dir.join("prompt.yaml")),
+    ] {
+        if let Ok(content) = fs::read_to_string(&path)
+            && !content.trim().is_empty()
+        {
+            summary.push_str(&format!("--- {} ---\n{}\n\n", title, content.trim_end()));
+        }
+    }
+
+    let existing: Vec<String> = fs::read_dir(save_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    if !existing.is_empty() {
+        summary.push_str(&format!(
+            "--- Files already in {} ---\n{}\n",
+            save_path.display(),
+            existing.join("\n")
+        ));
+    }
+
+    if summary.is_empty() {
+        summary.push_str("The earlier session left no notes or files.\n");
+    }
+    summary
+}
+
+/// Fails if creating `name` in `save_dir_base` would change a managed goal,
+/// or add a goal that the managed library hides.
+fn check_not_managed(save_dir_base: &Path, name: &str) -> Result<()> {
+    if let Ok(Some(existing)) = config::load_goal_config(save_dir_base, name)
+        && existing.managed
+    {
+        bail!(
+            "Goal '{}' in {} is managed centrally and can't be changed with claw add.",
+            name,
+            save_dir_base.display()
+        );
+    }
+
+    let paths = config::ConfigPaths::new()?;
+    if let Some(managed) = &paths.managed
+        && paths.global.is_some_and(|global| same_dir(&global, save_dir_base))
+        && config::load_goal_config(managed, name).is_ok_and(|goal| goal.is_some())
+    {
+        bail!(
+            "Goal '{}' is managed centrally in {}, and a global goal of the same name would be hidden by it. Use --local to override it in this repository, or choose another name.",
+            name,
+            managed.display()
+        );
+    }
+    Ok(())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 pub fn handle_add_command(
     name: &str,
     local: bool,
     global: bool,
+    resume: bool,
     claw_config: &ClawConfig,
 ) -> Result<()> {
-    // 1. Determine the final, unambiguous save path based on flags.
-    let paths = config::ConfigPaths::new()?;
-    let save_dir_base = match (local, global) {
-        (true, false) => {
-            let local_path = paths.local.unwrap_or_else(|| PathBuf::from(".claw"));
-            fs::create_dir_all(&local_path).with_context(|| {
-                format!(
-                    "Failed to create local directory at {}",
-                    local_path.display()
-                )
-            })?;
-            println!(
-                "--local flag used. Goal will be saved in: {}",
-                local_path.display()
+    let draft_dir = draft_dir(name)?;
+    let existing_draft = load_draft(&draft_dir);
+
+    // 1. Determine the final, unambiguous save path based on flags, or the
+    //    one recorded in the draft when resuming.
+    let save_dir_base = if resume {
+        let Some(draft) = &existing_draft else {
+            let drafts_root = draft_dir.parent().unwrap_or(&draft_dir);
+            let names = draft_names(drafts_root);
+            if names.is_empty() {
+                bail!("No draft found for goal '{}'.", name);
+            }
+            bail!(
+                "No draft found for goal '{}'. Drafts: {}",
+                name,
+                names.join(", ")
             );
-            local_path
-        }
-        (false, true) => {
-            let global_path = paths.global.unwrap();
-            println!(
-                "--global flag used. Goal will be saved in: {}",
-                global_path.display()
+        };
+        println!(
+            "Resuming the draft of '{}' started {}.",
+            name,
+            script_cache::format_age(draft.started_at, history::now_secs())
+        );
+        draft.base_dir.clone()
+    } else {
+        if existing_draft.is_some() {
+            bail!(
+                "A draft of '{}' from an unfinished session exists. Continue it with `claw add {} --resume`, or delete {} to start over.",
+                name,
+                name,
+                draft_dir.display()
             );
-            global_path
         }
-        (false, false) => paths.local.unwrap_or_else(|| paths.global.unwrap()),
-        (true, true) => unreachable!(),
+
+        let paths = config::ConfigPaths::new()?;
+        match (local, global) {
+            (true, false) => {
+                let local_path = paths.local.unwrap_or_else(|| PathBuf::from(".claw"));
+                fs::create_dir_all(&local_path).with_context(|| {
+                    format!(
+                        "Failed to create local directory at {}",
+                        local_path.display()
+                    )
+                })?;
+                println!(
+                    "--local flag used. Goal will be saved in: {}",
+                    local_path.display()
+                );
+                local_path
+            }
+            (false, true) => {
+                let global_path = paths.global.unwrap();
+                println!(
+                    "--global flag used. Goal will be saved in: {}",
+                    global_path.display()
+                );
+                global_path
+            }
+            (false, false) => paths.local.unwrap_or_else(|| paths.global.unwrap()),
+            (true, true) => unreachable!(),
+        }
     };
 
     let save_path = save_dir_base.join("goals").join(name);
+    check_not_managed(&save_dir_base, name)?;
+
+    // Record the session up front, so a crash still leaves a draft to resume
+    let now = history::now_secs();
+    let draft = Draft {
+        goal: name.to_string(),
+        base_dir: fs::canonicalize(&save_dir_base).unwrap_or(save_dir_base.clone()),
+        started_at: existing_draft.as_ref().map_or(now, |d| d.started_at),
+        updated_at: now,
+    };
+    save_draft(&draft_dir, &draft)?;
 
     // 2. Prepare and render the meta-prompt.
     let mut context = TeraContext::new();
     context.insert("save_path", &save_path.display().to_string());
     context.insert("goal_name", &name);
+    context.insert("draft_dir", &draft_dir.display().to_string());
+    if resume {
+        context.insert("resume", &resume_summary(&draft_dir, &save_path));
+    }
 
     const META_PROMPT_TEMPLATE: &str = include_str!("../../prompts/add_meta_prompt.txt");
 
@@ -59,13 +229,96 @@ pub fn handle_add_command(
     println!("The agent will create files in: {}", save_path.display());
     println!("Please follow the instructions from the assistant.");
 
+    config::find_and_load_policy()?.check_receiver(claw_config)?;
+
     // Check for large prompt warning
     runner::check_prompt_size_warning(&rendered_meta_prompt, &claw_config.prompt_arg_template);
 
-    // Create receiver and send prompt
-    let receiver = runner::create_receiver(claw_config);
-    receiver.send_prompt(&rendered_meta_prompt)?;
+    // Create receiver and send prompt. Ctrl-C ends the agent session but
+    // leaves claw running, so the draft is kept.
+    runner::install_interrupt_handler()?;
+    let receiver = runner::create_receiver(claw_config, &runner::ReceiverOptions::default());
+    let result = receiver.send_prompt(&rendered_meta_prompt);
 
-    println!("\nAgent session finished. Verify that the goal was created successfully.");
+    // 4. Keep the draft unless the goal was written and parses.
+    let finished = !runner::was_interrupted()
+        && matches!(config::load_goal_config(&save_dir_base, name), Ok(Some(_)));
+    if finished {
+        fs::remove_dir_all(&draft_dir).with_context(|| {
+            format!("Failed to remove the finished draft {}", draft_dir.display())
+        })?;
+    } else {
+        let partial = save_path.join("prompt.yaml");
+        if partial.exists() {
+            fs::copy(&partial, draft_dir.join("prompt.yaml")).with_context(|| {
+                format!("Failed to save {} to the draft", partial.display())
+            })?;
+        }
+        save_draft(
+            &draft_dir,
+            &Draft {
+                updated_at: history::now_secs(),
+                ..draft
+            },
+        )?;
+    }
+    result?;
+
+    if finished {
+        println!("\nAgent session finished. Verify that the goal was created successfully.");
+    } else {
+        println!(
+            "\nThe goal isn't finished yet. The session was saved in {}.",
+            draft_dir.display()
+        );
+        println!("Continue it with: claw add {} --resume", name);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resume_summary_includes_notes_draft_and_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let draft_dir = temp_dir.path().join("drafts/standup");
+        let save_path = temp_dir.path().join("goals/standup");
+        fs::create_dir_all(&draft_dir).unwrap();
+        fs::create_dir_all(&save_path).unwrap();
+        fs::write(draft_dir.join("notes.md"), "- context: git log\n").unwrap();
+        fs::write(draft_dir.join("prompt.yaml"), "name: Standup\n").unwrap();
+        fs::write(save_path.join("style.md"), "Be brief.").unwrap();
+
+        let summary = resume_summary(&draft_dir, &save_path);
+        assert!(summary.contains("--- Session notes ---\n- context: git log\n"));
+        assert!(summary.contains("--- Draft prompt.yaml ---\nname: Standup\n"));
+        assert!(summary.contains("style.md"));
+
+        let empty = temp_dir.path().join("drafts/empty");
+        assert_eq!(
+            resume_summary(&empty, &empty),
+            "The earlier session left no notes or files.\n"
+        );
+    }
+
+    #[test]
+    fn test_draft_round_trip_and_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        let draft = Draft {
+            goal: "standup".to_string(),
+            base_dir: PathBuf::from("/home/me/.config/claw"),
+            started_at: 100,
+            updated_at: 200,
+        };
+        save_draft(&temp_dir.path().join("standup"), &draft).unwrap();
+        fs::create_dir(temp_dir.path().join("stray")).unwrap();
+
+        let loaded = load_draft(&temp_dir.path().join("standup")).unwrap();
+        assert_eq!(loaded.base_dir, draft.base_dir);
+        assert_eq!(loaded.started_at, 100);
+        assert_eq!(draft_names(temp_dir.path()), vec!["standup"]);
+    }
+}
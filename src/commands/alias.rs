@@ -0,0 +1,28 @@
+//! `claw alias`: lists the command aliases defined in claw.yaml.
+
+use crate::alias;
+use crate::config::ClawConfig;
+
+/// Handles `claw alias`.
+pub fn handle_alias_command(claw_config: &ClawConfig) {
+    let aliases = claw_config.aliases.clone().unwrap_or_default();
+    if aliases.is_empty() {
+        println!("No aliases defined.");
+        println!("Define them in claw.yaml, e.g.:");
+        println!("  aliases:");
+        println!("    rv: \"review --context src --\"");
+        return;
+    }
+
+    let width = aliases.keys().map(String::len).max().unwrap_or(0);
+    for (name, definition) in &aliases {
+        let note = if alias::is_builtin(name) {
+            "  (ignored: a built-in subcommand has this name)".to_string()
+        } else if let Err(e) = alias::split(name, definition) {
+            format!("  (ignored: {})", e)
+        } else {
+            String::new()
+        };
+        println!("  {:width$} = {}{}", name, definition, note, width = width);
+    }
+}
@@ -0,0 +1,215 @@
+//! `claw bundle`: packs a recorded run into one zip archive, for bug
+//! reports or audits of what was sent to the model.
+//!
+//! The archive holds:
+//!
+//! - `manifest.json`: the goal, arguments, outcome, claw version and the
+//!   context files (path, size and SHA-256 of the text that was sent)
+//! - `prompt.md`: the prompt as sent
+//! - `goal/prompt.yaml` (or `goal/prompt.md`): the goal's definition
+//! - `response.md`: the response, if it was captured
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+use crate::history::{self, ArgSource, Citation, ContextFileRecord, HistoryEntry, RunStatus};
+use crate::version;
+
+/// Run IDs listed when the requested one isn't found.
+const RECENT_RUNS_SHOWN: usize = 5;
+
+/// The `manifest.json` of a bundle.
+#[derive(Debug, Serialize)]
+struct BundleManifest<'a> {
+    run_id: &'a str,
+    goal: &'a str,
+    status: RunStatus,
+    started_at: u64,
+    finished_at: u64,
+    args: BTreeMap<&'a String, &'a String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arg_sources: Option<&'a BTreeMap<String, ArgSource>>,
+    /// The claw version that made the run, if it was recorded.
+    claw_version: Option<&'a str>,
+    /// The claw version that wrote the bundle.
+    bundled_by: &'a str,
+    context_files: &'a [ContextFileRecord],
+    /// The context files the response cited, if it was asked to cite them.
+    #[serde(skip_serializing_if = "<[Citation]>::is_empty")]
+    citations: &'a [Citation],
+}
+
+/// Handles `claw bundle RUN_ID [-o FILE]`. `last` names the most recent run.
+pub fn handle_bundle_command(run_id: &str, output: Option<&Path>) -> Result<()> {
+    let runs = history::load_runs()?;
+    let run = if run_id == "last" {
+        runs.last()
+    } else {
+        runs.iter().find(|(id, _)| id == run_id)
+    };
+    let Some((run_id, entry)) = run else {
+        if runs.is_empty() {
+            bail!(
+                "No runs have been recorded yet. Runs are recorded unless record_history is false."
+            );
+        }
+        let recent: Vec<&str> = runs
+            .iter()
+            .rev()
+            .take(RECENT_RUNS_SHOWN)
+            .map(|(id, _)| id.as_str())
+            .collect();
+        bail!(
+            "No recorded run '{}'. The most recent runs are: {}",
+            run_id,
+            recent.join(", ")
+        );
+    };
+
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.zip", run_id)));
+    let contents = write_bundle(run_id, entry, &output)?;
+    println!(
+        "✓ Wrote {} (run {}: {})",
+        output.display(),
+        run_id,
+        contents.join(", ")
+    );
+    if entry.prompt.is_none() {
+        eprintln!(
+            "⚠️  Note: this run was recorded before claw kept prompts, so the bundle has no prompt.md."
+        );
+    }
+    if !entry.context_files.is_empty() || entry.prompt.is_some() {
+        println!("  Review it before sharing: the prompt includes your context files verbatim.");
+    }
+    Ok(())
+}
+
+/// Writes the bundle for a run to `path` and returns a description of what
+/// it holds, e.g. `["prompt", "goal definition", "2 context file(s)"]`.
+fn write_bundle(run_id: &str, entry: &HistoryEntry, path: &Path) -> Result<Vec<String>> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut add = |name: &str, content: &[u8]| -> Result<()> {
+        zip.start_file(name, options)
+            .with_context(|| format!("Failed to add {} to the bundle", name))?;
+        zip.write_all(content)
+            .with_context(|| format!("Failed to add {} to the bundle", name))
+    };
+
+    let manifest = BundleManifest {
+        run_id,
+        goal: &entry.goal,
+        status: entry.status,
+        started_at: entry.started_at,
+        finished_at: entry.finished_at,
+        args: entry.args.iter().collect(),
+        arg_sources: entry.arg_sources.as_ref(),
+        claw_version: entry.claw_version.as_deref(),
+        bundled_by: version::CURRENT,
+        context_files: &entry.context_files,
+        citations: &entry.citations,
+    };
+    let json = serde_json::to_string_pretty(&manifest).context("Failed to serialize manifest")?;
+    add("manifest.json", json.as_bytes())?;
+
+    let mut contents = Vec::new();
+    if let Some(prompt) = &entry.prompt {
+        add("prompt.md", prompt.as_bytes())?;
+        contents.push("prompt".to_string());
+    }
+    if let Some(definition) = &entry.goal_definition {
+        add(
+            &format!("goal/{}", definition.file),
+            definition.content.as_bytes(),
+        )?;
+        contents.push("goal definition".to_string());
+    }
+    if !entry.context_files.is_empty() {
+        contents.push(format!("{} context file(s)", entry.context_files.len()));
+    }
+    if let Some(response) = &entry.response {
+        add("response.md", response.as_bytes())?;
+        contents.push("response".to_string());
+    }
+    zip.finish()
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    if contents.is_empty() {
+        contents.push("manifest only".to_string());
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::GoalDefinition;
+    use std::collections::HashMap;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("bundle.zip");
+        let entry = HistoryEntry {
+            goal: "review".to_string(),
+            args: HashMap::from([("scope".to_string(), "api".to_string())]),
+            arg_sources: None,
+            started_at: 100,
+            finished_at: 104,
+            status: RunStatus::Completed,
+            response: Some("LGTM".to_string()),
+            prompt: Some("Review src/api.rs".to_string()),
+            goal_definition: Some(GoalDefinition {
+                file: "prompt.yaml".to_string(),
+                content: "name: Review\nprompt: Review\n".to_string(),
+            }),
+            context_files: vec![ContextFileRecord {
+                path: PathBuf::from("src/api.rs"),
+                sha256: "ab".repeat(32),
+                bytes: 10,
+            }],
+            citations: vec![history::Citation {
+                source: 1,
+                path: Some(PathBuf::from("src/api.rs")),
+            }],
+            claw_version: Some("0.9.0".to_string()),
+        };
+
+        let contents = write_bundle("100-review", &entry, &path).unwrap();
+        assert_eq!(
+            contents,
+            vec!["prompt", "goal definition", "1 context file(s)", "response"]
+        );
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut text = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut text)
+                .unwrap();
+            text
+        };
+        assert_eq!(read("prompt.md"), "Review src/api.rs");
+        assert_eq!(read("response.md"), "LGTM");
+        assert!(read("goal/prompt.yaml").contains("name: Review"));
+        let manifest: serde_json::Value = serde_json::from_str(&read("manifest.json")).unwrap();
+        assert_eq!(manifest["run_id"], "100-review");
+        assert_eq!(manifest["args"]["scope"], "api");
+        assert_eq!(manifest["context_files"][0]["path"], "src/api.rs");
+        assert_eq!(manifest["claw_version"], "0.9.0");
+        assert_eq!(manifest["citations"][0]["path"], "src/api.rs");
+    }
+}
@@ -0,0 +1,147 @@
+//! `claw collection`: installs, enables, disables and lists goal collections.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::collections::{self, InstalledCollection};
+use crate::config::{ConfigPaths, GoalSource};
+
+/// Handles `claw collection install DIR [--local | --global]`. Without a
+/// flag, the collection goes into the local `.claw/` if there is one, and
+/// the global config directory otherwise.
+pub fn handle_install_command(source: &Path, local: bool, global: bool) -> Result<()> {
+    let paths = ConfigPaths::new()?;
+    let base_dir = match (local, global) {
+        (true, _) => {
+            let local_path = paths.local.unwrap_or_else(|| PathBuf::from(".claw"));
+            fs::create_dir_all(&local_path).with_context(|| {
+                format!(
+                    "Failed to create local directory at {}",
+                    local_path.display()
+                )
+            })?;
+            local_path
+        }
+        (false, true) => paths.global.context("No global config directory found")?,
+        (false, false) => paths
+            .local
+            .or(paths.global)
+            .context("No local .claw/ or global config directory to install into")?,
+    };
+
+    let (previous, installed) = collections::install(source, &base_dir)?;
+    let label = |collection: &InstalledCollection| match &collection.manifest.version {
+        Some(version) => format!("{} {}", collection.manifest.name, version),
+        None => collection.manifest.name.clone(),
+    };
+    match previous {
+        Some(previous) => println!(
+            "✓ Replaced {} with {} in {}",
+            label(&previous),
+            label(&installed),
+            installed.directory.display()
+        ),
+        None => println!(
+            "✓ Installed {} in {}",
+            label(&installed),
+            installed.directory.display()
+        ),
+    }
+    println!("  Goals: {}", installed.goals.join(", "));
+    if !installed.enabled {
+        println!(
+            "  The collection is disabled; run `claw collection enable {}` to use its goals.",
+            installed.manifest.name
+        );
+    }
+    Ok(())
+}
+
+/// Handles `claw collection uninstall NAME`.
+pub fn handle_uninstall_command(name: &str) -> Result<()> {
+    let base_dir = find_changeable(name)?;
+    collections::uninstall(&base_dir, name)?;
+    println!("✓ Uninstalled collection '{}'", name);
+    Ok(())
+}
+
+/// Handles `claw collection enable NAME` and `claw collection disable NAME`.
+pub fn handle_enable_command(name: &str, enabled: bool) -> Result<()> {
+    let base_dir = find_changeable(name)?;
+    collections::set_enabled(&base_dir, name, enabled)?;
+    if enabled {
+        println!("✓ Enabled collection '{}'", name);
+    } else {
+        println!(
+            "✓ Disabled collection '{}'; its goals are hidden until you enable it",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Returns the config directory the named collection is installed in,
+/// local before global. Collections in the managed library can't be changed.
+fn find_changeable(name: &str) -> Result<PathBuf> {
+    let paths = ConfigPaths::new()?;
+    for (base_dir, source) in paths.goal_sources() {
+        if collections::find(base_dir, name).is_none() {
+            continue;
+        }
+        if source == GoalSource::Managed {
+            bail!(
+                "Collection '{}' is managed centrally in {}; it can't be changed here.",
+                name,
+                base_dir.display()
+            );
+        }
+        return Ok(base_dir.to_path_buf());
+    }
+    bail!(
+        "Collection '{}' is not installed. Run `claw collection list` to see the installed ones.",
+        name
+    )
+}
+
+/// Handles `claw collection list`.
+pub fn handle_list_command() -> Result<()> {
+    let paths = ConfigPaths::new()?;
+    let mut any = false;
+    for (base_dir, source) in paths.goal_sources() {
+        let installed = collections::installed(base_dir);
+        if installed.is_empty() {
+            continue;
+        }
+        if any {
+            println!();
+        }
+        any = true;
+        let title = match source {
+            GoalSource::Local => "Local",
+            GoalSource::Global => "Global",
+            GoalSource::Managed => "Managed",
+        };
+        println!("{} Collections ({}):", title, base_dir.display());
+        println!();
+        for collection in &installed {
+            print!("  {}", collection.manifest.name);
+            if let Some(version) = &collection.manifest.version {
+                print!(" {}", version);
+            }
+            if !collection.enabled {
+                print!(" [disabled]");
+            }
+            println!();
+            if let Some(description) = &collection.manifest.description {
+                println!("    {}", description);
+            }
+            println!("    Goals: {}", collection.goals.join(", "));
+        }
+    }
+    if !any {
+        println!("No collections installed.");
+        println!("Install one using: claw collection install <dir>");
+    }
+    Ok(())
+}
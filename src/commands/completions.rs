@@ -0,0 +1,369 @@
+//! Shell completion support.
+//!
+//! `claw completions <shell>` prints a small script that forwards every TAB
+//! press to the hidden `claw __complete` command. That command knows about
+//! subcommands, goal names, goal parameters and their values (from `choices`
+//! or `choices_script`), so completions follow the goals on disk without
+//! regenerating the script.
+
+use anyhow::Result;
+use clap::CommandFactory;
+
+use crate::alias;
+use crate::cli::{Cli, Shell};
+use crate::config::{self, GoalParameter, GoalSource};
+use crate::runner;
+use crate::trust;
+
+/// Subcommands offered in the first position, alongside goal names.
+fn subcommands() -> Vec<String> {
+    let mut names: Vec<String> = Cli::command()
+        .get_subcommands()
+        .filter(|command| !command.is_hide_set())
+        .map(|command| command.get_name().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The long flags `subcommand` accepts, or, for `None`, those accepted when
+/// running a goal, read from the command-line definition so new flags are
+/// offered as soon as they exist.
+fn flags(subcommand: Option<&str>) -> Vec<String> {
+    let mut cli = Cli::command();
+    // Building adds `--help` and copies the global flags into subcommands
+    cli.build();
+    let command = match subcommand {
+        None => &cli,
+        Some(name) => match cli.find_subcommand(name) {
+            Some(command) => command,
+            None => return Vec::new(),
+        },
+    };
+    let mut flags: Vec<String> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| arg.get_long())
+        .map(|long| format!("--{}", long))
+        .collect();
+    flags.sort();
+    flags.dedup();
+    flags
+}
+
+const BASH_SCRIPT: &str = r#"# bash completion for claw
+_claw() {
+    local IFS=$'\n'
+    COMPREPLY=($(claw __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _claw claw
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef claw
+_claw() {
+    local -a candidates
+    candidates=(${(f)"$(claw __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)"})
+    if (( ${#candidates} )); then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+if [[ "${funcstack[1]}" = "_claw" ]]; then
+    _claw "$@"
+else
+    compdef _claw claw
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"# fish completion for claw
+function __claw_complete
+    set -l tokens (commandline -opc)
+    set -l current (commandline -ct)
+    set -l candidates (claw __complete -- $tokens[2..-1] "$current" 2>/dev/null)
+    if set -q candidates[1]
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path "$current"
+    end
+end
+complete -c claw -f -a '(__claw_complete)'
+"#;
+
+/// Handles the `claw completions` command.
+pub fn handle_completions_command(shell: Shell) {
+    let script = match shell {
+        Shell::Bash => BASH_SCRIPT,
+        Shell::Zsh => ZSH_SCRIPT,
+        Shell::Fish => FISH_SCRIPT,
+    };
+    print!("{}", script);
+}
+
+/// Handles the hidden `claw __complete` command used by the completion scripts.
+///
+/// `words` are the command-line words after `claw`, the last one being the
+/// (possibly empty) word under the cursor. Candidates are printed one per
+/// line; printing nothing lets the shell fall back to file completion.
+pub fn handle_complete_command(words: &[String]) -> Result<()> {
+    let goals = config::find_all_goals().unwrap_or_default();
+    let mut goal_names: Vec<String> = goals.into_iter().map(|g| g.name).collect();
+    goal_names.dedup();
+
+    // Aliases complete like goals, and complete as their expansion once the
+    // cursor has moved past them
+    let aliases = config::find_and_load_claw_config(None)
+        .ok()
+        .and_then(|config| config.aliases)
+        .unwrap_or_default();
+    goal_names.extend(aliases.keys().filter(|name| !alias::is_builtin(name)).cloned());
+    let mut words = words.to_vec();
+    if words.len() > 1 {
+        let mut args = vec![String::from("claw")];
+        args.append(&mut words);
+        words = alias::expand(args, &aliases)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(1)
+            .collect();
+    }
+
+    // Pressing TAB must not run commands from goals that aren't trusted:
+    // global goals under the policy's safe mode, or an untrusted local .claw
+    let safe_mode = config::find_and_load_policy()
+        .map(|p| p.global_goals_safe_mode)
+        .unwrap_or(false);
+    let local_trusted = trust::is_local_trusted();
+
+    let parameters = |goal_name: &str| match config::find_and_load_goal(goal_name) {
+        Ok(goal) => {
+            let mut params = goal.config.parameters;
+            let allowed = match goal.source {
+                GoalSource::Global | GoalSource::Managed => !safe_mode,
+                GoalSource::Local => local_trusted,
+            };
+            if !allowed {
+                for param in &mut params {
+                    param.choices_script = None;
+                }
+            }
+            params
+        }
+        Err(_) => Vec::new(),
+    };
+
+    for candidate in complete(&words, &goal_names, parameters) {
+        println!("{}", candidate);
+    }
+    Ok(())
+}
+
+/// Computes completion candidates for the last word in `words`.
+fn complete(
+    words: &[String],
+    goal_names: &[String],
+    parameters: impl Fn(&str) -> Vec<GoalParameter>,
+) -> Vec<String> {
+    let (current, previous) = match words.split_last() {
+        Some((current, previous)) => (current.as_str(), previous),
+        None => ("", &[][..]),
+    };
+
+    let subcommands = subcommands();
+    let subcommand = previous
+        .first()
+        .map(String::as_str)
+        .filter(|word| subcommands.iter().any(|name| name == word));
+    let goal_position = usize::from(subcommand.is_some());
+
+    let candidates: Vec<String> = if let Some(separator) = previous.iter().position(|w| w == "--") {
+        // Everything after `--` is a template argument for the goal
+        let goal = previous[..separator]
+            .get(goal_position)
+            .filter(|word| !word.starts_with('-'));
+        match goal {
+            Some(goal) => {
+                complete_parameter(current, &previous[separator + 1..], &parameters(goal))
+            }
+            None => Vec::new(),
+        }
+    } else if current.starts_with('-') {
+        flags(subcommand)
+    } else if previous.len() == goal_position {
+        match subcommand {
+            None => subcommands
+                .into_iter()
+                .chain(goal_names.iter().cloned())
+                .collect(),
+            Some("diff-goal") | Some("docs") | Some("dry-run") | Some("prompt-lint")
+            | Some("session") | Some("test") => goal_names.to_vec(),
+            Some("collection") => ["disable", "enable", "install", "list", "uninstall"]
+                .iter()
+                .map(|action| action.to_string())
+                .collect(),
+            Some(_) => Vec::new(),
+        }
+    } else {
+        Vec::new()
+    };
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(current))
+        .collect()
+}
+
+/// Completes a template argument: a `--name`, or a value for the parameter
+/// named by the previous word.
+fn complete_parameter(current: &str, previous: &[String], params: &[GoalParameter]) -> Vec<String> {
+    let find = |name: &str| params.iter().find(|p| p.name == name);
+
+    // `--name=value` as a single word (zsh, fish)
+    if let Some(rest) = current.strip_prefix("--") {
+        return match rest.split_once('=') {
+            Some((name, _)) => find(name)
+                .map(parameter_values)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| format!("--{}={}", name, value))
+                .collect(),
+            None => params.iter().map(|p| format!("--{}", p.name)).collect(),
+        };
+    }
+
+    // bash splits `--name=value` into `--name`, `=`, `value`
+    let flag = match previous {
+        [.., flag, eq] if eq == "=" => Some(flag),
+        [.., flag] => Some(flag),
+        [] => None,
+    };
+    flag.and_then(|flag| flag.strip_prefix("--"))
+        .and_then(find)
+        .map(parameter_values)
+        .unwrap_or_default()
+}
+
+/// Returns the completion values for a parameter. Static `choices` win over
+/// `choices_script`; a failing script simply offers nothing.
+fn parameter_values(param: &GoalParameter) -> Vec<String> {
+    if let Some(choices) = &param.choices {
+        return choices.clone();
+    }
+    match &param.choices_script {
+        Some(script) => runner::run_script(&param.name, script)
+            .map(|output| {
+                output
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &[&str]) -> Vec<String> {
+        line.iter().map(|w| w.to_string()).collect()
+    }
+
+    fn params(_goal: &str) -> Vec<GoalParameter> {
+        vec![
+            GoalParameter {
+                name: "format".to_string(),
+                description: "Output format".to_string(),
+                required: false,
+                param_type: None,
+                default: None,
+                ui: None,
+                choices: Some(vec!["markdown".to_string(), "json".to_string()]),
+                choices_script: None,
+                env: None,
+            },
+            GoalParameter {
+                name: "branch".to_string(),
+                description: "Branch".to_string(),
+                required: false,
+                param_type: None,
+                default: None,
+                ui: None,
+                choices: None,
+                choices_script: Some("printf 'main\\nfeature\\n'".to_string()),
+                env: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_first_word_offers_subcommands_and_goals() {
+        let goals = vec!["review".to_string(), "refactor".to_string()];
+        let candidates = complete(&words(&["re"]), &goals, params);
+        assert_eq!(candidates, vec!["review", "refactor"]);
+
+        let candidates = complete(&words(&["dry-run", ""]), &goals, params);
+        assert_eq!(candidates, goals);
+
+        let candidates = complete(&words(&["collection", "en"]), &goals, params);
+        assert_eq!(candidates, vec!["enable"]);
+    }
+
+    #[test]
+    fn test_parameter_names_and_choices() {
+        let goals = vec!["review".to_string()];
+
+        let candidates = complete(&words(&["review", "--", "--f"]), &goals, params);
+        assert_eq!(candidates, vec!["--format"]);
+
+        let candidates = complete(&words(&["review", "--", "--format", ""]), &goals, params);
+        assert_eq!(candidates, vec!["markdown", "json"]);
+
+        let candidates = complete(&words(&["review", "--", "--format=j"]), &goals, params);
+        assert_eq!(candidates, vec!["--format=json"]);
+
+        let candidates = complete(
+            &words(&["review", "--", "--format", "=", "m"]),
+            &goals,
+            params,
+        );
+        assert_eq!(candidates, vec!["markdown"]);
+    }
+
+    #[test]
+    fn test_choices_script_values() {
+        let goals = vec!["review".to_string()];
+        let candidates = complete(
+            &words(&["dry-run", "review", "--", "--branch", "f"]),
+            &goals,
+            params,
+        );
+        assert_eq!(candidates, vec!["feature"]);
+    }
+
+    #[test]
+    fn test_flags_before_separator() {
+        let candidates = complete(&words(&["dry-run", "review", "--pr"]), &[], params);
+        assert_eq!(
+            candidates,
+            vec!["--pretty", "--profile", "--prompt-template", "--provenance"]
+        );
+
+        // Flags come from the command-line definition, global ones included
+        let candidates = complete(&words(&["review", "--no-s"]), &[], params);
+        assert_eq!(
+            candidates,
+            vec!["--no-script-cache", "--no-scripts", "--no-setup"]
+        );
+        let candidates = complete(&words(&["dry-run", "--ch"]), &[], params);
+        assert_eq!(candidates, vec!["--check"]);
+        let candidates = complete(&words(&["session", "--h"]), &[], params);
+        assert_eq!(candidates, vec!["--help"]);
+
+        // Paths are left to the shell
+        assert!(complete(&words(&["review", "-c", "sr"]), &[], params).is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context as AnyhowContext, Result};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::collections;
+use crate::config::{self, ConfigPaths, GoalSource};
+
+/// Handles the `claw diff-goal` command: shows how a local goal differs from
+/// the managed or global goal of the same name that it shadows.
+pub fn handle_diff_goal_command(goal_name: &str) -> Result<()> {
+    let config_paths = ConfigPaths::new()?;
+    let mut found = config_paths
+        .goal_sources()
+        .into_iter()
+        .filter_map(|(base_dir, source)| Some((goal_in(base_dir, goal_name)?, source)));
+
+    let Some((local, GoalSource::Local)) = found.next() else {
+        anyhow::bail!(
+            "Goal '{}' has no local version in .claw/goals to compare",
+            goal_name
+        );
+    };
+    let Some((shadowed, source)) = found.next() else {
+        anyhow::bail!(
+            "Local goal '{}' doesn't shadow a managed or global goal",
+            goal_name
+        );
+    };
+
+    let git = which::which("git").context("claw diff-goal needs git to show the diff")?;
+    println!("{} goal: {}", capitalize(&source.to_string()), shadowed.display());
+    println!("Local goal: {}", local.display());
+    io::stdout().flush()?;
+
+    // `git diff --no-index` exits with 1 when the directories differ
+    let status = Command::new(git)
+        .args(["diff", "--no-index", "--color=auto", "--"])
+        .arg(&shadowed)
+        .arg(&local)
+        .status()
+        .context("Failed to run git diff")?;
+    match status.code() {
+        Some(0) => println!("The local goal is the same as the {} one.", source),
+        Some(1) => {}
+        _ => anyhow::bail!("git diff failed with {}", status),
+    }
+    Ok(())
+}
+
+/// The directory of `goal_name` under `base_dir` or one of its enabled
+/// collections, the first one that defines it.
+fn goal_in(base_dir: &Path, goal_name: &str) -> Option<PathBuf> {
+    collections::goal_roots(base_dir)
+        .into_iter()
+        .map(|(root, _)| root.join("goals").join(goal_name))
+        .find(|dir| config::goal_definition_path(dir).exists())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
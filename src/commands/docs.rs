@@ -0,0 +1,241 @@
+use anyhow::{Context as AnyhowContext, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{DiscoveredGoal, find_all_goals};
+use crate::help;
+
+/// Lines of the prompt template shown on a goal's page.
+const PROMPT_EXCERPT_LINES: usize = 20;
+
+/// Handles the `claw docs` command: writes a markdown page for each goal
+/// (or each of `goal_names`) and an `index.md` linking them to `output_dir`.
+pub fn handle_docs_command(output_dir: &Path, goal_names: &[String]) -> Result<()> {
+    // A goal shadowed by another of the same name is never run, so only the
+    // first one found is documented
+    let mut seen = HashSet::new();
+    let mut goals: Vec<DiscoveredGoal> = find_all_goals()?
+        .into_iter()
+        .filter(|goal| seen.insert(goal.name.clone()))
+        .collect();
+
+    if !goal_names.is_empty() {
+        if let Some(missing) = goal_names.iter().find(|name| !seen.contains(*name)) {
+            anyhow::bail!("Goal '{}' not found (see `claw list`)", missing);
+        }
+        goals.retain(|goal| goal_names.contains(&goal.name));
+    }
+    if goals.is_empty() {
+        println!("No goals found.");
+        println!("Add a goal using: claw add <goal_name>");
+        return Ok(());
+    }
+
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    for goal in &goals {
+        let path = output_dir.join(format!("{}.md", goal.name));
+        fs::write(&path, render_page(goal))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    let index = output_dir.join("index.md");
+    fs::write(&index, render_index(&goals))
+        .with_context(|| format!("Failed to write {}", index.display()))?;
+
+    println!(
+        "Wrote {} goal page(s) and index.md to {}",
+        goals.len(),
+        output_dir.display()
+    );
+    Ok(())
+}
+
+/// The index page: a table of the goals with links to their pages.
+fn render_index(goals: &[DiscoveredGoal]) -> String {
+    let mut page =
+        String::from("# Goals\n\n| Goal | Description | Source |\n| --- | --- | --- |\n");
+    for goal in goals {
+        page.push_str(&format!(
+            "| [{}]({}.md) | {} | {} |\n",
+            table_cell(&goal.name),
+            goal.name,
+            table_cell(goal.config.description.as_deref().unwrap_or("")),
+            source(goal)
+        ));
+    }
+    page
+}
+
+/// A goal's page: its description, parameters, examples, scripts and the
+/// start of its prompt.
+fn render_page(goal: &DiscoveredGoal) -> String {
+    let config = &goal.config;
+    let mut page = format!("# {}\n\n", config.name);
+    if let Some(description) = &config.description {
+        page.push_str(&format!("{}\n\n", description));
+    }
+    page.push_str(&format!(
+        "Run with `claw {}` ({} goal",
+        goal.name,
+        source(goal)
+    ));
+    if let Some(requirement) = &config.requires_claw {
+        page.push_str(&format!(", requires claw {}", requirement));
+    }
+    page.push_str(").\n");
+
+    if !config.parameters.is_empty() {
+        page.push_str("\n## Parameters\n\n");
+        page.push_str("| Name | Type | Required | Default | Description |\n");
+        page.push_str("| --- | --- | --- | --- | --- |\n");
+        for param in &config.parameters {
+            let mut description = param.description.clone();
+            if let Some(choices) = &param.choices {
+                description.push_str(&format!(" Choices: {}.", choices.join(", ")));
+            }
+            if let Some(var) = &param.env {
+                description.push_str(&format!(" From environment: `${}`.", var));
+            }
+            page.push_str(&format!(
+                "| `--{}` | {} | {} | {} | {} |\n",
+                param.name,
+                param
+                    .param_type
+                    .as_ref()
+                    .map(help::format_type)
+                    .unwrap_or_default(),
+                if param.required { "yes" } else { "no" },
+                param
+                    .default
+                    .as_deref()
+                    .map(|default| format!("`{}`", table_cell(default)))
+                    .unwrap_or_default(),
+                table_cell(description.trim())
+            ));
+        }
+    }
+
+    if !config.examples.is_empty() {
+        page.push_str("\n## Examples\n");
+        for example in &config.examples {
+            page.push_str(&format!(
+                "\n{}\n\n```sh\n{}\n```\n",
+                example.description,
+                help::example_command(example, &goal.name)
+            ));
+        }
+    }
+
+    if !config.context_scripts.is_empty() {
+        page.push_str("\n## Context scripts\n\n| Name | Command |\n| --- | --- |\n");
+        for (name, script) in &config.context_scripts {
+            page.push_str(&format!(
+                "| `{}` | {} |\n",
+                name,
+                code_span(&table_cell(script.command()))
+            ));
+        }
+    }
+
+    let lines: Vec<&str> = config.prompt.lines().collect();
+    let excerpt = lines[..lines.len().min(PROMPT_EXCERPT_LINES)].join("\n");
+    let fence = fence_for(&config.prompt);
+    page.push_str(&format!("\n## Prompt\n\n{}text\n{}\n", fence, excerpt));
+    if lines.len() > PROMPT_EXCERPT_LINES {
+        page.push_str(&format!(
+            "... ({} more lines)\n",
+            lines.len() - PROMPT_EXCERPT_LINES
+        ));
+    }
+    page.push_str(&format!("{}\n", fence));
+    page
+}
+
+/// Where the goal comes from, e.g. `global` or `local, collection team`.
+fn source(goal: &DiscoveredGoal) -> String {
+    match &goal.collection {
+        Some(collection) => format!("{}, collection {}", goal.source, collection),
+        None => goal.source.to_string(),
+    }
+}
+
+/// Text that fits in one markdown table cell.
+fn table_cell(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+/// `text` as inline code, with enough backticks around it to hold any in it.
+fn code_span(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{ticks}{pad}{text}{pad}{ticks}")
+}
+
+/// A code fence longer than any run of backticks in `text`.
+fn fence_for(text: &str) -> String {
+    "`".repeat((longest_backtick_run(text) + 1).max(3))
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GoalSource;
+
+    #[test]
+    fn test_goal_page_documents_parameters_examples_and_scripts() {
+        let yaml = r#"
+name: Code Review
+description: Reviews the staged changes.
+parameters:
+  - name: focus
+    description: What to look at | first.
+    required: false
+    default: security
+    choices: [security, style]
+examples:
+  - description: Review the API
+    args: { focus: style }
+    context: [src/api]
+context_scripts:
+  diff: "git diff --staged | head -n 500"
+prompt: |
+  Review this:
+  ```diff
+  {{ Context.diff }}
+  ```
+"#;
+        let goal = DiscoveredGoal {
+            name: "review".to_string(),
+            source: GoalSource::Global,
+            config: serde_yaml::from_str(yaml).unwrap(),
+            collection: None,
+        };
+        let page = render_page(&goal);
+
+        assert!(page.starts_with("# Code Review\n\nReviews the staged changes.\n"));
+        assert!(page.contains("Run with `claw review` (global goal)."));
+        assert!(page.contains(
+            "| `--focus` |  | no | `security` | What to look at \\| first. Choices: security, style. |"
+        ));
+        assert!(page.contains("```sh\nclaw review --context src/api -- --focus=style\n```"));
+        assert!(page.contains("| `diff` | `git diff --staged \\| head -n 500` |"));
+        // The prompt's own fence doesn't end the excerpt
+        assert!(page.contains("````text\nReview this:\n```diff"));
+        assert!(page.ends_with("```\n````\n"));
+
+        let index = render_index(std::slice::from_ref(&goal));
+        assert!(index.contains("| [review](review.md) | Reviews the staged changes. | global |"));
+    }
+}
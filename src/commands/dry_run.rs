@@ -1,6 +1,22 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{LinesWithEndings, as_24_bit_terminal_escaped};
+
+use crate::config::{self, ClawConfig, LoadedGoal};
+use crate::history;
+use crate::prompt_core;
+use crate::providers;
+use crate::validation::ParameterValidator;
+
+/// The pager used for `--pretty` output when `$PAGER` is not set.
+const DEFAULT_PAGER: &str = "less -R";
 
 /// Handles the dry-run command by rendering a goal's prompt without executing the LLM.
 ///
@@ -8,6 +24,7 @@ use std::path::PathBuf;
 /// * `goal_name` - Name of the goal to render
 /// * `output_file` - Optional file path to write the rendered prompt
 /// * `rendered_prompt` - The fully rendered prompt string
+/// * `pretty` - Highlight and page the prompt when stdout is a terminal
 ///
 /// # Returns
 /// * `Ok(())` on success
@@ -15,11 +32,190 @@ use std::path::PathBuf;
 pub fn handle_dry_run_command(
     output_file: Option<&PathBuf>,
     rendered_prompt: &str,
+    pretty: bool,
 ) -> Result<()> {
+    // Pretty output only makes sense for a human looking at a terminal;
+    // when piped, fall back to the exact prompt bytes.
+    if pretty && output_file.is_none() && std::io::stdout().is_terminal() {
+        let highlighted = highlight_markdown(rendered_prompt)?;
+        return page_output(&highlighted);
+    }
+
     output_prompt(rendered_prompt, output_file)?;
     Ok(())
 }
 
+/// Handles `claw dry-run --check`: checks that each goal would render,
+/// printing one line per goal, and fails if any would not.
+pub fn handle_check_command(
+    goal_names: &[String],
+    args: &HashMap<String, String>,
+    claw_config: &ClawConfig,
+) -> Result<()> {
+    let mut failures = 0;
+    for goal_name in goal_names {
+        match check_goal(goal_name, args, claw_config) {
+            Ok(()) => println!("✓ {}", goal_name),
+            Err(e) => {
+                failures += 1;
+                println!("✗ {}: {:#}", goal_name, e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!(
+            "{} of {} goal(s) would fail to render",
+            failures,
+            goal_names.len()
+        );
+    }
+    Ok(())
+}
+
+/// Checks that a goal would render, without running its context scripts or
+/// providers. The prompt is rendered with an empty string for every script
+/// and built-in.
+fn check_goal(goal_name: &str, args: &HashMap<String, String>, claw_config: &ClawConfig) -> Result<()> {
+    let goal = config::find_and_load_goal(goal_name)?;
+    config::find_and_load_policy()?.check_goal(goal_name, &goal, claw_config)?;
+    render_offline(goal_name, &goal, args, claw_config, |_| String::new())?;
+    Ok(())
+}
+
+/// Renders a goal's prompt template without running its context scripts or
+/// providers; each script and built-in renders as `script_output(name)`.
+///
+/// Parameters get their defaults and `env:` values; required ones that are
+/// still missing get a placeholder (their first choice, if any), since they
+/// would be asked for. Scripts are only syntax-checked with `sh -n`.
+pub fn render_offline(
+    goal_name: &str,
+    goal: &LoadedGoal,
+    args: &HashMap<String, String>,
+    claw_config: &ClawConfig,
+    script_output: impl Fn(&str) -> String,
+) -> Result<String> {
+    let validator = ParameterValidator::new(&goal.config.parameters, goal_name.to_string());
+    let mut args = args.clone();
+    validator.apply_env(&mut args, |var| std::env::var(var).ok());
+    for param in validator.get_missing_required(&args) {
+        let placeholder = param
+            .choices
+            .as_ref()
+            .and_then(|choices| choices.first().cloned())
+            .unwrap_or_else(|| format!("<{}>", param.name));
+        args.insert(param.name, placeholder);
+    }
+    let args = validator.validate(&args)?;
+
+    let mut script_outputs: HashMap<String, String> = goal
+        .config
+        .context_builtin
+        .iter()
+        .map(|builtin| (builtin.name().to_string(), script_output(builtin.name())))
+        .collect();
+    for (name, script) in prompt_core::render_context_scripts(&goal.config, &args)? {
+        check_script_syntax(&name, &script)?;
+        script_outputs.insert(name.clone(), script_output(&name));
+    }
+
+    providers::create_providers(
+        &goal.config.providers,
+        claw_config.context_providers.as_deref().unwrap_or_default(),
+        None,
+    )?;
+
+    let mut tera = prompt_core::prompt_templates(&goal.config, goal)?;
+    history::register_tera_function(&mut tera);
+    let claw = prompt_core::ClawMeta::new(goal_name, history::now_secs());
+    prompt_core::render_prompt(&tera, &claw, &args, &script_outputs)
+}
+
+/// Checks a context script for shell syntax errors without running it.
+fn check_script_syntax(name: &str, script: &str) -> Result<()> {
+    let output = Command::new("sh")
+        .args(["-n", "-c", script])
+        .output()
+        .with_context(|| format!("Failed to check context script '{}'", name))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Context script '{}' has a syntax error: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Syntax-highlights a rendered prompt as markdown, including fenced code blocks.
+///
+/// Returns the prompt with 24-bit ANSI color escapes applied.
+fn highlight_markdown(prompt: &str) -> Result<String> {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let theme_set = ThemeSet::load_defaults();
+    let syntax = syntax_set
+        .find_syntax_by_extension("md")
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+    let theme = &theme_set.themes["base16-ocean.dark"];
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut output = String::with_capacity(prompt.len() * 2);
+    for line in LinesWithEndings::from(prompt) {
+        let ranges = highlighter
+            .highlight_line(line, &syntax_set)
+            .context("Failed to highlight prompt")?;
+        output.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+    }
+    // Reset colors so the terminal isn't left styled after the pager exits
+    output.push_str("\x1b[0m");
+
+    Ok(output)
+}
+
+/// Sends text through the user's `$PAGER` (default `less -R`).
+///
+/// Falls back to printing directly if the pager cannot be started.
+pub fn page_output(text: &str) -> Result<()> {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let pager_args = shlex::split(&pager).context("Could not parse $PAGER")?;
+
+    let Some((program, args)) = pager_args.split_first() else {
+        print!("{}", text);
+        return Ok(());
+    };
+
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => {
+            print!("{}", text);
+            return Ok(());
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything; that's not an error.
+        if let Err(e) = stdin.write_all(text.as_bytes())
+            && e.kind() != std::io::ErrorKind::BrokenPipe
+        {
+            return Err(e).context("Failed to write prompt to pager");
+        }
+    }
+
+    child
+        .wait()
+        .with_context(|| format!("Failed to wait for pager '{}'", program))?;
+    Ok(())
+}
+
 /// Outputs the rendered prompt either to stdout or to a file.
 ///
 /// # Arguments
@@ -42,8 +238,8 @@ fn output_prompt(prompt: &str, output_file: Option<&PathBuf>) -> Result<()> {
                 format!("Failed to write dry run output to {}", path.display())
             })?;
 
-            // Print confirmation to stdout
-            println!("Dry run output written to {}", path.display());
+            // Confirm on stderr, so stdout only ever carries the prompt
+            eprintln!("Dry run output written to {}", path.display());
             Ok(())
         }
     }
@@ -116,6 +312,16 @@ mod tests {
         assert_eq!(file_contents, "");
     }
 
+    #[test]
+    fn test_highlight_markdown_preserves_text() {
+        let prompt = "# Title\n\n```rust\nfn main() {}\n```\n";
+        let highlighted = highlight_markdown(prompt).unwrap();
+
+        assert!(highlighted.contains("\x1b["));
+        assert!(highlighted.contains("Title"));
+        assert!(highlighted.contains("main"));
+    }
+
     #[test]
     fn test_output_prompt_handles_large_prompt() {
         let temp_dir = TempDir::new().unwrap();
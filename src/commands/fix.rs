@@ -0,0 +1,329 @@
+//! `claw fix`: hands a failed shell command to a triage goal.
+//!
+//! `claw fix --install-hook bash` adds a hook to the shell's startup file
+//! that records each command's line, exit status and directory in
+//! `~/.config/claw/cache/last_command` after it runs. `claw fix --last`
+//! reads that record and runs the triage goal (`triage_goal` in
+//! `claw.yaml`, default `triage`) with it. Shells don't keep what a command
+//! printed, so claw runs a failed command again, once the user agrees, to
+//! capture its output.
+
+use anyhow::{Context, Result};
+use directories::BaseDirs;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::cli::Shell;
+use crate::config::ConfigPaths;
+use crate::param_prompt;
+use crate::redact;
+
+/// Marks the startup file line that loads the hook.
+const MARKER: &str = "# claw fix hook";
+
+/// The triage goal run when `claw.yaml` doesn't name one.
+pub const DEFAULT_TRIAGE_GOAL: &str = "triage";
+
+/// Bytes of a command's output passed to the goal; the end is kept, since
+/// that is where errors are.
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// A command recorded by the shell hook.
+#[derive(Debug, PartialEq)]
+struct LastCommand {
+    status: i32,
+    cwd: PathBuf,
+    command: String,
+}
+
+/// Reads the last recorded command and returns the triage goal's arguments,
+/// or `None` if the command succeeded. With `rerun`, a failed command is run
+/// again to capture its output: without asking if `yes`, else after asking on
+/// a terminal.
+pub fn triage_args(rerun: bool, yes: bool) -> Result<Option<Vec<String>>> {
+    let path = record_path()?;
+    let record = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "No command recorded in {}. Install the shell hook first: claw fix --install-hook bash|zsh|fish",
+            path.display()
+        )
+    })?;
+    let last = parse_record(&record).with_context(|| format!("{} is malformed", path.display()))?;
+    if last.status == 0 {
+        println!("The last command succeeded: {}", last.command);
+        return Ok(None);
+    }
+    eprintln!("Last command (exit status {}): {}", last.status, last.command);
+
+    let output = if rerun && (yes || confirm_rerun()?) {
+        eprintln!("Running it again in {} to capture its output...", last.cwd.display());
+        Some(capture_output(&last)?)
+    } else {
+        None
+    };
+
+    let mut args = vec![
+        format!("--command={}", last.command),
+        format!("--exit_status={}", last.status),
+        format!("--cwd={}", last.cwd.display()),
+    ];
+    if let Some(output) = output {
+        args.push(format!("--output={}", redact::redact(&output)));
+    }
+    Ok(Some(args))
+}
+
+/// Handles `claw fix --install-hook SHELL`: writes the hook script and loads
+/// it from the shell's startup file.
+pub fn handle_install_hook_command(shell: Shell) -> Result<()> {
+    let record = record_path()?;
+    let dir = record.parent().context("The record path has no directory")?;
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let hook = dir.join(format!("fix-hook.{}", shell_name(shell)));
+    fs::write(&hook, hook_script(shell, &record))
+        .with_context(|| format!("Failed to write {}", hook.display()))?;
+
+    let rc = startup_file(shell)?;
+    let existing = fs::read_to_string(&rc).unwrap_or_default();
+    if existing.lines().any(|line| line.contains(MARKER)) {
+        println!("{} already loads the hook.", rc.display());
+    } else {
+        if let Some(parent) = rc.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&rc)
+            .with_context(|| format!("Failed to open {}", rc.display()))?;
+        let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+        writeln!(file, "{}source {}  {}", separator, quote(&hook), MARKER)
+            .with_context(|| format!("Failed to update {}", rc.display()))?;
+        println!("Added the claw fix hook to {}", rc.display());
+    }
+    println!(
+        "Open a new shell (or run `source {}`), then `claw fix --last` after a command fails.",
+        quote(&hook)
+    );
+    Ok(())
+}
+
+/// Handles `claw fix --uninstall-hook SHELL`: removes the line that loads
+/// the hook from the shell's startup file.
+pub fn handle_uninstall_hook_command(shell: Shell) -> Result<()> {
+    let rc = startup_file(shell)?;
+    let Ok(existing) = fs::read_to_string(&rc) else {
+        println!("{} doesn't exist; nothing to remove.", rc.display());
+        return Ok(());
+    };
+    let kept: Vec<&str> = existing.lines().filter(|line| !line.contains(MARKER)).collect();
+    if kept.len() == existing.lines().count() {
+        println!("{} doesn't load the claw fix hook.", rc.display());
+        return Ok(());
+    }
+    let mut updated = kept.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    fs::write(&rc, updated).with_context(|| format!("Failed to update {}", rc.display()))?;
+    println!("Removed the claw fix hook from {}", rc.display());
+    Ok(())
+}
+
+/// Where the hook records the last command.
+fn record_path() -> Result<PathBuf> {
+    ConfigPaths::new()?
+        .global
+        .map(|dir| dir.join("cache").join("last_command"))
+        .context("Could not find the global claw config directory")
+}
+
+/// Parses the hook's record: the exit status, the directory, then the
+/// command, which may span several lines.
+fn parse_record(record: &str) -> Option<LastCommand> {
+    let mut parts = record.splitn(3, '\n');
+    let status = parts.next()?.trim().parse().ok()?;
+    let cwd = PathBuf::from(parts.next()?);
+    let command = parts.next()?.trim_end().to_string();
+    if command.is_empty() {
+        return None;
+    }
+    Some(LastCommand { status, cwd, command })
+}
+
+fn confirm_rerun() -> Result<bool> {
+    if !param_prompt::is_interactive() {
+        eprintln!("Not running the command again to capture its output (use --yes to).");
+        return Ok(false);
+    }
+    eprint!("Run it again to capture its output? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Runs the command again with the user's shell and returns what it printed,
+/// standard output first, cut to its last [`MAX_OUTPUT_BYTES`].
+fn capture_output(last: &LastCommand) -> Result<String> {
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "sh".to_string());
+    let output = Command::new(&shell)
+        .arg("-c")
+        .arg(&last.command)
+        .current_dir(&last.cwd)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run the command again with {}", shell))?;
+    if output.status.code() != Some(last.status) {
+        eprintln!("Note: this time it exited with {}.", output.status);
+    }
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(tail(&text, MAX_OUTPUT_BYTES).to_string())
+}
+
+/// The last `max` bytes of `text`, starting at a character boundary.
+fn tail(text: &str, max: usize) -> &str {
+    let mut start = text.len().saturating_sub(max);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+fn shell_name(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => "bash",
+        Shell::Zsh => "zsh",
+        Shell::Fish => "fish",
+    }
+}
+
+/// The startup file interactive shells of `shell` read.
+fn startup_file(shell: Shell) -> Result<PathBuf> {
+    let home = BaseDirs::new()
+        .map(|dirs| dirs.home_dir().to_path_buf())
+        .context("Could not find your home directory")?;
+    Ok(match shell {
+        Shell::Bash => home.join(".bashrc"),
+        Shell::Zsh => std::env::var_os("ZDOTDIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or(home)
+            .join(".zshrc"),
+        Shell::Fish => home.join(".config/fish/config.fish"),
+    })
+}
+
+/// The hook that records each command in `record`, skipping `claw fix`
+/// itself so it can read the command before it.
+fn hook_script(shell: Shell, record: &Path) -> String {
+    let record = quote(record);
+    match shell {
+        Shell::Bash => format!(
+            "{MARKER}: records each command for `claw fix --last`\n\
+             __claw_record() {{\n\
+             \x20 local claw_status=$? claw_command\n\
+             \x20 claw_command=$(HISTTIMEFORMAT= builtin history 1)\n\
+             \x20 claw_command=${{claw_command#*[0-9]  }}\n\
+             \x20 case $claw_command in\n\
+             \x20   'claw fix'*) ;;\n\
+             \x20   *) printf '%s\\n%s\\n%s\\n' \"$claw_status\" \"$PWD\" \"$claw_command\" > {record} ;;\n\
+             \x20 esac\n\
+             \x20 return $claw_status\n\
+             }}\n\
+             PROMPT_COMMAND=\"__claw_record${{PROMPT_COMMAND:+; $PROMPT_COMMAND}}\"\n"
+        ),
+        Shell::Zsh => format!(
+            "{MARKER}: records each command for `claw fix --last`\n\
+             __claw_preexec() {{ __claw_command=$1 }}\n\
+             __claw_precmd() {{\n\
+             \x20 local claw_status=$?\n\
+             \x20 if [[ -n $__claw_command && $__claw_command != 'claw fix'* ]]; then\n\
+             \x20   printf '%s\\n%s\\n%s\\n' $claw_status $PWD $__claw_command > {record}\n\
+             \x20 fi\n\
+             \x20 __claw_command=\n\
+             }}\n\
+             autoload -Uz add-zsh-hook\n\
+             add-zsh-hook preexec __claw_preexec\n\
+             add-zsh-hook precmd __claw_precmd\n"
+        ),
+        Shell::Fish => format!(
+            "{MARKER}: records each command for `claw fix --last`\n\
+             function __claw_record --on-event fish_postexec\n\
+             \x20   set -l claw_status $status\n\
+             \x20   string match -q 'claw fix*' -- $argv[1]; and return\n\
+             \x20   printf '%s\\n%s\\n%s\\n' $claw_status $PWD $argv[1] > {record}\n\
+             end\n"
+        ),
+    }
+}
+
+/// Quotes a path for the shell if it needs it.
+fn quote(path: &Path) -> String {
+    let path = path.display().to_string();
+    shlex::try_quote(&path)
+        .map(|quoted| quoted.into_owned())
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record_keeps_multiline_commands() {
+        let last = parse_record("2\n/work\nfor f in *; do\n  cat $f\ndone\n").unwrap();
+        assert_eq!(
+            last,
+            LastCommand {
+                status: 2,
+                cwd: PathBuf::from("/work"),
+                command: "for f in *; do\n  cat $f\ndone".to_string(),
+            }
+        );
+        assert_eq!(parse_record("oops\n/work\nls\n"), None);
+        assert_eq!(parse_record("1\n/work\n\n"), None);
+    }
+
+    #[test]
+    fn test_tail_keeps_the_end_on_a_char_boundary() {
+        assert_eq!(tail("abcdef", 3), "def");
+        assert_eq!(tail("abc", 10), "abc");
+        assert_eq!(tail("aé", 1), "");
+    }
+
+    #[test]
+    fn test_bash_hook_records_failed_commands() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let record = dir.path().join("last command");
+        let hook = dir.path().join("hook.bash");
+        fs::write(&hook, hook_script(Shell::Bash, &record)).unwrap();
+
+        // An interactive shell reading the commands from stdin, so history
+        // and PROMPT_COMMAND work as they do at a prompt
+        let mut bash = Command::new("bash")
+            .args(["--norc", "-i"])
+            .env("HISTFILE", "/dev/null")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let commands = format!(
+            "source {}\nls /nonexistent-claw-dir 2>/dev/null\nclaw fix --last\nexit\n",
+            quote(&hook)
+        );
+        bash.stdin.take().unwrap().write_all(commands.as_bytes()).unwrap();
+        bash.wait().unwrap();
+
+        let last = parse_record(&fs::read_to_string(&record).unwrap()).unwrap();
+        assert_ne!(last.status, 0);
+        assert_eq!(last.command, "ls /nonexistent-claw-dir 2>/dev/null");
+    }
+}
@@ -0,0 +1,265 @@
+//! `claw hooks`: git hooks that run goals.
+//!
+//! `claw hooks install commit-msg=tidy-message pre-push=review` writes a
+//! small shell script for each hook into the repository's hooks directory.
+//! The script runs the goal with what git hands the hook: the commit message
+//! file for `commit-msg` and `prepare-commit-msg`, the remote and the range
+//! of commits being pushed for `pre-push`. A failing goal fails the hook.
+//! Scripts carry a marker line, so `uninstall` and `status` only ever touch
+//! hooks that claw wrote.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config;
+
+/// Marks a hook script as written by claw; followed by the goal name.
+const MARKER: &str = "# claw-goal: ";
+
+/// Client-side hooks git runs, in the order `git help hooks` lists them.
+const HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "post-rewrite",
+    "pre-auto-gc",
+];
+
+/// Handles `claw hooks install HOOK=GOAL...`.
+pub fn handle_install_command(assignments: &[String], force: bool) -> Result<()> {
+    let pairs = assignments
+        .iter()
+        .map(|assignment| parse_assignment(assignment))
+        .collect::<Result<Vec<_>>>()?;
+    for (_, goal) in &pairs {
+        config::find_and_load_goal(goal)?;
+    }
+
+    let dir = hooks_dir()?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create hooks directory {}", dir.display()))?;
+    for (hook, goal) in &pairs {
+        let path = dir.join(hook);
+        if let Ok(existing) = fs::read_to_string(&path)
+            && managed_goal(&existing).is_none()
+            && !force
+        {
+            bail!(
+                "{} already exists and wasn't installed by claw. Use --force to replace it.",
+                path.display()
+            );
+        }
+        fs::write(&path, hook_script(hook, goal))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        make_executable(&path)?;
+        println!("Installed {} → claw {}", hook, goal);
+    }
+    Ok(())
+}
+
+/// Handles `claw hooks uninstall [HOOK...]`: removes the named hooks, or
+/// every hook claw installed.
+pub fn handle_uninstall_command(hooks: &[String]) -> Result<()> {
+    let dir = hooks_dir()?;
+    let names: Vec<&str> = if hooks.is_empty() {
+        HOOKS.to_vec()
+    } else {
+        for hook in hooks {
+            check_hook_name(hook)?;
+        }
+        hooks.iter().map(String::as_str).collect()
+    };
+
+    let mut removed = 0;
+    for hook in names {
+        let path = dir.join(hook);
+        let Ok(script) = fs::read_to_string(&path) else {
+            if !hooks.is_empty() {
+                println!("{} isn't installed", hook);
+            }
+            continue;
+        };
+        if managed_goal(&script).is_none() {
+            println!("Leaving {}: it wasn't installed by claw", hook);
+            continue;
+        }
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        println!("Removed {}", hook);
+        removed += 1;
+    }
+    if removed == 0 && hooks.is_empty() {
+        println!("No hooks installed by claw.");
+    }
+    Ok(())
+}
+
+/// Handles `claw hooks status`: lists the hooks present and what they run.
+pub fn handle_status_command() -> Result<()> {
+    let dir = hooks_dir()?;
+    let width = HOOKS.iter().map(|hook| hook.len()).max().unwrap_or(0);
+    let mut any = false;
+    for hook in HOOKS {
+        let Ok(script) = fs::read_to_string(dir.join(hook)) else {
+            continue;
+        };
+        any = true;
+        match managed_goal(&script) {
+            Some(goal) => println!("  {:<width$}  claw {}", hook, goal, width = width),
+            None => println!("  {:<width$}  (not managed by claw)", hook, width = width),
+        }
+    }
+    if !any {
+        println!("No git hooks installed in {}.", dir.display());
+    }
+    Ok(())
+}
+
+/// Splits `commit-msg=goal` into the hook and goal names.
+fn parse_assignment(assignment: &str) -> Result<(String, String)> {
+    let Some((hook, goal)) = assignment.split_once('=') else {
+        bail!(
+            "Expected HOOK=GOAL, e.g. pre-push=review, but got '{}'",
+            assignment
+        );
+    };
+    let (hook, goal) = (hook.trim(), goal.trim());
+    check_hook_name(hook)?;
+    if goal.is_empty() {
+        bail!("No goal given for the {} hook", hook);
+    }
+    Ok((hook.to_string(), goal.to_string()))
+}
+
+fn check_hook_name(hook: &str) -> Result<()> {
+    if !HOOKS.contains(&hook) {
+        bail!(
+            "Unknown git hook '{}'. Supported hooks: {}",
+            hook,
+            HOOKS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the goal a hook script runs, if claw wrote it.
+fn managed_goal(script: &str) -> Option<String> {
+    script
+        .lines()
+        .find_map(|line| line.strip_prefix(MARKER))
+        .map(|goal| goal.trim().to_string())
+}
+
+/// The repository's hooks directory, honoring `core.hooksPath`.
+fn hooks_dir() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("Not inside a git repository");
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to make {} executable", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Writes the hook script that runs `goal`.
+fn hook_script(hook: &str, goal: &str) -> String {
+    let quoted = shlex::try_quote(goal)
+        .map(|q| q.to_string())
+        .unwrap_or_else(|_| goal.to_string());
+    let mut script = format!(
+        "#!/bin/sh\n\
+         # Installed by `claw hooks install`; remove with `claw hooks uninstall {hook}`.\n\
+         {MARKER}{goal}\n\
+         command -v claw >/dev/null 2>&1 || {{ echo \"claw not found in PATH; skipping the {hook} hook\" >&2; exit 0; }}\n"
+    );
+
+    let run = match hook {
+        "commit-msg" | "prepare-commit-msg" | "applypatch-msg" => {
+            format!("exec claw {} -- --message_file \"$1\" </dev/null\n", quoted)
+        }
+        "pre-push" => format!(
+            "\
+while read -r local_ref local_sha remote_ref remote_sha; do
+    # Skip branch deletions (an all-zero local object name)
+    case \"$local_sha\" in *[!0]*) ;; *) continue ;; esac
+    case \"$remote_sha\" in
+        *[!0]*) range=\"$remote_sha..$local_sha\" ;;
+        *) if git rev-parse -q --verify \"$1/HEAD\" >/dev/null; then
+               range=\"$1/HEAD..$local_sha\"
+           else
+               range=\"$local_sha\"
+           fi ;;
+    esac
+    claw {} -- --remote \"$1\" --push_range \"$range\" </dev/null || exit $?
+done
+",
+            quoted
+        ),
+        _ => format!("exec claw {} </dev/null\n", quoted),
+    };
+    script.push_str(&run);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("pre-push=review").unwrap(),
+            ("pre-push".to_string(), "review".to_string())
+        );
+        assert!(
+            parse_assignment("pre-pusj=review")
+                .unwrap_err()
+                .to_string()
+                .contains("Unknown git hook 'pre-pusj'")
+        );
+        assert!(parse_assignment("pre-push").is_err());
+        assert!(parse_assignment("pre-push=").is_err());
+    }
+
+    #[test]
+    fn test_hook_script_passes_hook_context() {
+        let commit_msg = hook_script("commit-msg", "tidy-message");
+        assert!(commit_msg.starts_with("#!/bin/sh\n"));
+        assert_eq!(managed_goal(&commit_msg).as_deref(), Some("tidy-message"));
+        assert!(commit_msg.contains("exec claw tidy-message -- --message_file \"$1\""));
+
+        let pre_push = hook_script("pre-push", "review");
+        assert!(pre_push.contains("--push_range \"$range\""));
+        assert!(pre_push.contains("range=\"$remote_sha..$local_sha\""));
+
+        let pre_commit = hook_script("pre-commit", "lint it");
+        assert!(pre_commit.contains("exec claw 'lint it' </dev/null"));
+
+        assert_eq!(managed_goal("#!/bin/sh\nmake test\n"), None);
+    }
+}
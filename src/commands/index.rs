@@ -0,0 +1,31 @@
+use crate::config::ClawConfig;
+use crate::repo_map;
+use anyhow::Result;
+
+/// Handles the `claw index` command.
+pub fn handle_index_command(claw_config: &ClawConfig) -> Result<()> {
+    let root = std::env::current_dir()?;
+    let index = repo_map::build(
+        &root,
+        claw_config
+            .excluded_directories
+            .as_deref()
+            .unwrap_or_default(),
+        claw_config
+            .excluded_extensions
+            .as_deref()
+            .unwrap_or_default(),
+    );
+    let path = repo_map::save(&index)?;
+
+    let symbols: usize = index.files.iter().map(|f| f.symbols.len()).sum();
+    println!(
+        "Indexed {} files and {} symbols in {}",
+        index.files.len(),
+        symbols,
+        root.display()
+    );
+    println!("Saved to {}", path.display());
+    println!("Add --repo-map when running a goal to include the map in its prompt.");
+    Ok(())
+}
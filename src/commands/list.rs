@@ -1,10 +1,37 @@
 use crate::config::{find_all_goals, ConfigPaths, DiscoveredGoal, GoalSource};
+use crate::markdown;
 use anyhow::Result;
+use std::io::IsTerminal;
+
+/// Characters of context shown on each side of a search match.
+const SEARCH_CONTEXT_CHARS: usize = 50;
+
+/// A line of a goal that contains the search term.
+#[derive(Debug, PartialEq, Eq)]
+struct SearchMatch {
+    /// Where the line is, e.g. `description` or `prompt:12`.
+    field: String,
+    text: String,
+}
 
 /// Handles the `claw list` command.
-pub fn handle_list_command(show_local_only: bool, show_global_only: bool) -> Result<()> {
+pub fn handle_list_command(
+    show_local_only: bool,
+    show_global_only: bool,
+    search: Option<&str>,
+    collection: Option<&str>,
+) -> Result<()> {
     let paths = ConfigPaths::new()?;
-    let goals = find_all_goals()?;
+    let mut goals = find_all_goals()?;
+
+    if let Some(collection) = collection {
+        goals.retain(|g| g.collection.as_deref() == Some(collection));
+        if goals.is_empty() {
+            println!("No goals found in collection '{}'.", collection);
+            println!("See the installed collections using: claw collection list");
+            return Ok(());
+        }
+    }
 
     if goals.is_empty() {
         println!("No goals found.");
@@ -12,6 +39,21 @@ pub fn handle_list_command(show_local_only: bool, show_global_only: bool) -> Res
         return Ok(());
     }
 
+    if let Some(term) = search {
+        let goals: Vec<&DiscoveredGoal> = goals
+            .iter()
+            .filter(|g| match g.source {
+                GoalSource::Local => !show_global_only,
+                GoalSource::Global | GoalSource::Managed => !show_local_only,
+            })
+            .collect();
+        print_search_results(&goals, term, std::io::stdout().is_terminal());
+        return Ok(());
+    }
+
+    // Render the markdown in descriptions only for a terminal
+    let styled = std::io::stdout().is_terminal();
+
     // Filter goals based on flags
     let local_goals: Vec<&DiscoveredGoal> = goals
         .iter()
@@ -23,6 +65,11 @@ pub fn handle_list_command(show_local_only: bool, show_global_only: bool) -> Res
         .filter(|g| g.source == GoalSource::Global)
         .collect();
 
+    let managed_goals: Vec<&DiscoveredGoal> = goals
+        .iter()
+        .filter(|g| g.source == GoalSource::Managed)
+        .collect();
+
     // Display local goals
     if !show_global_only && !local_goals.is_empty() {
         let local_path = paths
@@ -33,7 +80,7 @@ pub fn handle_list_command(show_local_only: bool, show_global_only: bool) -> Res
         println!("Local Goals ({}):", local_path);
         println!();
         for goal in &local_goals {
-            print_goal_info(goal);
+            print_goal_info(goal, styled);
         }
     }
 
@@ -50,21 +97,51 @@ pub fn handle_list_command(show_local_only: bool, show_global_only: bool) -> Res
         println!("Global Goals ({}):", global_path);
         println!();
         for goal in &global_goals {
-            print_goal_info(goal);
+            print_goal_info(goal, styled);
+        }
+    }
+
+    // Display managed goals
+    if !show_local_only && !managed_goals.is_empty() {
+        if (!show_global_only && !local_goals.is_empty()) || !global_goals.is_empty() {
+            println!(); // Separator between sections
+        }
+        let managed_path = paths
+            .managed
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        println!("Managed Goals ({}, read-only):", managed_path);
+        println!();
+        for goal in &managed_goals {
+            print_goal_info(goal, styled);
         }
     }
 
     Ok(())
 }
 
-/// Prints information about a single goal.
-fn print_goal_info(goal: &DiscoveredGoal) {
-    // CLI name - human name
-    println!("  {} - {}", goal.name, goal.config.name);
+/// Prints information about a single goal. With `styled`, the markdown in
+/// its description is rendered for a terminal.
+fn print_goal_info(goal: &DiscoveredGoal, styled: bool) {
+    // CLI name - human name, marking goals claw won't change and the
+    // collection a goal comes from
+    let mut tags = String::new();
+    if goal.config.managed {
+        tags.push_str(" [managed]");
+    }
+    if let Some(collection) = &goal.collection {
+        tags.push_str(&format!(" [{}]", collection));
+    }
+    println!("  {} - {}{}", goal.name, goal.config.name, tags);
 
     // Description (indented)
     if let Some(desc) = &goal.config.description {
-        println!("    {}", desc);
+        if styled {
+            print!("{}", markdown::render_indented(desc, 4, 76));
+        } else {
+            println!("    {}", desc);
+        }
     }
 
     // Parameter count
@@ -87,11 +164,108 @@ fn print_goal_info(goal: &DiscoveredGoal) {
     println!(); // Blank line between goals
 }
 
+/// Finds the lines of a goal's name, description, prompt and parameter
+/// names that contain `term`, ignoring case.
+fn search_goal(goal: &DiscoveredGoal, term: &str) -> Vec<SearchMatch> {
+    let term = term.to_lowercase();
+    let mut candidates = vec![
+        ("goal".to_string(), goal.name.clone()),
+        ("name".to_string(), goal.config.name.clone()),
+    ];
+    if let Some(desc) = &goal.config.description {
+        candidates.push(("description".to_string(), desc.clone()));
+    }
+    for (i, line) in goal.config.prompt.lines().enumerate() {
+        candidates.push((format!("prompt:{}", i + 1), line.to_string()));
+    }
+    for param in &goal.config.parameters {
+        candidates.push(("parameter".to_string(), param.name.clone()));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|(_, text)| text.to_lowercase().contains(&term))
+        .map(|(field, text)| SearchMatch {
+            field,
+            text: excerpt(text.trim(), &term),
+        })
+        .collect()
+}
+
+/// Shortens a long line to the text around the first match of `term`
+/// (already lowercase).
+fn excerpt(text: &str, term: &str) -> String {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte lengths, so work in characters
+    let Some(start) = lower.find(term).map(|i| lower[..i].chars().count()) else {
+        return text.to_string();
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let from = start.saturating_sub(SEARCH_CONTEXT_CHARS);
+    let to = (start + term.chars().count() + SEARCH_CONTEXT_CHARS).min(chars.len());
+    let mut excerpt: String = chars[from..to].iter().collect();
+    if from > 0 {
+        excerpt.insert_str(0, "...");
+    }
+    if to < chars.len() {
+        excerpt.push_str("...");
+    }
+    excerpt
+}
+
+/// Wraps each match of `term` in bold yellow.
+fn highlight(text: &str, term: &str) -> String {
+    let pattern = regex::RegexBuilder::new(&regex::escape(term))
+        .case_insensitive(true)
+        .build();
+    match pattern {
+        Ok(pattern) => pattern
+            .replace_all(text, "\x1b[1;33m$0\x1b[0m")
+            .to_string(),
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Prints the goals that mention `term`, each with its matching lines.
+fn print_search_results(goals: &[&DiscoveredGoal], term: &str, color: bool) {
+    let mut matched = 0;
+    for goal in goals {
+        let matches = search_goal(goal, term);
+        if matches.is_empty() {
+            continue;
+        }
+        matched += 1;
+
+        let source = match goal.source {
+            GoalSource::Local => "local",
+            GoalSource::Global => "global",
+            GoalSource::Managed => "managed",
+        };
+        println!("  {} - {} ({})", goal.name, goal.config.name, source);
+        let width = matches.iter().map(|m| m.field.len()).max().unwrap_or(0);
+        for m in &matches {
+            let text = if color {
+                highlight(&m.text, term)
+            } else {
+                m.text.clone()
+            };
+            println!("    {:<width$}  {}", m.field, text, width = width);
+        }
+        println!();
+    }
+
+    if matched == 0 {
+        println!("No goals mention '{}'.", term);
+    } else {
+        println!("{} goal(s) mention '{}'.", matched, term);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{GoalParameter, ParameterType, PromptConfig};
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
 
     fn create_test_goal_with_params(
         name: &str,
@@ -108,6 +282,10 @@ mod tests {
                 required: true,
                 param_type: Some(ParameterType::String),
                 default: None,
+                ui: None,
+                choices: None,
+                choices_script: None,
+                env: None,
             });
         }
 
@@ -118,6 +296,10 @@ mod tests {
                 required: false,
                 param_type: Some(ParameterType::String),
                 default: Some("default".to_string()),
+                ui: None,
+                choices: None,
+                choices_script: None,
+                env: None,
             });
         }
 
@@ -128,9 +310,11 @@ mod tests {
                 name: format!("{} Display Name", name),
                 description: Some(format!("{} description", name)),
                 parameters,
-                context_scripts: HashMap::new(),
+                context_scripts: IndexMap::new(),
                 prompt: "test".to_string(),
+                ..Default::default()
             },
+            collection: None,
         }
     }
 
@@ -138,13 +322,53 @@ mod tests {
     fn test_print_goal_info_no_params() {
         let goal = create_test_goal_with_params("test", GoalSource::Local, 0, 0);
         // Just ensure it doesn't panic
-        print_goal_info(&goal);
+        print_goal_info(&goal, false);
     }
 
     #[test]
     fn test_print_goal_info_with_params() {
         let goal = create_test_goal_with_params("test", GoalSource::Local, 2, 1);
         // Just ensure it doesn't panic
-        print_goal_info(&goal);
+        print_goal_info(&goal, false);
+    }
+
+    #[test]
+    fn test_search_goal_matches_fields_ignoring_case() {
+        let mut goal = create_test_goal_with_params("review", GoalSource::Local, 1, 0);
+        goal.config.prompt = "You are a reviewer.\nSummarize the Release notes.\nBe brief.".to_string();
+        goal.config.parameters[0].name = "release_tag".to_string();
+
+        let matches = search_goal(&goal, "RELEASE");
+        assert_eq!(
+            matches,
+            vec![
+                SearchMatch {
+                    field: "prompt:2".to_string(),
+                    text: "Summarize the Release notes.".to_string()
+                },
+                SearchMatch {
+                    field: "parameter".to_string(),
+                    text: "release_tag".to_string()
+                },
+            ]
+        );
+        assert_eq!(search_goal(&goal, "display name")[0].field, "name");
+        assert!(search_goal(&goal, "deploy").is_empty());
+    }
+
+    #[test]
+    fn test_excerpt_and_highlight() {
+        let long = format!("{}needle{}", "a".repeat(80), "b".repeat(80));
+        let short = excerpt(&long, "needle");
+        assert_eq!(
+            short,
+            format!("...{}needle{}...", "a".repeat(50), "b".repeat(50))
+        );
+        assert_eq!(excerpt("short Needle", "needle"), "short Needle");
+
+        assert_eq!(
+            highlight("a Needle and a needle", "needle"),
+            "a \x1b[1;33mNeedle\x1b[0m and a \x1b[1;33mneedle\x1b[0m"
+        );
     }
 }
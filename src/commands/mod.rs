@@ -1,3 +1,20 @@
 pub mod add;
+pub mod alias;
+pub mod bundle;
+pub mod collection;
+pub mod completions;
+pub mod diff_goal;
+pub mod docs;
+pub mod fix;
 pub mod dry_run;
+pub mod hooks;
+pub mod index;
 pub mod list;
+pub mod ping;
+pub mod prompt_lint;
+pub mod schema;
+pub mod session;
+pub mod stats;
+pub mod test;
+pub mod trust;
+pub mod upgrade_goals;
@@ -0,0 +1,79 @@
+//! `claw ping`: checks that the configured receiver works, by sending it a
+//! trivial prompt instead of a real goal.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::time::Instant;
+
+use crate::config::{self, ClawConfig, ReceiverType};
+use crate::runner::{self, ReceiverOptions};
+use crate::trust;
+
+/// The prompt sent by `claw ping`.
+const PING_PROMPT: &str =
+    "This is a connectivity check from claw. Reply with the single word: pong";
+
+/// Handles `claw ping`: sends the ping prompt through the receiver and
+/// reports how long the reply took.
+pub fn handle_ping_command(claw_config: &ClawConfig) -> Result<()> {
+    config::find_and_load_policy()?.check_receiver(claw_config)?;
+    trust::ensure_local_config_trusted()?;
+
+    let receiver_type = claw_config
+        .receiver_type
+        .clone()
+        .unwrap_or(ReceiverType::Generic);
+    let target = match receiver_type {
+        ReceiverType::Generic => claw_config
+            .llm_command
+            .clone()
+            .context("No llm_command is configured. Set it in claw.yaml or pass --llm.")?,
+        ReceiverType::ClaudeCli => "claude".to_string(),
+        ReceiverType::Tmux => {
+            let pane = claw_config
+                .tmux_target
+                .clone()
+                .context("No tmux_target is configured for the Tmux receiver.")?;
+            format!("tmux pane {}", pane)
+        }
+    };
+
+    // Capture the reply in a scratch file, so it isn't mixed into the report
+    let reply_path = std::env::temp_dir().join(format!("claw-ping-{}.txt", std::process::id()));
+    let options = ReceiverOptions {
+        tee: Some(reply_path.clone()),
+        background: true,
+        ..Default::default()
+    };
+
+    println!("Pinging {}...", target);
+    let started = Instant::now();
+    let result = runner::create_receiver(claw_config, &options).send_prompt(PING_PROMPT);
+    let elapsed = started.elapsed().as_secs_f64();
+    let reply = fs::read_to_string(&reply_path).unwrap_or_default();
+    let _ = fs::remove_file(&reply_path);
+    result.with_context(|| format!("{} failed to answer the ping", target))?;
+
+    if receiver_type == ReceiverType::Tmux {
+        println!(
+            "✓ Sent to {} in {:.1}s; look there for the reply.",
+            target, elapsed
+        );
+    } else if reply.to_lowercase().contains("pong") {
+        println!("✓ pong from {} in {:.1}s", target, elapsed);
+    } else if reply.trim().is_empty() {
+        bail!(
+            "{} exited after {:.1}s without printing a reply. Check that prompt_arg_template \
+             makes it answer non-interactively.",
+            target,
+            elapsed
+        );
+    } else {
+        let reply: String = reply.trim().chars().take(200).collect();
+        println!(
+            "⚠️  {} answered in {:.1}s, but not with \"pong\":\n{}",
+            target, elapsed, reply
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::config::{self, ClawConfig};
+use crate::lint::{self, LintFinding, Severity};
+use anyhow::Result;
+
+/// Handles the `claw prompt-lint` command.
+///
+/// Lints a single goal when `goal_name` is given, otherwise every goal
+/// discovered locally and globally. Fails if any finding has error severity.
+pub fn handle_prompt_lint_command(goal_name: Option<&str>, claw_config: &ClawConfig) -> Result<()> {
+    let lint_config = claw_config.prompt_lint.clone().unwrap_or_default();
+
+    let goals = match goal_name {
+        Some(name) => {
+            let goal = config::find_and_load_goal(name)?;
+            vec![(name.to_string(), goal.config)]
+        }
+        None => config::find_all_goals()?
+            .into_iter()
+            .map(|g| (format!("{} ({})", g.name, g.source), g.config))
+            .collect(),
+    };
+
+    if goals.is_empty() {
+        println!("No goals found.");
+        return Ok(());
+    }
+
+    let mut error_count = 0;
+    let mut total = 0;
+    for (label, goal) in &goals {
+        let findings = lint::lint_prompt(goal, &lint_config);
+        print_goal_findings(label, &findings);
+        error_count += findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count();
+        total += findings.len();
+    }
+
+    println!(
+        "Linted {} goal(s): {} finding(s), {} error(s).",
+        goals.len(),
+        total,
+        error_count
+    );
+
+    if error_count > 0 {
+        anyhow::bail!("Prompt lint failed with {} error(s)", error_count);
+    }
+
+    Ok(())
+}
+
+/// Prints the findings for a single goal.
+fn print_goal_findings(label: &str, findings: &[LintFinding]) {
+    if findings.is_empty() {
+        println!("{}: ok", label);
+    } else {
+        println!("{}:", label);
+        for finding in findings {
+            println!("  {}", finding);
+        }
+    }
+    println!();
+}
@@ -0,0 +1,16 @@
+use crate::cli::SchemaKind;
+use crate::config::{ClawConfig, PromptConfig};
+use anyhow::Result;
+
+/// Handles the `claw schema` command.
+///
+/// Prints the JSON Schema for `claw.yaml` or `prompt.yaml`, generated from
+/// the types claw parses them into, for use with YAML language servers.
+pub fn handle_schema_command(kind: SchemaKind) -> Result<()> {
+    let schema = match kind {
+        SchemaKind::Claw => schemars::schema_for!(ClawConfig),
+        SchemaKind::Prompt => schemars::schema_for!(PromptConfig),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
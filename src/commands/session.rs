@@ -0,0 +1,174 @@
+//! `claw session`: a conversation that starts from a goal's prompt and goes
+//! on with what the user types after each response.
+//!
+//! Most LLM CLIs answer one prompt and exit, so each follow-up is sent with
+//! the whole conversation so far. CLIs that keep the conversation
+//! themselves can be given `session_prompt_arg_template` (e.g. claude's
+//! `--continue`), and then get only the new message, as does a tmux pane.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+use crate::config::{ClawConfig, ReceiverType};
+use crate::runner;
+
+/// Typed instead of a follow-up to end the session, as is Ctrl-D.
+const EXIT_COMMANDS: &[&str] = &["/exit", "/quit"];
+
+/// One exchange after the goal's prompt.
+#[derive(Debug, Clone, PartialEq)]
+struct Turn {
+    /// `None` when the receiver keeps the response to itself (tmux).
+    response: Option<String>,
+    /// The follow-up typed after the response, if there was one.
+    message: Option<String>,
+}
+
+/// Runs a session that starts by sending `first_prompt`, the goal's rendered
+/// prompt, to the receiver in `claw_config`. Each turn's prompt is passed to
+/// `send` with the config to send it with and the file to capture the
+/// response in, if it can be. With `save`, the conversation is written there
+/// as markdown when the session ends. A Ctrl-C ends the session too.
+pub fn handle_session_command(
+    claw_config: &ClawConfig,
+    first_prompt: &str,
+    save: Option<&Path>,
+    mut send: impl FnMut(&ClawConfig, &str, Option<&Path>) -> Result<()>,
+) -> Result<()> {
+    if claw_config.fan_out.is_some() {
+        anyhow::bail!("claw session talks to one receiver; it can't be used with fan_out");
+    }
+    let tmux = claw_config.receiver_type == Some(ReceiverType::Tmux);
+    let sends_only_new_messages = tmux || claw_config.session_prompt_arg_template.is_some();
+
+    // The response is captured to keep it in the conversation, except in a
+    // tmux pane, where it stays
+    let capture = if tmux {
+        None
+    } else {
+        Some(NamedTempFile::new().context("Failed to create a file to capture responses in")?)
+    };
+    let capture_path = capture.as_ref().map(|file| file.path());
+
+    eprintln!(
+        "Starting a session with {}. After each response, type a follow-up, \
+         or /exit (Ctrl-D) to end.",
+        claw_config.receiver_label()
+    );
+    let mut config = claw_config.clone();
+    let mut turns = Vec::new();
+    let mut prompt = first_prompt.to_string();
+    let stdin = io::stdin();
+    let result = loop {
+        let sent = send(&config, &prompt, capture_path);
+        if runner::was_interrupted() {
+            break Ok(());
+        }
+        if let Err(e) = sent {
+            break Err(e);
+        }
+        let response = capture_path.map(|path| fs::read_to_string(path).unwrap_or_default());
+        let message = match read_follow_up(&mut stdin.lock()) {
+            Ok(message) => message,
+            Err(e) => break Err(e),
+        };
+        turns.push(Turn {
+            response,
+            message: message.clone(),
+        });
+        let Some(message) = message else {
+            break Ok(());
+        };
+
+        prompt = if sends_only_new_messages {
+            message
+        } else {
+            format_conversation(first_prompt, &turns)
+        };
+        if let Some(template) = &claw_config.session_prompt_arg_template {
+            config.prompt_arg_template = template.clone();
+        }
+    };
+
+    if let Some(path) = save {
+        fs::write(path, format_conversation(first_prompt, &turns))
+            .with_context(|| format!("Failed to save the session to {}", path.display()))?;
+        eprintln!("Session saved to {}", path.display());
+    }
+    result
+}
+
+/// Asks for the next message, until one that isn't blank is typed. `None`
+/// ends the session.
+fn read_follow_up(input: &mut impl BufRead) -> Result<Option<String>> {
+    loop {
+        eprint!("\nyou> ");
+        io::stderr().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line).context("Failed to read the follow-up")? == 0 {
+            eprintln!();
+            return Ok(None);
+        }
+        let message = line.trim();
+        if EXIT_COMMANDS.contains(&message) {
+            return Ok(None);
+        }
+        if !message.is_empty() {
+            return Ok(Some(message.to_string()));
+        }
+    }
+}
+
+/// The conversation so far as one prompt: the goal's prompt, then each
+/// response and follow-up under a heading.
+fn format_conversation(first_prompt: &str, turns: &[Turn]) -> String {
+    let mut conversation = first_prompt.trim_end().to_string();
+    for turn in turns {
+        if let Some(response) = &turn.response {
+            conversation.push_str(&format!("\n\n## Assistant\n\n{}", response.trim()));
+        }
+        if let Some(message) = &turn.message {
+            conversation.push_str(&format!("\n\n## User\n\n{}", message));
+        }
+    }
+    conversation.push('\n');
+    conversation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_conversation_alternates_turns() {
+        let turns = vec![
+            Turn {
+                response: Some("First answer\n".to_string()),
+                message: Some("Why?".to_string()),
+            },
+            Turn {
+                response: Some("Because.".to_string()),
+                message: None,
+            },
+        ];
+        assert_eq!(
+            format_conversation("Review this\n", &turns),
+            "Review this\n\n## Assistant\n\nFirst answer\n\n## User\n\nWhy?\n\n\
+             ## Assistant\n\nBecause.\n"
+        );
+    }
+
+    #[test]
+    fn test_read_follow_up_skips_blank_lines_and_stops_on_exit() {
+        let mut input = io::Cursor::new("\n  \nmore please\n/exit\n");
+        assert_eq!(
+            read_follow_up(&mut input).unwrap().as_deref(),
+            Some("more please")
+        );
+        assert_eq!(read_follow_up(&mut input).unwrap(), None);
+        assert_eq!(read_follow_up(&mut io::Cursor::new("")).unwrap(), None);
+    }
+}
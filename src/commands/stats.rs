@@ -0,0 +1,217 @@
+use crate::config;
+use crate::history::{self, ArgSource, HistoryEntry};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// How often one parameter was given across a goal's recorded runs.
+#[derive(Debug, PartialEq, Eq)]
+struct ParameterUsage {
+    name: String,
+    supplied: usize,
+    defaulted: usize,
+    /// Runs where the parameter had no value at all.
+    omitted: usize,
+}
+
+/// Handles the `claw stats` command.
+pub fn handle_stats_command(goal: Option<&str>) -> Result<()> {
+    let entries = history::load_all()?;
+    match goal {
+        Some(goal) => print_goal_stats(goal, &entries),
+        None => print_run_counts(&entries),
+    }
+    Ok(())
+}
+
+/// Prints the number of recorded runs per goal, most used first.
+fn print_run_counts(entries: &[HistoryEntry]) {
+    if entries.is_empty() {
+        println!("No recorded runs.");
+        return;
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(entry.goal.as_str()).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let width = counts.iter().map(|(goal, _)| goal.len()).max().unwrap_or(0);
+    println!("Recorded runs:");
+    for (goal, count) in counts {
+        println!("  {:<width$}  {}", goal, count, width = width);
+    }
+    println!();
+    println!("Use `claw stats --goal <name>` to see how its parameters are used.");
+}
+
+/// Prints how often each of a goal's parameters was supplied or defaulted.
+fn print_goal_stats(goal: &str, entries: &[HistoryEntry]) {
+    let runs: Vec<&HistoryEntry> = entries.iter().filter(|e| e.goal == goal).collect();
+    if runs.is_empty() {
+        println!("No recorded runs for goal '{}'.", goal);
+        return;
+    }
+
+    // Parameters the goal defines today, so unused ones show up too
+    let defined: Vec<String> = config::find_and_load_goal(goal)
+        .map(|g| g.config.parameters.into_iter().map(|p| p.name).collect())
+        .unwrap_or_default();
+
+    let tracked: Vec<&HistoryEntry> = runs
+        .iter()
+        .copied()
+        .filter(|e| e.arg_sources.is_some())
+        .collect();
+    println!(
+        "Goal '{}': {} recorded run(s), {} with parameter tracking",
+        goal,
+        runs.len(),
+        tracked.len()
+    );
+    if tracked.is_empty() {
+        return;
+    }
+
+    let usage = parameter_usage(&tracked, &defined);
+    if usage.is_empty() {
+        println!("No parameters were used.");
+        return;
+    }
+
+    let width = usage
+        .iter()
+        .map(|u| u.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Parameter".len());
+    println!();
+    println!(
+        "  {:<width$}  Supplied  Defaulted  Omitted",
+        "Parameter",
+        width = width
+    );
+    for u in &usage {
+        println!(
+            "  {:<width$}  {:>8}  {:>9}  {:>7}",
+            u.name,
+            u.supplied,
+            u.defaulted,
+            u.omitted,
+            width = width
+        );
+    }
+
+    let never: Vec<&str> = usage
+        .iter()
+        .filter(|u| u.supplied == 0)
+        .map(|u| u.name.as_str())
+        .collect();
+    if !never.is_empty() {
+        println!();
+        println!(
+            "Never supplied: {}. Consider removing them or folding them into the prompt.",
+            never.join(", ")
+        );
+    }
+}
+
+/// Counts, per parameter, the runs that supplied, defaulted or omitted it.
+/// `defined` parameters are listed first, in order, followed by any other
+/// names found in the runs.
+fn parameter_usage(runs: &[&HistoryEntry], defined: &[String]) -> Vec<ParameterUsage> {
+    let mut names: Vec<String> = defined.to_vec();
+    for run in runs {
+        for name in run.arg_sources.iter().flat_map(|sources| sources.keys()) {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let count = |wanted: ArgSource| {
+                runs.iter()
+                    .filter(|run| {
+                        run.arg_sources
+                            .as_ref()
+                            .and_then(|sources| sources.get(&name))
+                            == Some(&wanted)
+                    })
+                    .count()
+            };
+            let supplied = count(ArgSource::Supplied);
+            let defaulted = count(ArgSource::Defaulted);
+            ParameterUsage {
+                omitted: runs.len() - supplied - defaulted,
+                name,
+                supplied,
+                defaulted,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::RunStatus;
+    use std::collections::HashMap;
+
+    fn run(sources: &[(&str, ArgSource)]) -> HistoryEntry {
+        HistoryEntry {
+            goal: "review".to_string(),
+            args: HashMap::new(),
+            arg_sources: Some(
+                sources
+                    .iter()
+                    .map(|(name, source)| (name.to_string(), *source))
+                    .collect(),
+            ),
+            started_at: 0,
+            finished_at: 0,
+            status: RunStatus::Completed,
+            response: None,
+            prompt: None,
+            goal_definition: None,
+            context_files: Vec::new(),
+            citations: Vec::new(),
+            claw_version: None,
+        }
+    }
+
+    #[test]
+    fn test_parameter_usage_counts_sources() {
+        let first = run(&[
+            ("scope", ArgSource::Supplied),
+            ("format", ArgSource::Defaulted),
+        ]);
+        let second = run(&[
+            ("scope", ArgSource::Supplied),
+            ("extra", ArgSource::Supplied),
+        ]);
+        let defined = vec![
+            "scope".to_string(),
+            "format".to_string(),
+            "tone".to_string(),
+        ];
+
+        let usage = parameter_usage(&[&first, &second], &defined);
+        let summary: Vec<(&str, usize, usize, usize)> = usage
+            .iter()
+            .map(|u| (u.name.as_str(), u.supplied, u.defaulted, u.omitted))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("scope", 2, 0, 0),
+                ("format", 0, 1, 1),
+                ("tone", 0, 0, 2),
+                ("extra", 1, 0, 1),
+            ]
+        );
+    }
+}
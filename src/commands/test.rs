@@ -0,0 +1,299 @@
+//! `claw test`: snapshot tests for goal prompts.
+//!
+//! Each case in a goal's `tests.yaml` gives parameter values and fixed
+//! context script outputs:
+//!
+//! ```yaml
+//! cases:
+//!   - name: api
+//!     args: { scope: api }
+//!     context: { staged_diff: "diff --git a/src/api.rs b/src/api.rs ..." }
+//! ```
+//!
+//! The rendered prompt of each case is compared with
+//! `__snapshots__/<case>.md` in the goal directory. `--update-snapshots`
+//! rewrites the snapshots instead, so prompt changes show up in code review
+//! as snapshot diffs. Scripts and providers never run; scripts without a
+//! fixed output render as `<output of NAME>`.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::commands::dry_run;
+use crate::config::{self, ClawConfig, LoadedGoal};
+use crate::matrix;
+use crate::response;
+
+/// The contents of a goal's `tests.yaml`.
+#[derive(Debug, Deserialize)]
+struct TestCases {
+    cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestCase {
+    /// Names the snapshot file, `__snapshots__/<name>.md`.
+    name: String,
+    /// Parameter values, as with `-- --name value`.
+    #[serde(default)]
+    args: BTreeMap<String, Value>,
+    /// Output of context scripts and built-ins, by name.
+    #[serde(default)]
+    context: HashMap<String, String>,
+}
+
+impl TestCase {
+    /// The case used for goals without a `tests.yaml`: defaults only.
+    fn default_case() -> TestCase {
+        TestCase {
+            name: "default".to_string(),
+            args: BTreeMap::new(),
+            context: HashMap::new(),
+        }
+    }
+}
+
+/// How a case's rendered prompt compares with its snapshot.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Matches,
+    Missing,
+    /// The first line that differs (1-based), with the snapshot's and the
+    /// rendered prompt's version of it.
+    Differs {
+        line: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+}
+
+/// Handles `claw test [GOAL] [--update-snapshots]`.
+pub fn handle_test_command(
+    goal_name: Option<&str>,
+    update: bool,
+    claw_config: &ClawConfig,
+) -> Result<()> {
+    let goal_names = match goal_name {
+        Some(name) => vec![name.to_string()],
+        None => tested_goals()?,
+    };
+    if goal_names.is_empty() {
+        println!(
+            "No goals have snapshot tests. Add {} or run `claw test <goal> --update-snapshots`.",
+            config::TESTS_FILE
+        );
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    let mut total = 0;
+    for goal_name in &goal_names {
+        let goal = config::find_and_load_goal(goal_name)?;
+        if update && goal.config.managed {
+            bail!(
+                "Goal '{}' is managed centrally; its snapshots can't be updated.",
+                goal_name
+            );
+        }
+        for case in load_cases(&goal)? {
+            total += 1;
+            let label = format!("{}/{}", goal_name, case.name);
+            let rendered = match render_case(goal_name, &goal, &case, claw_config) {
+                Ok(rendered) => rendered,
+                Err(e) => {
+                    failures += 1;
+                    println!("✗ {}: {:#}", label, e);
+                    continue;
+                }
+            };
+
+            let path = goal
+                .directory
+                .join(config::SNAPSHOTS_DIR)
+                .join(format!("{}.md", case.name));
+            let snapshot = fs::read_to_string(&path).ok();
+            let outcome = compare(snapshot.as_deref(), &rendered);
+
+            if update {
+                if outcome != Outcome::Matches {
+                    write_snapshot(&path, &rendered)?;
+                    println!("✎ {}: wrote {}", label, path.display());
+                } else {
+                    println!("✓ {}", label);
+                }
+                continue;
+            }
+            match outcome {
+                Outcome::Matches => println!("✓ {}", label),
+                Outcome::Missing => {
+                    failures += 1;
+                    println!("✗ {}: no snapshot at {}", label, path.display());
+                }
+                Outcome::Differs {
+                    line,
+                    expected,
+                    actual,
+                } => {
+                    failures += 1;
+                    println!("✗ {}: the prompt differs from {}", label, path.display());
+                    println!("    line {}:", line);
+                    println!("    - {}", expected.as_deref().unwrap_or("(end of snapshot)"));
+                    println!("    + {}", actual.as_deref().unwrap_or("(end of prompt)"));
+                }
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!(
+            "{} of {} snapshot test(s) failed. If the changes are intended, run `claw test{} --update-snapshots` and review the snapshot diffs.",
+            failures,
+            total,
+            goal_name.map(|name| format!(" {}", name)).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Names of the goals that have a `tests.yaml` or snapshots.
+fn tested_goals() -> Result<Vec<String>> {
+    let mut names: Vec<String> = config::find_all_goals()?
+        .into_iter()
+        .map(|goal| goal.name)
+        .collect();
+    names.dedup();
+
+    let mut tested = Vec::new();
+    for name in names {
+        let goal = config::find_and_load_goal(&name)?;
+        if goal.directory.join(config::TESTS_FILE).exists()
+            || goal.directory.join(config::SNAPSHOTS_DIR).is_dir()
+        {
+            tested.push(name);
+        }
+    }
+    Ok(tested)
+}
+
+/// Loads the goal's cases, or the default case if it has no `tests.yaml`.
+fn load_cases(goal: &LoadedGoal) -> Result<Vec<TestCase>> {
+    let path = goal.directory.join(config::TESTS_FILE);
+    if !path.exists() {
+        return Ok(vec![TestCase::default_case()]);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let tests: TestCases = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut seen = Vec::new();
+    for case in &tests.cases {
+        if case.name.is_empty() || case.name.contains(['/', '\\']) {
+            bail!(
+                "Invalid case name '{}' in {}: it names the snapshot file",
+                case.name,
+                path.display()
+            );
+        }
+        if seen.contains(&case.name) {
+            bail!("Duplicate case '{}' in {}", case.name, path.display());
+        }
+        seen.push(case.name.clone());
+    }
+    Ok(tests.cases)
+}
+
+/// Renders the prompt for one case, including the output instructions
+/// claw appends for goals with `outputs`.
+fn render_case(
+    goal_name: &str,
+    goal: &LoadedGoal,
+    case: &TestCase,
+    claw_config: &ClawConfig,
+) -> Result<String> {
+    let args = case
+        .args
+        .iter()
+        .map(|(name, value)| {
+            let value = matrix::scalar_to_string(value).with_context(|| {
+                format!("Parameter '{}' must be a string, number or boolean", name)
+            })?;
+            Ok((name.clone(), value))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    let mut rendered = dry_run::render_offline(goal_name, goal, &args, claw_config, |name| {
+        case.context
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| format!("<output of {}>", name))
+    })?;
+    if !goal.config.outputs.is_empty() {
+        rendered.push_str("\n\n");
+        rendered.push_str(&response::output_instructions(&goal.config.outputs));
+    }
+    Ok(rendered)
+}
+
+fn compare(snapshot: Option<&str>, rendered: &str) -> Outcome {
+    let Some(snapshot) = snapshot else {
+        return Outcome::Missing;
+    };
+    if snapshot == rendered {
+        return Outcome::Matches;
+    }
+
+    let mut expected = snapshot.split('\n');
+    let mut actual = rendered.split('\n');
+    let mut line = 1;
+    loop {
+        let (e, a) = (expected.next(), actual.next());
+        if e != a {
+            return Outcome::Differs {
+                line,
+                expected: e.map(str::to_string),
+                actual: a.map(str::to_string),
+            };
+        }
+        line += 1;
+    }
+}
+
+fn write_snapshot(path: &Path, rendered: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(path, rendered).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_reports_first_differing_line() {
+        assert_eq!(compare(None, "a"), Outcome::Missing);
+        assert_eq!(compare(Some("a\nb"), "a\nb"), Outcome::Matches);
+        assert_eq!(
+            compare(Some("a\nb\nc"), "a\nB\nc"),
+            Outcome::Differs {
+                line: 2,
+                expected: Some("b".to_string()),
+                actual: Some("B".to_string()),
+            }
+        );
+        assert_eq!(
+            compare(Some("a"), "a\nmore"),
+            Outcome::Differs {
+                line: 2,
+                expected: None,
+                actual: Some("more".to_string()),
+            }
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use crate::trust::{self, Decision};
+use anyhow::Result;
+
+/// Handles the `claw trust` command.
+pub fn handle_trust_command(revoke: bool) -> Result<()> {
+    if revoke {
+        let dir = trust::set_local_decision(Decision::Denied)?;
+        println!("claw will no longer run commands from {}", dir.display());
+    } else {
+        let dir = trust::set_local_decision(Decision::Trusted)?;
+        println!("Trusted {}", dir.display());
+        println!("claw will ask again if anything in it changes.");
+    }
+    Ok(())
+}
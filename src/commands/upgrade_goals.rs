@@ -0,0 +1,114 @@
+use anyhow::{Context as AnyhowContext, Result};
+use std::io::{self, Write};
+use std::process::Command;
+
+use crate::bundled_goals::{self, BundledFile, Status};
+use crate::config::{self, ConfigPaths};
+use crate::param_prompt;
+
+/// Handles the `claw upgrade-goals` command: offers this release's version
+/// of each bundled goal file the user hasn't changed since it was installed.
+pub fn handle_upgrade_goals_command(yes: bool) -> Result<()> {
+    let config_dir = ConfigPaths::new()?
+        .global
+        .context("No global config directory found. Run `claw list` to create it.")?;
+    let assets_dir = config::find_assets_dir()?;
+    let files = bundled_goals::compare(&assets_dir, &config_dir)?;
+
+    for file in &files {
+        match file.status {
+            Status::Customized => println!(
+                "Kept {} (changed since it was installed). This release's version: {}",
+                file.relative,
+                file.bundled.display()
+            ),
+            Status::Removed => println!("Skipped {} (deleted since it was installed)", file.relative),
+            Status::Current | Status::New | Status::Outdated => {}
+        }
+    }
+
+    let upgradable: Vec<&BundledFile> = files
+        .iter()
+        .filter(|file| matches!(file.status, Status::New | Status::Outdated))
+        .collect();
+    if upgradable.is_empty() {
+        println!("Bundled goals are up to date.");
+        return Ok(());
+    }
+
+    let mut upgraded = 0;
+    for file in upgradable {
+        let answer = if yes {
+            Some(true)
+        } else {
+            if file.status == Status::Outdated {
+                show_diff(file);
+            }
+            confirm(file)?
+        };
+        match answer {
+            Some(true) => {
+                bundled_goals::install(&config_dir, file)?;
+                upgraded += 1;
+                println!(
+                    "✓ {} {}",
+                    if file.status == Status::New { "Added" } else { "Updated" },
+                    file.relative
+                );
+            }
+            Some(false) => println!("Left {} as it is", file.relative),
+            None => {
+                println!(
+                    "{} has {}: run `claw upgrade-goals --yes` to apply it",
+                    file.relative,
+                    if file.status == Status::New { "been added" } else { "an update" }
+                );
+            }
+        }
+    }
+    if upgraded > 0 {
+        println!("{} bundled goal file(s) upgraded in {}", upgraded, config_dir.display());
+    }
+    Ok(())
+}
+
+/// Prints how this release changes an installed file, if git is available.
+fn show_diff(file: &BundledFile) {
+    let Ok(git) = which::which("git") else {
+        println!(
+            "\n{} has an update: {}",
+            file.relative,
+            file.bundled.display()
+        );
+        return;
+    };
+    println!("\n{} has an update:", file.relative);
+    let _ = io::stdout().flush();
+    // `git diff --no-index` exits with 1 when the files differ
+    let _ = Command::new(git)
+        .args(["diff", "--no-index", "--no-color", "--"])
+        .arg(&file.installed)
+        .arg(&file.bundled)
+        .status();
+}
+
+/// Asks on the terminal whether to install a bundled file.
+fn confirm(file: &BundledFile) -> Result<Option<bool>> {
+    if !param_prompt::is_interactive() {
+        return Ok(None);
+    }
+
+    if file.status == Status::New {
+        eprint!("Add {}? [y/N] ", file.relative);
+    } else {
+        eprint!("Update {}? [y/N] ", file.relative);
+    }
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(Some(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes"
+    )))
+}
@@ -1,12 +1,26 @@
 use anyhow::Context as AnyhowContext;
 use anyhow::Result;
 use directories::BaseDirs;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::bundled_goals;
+use crate::collections;
+use crate::lint::LintConfig;
+use crate::matrix;
+use crate::policy::Policy;
+use crate::prompt_core::GoalFiles;
+use crate::response::ResponseAssertions;
+use crate::theme::TuiTheme;
+use crate::tokens::Tokenizer;
+use crate::version;
 
 /// Helper functions for constructing standard configuration paths.
 mod paths {
@@ -17,12 +31,20 @@ mod paths {
     }
 
     pub fn goal_prompt(base_dir: &Path, goal_name: &str) -> PathBuf {
-        goal_dir(base_dir, goal_name).join("prompt.yaml")
+        goal_dir(base_dir, goal_name).join(super::PROMPT_FILE)
+    }
+
+    pub fn goal_markdown(base_dir: &Path, goal_name: &str) -> PathBuf {
+        goal_dir(base_dir, goal_name).join(super::PROMPT_MARKDOWN_FILE)
     }
 
     pub fn claw_config(base_dir: &Path) -> PathBuf {
         base_dir.join("claw.yaml")
     }
+
+    pub fn policy(base_dir: &Path) -> PathBuf {
+        base_dir.join("policy.yaml")
+    }
 }
 
 /// Generic function to load and parse a YAML config file.
@@ -55,6 +77,7 @@ where
 /// 3. Default value (if provided)
 ///
 /// The `loader_fn` is called with the base directory to attempt loading the config.
+#[allow(clippy::collapsible_if)]
 fn cascade_load_config<T, F>(paths: &ConfigPaths, loader_fn: F, default: Option<T>) -> Result<T>
 where
     F: Fn(&Path) -> Result<Option<T>>,
@@ -78,7 +101,7 @@ where
 }
 
 /// Defines how errors during context processing should be handled.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ErrorHandlingMode {
     /// Fail immediately on any error.
@@ -89,11 +112,85 @@ pub enum ErrorHandlingMode {
     Ignore,
 }
 
+impl ErrorHandlingMode {
+    /// The per-error policy this mode applies to every class of error.
+    pub fn policy(&self) -> ErrorPolicy {
+        match self {
+            ErrorHandlingMode::Strict => ErrorPolicy::Fail,
+            ErrorHandlingMode::Flexible => ErrorPolicy::Ask,
+            ErrorHandlingMode::Ignore => ErrorPolicy::Skip,
+        }
+    }
+}
+
+/// What to do with a single class of context processing error.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorPolicy {
+    /// Stop with an error.
+    Fail,
+    /// List the problem and ask whether to continue without the file.
+    Ask,
+    /// Leave the file out and log a warning.
+    Skip,
+}
+
+/// Per-error-class overrides of `error_handling_mode`.
+/// Classes that are not set follow the mode.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, PartialEq)]
+pub struct ContextErrorPolicies {
+    #[serde(default)]
+    pub on_file_too_large: Option<ErrorPolicy>,
+
+    #[serde(default)]
+    pub on_too_many_files: Option<ErrorPolicy>,
+
+    #[serde(default)]
+    pub on_permission_denied: Option<ErrorPolicy>,
+
+    /// Binary files are skipped unless this says otherwise.
+    #[serde(default)]
+    pub on_binary: Option<ErrorPolicy>,
+
+    #[serde(default)]
+    pub on_invalid_utf8: Option<ErrorPolicy>,
+
+    #[serde(default)]
+    pub on_io_error: Option<ErrorPolicy>,
+}
+
+/// What to do when a prompt passed with `{{prompt}}` is too long for the
+/// command line.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgOverflow {
+    /// Send the prompt on stdin instead, with a notice.
+    #[default]
+    Stdin,
+    /// Stop with an error.
+    Fail,
+}
+
+/// A layer around sending the prompt to a receiver; see `middlewares`.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiverMiddleware {
+    /// Note on stderr when the prompt is sent and how the receiver fared.
+    Log,
+    /// Mask anything that looks like a secret in the prompt first.
+    Redact,
+    /// Send the prompt again when the LLM command fails, per `retries`.
+    Retry,
+    /// Report the prompt's size in tokens, and its cost with
+    /// `prompt_token_price`.
+    Meter,
+}
+
 /// Defines the type of receiver used to send prompts to the LLM.
 ///
 /// Receivers abstract the delivery mechanism for prompts, allowing
 /// different strategies for passing prompts to various LLM tools.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum ReceiverType {
     /// Generic receiver that uses the configured `llm_command`.
     /// Supports both stdin and argument-based prompt passing.
@@ -101,15 +198,20 @@ pub enum ReceiverType {
     /// Convenience receiver that hardcodes "claude" as the command.
     /// Ignores the `llm_command` config field.
     ClaudeCli,
+    /// Pastes the prompt into an existing tmux pane (`tmux_target`) where an
+    /// interactive LLM CLI is already running.
+    Tmux,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for ReceiverType {
     fn default() -> Self {
         ReceiverType::Generic
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Settings from `claw.yaml`.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct ClawConfig {
     /// The executable name of the LLM command-line tool.
     /// Optional - only required when using Generic receiver type.
@@ -122,11 +224,89 @@ pub struct ClawConfig {
     #[serde(default = "default_prompt_arg_template")]
     pub prompt_arg_template: String,
 
+    /// What to do when a prompt passed with `{{prompt}}` is longer than the
+    /// OS allows for a command line: `stdin` (default) sends it on stdin
+    /// instead, with a notice; `fail` stops with an error.
+    #[serde(default)]
+    pub prompt_arg_overflow: Option<ArgOverflow>,
+
+    /// The arguments used instead of `prompt_arg_template` when a prompt that
+    /// is too long goes to stdin, e.g. `"-p"` (default: the template without
+    /// the arguments holding `{{prompt}}`).
+    #[serde(default)]
+    pub prompt_stdin_template: Option<String>,
+
+    /// The longest prompt, in KB, passed as an argument (default: what the
+    /// OS allows: 128 KB on Linux, about 1 MB on macOS, 32 KB on Windows).
+    #[serde(default)]
+    pub max_prompt_arg_kb: Option<u64>,
+
+    /// How many times an LLM command that exits non-zero is run again
+    /// before the run fails (default: 0).
+    #[serde(default)]
+    pub retries: Option<u32>,
+
+    /// The wait before the first retry in milliseconds, doubled for each
+    /// further one and randomized by up to half (default: 1000).
+    #[serde(default)]
+    pub backoff_ms: Option<u64>,
+
+    /// The `prompt_arg_template` for the follow-ups of `claw session`, for
+    /// CLIs that keep the conversation themselves, e.g. `--continue -p
+    /// {{prompt}}` for claude. Follow-ups then send only the new message
+    /// instead of the whole conversation.
+    #[serde(default)]
+    pub session_prompt_arg_template: Option<String>,
+
+    /// Layers wrapped around sending the prompt to the receiver, outermost
+    /// first, e.g. `[log, redact, meter]`. `retry` (from `retries`) is the
+    /// innermost layer unless listed. A profile or a goal's `receiver` can
+    /// set its own, so each fallback or fan-out receiver gets its own chain.
+    #[serde(default)]
+    pub middlewares: Option<Vec<ReceiverMiddleware>>,
+
+    /// Dollars per million prompt tokens, for the `meter` middleware's cost
+    /// estimate (default: tokens only).
+    #[serde(default)]
+    pub prompt_token_price: Option<f64>,
+
     /// The type of receiver to use for sending prompts.
     /// Defaults to Generic if not specified for backward compatibility.
     #[serde(default)]
     pub receiver_type: Option<ReceiverType>,
 
+    /// The tmux pane the Tmux receiver pastes prompts into, e.g. `llm` or `work:1.0`.
+    /// Required when receiver_type is Tmux.
+    #[serde(default)]
+    pub tmux_target: Option<String>,
+
+    /// How goals' `model_options` are passed to the LLM command, one flag
+    /// template per option with `{{value}}` as the placeholder, e.g.
+    /// `{ temperature: "--temperature {{value}}" }`. Options without a
+    /// template are left out with a note.
+    #[serde(default)]
+    pub model_option_flags: Option<BTreeMap<String, String>>,
+
+    /// Environment variables set for every goal's context scripts and LLM
+    /// command, e.g. `{ AWS_PROFILE: dev }`. Values can use `{{ Args.<name> }}`;
+    /// a goal's own `env` wins over these.
+    #[serde(default)]
+    pub env: Option<BTreeMap<String, String>>,
+
+    /// Profiles to send the prompt with, in order, when the receiver fails
+    /// (command missing, network down, non-zero exit), e.g. `[ollama, clipboard]`.
+    /// Only a profile's receiver settings are used: `receiver_type`,
+    /// `llm_command`, `prompt_arg_template`, `prompt_stdin_template`,
+    /// `tmux_target` and `model_option_flags`.
+    #[serde(default)]
+    pub receiver_fallbacks: Option<Vec<String>>,
+
+    /// Profiles to send every prompt to at once, e.g. `[claude, ollama]`, to
+    /// compare their responses side by side. Only their receiver settings
+    /// are used, as with `receiver_fallbacks`, which this takes precedence over.
+    #[serde(default)]
+    pub fan_out: Option<Vec<String>>,
+
     // Context Management 2.0 fields
     /// Maximum file size in KB that can be included as context.
     #[serde(default)]
@@ -136,10 +316,20 @@ pub struct ClawConfig {
     #[serde(default)]
     pub max_files_per_directory: Option<usize>,
 
+    /// Lines of context files longer than this many characters are truncated
+    /// with a note, so minified or data files don't flood the prompt
+    /// (default: 2000; 0 keeps lines whole).
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+
     /// How to handle errors during context processing: "strict", "flexible", or "ignore".
     #[serde(default)]
     pub error_handling_mode: Option<ErrorHandlingMode>,
 
+    /// Per-error-class overrides of `error_handling_mode`, e.g. `on_binary: skip`.
+    #[serde(default)]
+    pub context_errors: Option<ContextErrorPolicies>,
+
     /// Directories to exclude when scanning for context files.
     #[serde(default)]
     pub excluded_directories: Option<Vec<String>>,
@@ -147,6 +337,184 @@ pub struct ClawConfig {
     /// File extensions to exclude when scanning for context files.
     #[serde(default)]
     pub excluded_extensions: Option<Vec<String>>,
+
+    /// Gitignore-style patterns of files, e.g. `"**/.env*"` or
+    /// `"**/secrets/**"`, that `--context` includes only after confirming
+    /// each one on the terminal. Without a terminal they are left out.
+    #[serde(default)]
+    pub sensitive_paths: Option<Vec<String>>,
+
+    /// External executables that goals can use to contribute context sections.
+    #[serde(default)]
+    pub context_providers: Option<Vec<ProviderConfig>>,
+
+    /// Seconds a context script may run before it and everything it started
+    /// are killed, for scripts without their own `timeout_secs`, and a
+    /// context provider may run (default: no limit).
+    #[serde(default)]
+    pub script_timeout_secs: Option<u64>,
+
+    /// Seconds a context script's output is reused for instead of running
+    /// it again, for scripts without their own `cache_ttl_secs` (default:
+    /// no caching). Outputs are cached by command, directory, shell and
+    /// environment.
+    #[serde(default)]
+    pub script_cache_ttl_secs: Option<u64>,
+
+    /// The shell context scripts run with, for scripts without their own
+    /// `shell` (default: `cmd` on Windows, `sh` elsewhere).
+    #[serde(default)]
+    pub script_shell: Option<ScriptShell>,
+
+    /// The goal `claw fix --last` runs with the failed command (default:
+    /// `triage`). It is given `command`, `exit_status`, `cwd` and, when
+    /// captured, `output` as arguments.
+    #[serde(default)]
+    pub triage_goal: Option<String>,
+
+    /// Settings for `claw prompt-lint`: line length and per-rule severities.
+    #[serde(default)]
+    pub prompt_lint: Option<LintConfig>,
+
+    /// Whether `--context` output tells the model about claw's limits in a
+    /// Notes section (default: true).
+    #[serde(default)]
+    pub context_notes: Option<bool>,
+
+    /// Whether files deeper than `--recurse_depth` still appear in the
+    /// `--context` directory tree, without their contents (default: false).
+    #[serde(default)]
+    pub list_beyond_depth: Option<bool>,
+
+    /// Whether `--context` files are numbered and the model is asked to cite
+    /// them as `[n]`; runs record which files the response cited
+    /// (default: false).
+    #[serde(default)]
+    pub cite_context: Option<bool>,
+
+    /// Whether a goal run fails when its response is empty or only
+    /// whitespace (default: false).
+    #[serde(default)]
+    pub fail_on_empty_response: Option<bool>,
+
+    /// Whether claw exits with the LLM command's exit code when it fails,
+    /// instead of 1 (default: true).
+    #[serde(default)]
+    pub propagate_exit_code: Option<bool>,
+
+    /// Whether goal runs open the live run view: claw's phases on the left,
+    /// the LLM output streaming on the right (default: false).
+    #[serde(default)]
+    pub live_view: Option<bool>,
+
+    /// Colors of the goal browser: `auto` (the default) picks `dark` or
+    /// `light` from the terminal's background, or `high-contrast`.
+    // Read by the goal browser, which is currently disabled in `main`
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub tui_theme: Option<TuiTheme>,
+
+    /// Whether a response longer than the terminal is opened in `$PAGER`
+    /// (default: `less -R`) once the LLM finishes, after streaming live
+    /// (default: false).
+    #[serde(default)]
+    pub page_response: Option<bool>,
+
+    /// Prompts larger than this many KB are summarized in the pager before
+    /// sending, with the choice to send, edit or abort (default: 512; 0 never
+    /// asks). Only when claw can ask on the terminal.
+    #[serde(default)]
+    pub confirm_prompt_kb: Option<u64>,
+
+    /// Tokenizer used for the prompt token estimates claw reports: `cl100k`,
+    /// `o200k` or `llama`. Guessed from the receiver and `llm_command` when
+    /// unset; set it per backend with profiles.
+    #[serde(default)]
+    pub tokenizer: Option<Tokenizer>,
+
+    /// Tools listed by the `toolchains` built-in context section. Each entry
+    /// is a command name, optionally followed by the arguments that print its
+    /// version (default: `--version`), e.g. `"java -version"`. Defaults to
+    /// common compilers, runtimes and build tools.
+    #[serde(default)]
+    pub toolchain_report: Option<Vec<String>>,
+
+    /// The command the `failing_tests` built-in context section runs, e.g.
+    /// `"cargo test --workspace"`. Guessed from the project's files
+    /// (`Cargo.toml`, `go.mod`, `package.json`, `pyproject.toml`) when unset.
+    #[serde(default)]
+    pub test_command: Option<String>,
+
+    /// Whether goal runs are recorded in `~/.config/claw/history/` (default: true).
+    #[serde(default)]
+    pub record_history: Option<bool>,
+
+    /// The most runs kept in history; recording a run past it removes the
+    /// oldest (default: 1000).
+    #[serde(default)]
+    pub history_limit: Option<usize>,
+
+    /// A read-only goals library managed centrally, e.g. a mounted share or
+    /// a synced repository, laid out like `.claw/` (`goals/<name>/prompt.yaml`).
+    /// Its goals take precedence over global ones and can't be changed with
+    /// `claw add`. Relative paths are resolved from this file's directory.
+    // Read by ConfigPaths::new before the rest of the config; declared here
+    // for `claw schema`
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub managed_goals: Option<PathBuf>,
+
+    /// Named sets of settings, e.g. `work`, `personal` or `offline`, selected
+    /// per invocation with `--profile NAME` or the `CLAW_PROFILE` environment
+    /// variable. A profile's settings replace the top-level ones of the same name.
+    // Applied to the raw YAML before parsing; declared here for `claw schema`
+    #[allow(dead_code)]
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, ClawConfig>")]
+    pub profiles: BTreeMap<String, serde_yaml::Value>,
+
+    /// Command aliases: `claw NAME ...` runs `claw DEFINITION ...`, e.g.
+    /// `rv: "review --context src --"`. Built-in subcommands can't be redefined.
+    #[serde(default)]
+    pub aliases: Option<BTreeMap<String, String>>,
+
+    /// Set when `--llm`, `--receiver` or `--prompt-template` chose the
+    /// receiver, which then wins over a goal's `receiver`.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub receiver_from_cli: bool,
+
+    /// Set by `--no-script-cache`: run every context script, whatever its
+    /// cache TTL.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub script_cache_disabled: bool,
+
+    /// Set by `--no-scripts`: render a placeholder for each context script
+    /// instead of running it.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub scripts_disabled: bool,
+
+    /// Set by `--json-status`: where to write a JSON summary of the run,
+    /// `-` for stderr.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub json_status: Option<PathBuf>,
+}
+
+/// Declares an external context provider executable.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ProviderConfig {
+    /// The name goals use to refer to this provider.
+    pub name: String,
+
+    /// The executable to invoke.
+    pub command: String,
+
+    /// Extra arguments passed to the executable.
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 /// Provides the default value for `prompt_arg_template` during deserialization.
@@ -161,11 +529,26 @@ impl Default for ClawConfig {
             // We default to "claude" as it's a common tool with a simple invocation.
             llm_command: Some("claude".to_string()),
             prompt_arg_template: default_prompt_arg_template(),
+            prompt_arg_overflow: Some(ArgOverflow::Stdin),
+            prompt_stdin_template: None,
+            max_prompt_arg_kb: None, // Detected from the OS when used
+            retries: Some(0),
+            backoff_ms: Some(1000),
+            session_prompt_arg_template: None,
+            middlewares: None,
+            prompt_token_price: None,
             receiver_type: None, // Defaults to Generic when used
+            tmux_target: None,
+            model_option_flags: None,
+            env: None,
+            receiver_fallbacks: None,
+            fan_out: None,
             // Context Management 2.0 defaults
             max_file_size_kb: Some(1024), // 1 MB
             max_files_per_directory: Some(50),
+            max_line_length: Some(2000),
             error_handling_mode: Some(ErrorHandlingMode::Flexible),
+            context_errors: None,
             excluded_directories: Some(vec![
                 ".git".to_string(),
                 "node_modules".to_string(),
@@ -182,7 +565,222 @@ impl Default for ClawConfig {
                 "o".to_string(),
                 "a".to_string(),
             ]),
+            sensitive_paths: None,
+            context_providers: None,
+            script_timeout_secs: None,
+            script_cache_ttl_secs: None,
+            script_shell: None,
+            triage_goal: None,
+            prompt_lint: None,
+            context_notes: Some(true),
+            list_beyond_depth: Some(false),
+            cite_context: Some(false),
+            fail_on_empty_response: Some(false),
+            propagate_exit_code: Some(true),
+            live_view: Some(false),
+            tui_theme: Some(TuiTheme::Auto),
+            page_response: Some(false),
+            confirm_prompt_kb: Some(512),
+            tokenizer: None, // Guessed from the receiver when used
+            toolchain_report: None, // builtins::DEFAULT_TOOLCHAIN_REPORT when used
+            test_command: None,
+            record_history: Some(true),
+            history_limit: None, // history::DEFAULT_HISTORY_LIMIT when used
+            managed_goals: None,
+            profiles: BTreeMap::new(),
+            aliases: None,
+            receiver_from_cli: false,
+            script_cache_disabled: false,
+            scripts_disabled: false,
+            json_status: None,
+        }
+    }
+}
+
+/// The receiver settings of a profile, used when it is a receiver fallback,
+/// or of a goal's `receiver`.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, PartialEq)]
+pub struct ReceiverSettings {
+    #[serde(default)]
+    pub receiver_type: Option<ReceiverType>,
+    #[serde(default)]
+    pub llm_command: Option<String>,
+    #[serde(default)]
+    pub prompt_arg_template: Option<String>,
+    #[serde(default)]
+    pub prompt_stdin_template: Option<String>,
+    #[serde(default)]
+    pub tmux_target: Option<String>,
+    #[serde(default)]
+    pub model_option_flags: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub middlewares: Option<Vec<ReceiverMiddleware>>,
+}
+
+/// Where a goal sends its prompt instead of `claw.yaml`'s receiver.
+#[derive(Debug, Clone, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum GoalReceiver {
+    /// A profile in `claw.yaml` whose receiver settings are used, e.g. `local`.
+    Profile(String),
+    /// Receiver settings of the goal's own, e.g. `{ llm_command: ollama }`.
+    Settings(ReceiverSettings),
+}
+
+impl ClawConfig {
+    /// How long `script` may run: its own `timeout_secs`, or else
+    /// `script_timeout_secs`.
+    pub fn script_timeout(&self, script: &ContextScript) -> Option<Duration> {
+        script
+            .timeout_secs()
+            .or(self.script_timeout_secs)
+            .map(Duration::from_secs)
+    }
+
+    /// How long `script`'s output is reused for: its own `cache_ttl_secs`, or
+    /// else `script_cache_ttl_secs`. `None` when it isn't cached.
+    pub fn script_cache_ttl(&self, script: &ContextScript) -> Option<Duration> {
+        if self.script_cache_disabled {
+            return None;
+        }
+        script
+            .cache_ttl_secs()
+            .or(self.script_cache_ttl_secs)
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// The shell `script` runs with: its own `shell`, or else `script_shell`.
+    pub fn script_shell(&self, script: &ContextScript) -> ScriptShell {
+        script
+            .shell()
+            .or(self.script_shell.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Describes where prompts go, e.g. `claude`, `ollama` or `tmux pane llm:0.1`.
+    pub fn receiver_label(&self) -> String {
+        match self.receiver_type.clone().unwrap_or_default() {
+            ReceiverType::Generic => self
+                .llm_command
+                .clone()
+                .unwrap_or_else(|| "(no llm_command)".to_string()),
+            ReceiverType::ClaudeCli => "claude".to_string(),
+            ReceiverType::Tmux => format!(
+                "tmux pane {}",
+                self.tmux_target.as_deref().unwrap_or("(no tmux_target)")
+            ),
+        }
+    }
+
+    /// The receivers to try in order: this config's, then one per profile
+    /// in `receiver_fallbacks`, each with a label for messages. A profile's
+    /// receiver settings replace this config's; its `llm_command` implies
+    /// the Generic receiver unless it sets `receiver_type`.
+    pub fn receiver_chain(&self) -> Result<Vec<(String, ClawConfig)>> {
+        let mut primary = self.clone();
+        primary.receiver_fallbacks = None;
+        primary.fan_out = None;
+        let mut chain = vec![(primary.receiver_label(), primary.clone())];
+
+        for name in self.receiver_fallbacks.iter().flatten() {
+            let config = primary.with_profile_receiver(name, "receiver_fallbacks")?;
+            chain.push((
+                format!("{} (profile '{}')", config.receiver_label(), name),
+                config,
+            ));
+        }
+        Ok(chain)
+    }
+
+    /// The receivers of the profiles in `fan_out`, by profile name.
+    pub fn fan_out_receivers(&self) -> Result<Vec<(String, ClawConfig)>> {
+        let mut primary = self.clone();
+        primary.receiver_fallbacks = None;
+        primary.fan_out = None;
+        self.fan_out
+            .iter()
+            .flatten()
+            .map(|name| Ok((name.clone(), primary.with_profile_receiver(name, "fan_out")?)))
+            .collect()
+    }
+
+    /// This config with the goal's `receiver`, then its own `llm_command`
+    /// and `prompt_arg_template`, applied, or `None` when the goal sets none
+    /// of them or the command line chose the receiver.
+    pub fn for_goal(&self, goal: &PromptConfig) -> Result<Option<ClawConfig>> {
+        let overrides = ReceiverSettings {
+            llm_command: goal.llm_command.clone(),
+            prompt_arg_template: goal.prompt_arg_template.clone(),
+            ..Default::default()
+        };
+        let has_overrides = overrides != ReceiverSettings::default();
+        if self.receiver_from_cli || (goal.receiver.is_none() && !has_overrides) {
+            return Ok(None);
+        }
+        let mut config = match &goal.receiver {
+            None => self.clone(),
+            Some(GoalReceiver::Profile(name)) => {
+                self.with_profile_receiver(name, "The goal's receiver")?
+            }
+            Some(GoalReceiver::Settings(settings)) => {
+                self.with_receiver_settings(settings.clone(), "The goal's receiver")?
+            }
+        };
+        if has_overrides {
+            config = config.with_receiver_settings(overrides, "The goal")?;
+        }
+        Ok(Some(config))
+    }
+
+    /// This config with the receiver settings of profile `name` applied,
+    /// which `setting` names.
+    fn with_profile_receiver(&self, name: &str, setting: &str) -> Result<ClawConfig> {
+        let profile = self.profiles.get(name).with_context(|| {
+            format!("{} names profile '{}', which is not defined", setting, name)
+        })?;
+        let settings: ReceiverSettings = serde_yaml::from_value(profile.clone())
+            .with_context(|| format!("Invalid receiver settings in profile '{}'", name))?;
+        self.with_receiver_settings(settings, &format!("Profile '{}' in {}", name, setting))
+    }
+
+    /// This config with `settings` applied. `source` describes where they
+    /// come from, for errors.
+    fn with_receiver_settings(&self, settings: ReceiverSettings, source: &str) -> Result<ClawConfig> {
+        let mut config = self.clone();
+        if settings.llm_command.is_some() {
+            config.llm_command = settings.llm_command;
+            config.receiver_type = Some(ReceiverType::Generic);
+        }
+        if let Some(receiver_type) = settings.receiver_type {
+            config.receiver_type = Some(receiver_type);
+        }
+        // The stdin arguments belong with the profile's own template
+        if let Some(template) = settings.prompt_arg_template {
+            config.prompt_arg_template = template;
+            config.prompt_stdin_template = settings.prompt_stdin_template;
+        } else if settings.prompt_stdin_template.is_some() {
+            config.prompt_stdin_template = settings.prompt_stdin_template;
+        }
+        if settings.tmux_target.is_some() {
+            config.tmux_target = settings.tmux_target;
+        }
+        if settings.model_option_flags.is_some() {
+            config.model_option_flags = settings.model_option_flags;
+        }
+        if settings.middlewares.is_some() {
+            config.middlewares = settings.middlewares;
+        }
+
+        let receiver_type = config.receiver_type.clone().unwrap_or_default();
+        if receiver_type == ReceiverType::Generic && config.llm_command.is_none() {
+            anyhow::bail!("{} sets no llm_command", source);
+        }
+        if receiver_type == ReceiverType::Tmux && config.tmux_target.is_none() {
+            anyhow::bail!("{} sets no tmux_target", source);
         }
+        Ok(config)
     }
 }
 
@@ -190,10 +788,12 @@ impl Default for ClawConfig {
 pub enum GoalSource {
     Local,
     Global,
+    /// The read-only library configured with `managed_goals`.
+    Managed,
 }
 
 /// Represents the type of a goal parameter.
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ParameterType {
     String,
@@ -202,7 +802,7 @@ pub enum ParameterType {
 }
 
 /// Represents a single parameter definition for a goal.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct GoalParameter {
     /// The name of the parameter (e.g., "scope", "format").
     pub name: String,
@@ -221,13 +821,259 @@ pub struct GoalParameter {
     /// Optional default value for the parameter (only valid if required is false).
     #[serde(default)]
     pub default: Option<String>,
+
+    /// Optional hints for how interactive prompts should collect this parameter.
+    #[serde(default)]
+    pub ui: Option<ParameterUi>,
+
+    /// Fixed set of accepted values. Used for validation and shell completion.
+    #[serde(default)]
+    pub choices: Option<Vec<String>>,
+
+    /// Shell command whose output lines are offered as completions for this
+    /// parameter (e.g. `git branch --format='%(refname:short)'`).
+    #[serde(default)]
+    pub choices_script: Option<String>,
+
+    /// Environment variable whose value is used when the parameter isn't
+    /// given on the command line (e.g. `JIRA_TICKET` in CI). It is still
+    /// validated like any other value.
+    #[serde(default)]
+    pub env: Option<String>,
+}
+
+/// A documented invocation of a goal, shown by `--explain` and run with
+/// `claw <goal> --example N`.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GoalExample {
+    /// What the example does, e.g. "Review only the API layer".
+    pub description: String,
+
+    /// Parameter values, e.g. `{ scope: api }`.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub args: BTreeMap<String, serde_yaml::Value>,
+
+    /// Paths passed as `--context`.
+    #[serde(default)]
+    pub context: Vec<PathBuf>,
+}
+
+impl GoalExample {
+    /// Returns the example's parameters as template arguments, e.g. `--scope=api`.
+    pub fn template_args(&self) -> Result<Vec<String>> {
+        self.args
+            .iter()
+            .map(|(name, value)| {
+                let value = matrix::scalar_to_string(value).with_context(|| {
+                    format!(
+                        "Example parameter '{}' must be a string, number or boolean",
+                        name
+                    )
+                })?;
+                Ok(format!("--{}={}", name, value))
+            })
+            .collect()
+    }
+}
+
+/// A context script: a shell command, or a command with prefetch settings.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum ContextScript {
+    Command(String),
+    Detailed {
+        /// The shell command, a Tera template like the short form.
+        run: String,
+        /// Let `claw daemon` run the script in the background; renders use
+        /// its most recent output instead of waiting for it. The command,
+        /// and the goal's `env` and `working_dir`, can't use `Args`.
+        #[serde(default)]
+        prefetch: bool,
+        /// Seconds between background runs (default: 600, at least 60).
+        #[serde(default)]
+        every: Option<u64>,
+        /// Seconds the script may run before it is killed, over
+        /// `script_timeout_secs` in `claw.yaml`.
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+        /// The shell to run the command with, over `script_shell` in `claw.yaml`.
+        #[serde(default)]
+        shell: Option<ScriptShell>,
+        /// Seconds the script's output is reused for, over
+        /// `script_cache_ttl_secs` in `claw.yaml`; 0 turns caching off.
+        #[serde(default)]
+        cache_ttl_secs: Option<u64>,
+    },
+}
+
+impl ContextScript {
+    /// The shell command template.
+    pub fn command(&self) -> &str {
+        match self {
+            ContextScript::Command(run) | ContextScript::Detailed { run, .. } => run,
+        }
+    }
+
+    /// Whether `claw daemon` prefetches the script.
+    pub fn prefetch(&self) -> bool {
+        matches!(self, ContextScript::Detailed { prefetch: true, .. })
+    }
+
+    /// The script's own shell, if it sets one.
+    pub fn shell(&self) -> Option<&ScriptShell> {
+        match self {
+            ContextScript::Detailed { shell, .. } => shell.as_ref(),
+            ContextScript::Command(_) => None,
+        }
+    }
+
+    /// The script's own cache TTL in seconds, if it sets one.
+    pub fn cache_ttl_secs(&self) -> Option<u64> {
+        match self {
+            ContextScript::Detailed { cache_ttl_secs, .. } => *cache_ttl_secs,
+            ContextScript::Command(_) => None,
+        }
+    }
+
+    /// The script's own timeout in seconds, if it sets one.
+    pub fn timeout_secs(&self) -> Option<u64> {
+        match self {
+            ContextScript::Detailed { timeout_secs, .. } => *timeout_secs,
+            ContextScript::Command(_) => None,
+        }
+    }
+
+    /// Seconds between background runs of a prefetched script.
+    pub fn prefetch_interval(&self) -> u64 {
+        match self {
+            ContextScript::Detailed { every, .. } => {
+                every.unwrap_or(DEFAULT_PREFETCH_INTERVAL).max(MIN_PREFETCH_INTERVAL)
+            }
+            ContextScript::Command(_) => DEFAULT_PREFETCH_INTERVAL,
+        }
+    }
+}
+
+impl From<&str> for ContextScript {
+    fn from(run: &str) -> Self {
+        ContextScript::Command(run.to_string())
+    }
+}
+
+/// A shell that context scripts are run with.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ScriptShell {
+    /// One of the shells claw knows how to call.
+    Named(ShellName),
+    /// A command line the script is added to as the last argument, e.g.
+    /// `zsh -c` or `bash -eo pipefail -c`.
+    Command(String),
+}
+
+/// The shells `script_shell` can name.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellName {
+    /// `sh -c`, the default except on Windows.
+    Sh,
+    /// `bash -c`.
+    Bash,
+    /// `cmd /C`, the default on Windows.
+    Cmd,
+    /// Windows PowerShell, `powershell -NoProfile -Command`.
+    Powershell,
+    /// PowerShell 7, `pwsh -NoProfile -Command`.
+    Pwsh,
+}
+
+impl ScriptShell {
+    /// The program and the arguments that go before the command.
+    pub fn invocation(&self) -> Result<(String, Vec<String>)> {
+        let mut words = match self {
+            ScriptShell::Named(name) => name.words().iter().map(|word| word.to_string()).collect(),
+            ScriptShell::Command(line) => shlex::split(line)
+                .filter(|words| !words.is_empty())
+                .with_context(|| format!("script_shell `{}` isn't a command line", line))?,
+        };
+        let program = words.remove(0);
+        Ok((program, words))
+    }
+}
+
+impl ShellName {
+    /// The program and the arguments that go before the command.
+    fn words(self) -> &'static [&'static str] {
+        match self {
+            ShellName::Sh => &["sh", "-c"],
+            ShellName::Bash => &["bash", "-c"],
+            ShellName::Cmd => &["cmd", "/C"],
+            ShellName::Powershell => &["powershell", "-NoProfile", "-Command"],
+            ShellName::Pwsh => &["pwsh", "-NoProfile", "-Command"],
+        }
+    }
+}
+
+impl Default for ScriptShell {
+    fn default() -> Self {
+        if cfg!(windows) {
+            ScriptShell::Named(ShellName::Cmd)
+        } else {
+            ScriptShell::Named(ShellName::Sh)
+        }
+    }
+}
+
+impl fmt::Display for ScriptShell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptShell::Named(name) => write!(f, "{}", name.words()[0]),
+            ScriptShell::Command(line) => write!(f, "`{}`", line),
+        }
+    }
+}
+
+/// Seconds between background runs of a prefetched script by default.
+const DEFAULT_PREFETCH_INTERVAL: u64 = 600;
+
+/// The shortest interval a prefetched script can ask for, so a daemon
+/// can't run an expensive scan back to back.
+const MIN_PREFETCH_INTERVAL: u64 = 60;
+
+/// Presentation hints for collecting a parameter interactively.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize, PartialEq)]
+pub struct ParameterUi {
+    /// Accept multiple lines of input (finished with an empty line).
+    #[serde(default)]
+    pub multiline: bool,
+
+    /// Hide the input as it is typed.
+    #[serde(default)]
+    pub password: bool,
+
+    /// Restrict a numeric value to a range.
+    #[serde(default)]
+    pub slider: Option<SliderHint>,
+
+    /// Example text shown before the user types a value.
+    #[serde(default)]
+    pub placeholder: Option<String>,
+}
+
+/// Inclusive numeric bounds for a slider-style parameter.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, Serialize, PartialEq)]
+pub struct SliderHint {
+    pub min: f64,
+    pub max: f64,
 }
 
 /// Represents the structure of a `prompt.yaml` file.
 ///
 /// This struct is derived with `serde::Deserialize` to allow for automatic
 /// parsing from a YAML string into a typed Rust object.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 pub struct PromptConfig {
     /// A user-friendly name for the goal, e.g., "Staged Git Changes Code Review".
     pub name: String,
@@ -240,16 +1086,278 @@ pub struct PromptConfig {
     #[serde(default)]
     pub parameters: Vec<GoalParameter>,
 
+    /// Example invocations listed by `--explain`, runnable with `--example N`.
+    #[serde(default)]
+    pub examples: Vec<GoalExample>,
+
     /// A map of script names to the shell commands to be executed.
     /// The key is the name used in the template (e.g., `staged_diff`),
     /// and the value is the command to run (e.g., "git diff --staged").
     /// `#[serde(default)]` ensures that if `context_scripts` is missing from
-    /// the YAML, this field will be an empty HashMap instead of causing an error.
+    /// the YAML, this field will be an empty map instead of causing an error.
+    /// Scripts keep the order they are declared in, and run in that order.
+    /// A script can also be given as `{ run, prefetch, every }` to have
+    /// `claw daemon` run it in the background.
+    #[serde(default)]
+    pub context_scripts: IndexMap<String, ContextScript>,
+
+    /// Whether `--context` directory scans skip files ignored by `.gitignore`
+    /// and hidden files (default: true). Set to false for goals that analyze
+    /// build output or coverage reports.
+    #[serde(default)]
+    pub respect_gitignore: Option<bool>,
+
+    /// Built-in context sections gathered by claw itself, e.g. `git_status`.
+    /// Each is available as `{{ Context.<name> }}`; a context script with
+    /// the same name takes precedence.
     #[serde(default)]
-    pub context_scripts: HashMap<String, String>,
+    pub context_builtin: Vec<ContextBuiltin>,
+
+    /// Names of context providers (from `claw.yaml`) whose sections are
+    /// appended to the rendered prompt.
+    #[serde(default)]
+    pub providers: Vec<String>,
 
     /// The Tera template string for the prompt.
     pub prompt: String,
+
+    /// Optional Tera template applied to the captured model response
+    /// (`--tee`) before it is saved, e.g. to add a header or frontmatter.
+    #[serde(default)]
+    pub response_template: Option<String>,
+
+    /// Environment variables set for the goal's context scripts and LLM
+    /// command, over `claw.yaml`'s `env`. Values are Tera templates with
+    /// `Args`, e.g. `{ TICKET: "{{ Args.ticket }}" }`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// The directory the goal's context scripts and LLM command run in, a
+    /// Tera template with `Args`, e.g. `packages/{{ Args.package }}`.
+    /// Relative paths are resolved from where claw is run; `--context` paths
+    /// still are too.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Shell commands the response is piped through once the LLM finishes,
+    /// e.g. `jq .` or a script that files a ticket. Each gets the previous
+    /// one's output on stdin; the last one's output is shown and saved.
+    #[serde(default)]
+    pub post_process: Vec<String>,
+
+    /// Named artifacts to split the response into, e.g.
+    /// `{ spec: spec.md, tasks: tasks.md }`. claw asks the model to mark each
+    /// section and writes it to its path.
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+
+    /// Experimental: what the model's response contains. With `unified-diff`,
+    /// the response is applied to the working tree after a preview and
+    /// confirmation.
+    #[serde(default)]
+    pub expects: Option<ResponseFormat>,
+
+    /// Generation settings for this goal, e.g. `{ temperature: 0.2 }`,
+    /// passed to the LLM by the receiver (see `model_option_flags`).
+    #[serde(default)]
+    pub model_options: ModelOptions,
+
+    /// Where this goal sends its prompt instead of the receiver in
+    /// `claw.yaml`: a profile name, e.g. `local`, or receiver settings, e.g.
+    /// `{ llm_command: ollama, prompt_arg_template: "run llama3" }`. The
+    /// receiver fallbacks still apply; `--llm`, `--receiver` and
+    /// `--prompt-template` win over it.
+    #[serde(default)]
+    pub receiver: Option<GoalReceiver>,
+
+    /// The LLM command for this goal, e.g. `aider` or `sgpt`, over
+    /// `claw.yaml` and the goal's `receiver`. Uses the generic receiver.
+    #[serde(default)]
+    pub llm_command: Option<String>,
+
+    /// How the prompt is passed to this goal's LLM command, e.g.
+    /// `"--message {{prompt}}"`, over `claw.yaml` and the goal's `receiver`.
+    #[serde(default)]
+    pub prompt_arg_template: Option<String>,
+
+    /// Checks the response must pass, e.g.
+    /// `{ contains: ["## Summary"], max_words: 800 }`. A failing response is
+    /// asked for again with the problems listed, up to `retries` times.
+    #[serde(default)]
+    pub assert_response: Option<ResponseAssertions>,
+
+    /// The claw versions this goal works with, e.g. `">=0.5"`. Older
+    /// installs refuse to load the goal with an upgrade hint, instead of
+    /// failing on features they don't know.
+    #[serde(default)]
+    pub requires_claw: Option<String>,
+
+    /// Marks a goal as centrally managed: `claw add` refuses to change it,
+    /// and `claw list` and the goal browser show it as read-only. Goals from
+    /// the `managed_goals` library are always managed.
+    #[serde(default)]
+    pub managed: bool,
+
+    /// Keeps runs of this goal in the same repository from overlapping,
+    /// e.g. from cron or CI: `true` refuses a second run while one is in
+    /// progress, `wait` queues it until the first finishes.
+    #[serde(default)]
+    pub exclusive: Option<Exclusive>,
+}
+
+impl PromptConfig {
+    /// Fails if a prefetched script could depend on the run's arguments
+    /// through its command or the goal's `env` or `working_dir`. `claw
+    /// daemon` runs it without any, so its output would stand in for
+    /// whatever arguments a run passes.
+    fn check_prefetched_scripts(&self) -> Result<()> {
+        let args_pattern = regex::Regex::new(r"\{[{%][^}]*\bArgs\b").expect("valid Args pattern");
+        let uses_args = |template: &str| args_pattern.is_match(template);
+        for (name, script) in &self.context_scripts {
+            if !script.prefetch() {
+                continue;
+            }
+            let source = if uses_args(script.command()) {
+                "its command"
+            } else if self.env.values().any(|value| uses_args(value)) {
+                "the goal's env"
+            } else if self.working_dir.as_deref().is_some_and(uses_args) {
+                "the goal's working_dir"
+            } else {
+                continue;
+            };
+            anyhow::bail!(
+                "Context script '{}' is prefetched, but {} uses Args, and `claw daemon` \
+                 runs it without arguments. Drop `prefetch: true` or the Args.",
+                name,
+                source
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether running this goal executes shell commands of its own: context
+    /// scripts, providers, the project's tests, the tools `claw_config` sets
+    /// in `toolchain_report` or `post_process` steps, or an LLM command it
+    /// chooses or runs with its own `env` or `working_dir`.
+    pub fn runs_commands(&self, claw_config: &ClawConfig) -> bool {
+        !self.context_scripts.is_empty()
+            || !self.providers.is_empty()
+            || !self.post_process.is_empty()
+            || self.context_builtin.contains(&ContextBuiltin::FailingTests)
+            || (self.context_builtin.contains(&ContextBuiltin::Toolchains)
+                && claw_config.toolchain_report.is_some())
+            || self.receiver.is_some()
+            || self.llm_command.is_some()
+            || self.prompt_arg_template.is_some()
+            || !self.env.is_empty()
+            || self.working_dir.is_some()
+    }
+}
+
+/// Whether a goal runs alone in its repository (`exclusive`).
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Exclusive {
+    /// `true` refuses a run while another is in progress; `false` allows it.
+    Flag(bool),
+    /// `wait` queues a run until the one in progress finishes.
+    Queue(ExclusiveQueue),
+}
+
+/// The `wait` keyword of `exclusive`.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExclusiveQueue {
+    Wait,
+}
+
+impl Exclusive {
+    /// Whether runs take the goal's lock at all.
+    pub fn is_enabled(self) -> bool {
+        self != Exclusive::Flag(false)
+    }
+
+    /// Whether a run waits for the lock instead of failing.
+    pub fn waits(self) -> bool {
+        matches!(self, Exclusive::Queue(ExclusiveQueue::Wait))
+    }
+}
+
+/// Generation settings a goal asks the model for.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ModelOptions {
+    /// Sampling temperature; lower values give more focused answers.
+    #[serde(default)]
+    pub temperature: Option<f64>,
+
+    /// The most tokens the response may use.
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+
+    /// Nucleus sampling: only the most likely tokens within this
+    /// probability mass are considered.
+    #[serde(default)]
+    pub top_p: Option<f64>,
+}
+
+impl ModelOptions {
+    /// Returns the options that are set, by name, e.g. `("temperature", "0.2")`.
+    pub fn values(&self) -> Vec<(&'static str, String)> {
+        [
+            ("temperature", self.temperature.map(|v| v.to_string())),
+            ("max_tokens", self.max_tokens.map(|v| v.to_string())),
+            ("top_p", self.top_p.map(|v| v.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+/// The part of a `prompt.yaml` read before the rest, so a goal written for
+/// a newer claw fails with a version error rather than a parse error.
+#[derive(Deserialize)]
+struct VersionRequirement {
+    #[serde(default)]
+    requires_claw: Option<String>,
+}
+
+/// The part of a `claw.yaml` read while resolving config paths.
+#[derive(Deserialize)]
+struct ManagedGoalsSetting {
+    #[serde(default)]
+    managed_goals: Option<PathBuf>,
+}
+
+/// Environment details claw can gather without a shell.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextBuiltin {
+    /// The branch and changed files, as `git status --short --branch` shows
+    /// them.
+    GitStatus,
+    /// The staged changes, as `git diff --staged` shows them.
+    GitDiffStaged,
+    /// Operating system, architecture and distribution.
+    OsInfo,
+    /// `rustc` and `cargo` versions, plus the project's toolchain file.
+    RustToolchain,
+    /// Versions of common development tools (`toolchain_report` in
+    /// `claw.yaml`), or "not installed".
+    Toolchains,
+    /// The failing tests' names and output, and the source files the output
+    /// points at, from running `test_command` in `claw.yaml`.
+    FailingTests,
+}
+
+/// The kind of response a goal asks the model for.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseFormat {
+    /// A patch in unified diff format.
+    UnifiedDiff,
 }
 
 /// Holds the resolved paths for local (repository) and global (user) configurations.
@@ -259,16 +1367,42 @@ pub struct ConfigPaths {
     pub local: Option<PathBuf>,
     /// The path to the global `~/.config/claw/` directory, if it exists.
     pub global: Option<PathBuf>,
+    /// The read-only goals library from `managed_goals` in `claw.yaml`.
+    pub managed: Option<PathBuf>,
 }
 
 impl ConfigPaths {
     /// Finds and returns the local and global configuration paths.
     pub fn new() -> Result<Self> {
+        let local = find_local_config_dir()?;
+        let global = find_global_config_dir();
+        let managed = find_managed_goals_dir(local.as_deref(), global.as_deref());
         Ok(Self {
-            local: find_local_config_dir()?,
-            global: find_global_config_dir(),
+            local,
+            global,
+            managed,
         })
     }
+
+    /// The directories goals are loaded from, in lookup order: local, then
+    /// the managed library (so global goals can't shadow it), then global.
+    pub fn goal_sources(&self) -> Vec<(&Path, GoalSource)> {
+        [
+            (&self.local, GoalSource::Local),
+            (&self.managed, GoalSource::Managed),
+            (&self.global, GoalSource::Global),
+        ]
+        .into_iter()
+        .filter_map(|(dir, source)| Some((dir.as_deref()?, source)))
+        .collect()
+    }
+
+    /// Returns true if the local `.claw/` directory contains a `claw.yaml`.
+    pub fn has_local_claw_config(&self) -> bool {
+        self.local
+            .as_deref()
+            .is_some_and(|local| paths::claw_config(local).exists())
+    }
 }
 
 /// Searches upwards from the current directory for a `.claw` directory.
@@ -285,23 +1419,143 @@ fn find_local_config_dir() -> Result<Option<PathBuf>> {
 
 /// Returns the path to the global config directory, `~/.config/claw/`.
 fn find_global_config_dir() -> Option<PathBuf> {
-    if let Some(base_dirs) = BaseDirs::new() {
-        let config_dir = base_dirs.config_dir().join("claw");
-        if config_dir.exists() {
-            return Some(config_dir);
-        }
+    global_config_dir_path().filter(|config_dir| config_dir.exists())
+}
+
+/// Returns where the global config directory is, whether or not it exists.
+pub fn global_config_dir_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|base_dirs| base_dirs.config_dir().join("claw"))
+}
+
+/// Returns the `managed_goals` directory set in the local or else the global
+/// `claw.yaml`. A `claw.yaml` that doesn't parse is skipped here; loading
+/// the config reports the error.
+fn find_managed_goals_dir(local: Option<&Path>, global: Option<&Path>) -> Option<PathBuf> {
+    [local, global].into_iter().flatten().find_map(|base_dir| {
+        let setting: ManagedGoalsSetting =
+            load_yaml_config(&paths::claw_config(base_dir)).ok()??;
+        let dir = setting.managed_goals?;
+        let dir = match dir.strip_prefix("~") {
+            Ok(rest) => BaseDirs::new()?.home_dir().join(rest),
+            Err(_) => base_dir.join(dir),
+        };
+        Some(dir)
+    })
+}
+
+/// The file that defines a goal, inside its directory.
+pub const PROMPT_FILE: &str = "prompt.yaml";
+
+/// The alternative to `prompt.yaml`: YAML front matter for the settings,
+/// followed by the prompt as plain markdown.
+pub const PROMPT_MARKDOWN_FILE: &str = "prompt.md";
+
+/// Returns the file that defines the goal in `directory`: its `prompt.yaml`,
+/// or its `prompt.md` if it has no `prompt.yaml`.
+pub fn goal_definition_path(directory: &Path) -> PathBuf {
+    let yaml = directory.join(PROMPT_FILE);
+    let markdown = directory.join(PROMPT_MARKDOWN_FILE);
+    if !yaml.exists() && markdown.exists() {
+        markdown
+    } else {
+        yaml
     }
-    None
 }
 
-/// Loads and parses a `prompt.yaml` file for a specific goal from a base directory.
+/// Loads and parses a `prompt.yaml` (or `prompt.md`) file for a specific
+/// goal from a base directory.
 ///
 /// It returns `Ok(Some(config))` if the goal is found and parsed successfully.
-/// It returns `Ok(None)` if the `prompt.yaml` file does not exist.
-/// It returns an `Err` if the file exists but cannot be read or parsed.
+/// It returns `Ok(None)` if the goal has neither file.
+/// It returns an `Err` if the file exists but cannot be read or parsed, or if
+/// the goal has both files.
+/// It also returns an `Err` if the goal's `requires_claw` excludes this
+/// version, or a prefetched script could depend on the goal's arguments.
 pub fn load_goal_config(base_dir: &Path, goal_name: &str) -> Result<Option<PromptConfig>> {
     let path = paths::goal_prompt(base_dir, goal_name);
-    load_yaml_config(&path)
+    let markdown_path = paths::goal_markdown(base_dir, goal_name);
+    let value: serde_yaml::Value = match (path.exists(), markdown_path.exists()) {
+        (false, false) => return Ok(None),
+        (true, true) => anyhow::bail!(
+            "Goal '{}' has both {} and {}; keep only one",
+            goal_name,
+            PROMPT_FILE,
+            PROMPT_MARKDOWN_FILE
+        ),
+        (true, false) => load_yaml_config(&path)?.unwrap_or_default(),
+        (false, true) => {
+            let content = fs::read_to_string(&markdown_path)
+                .with_context(|| format!("Failed to read {}", markdown_path.display()))?;
+            parse_markdown_goal(&content)
+                .with_context(|| format!("Failed to parse {}", markdown_path.display()))?
+        }
+    };
+    let path = if path.exists() { path } else { markdown_path };
+
+    if let Ok(VersionRequirement {
+        requires_claw: Some(requirement),
+    }) = serde_yaml::from_value::<VersionRequirement>(value.clone())
+    {
+        let supported = version::satisfies(version::CURRENT, &requirement)
+            .with_context(|| format!("Invalid requires_claw in {}", path.display()))?;
+        if !supported {
+            anyhow::bail!(
+                "Goal '{}' requires claw {}, but this is claw {}. Upgrade claw to use it: https://github.com/ayeganov/claw/releases",
+                goal_name,
+                requirement,
+                version::CURRENT
+            );
+        }
+    }
+    let config: PromptConfig = serde_yaml::from_value(value)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    config
+        .check_prefetched_scripts()
+        .with_context(|| format!("Invalid goal {}", path.display()))?;
+    Ok(Some(config))
+}
+
+/// Turns a `prompt.md` into the mapping a `prompt.yaml` would hold: the
+/// YAML front matter between the leading `---` lines, with everything after
+/// the closing `---` line as its `prompt`.
+fn parse_markdown_goal(content: &str) -> Result<serde_yaml::Value> {
+    let missing = || {
+        anyhow::anyhow!(
+            "{} must start with YAML front matter between `---` lines, with at least a `name`",
+            PROMPT_MARKDOWN_FILE
+        )
+    };
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+        .ok_or_else(missing)?;
+
+    let mut front_matter_len = 0;
+    let mut body = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            body = Some(&rest[front_matter_len + line.len()..]);
+            break;
+        }
+        front_matter_len += line.len();
+    }
+    let body = body.ok_or_else(missing)?;
+
+    let mut front_matter: serde_yaml::Mapping = match serde_yaml::from_str(&rest[..front_matter_len])
+        .context("Invalid YAML front matter")?
+    {
+        serde_yaml::Value::Null => serde_yaml::Mapping::new(),
+        serde_yaml::Value::Mapping(mapping) => mapping,
+        _ => return Err(missing()),
+    };
+    if front_matter.contains_key("prompt") {
+        anyhow::bail!(
+            "The front matter of {} can't set `prompt`; the markdown after it is the prompt",
+            PROMPT_MARKDOWN_FILE
+        );
+    }
+    front_matter.insert("prompt".into(), body.into());
+    Ok(serde_yaml::Value::Mapping(front_matter))
 }
 
 /// Represents a successfully loaded goal configuration, including its content
@@ -311,35 +1565,85 @@ pub fn load_goal_config(base_dir: &Path, goal_name: &str) -> Result<Option<Promp
 pub struct LoadedGoal {
     pub config: PromptConfig,
     pub directory: PathBuf,
+    pub source: GoalSource,
+}
+
+/// Directory in a goal with the prompt snapshots written by `claw test`.
+pub const SNAPSHOTS_DIR: &str = "__snapshots__";
+
+/// File in a goal with the cases `claw test` renders.
+pub const TESTS_FILE: &str = "tests.yaml";
+
+/// The goal's files, as templates for `{% include %}`, named by their path
+/// within the goal directory. `claw test`'s cases and snapshots are left
+/// out, since they hold rendered text.
+impl GoalFiles for LoadedGoal {
+    fn template_files(&self) -> Result<Vec<(String, String)>> {
+        walkdir::WalkDir::new(&self.directory)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != SNAPSHOTS_DIR)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| entry.depth() > 1 || entry.file_name() != TESTS_FILE)
+            .map(|entry| {
+                let name = entry
+                    .path()
+                    .strip_prefix(&self.directory)
+                    .unwrap_or(entry.path())
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let content = fs::read_to_string(entry.path())
+                    .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+                Ok((name, content))
+            })
+            .collect()
+    }
 }
 
 /// Implements the configuration cascade to find and load a specific goal.
 ///
 /// 1. Searches for the goal in the local `.claw/` directory.
-/// 2. If not found, falls back to the global `~/.config/claw/` directory.
-/// 3. Returns an error if the goal is not found in either location.
+/// 2. If not found, searches the `managed_goals` library, if configured.
+/// 3. If not found, falls back to the global `~/.config/claw/` directory.
+/// 4. Returns an error if the goal is not found in any location.
+///
+/// Each location's own goals come before those of its enabled collections.
 pub fn find_and_load_goal(goal_name: &str) -> Result<LoadedGoal> {
     let paths = ConfigPaths::new()?;
-    let goal_name = goal_name.to_string();
-
-    cascade_load_config(
-        &paths,
-        |base_dir| {
-            if let Some(config) = load_goal_config(base_dir, &goal_name)? {
-                let directory = paths::goal_dir(base_dir, &goal_name);
-                Ok(Some(LoadedGoal { config, directory }))
-            } else {
-                Ok(None)
+
+    for (base_dir, source) in paths.goal_sources() {
+        for (root, _) in collections::goal_roots(base_dir) {
+            if let Some(config) = load_source_goal(&root, goal_name, source)? {
+                return Ok(LoadedGoal {
+                    config,
+                    directory: paths::goal_dir(&root, goal_name),
+                    source,
+                });
             }
-        },
-        None,
+        }
+    }
+    anyhow::bail!(
+        "Goal '{}' not found in local or global configuration",
+        goal_name
     )
-    .with_context(|| {
-        format!(
-            "Goal '{}' not found in local or global configuration",
-            goal_name
-        )
-    })
+}
+
+/// Loads a goal from one of the goal sources, marking goals from the
+/// managed library as managed.
+fn load_source_goal(
+    base_dir: &Path,
+    goal_name: &str,
+    source: GoalSource,
+) -> Result<Option<PromptConfig>> {
+    let mut config = load_goal_config(base_dir, goal_name)?;
+    if source == GoalSource::Managed
+        && let Some(config) = &mut config
+    {
+        config.managed = true;
+    }
+    Ok(config)
 }
 
 /// Finds and loads the `claw.yaml` configuration, applying the cascade and defaults.
@@ -348,19 +1652,119 @@ pub fn find_and_load_goal(goal_name: &str) -> Result<LoadedGoal> {
 /// 2. If not found, falls back to the global `~/.config/claw/` directory.
 /// 3. If no file is found in either location, it returns `ClawConfig::default()`.
 /// This function always returns a valid configuration.
-pub fn find_and_load_claw_config() -> Result<ClawConfig> {
+///
+/// `profile` selects one of the config's `profiles`; if it is `None`, the
+/// `CLAW_PROFILE` environment variable is used.
+#[allow(clippy::doc_lazy_continuation)]
+pub fn find_and_load_claw_config(profile: Option<&str>) -> Result<ClawConfig> {
     let paths = ConfigPaths::new()?;
-    cascade_load_config(
-        &paths,
-        load_claw_config_from_dir,
-        Some(ClawConfig::default()),
-    )
+    let profile = profile
+        .map(str::to_string)
+        .or_else(|| env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()));
+
+    match profile {
+        Some(name) => cascade_load_config(
+            &paths,
+            |base_dir| load_claw_config_from_dir(base_dir, Some(&name)),
+            None,
+        )
+        .with_context(|| format!("Failed to load claw.yaml for profile '{}'", name)),
+        None => cascade_load_config(
+            &paths,
+            |base_dir| load_claw_config_from_dir(base_dir, None),
+            Some(ClawConfig::default()),
+        ),
+    }
+}
+
+/// Environment variable that selects a `claw.yaml` profile when `--profile`
+/// isn't given.
+pub const PROFILE_ENV: &str = "CLAW_PROFILE";
+
+/// Environment variable that skips first-run setup, like `--no-setup`, for
+/// containers and other throwaway homes.
+pub const NO_SETUP_ENV: &str = "CLAW_NO_FIRST_RUN_SETUP";
+
+/// Whether `CLAW_NO_FIRST_RUN_SETUP` is set to anything but empty, `0` or
+/// `false`.
+pub fn first_run_setup_disabled() -> bool {
+    env::var(NO_SETUP_ENV)
+        .is_ok_and(|value| !matches!(value.trim(), "" | "0") && !value.eq_ignore_ascii_case("false"))
 }
 
-/// Helper to attempt loading a `claw.yaml` from a single directory.
-fn load_claw_config_from_dir(base_dir: &Path) -> Result<Option<ClawConfig>> {
+/// Loads the repository policy from the local `.claw/policy.yaml`.
+///
+/// Policies are repository-wide by design, so there is no global fallback.
+/// Returns the permissive default policy if the file does not exist.
+pub fn find_and_load_policy() -> Result<Policy> {
+    match &ConfigPaths::new()?.local {
+        Some(local) => load_policy(local),
+        None => Ok(Policy::default()),
+    }
+}
+
+/// Loads the policy of the `.claw` directory `local`, with a relative audit
+/// log resolved.
+pub fn load_policy(local: &Path) -> Result<Policy> {
+    let mut policy: Policy = load_yaml_config(&paths::policy(local))?.unwrap_or_default();
+    // A relative audit log is kept in the repository, next to `.claw/`
+    if let Some(audit_log) = &mut policy.audit_log
+        && audit_log.is_relative()
+        && let Some(root) = local.parent()
+    {
+        *audit_log = root.join(&audit_log);
+    }
+    Ok(policy)
+}
+
+/// Helper to attempt loading a `claw.yaml` from a single directory, with
+/// the settings of `profile` applied.
+fn load_claw_config_from_dir(base_dir: &Path, profile: Option<&str>) -> Result<Option<ClawConfig>> {
     let path = paths::claw_config(base_dir);
-    load_yaml_config(&path)
+    let Some(mut value) = load_yaml_config::<serde_yaml::Value>(&path)? else {
+        return Ok(None);
+    };
+    if let Some(name) = profile {
+        apply_profile(&mut value, name).with_context(|| format!("In {}", path.display()))?;
+    }
+    let config = serde_yaml::from_value(value)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(config))
+}
+
+/// Replaces the top-level settings in a parsed `claw.yaml` with those of
+/// the profile `name`.
+fn apply_profile(config: &mut serde_yaml::Value, name: &str) -> Result<()> {
+    let profiles = config.get("profiles").and_then(serde_yaml::Value::as_mapping);
+    let Some(profile) = profiles.and_then(|profiles| profiles.get(name)) else {
+        let available: Vec<&str> = profiles
+            .into_iter()
+            .flat_map(|profiles| profiles.keys())
+            .filter_map(serde_yaml::Value::as_str)
+            .collect();
+        if available.is_empty() {
+            anyhow::bail!("Profile '{}' is not defined: there are no profiles", name);
+        }
+        anyhow::bail!(
+            "Profile '{}' is not defined. Available profiles: {}",
+            name,
+            available.join(", ")
+        );
+    };
+
+    let settings = match profile {
+        serde_yaml::Value::Null => serde_yaml::Mapping::new(),
+        serde_yaml::Value::Mapping(settings) => settings.clone(),
+        _ => anyhow::bail!("Profile '{}' must be a mapping of settings", name),
+    };
+    if let Some(config) = config.as_mapping_mut() {
+        for (key, value) in settings {
+            if key.as_str() != Some("profiles") {
+                config.insert(key, value);
+            }
+        }
+    }
+    Ok(())
 }
 
 impl fmt::Display for GoalSource {
@@ -368,6 +1772,7 @@ impl fmt::Display for GoalSource {
         match self {
             GoalSource::Local => write!(f, "local"),
             GoalSource::Global => write!(f, "global"),
+            GoalSource::Managed => write!(f, "managed"),
         }
     }
 }
@@ -378,10 +1783,16 @@ pub struct DiscoveredGoal {
     pub name: String,
     pub source: GoalSource,
     pub config: PromptConfig,
+    /// The installed collection the goal comes from, if any.
+    pub collection: Option<String>,
 }
 
 /// Scans a goals directory and returns discovered goals with the given source.
-fn scan_goals_dir(base_dir: &Path, source: GoalSource) -> Result<Vec<DiscoveredGoal>> {
+fn scan_goals_dir(
+    base_dir: &Path,
+    source: GoalSource,
+    collection: Option<&str>,
+) -> Result<Vec<DiscoveredGoal>> {
     let mut discovered = Vec::new();
     let goals_dir = base_dir.join("goals");
 
@@ -393,11 +1804,12 @@ fn scan_goals_dir(base_dir: &Path, source: GoalSource) -> Result<Vec<DiscoveredG
         let entry = entry?;
         if entry.file_type()?.is_dir() {
             let name = entry.file_name().to_string_lossy().to_string();
-            if let Some(config) = load_goal_config(base_dir, &name)? {
+            if let Some(config) = load_source_goal(base_dir, &name, source)? {
                 discovered.push(DiscoveredGoal {
                     name,
                     source,
                     config,
+                    collection: collection.map(str::to_string),
                 });
             }
         }
@@ -406,20 +1818,17 @@ fn scan_goals_dir(base_dir: &Path, source: GoalSource) -> Result<Vec<DiscoveredG
     Ok(discovered)
 }
 
-/// Scans local and global directories to find all available goals.
-/// Local goals with the same name as global goals will override them.
+/// Scans the local, managed and global directories, and their enabled
+/// collections, to find all available goals. Goals earlier in the lookup
+/// order override those with the same name.
 pub fn find_all_goals() -> Result<Vec<DiscoveredGoal>> {
     let paths = ConfigPaths::new()?;
     let mut discovered_goals = Vec::new();
 
-    // Priority 1: Find all local goals
-    if let Some(local_path) = &paths.local {
-        discovered_goals.extend(scan_goals_dir(local_path, GoalSource::Local)?);
-    }
-
-    // Priority 2: Find all global goals
-    if let Some(global_path) = &paths.global {
-        discovered_goals.extend(scan_goals_dir(global_path, GoalSource::Global)?);
+    for (base_dir, source) in paths.goal_sources() {
+        for (root, collection) in collections::goal_roots(base_dir) {
+            discovered_goals.extend(scan_goals_dir(&root, source, collection.as_deref())?);
+        }
     }
 
     // Sort goals alphabetically by name for a clean display
@@ -428,7 +1837,7 @@ pub fn find_all_goals() -> Result<Vec<DiscoveredGoal>> {
     Ok(discovered_goals)
 }
 
-fn find_assets_dir() -> Result<PathBuf> {
+pub fn find_assets_dir() -> Result<PathBuf> {
     let exe_path = env::current_exe().context("Failed to get current executable path")?;
 
     // Resolve symlinks to get the actual executable location.
@@ -505,8 +1914,9 @@ pub fn ensure_global_config_exists() -> Result<()> {
             return Ok(());
         }
 
-        // This is a first-time setup - show welcome message
-        println!(
+        // This is a first-time setup - show welcome message, on stderr so it
+        // doesn't end up in a piped prompt or response
+        eprintln!(
             "
 Welcome to claw! 🐾
 This looks like your first time. I'm creating a global config directory for you at:
@@ -532,11 +1942,13 @@ You can edit claw.yaml to change the underlying LLM command.
         // Copy entire assets directory contents to config directory
         fs_extra::dir::copy(&assets_dir, &config_dir, &copy_options)
             .context("Failed to copy assets to config directory")?;
+        // So `claw upgrade-goals` can tell these copies from edited ones
+        bundled_goals::record_current(&assets_dir, &config_dir)?;
 
         // Show success message with example command
-        println!("I've also added some example goals. Try one out by running:");
-        println!("claw example -- --topic=\"the history of the Rust programming language\"");
-        println!("--------------------------------------------------------------------");
+        eprintln!("I've also added some example goals. Try one out by running:");
+        eprintln!("claw example -- --topic=\"the history of the Rust programming language\"");
+        eprintln!("--------------------------------------------------------------------");
     }
     Ok(())
 }
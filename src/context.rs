@@ -1,24 +1,124 @@
-use anyhow::Result;
+use anyhow::{Context as AnyhowContext, Result};
 use content_inspector::{ContentType, inspect};
-use ignore::WalkBuilder;
+use ignore::{Match, WalkBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use termtree::Tree;
 
-use crate::config::ErrorHandlingMode;
+use crate::config::{ContextErrorPolicies, ErrorHandlingMode, ErrorPolicy};
+use crate::context_picker;
+use crate::notebook;
+use crate::param_prompt;
+use crate::provenance::{ProvenanceMap, Source};
 
 /// Configuration for context file discovery and processing.
 #[derive(Debug, Clone)]
 pub struct ContextConfig {
+    /// Files and directories, each optionally followed by root options, e.g.
+    /// `docs:depth=0,ext=md` (see `parse_root`).
     pub paths: Vec<PathBuf>,
     pub recurse_depth: Option<usize>,
+    /// Whether directory scans skip files ignored by `.gitignore` and hidden files.
+    pub respect_gitignore: bool,
     pub max_file_size_kb: u64,
     pub max_files_per_directory: usize,
+    /// Longer lines are truncated with a note; 0 keeps lines whole.
+    pub max_line_length: usize,
     pub error_handling_mode: ErrorHandlingMode,
+    pub error_policies: ContextErrorPolicies,
     pub excluded_directories: Vec<String>,
     pub excluded_extensions: Vec<String>,
+    /// Gitignore-style patterns of files included only after the user
+    /// confirms each one.
+    pub sensitive_paths: Vec<String>,
+    /// Whether to describe the limits above to the model in a Notes section.
+    pub notes: bool,
+    /// Whether files deeper than `recurse_depth` are listed in the tree
+    /// without their contents.
+    pub list_beyond_depth: bool,
+    /// Whether files are numbered and the model is asked to cite them.
+    pub citations: bool,
+}
+
+/// Settings for one `--context` root that override the run's, given as a
+/// suffix: `src:depth=2` or `docs:depth=0,ext=md,ext=txt`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RootOptions {
+    /// Recursion depth for this root instead of `--recurse_depth`.
+    pub depth: Option<usize>,
+    /// Only files with these extensions are included, if any are given.
+    pub extensions: Vec<String>,
+    /// Directory names skipped in this root, on top of `excluded_directories`.
+    pub excluded_directories: Vec<String>,
+}
+
+impl RootOptions {
+    fn is_empty(&self) -> bool {
+        *self == RootOptions::default()
+    }
+
+    /// Describes the options for the Notes section.
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(depth) = self.depth {
+            parts.push(format!("recursion depth {}", depth));
+        }
+        if !self.extensions.is_empty() {
+            parts.push(format!("only .{} files", self.extensions.join(", .")));
+        }
+        if !self.excluded_directories.is_empty() {
+            parts.push(format!("also excluding {}", self.excluded_directories.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Splits a `--context` argument into its path and root options.
+///
+/// The options follow the last `:` as comma-separated `key=value` pairs:
+/// `depth`, `ext` and `exclude`, the last two repeatable. An argument that
+/// names an existing path, or whose last `:` isn't followed by `key=value`,
+/// is taken as a plain path, so colons in file names keep working.
+pub fn parse_root(spec: &Path) -> Result<(PathBuf, RootOptions)> {
+    let text = spec.to_string_lossy();
+    let Some((path, suffix)) = text.rsplit_once(':') else {
+        return Ok((spec.to_path_buf(), RootOptions::default()));
+    };
+    if spec.exists() || path.is_empty() || !suffix.contains('=') {
+        return Ok((spec.to_path_buf(), RootOptions::default()));
+    }
+
+    let mut options = RootOptions::default();
+    for option in suffix.split(',') {
+        let (key, value) = option.split_once('=').unwrap_or((option, ""));
+        if value.is_empty() {
+            anyhow::bail!(
+                "Invalid context option '{}' in '{}': expected key=value",
+                option,
+                text
+            );
+        }
+        match key {
+            "depth" => {
+                options.depth = Some(value.parse().with_context(|| {
+                    format!("Invalid depth '{}' in '{}': expected a number", value, text)
+                })?)
+            }
+            "ext" => options
+                .extensions
+                .push(value.trim_start_matches('.').to_string()),
+            "exclude" => options.excluded_directories.push(value.to_string()),
+            _ => anyhow::bail!(
+                "Unknown context option '{}' in '{}': use depth, ext or exclude",
+                key,
+                text
+            ),
+        }
+    }
+    Ok((PathBuf::from(path), options))
 }
 
 /// Represents a discovered file with metadata.
@@ -27,6 +127,8 @@ pub struct DiscoveredFile {
     pub path: PathBuf,
     pub size: u64,
     pub relative_path: PathBuf,
+    /// Deeper than `recurse_depth`: shown in the tree, but not read.
+    pub listed_only: bool,
 }
 
 /// The content of a successfully read file.
@@ -50,11 +152,12 @@ pub enum ContextError {
         limit: u64,
     },
     TooManyFiles {
+        /// The file left out.
+        path: PathBuf,
         directory: PathBuf,
         count: usize,
         limit: usize,
     },
-    #[allow(dead_code)]
     BinaryFile(PathBuf),
     Utf8Error(PathBuf),
     IoError {
@@ -85,6 +188,7 @@ impl std::fmt::Display for ContextError {
                 directory,
                 count,
                 limit,
+                ..
             } => {
                 write!(
                     f,
@@ -107,12 +211,50 @@ impl std::fmt::Display for ContextError {
     }
 }
 
-/// Result of context processing, including files, errors, and warnings.
+impl ContextError {
+    /// The file (or, for too many files, the file left out) the error is about.
+    fn path(&self) -> &Path {
+        match self {
+            ContextError::FileNotFound(path)
+            | ContextError::PermissionDenied(path)
+            | ContextError::FileTooLarge { path, .. }
+            | ContextError::TooManyFiles { path, .. }
+            | ContextError::BinaryFile(path)
+            | ContextError::Utf8Error(path)
+            | ContextError::IoError { path, .. } => path,
+        }
+    }
+
+    /// Returns how this error should be handled: its class's policy from
+    /// `context_errors`, or the policy of the overall mode.
+    fn policy(&self, mode: &ErrorHandlingMode, policies: &ContextErrorPolicies) -> ErrorPolicy {
+        let configured = match self {
+            ContextError::FileNotFound(_) => None,
+            ContextError::PermissionDenied(_) => policies.on_permission_denied,
+            ContextError::FileTooLarge { .. } => policies.on_file_too_large,
+            ContextError::TooManyFiles { .. } => policies.on_too_many_files,
+            // Binary files have always been left out without asking
+            ContextError::BinaryFile(_) => {
+                return policies.on_binary.unwrap_or(ErrorPolicy::Skip);
+            }
+            ContextError::Utf8Error(_) => policies.on_invalid_utf8,
+            ContextError::IoError { .. } => policies.on_io_error,
+        };
+        configured.unwrap_or_else(|| mode.policy())
+    }
+}
+
+/// Result of context processing: the files that were read and the errors
+/// for the ones that were not.
 #[derive(Debug)]
 pub struct ContextResult {
     pub files: Vec<FileContent>,
     pub errors: Vec<ContextError>,
-    pub warnings: Vec<String>,
+    /// Files shown in the tree without their contents.
+    pub listed: Vec<PathBuf>,
+    /// Files left out for `max_file_size_kb` or `max_files_per_directory`,
+    /// which Flexible mode can read again with the limits raised.
+    pub over_limit: Vec<DiscoveredFile>,
 }
 
 /// Discovers files from the given paths, applying recursion and filtering rules.
@@ -120,7 +262,9 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
     let mut discovered = Vec::new();
     let cwd = std::env::current_dir()?;
 
-    for path in &config.paths {
+    for spec in &config.paths {
+        let (path, options) = parse_root(spec)?;
+        let path = &path;
         if !path.exists() {
             anyhow::bail!("Path does not exist: {}", path.display());
         }
@@ -133,15 +277,20 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
                 path: path.clone(),
                 size: metadata.len(),
                 relative_path: relative.to_path_buf(),
+                listed_only: false,
             });
         } else if path.is_dir() {
             // Directory: use walkdir with filters
-            let max_depth = config.recurse_depth.map(|d| d + 1);
+            let max_depth = options.depth.or(config.recurse_depth).map(|d| d + 1);
+            // Deeper files are still walked when they are to be listed
+            let walk_depth = max_depth.filter(|_| !config.list_beyond_depth);
 
             let mut builder = WalkBuilder::new(path);
-            builder.standard_filters(true); // Respects .gitignore
+            builder.standard_filters(config.respect_gitignore);
+            // Walk in name order, so the prompt is the same on every machine
+            builder.sort_by_file_name(|a, b| a.cmp(b));
 
-            if let Some(depth) = max_depth {
+            if let Some(depth) = walk_depth {
                 builder.max_depth(Some(depth));
             }
 
@@ -154,12 +303,20 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
                     continue;
                 }
 
-                // Check if file extension is excluded
-                if let Some(ext) = file_path.extension() {
-                    let ext_str = ext.to_string_lossy().to_string();
-                    if config.excluded_extensions.contains(&ext_str) {
-                        continue;
-                    }
+                // Check if file extension is excluded, or not among the
+                // root's own extensions
+                let ext_str = file_path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_string());
+                if let Some(ext_str) = &ext_str
+                    && config.excluded_extensions.contains(ext_str)
+                {
+                    continue;
+                }
+                if !options.extensions.is_empty()
+                    && !ext_str.is_some_and(|ext| options.extensions.contains(&ext))
+                {
+                    continue;
                 }
 
                 // Check if any parent directory is in excluded list
@@ -167,7 +324,9 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
                 for ancestor in file_path.ancestors() {
                     if let Some(name) = ancestor.file_name() {
                         let name_str = name.to_string_lossy().to_string();
-                        if config.excluded_directories.contains(&name_str) {
+                        if config.excluded_directories.contains(&name_str)
+                            || options.excluded_directories.contains(&name_str)
+                        {
                             skip = true;
                             break;
                         }
@@ -184,6 +343,7 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
                     path: file_path.to_path_buf(),
                     size: metadata.len(),
                     relative_path: relative.to_path_buf(),
+                    listed_only: max_depth.is_some_and(|depth| entry.depth() > depth),
                 });
             }
         }
@@ -192,6 +352,90 @@ pub fn discover_files(config: &ContextConfig) -> Result<Vec<DiscoveredFile>> {
     Ok(discovered)
 }
 
+/// Builds a matcher for `sensitive_paths` patterns. They use `.gitignore`
+/// syntax: `.env*` matches in any directory, `!` exempts a path.
+fn sensitive_matcher(patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new("");
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("Invalid sensitive_paths pattern '{}'", pattern))?;
+    }
+    builder.build().context("Invalid sensitive_paths patterns")
+}
+
+/// Returns the pattern that marks `path` as sensitive, matching the file
+/// itself or any of its directories.
+fn sensitive_pattern(matcher: &Gitignore, path: &Path) -> Option<String> {
+    path.ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .enumerate()
+        .find_map(|(i, ancestor)| {
+            match matcher.matched(ancestor, i > 0) {
+                Match::None => None,
+                // A `!pattern` exemption ends the search
+                Match::Whitelist(_) => Some(None),
+                Match::Ignore(glob) => Some(Some(glob.original().to_string())),
+            }
+        })
+        .flatten()
+}
+
+/// Drops the files matching `sensitive_paths` that the user doesn't
+/// confirm one by one. Without a terminal to ask on, they are all left out.
+pub fn confirm_sensitive_files(
+    files: Vec<DiscoveredFile>,
+    config: &ContextConfig,
+) -> Result<Vec<DiscoveredFile>> {
+    if config.sensitive_paths.is_empty() {
+        return Ok(files);
+    }
+    let matcher = sensitive_matcher(&config.sensitive_paths)?;
+
+    let mut kept = Vec::with_capacity(files.len());
+    for file in files {
+        // Only a listed file's name reaches the prompt, not its contents
+        let pattern = (!file.listed_only)
+            .then(|| sensitive_pattern(&matcher, &file.relative_path))
+            .flatten();
+        let Some(pattern) = pattern else {
+            kept.push(file);
+            continue;
+        };
+        match confirm_sensitive(&file.relative_path, &pattern)? {
+            Some(true) => kept.push(file),
+            Some(false) => eprintln!("Leaving out {}", file.relative_path.display()),
+            None => eprintln!(
+                "Leaving out {}: it matches sensitive_paths ('{}') and there is no terminal to confirm it.",
+                file.relative_path.display(),
+                pattern
+            ),
+        }
+    }
+    Ok(kept)
+}
+
+/// Asks on the terminal whether to include a sensitive file in the prompt.
+fn confirm_sensitive(path: &Path, pattern: &str) -> Result<Option<bool>> {
+    if !param_prompt::is_interactive() {
+        return Ok(None);
+    }
+
+    eprint!(
+        "⚠️  {} matches sensitive_paths ('{}'). Include it in the prompt? [y/N] ",
+        path.display(),
+        pattern
+    );
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(Some(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes"
+    )))
+}
+
 /// Checks if a file appears to be binary using content inspection.
 fn is_binary_file(path: &Path) -> io::Result<bool> {
     let mut file = fs::File::open(path)?;
@@ -213,21 +457,31 @@ pub fn validate_and_read_files(
     let mut result = ContextResult {
         files: Vec::new(),
         errors: Vec::new(),
-        warnings: Vec::new(),
+        listed: Vec::new(),
+        over_limit: Vec::new(),
     };
 
     // Track file counts per directory
     let mut dir_counts: HashMap<PathBuf, usize> = HashMap::new();
 
     for file in files {
+        if file.listed_only {
+            result.listed.push(file.relative_path);
+            continue;
+        }
+
+        // Notebooks are mostly outputs, so their limit applies once rendered
+        let is_notebook = notebook::is_notebook(&file.path);
+
         // Check file size limit
         let size_kb = file.size / 1024;
-        if size_kb > config.max_file_size_kb {
+        if !is_notebook && size_kb > config.max_file_size_kb {
             result.errors.push(ContextError::FileTooLarge {
                 path: file.path.clone(),
                 size: size_kb,
                 limit: config.max_file_size_kb,
             });
+            result.over_limit.push(file);
             continue;
         }
 
@@ -237,10 +491,12 @@ pub fn validate_and_read_files(
             *count += 1;
             if *count > config.max_files_per_directory {
                 result.errors.push(ContextError::TooManyFiles {
+                    path: file.path.clone(),
                     directory: parent.to_path_buf(),
                     count: *count,
                     limit: config.max_files_per_directory,
                 });
+                result.over_limit.push(file);
                 continue;
             }
         }
@@ -248,9 +504,7 @@ pub fn validate_and_read_files(
         // Check if binary file
         match is_binary_file(&file.path) {
             Ok(true) => {
-                result
-                    .warnings
-                    .push(format!("Skipped binary file: {}", file.path.display()));
+                result.errors.push(ContextError::BinaryFile(file.path.clone()));
                 continue;
             }
             Ok(false) => {}
@@ -271,11 +525,34 @@ pub fn validate_and_read_files(
 
         // Read file content
         match fs::read_to_string(&file.path) {
+            Ok(content) if is_notebook => match notebook::render(&content) {
+                Ok(rendered) => {
+                    let size_kb = rendered.len() as u64 / 1024;
+                    if size_kb > config.max_file_size_kb {
+                        result.errors.push(ContextError::FileTooLarge {
+                            path: file.path.clone(),
+                            size: size_kb,
+                            limit: config.max_file_size_kb,
+                        });
+                        result.over_limit.push(file);
+                    } else {
+                        result.files.push(FileContent {
+                            path: file.path,
+                            relative_path: file.relative_path,
+                            content: truncate_long_lines(rendered, config.max_line_length),
+                        });
+                    }
+                }
+                Err(e) => result.errors.push(ContextError::IoError {
+                    path: file.path,
+                    error: format!("{:#}", e),
+                }),
+            },
             Ok(content) => {
                 result.files.push(FileContent {
                     path: file.path,
                     relative_path: file.relative_path,
-                    content,
+                    content: truncate_long_lines(content, config.max_line_length),
                 });
             }
             Err(e) => {
@@ -298,82 +575,224 @@ pub fn validate_and_read_files(
     result
 }
 
-/// Handles errors based on the configured error handling mode.
-pub fn handle_errors(result: &ContextResult, mode: &ErrorHandlingMode) -> Result<bool> {
+/// Cuts lines longer than `max_length` characters, noting how long each
+/// was. A `max_length` of 0 keeps lines whole.
+fn truncate_long_lines(content: String, max_length: usize) -> String {
+    if max_length == 0 || content.lines().all(|line| line.len() <= max_length) {
+        return content;
+    }
+
+    let mut truncated = String::with_capacity(content.len().min(max_length * 64));
+    for line in content.split_inclusive('\n') {
+        let (text, ending) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+        let length = text.chars().count();
+        match text.char_indices().nth(max_length) {
+            Some((cut, _)) => truncated.push_str(&format!(
+                "{} [... line truncated: {} characters in total]{}",
+                &text[..cut],
+                length,
+                ending
+            )),
+            None => truncated.push_str(line),
+        }
+    }
+    truncated
+}
+
+/// Handles errors based on the configured error handling mode and the
+/// per-error-class policies.
+///
+/// Errors whose policy is `fail` abort processing; `skip` errors are logged;
+/// `ask` errors are listed and the user decides whether to continue. On a
+/// terminal, that's a checklist where files left out for a limit can be
+/// picked to read again with the limits raised.
+pub fn handle_errors(result: &mut ContextResult, config: &ContextConfig) -> Result<bool> {
     if result.errors.is_empty() {
         return Ok(true);
     }
 
-    match mode {
-        ErrorHandlingMode::Strict => {
-            // Fail immediately on any error
-            let error_messages: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
-            anyhow::bail!(
-                "Context processing failed with {} error(s):\n  {}",
-                result.errors.len(),
-                error_messages.join("\n  ")
-            );
+    let mut failed = Vec::new();
+    let mut asked = Vec::new();
+    let mut skipped = Vec::new();
+    for error in &result.errors {
+        match error.policy(&config.error_handling_mode, &config.error_policies) {
+            ErrorPolicy::Fail => failed.push(error),
+            ErrorPolicy::Ask => asked.push(error),
+            ErrorPolicy::Skip => skipped.push(error),
         }
-        ErrorHandlingMode::Flexible => {
-            // Display errors and warnings, then prompt user
-            eprintln!("\n⚠️  Context Processing Issues Detected:");
-            eprintln!("=====================================");
-
-            if !result.errors.is_empty() {
-                eprintln!("\nErrors ({}):", result.errors.len());
-                for error in &result.errors {
-                    eprintln!("  • {}", error);
-                }
-            }
-
-            if !result.warnings.is_empty() {
-                eprintln!("\nWarnings ({}):", result.warnings.len());
-                for warning in &result.warnings {
-                    eprintln!("  • {}", warning);
-                }
-            }
+    }
 
-            eprintln!("\nSuccessfully processed {} file(s).", result.files.len());
-            eprintln!("\nDo you want to continue with the available files? (y/n): ");
+    if !failed.is_empty() {
+        // Fail immediately on any error
+        let error_messages: Vec<String> = failed.iter().map(|e| e.to_string()).collect();
+        anyhow::bail!(
+            "Context processing failed with {} error(s):\n  {}",
+            failed.len(),
+            error_messages.join("\n  ")
+        );
+    }
 
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            let input = input.trim().to_lowercase();
+    if !skipped.is_empty() {
+        // Log and continue
+        eprintln!("\n⚠️  Errors (ignored):");
+        for error in &skipped {
+            eprintln!("  • {}", error);
+        }
+    }
 
-            if input == "y" || input == "yes" {
-                Ok(true)
-            } else {
-                anyhow::bail!("Context processing aborted by user.");
-            }
+    let mut retry = Vec::new();
+    if !asked.is_empty() && param_prompt::is_interactive() {
+        let over_limit = |error: &ContextError| {
+            result
+                .over_limit
+                .iter()
+                .find(|file| file.path == error.path())
+        };
+        let issues: Vec<context_picker::Issue> = asked
+            .iter()
+            .map(|error| context_picker::Issue {
+                description: error.to_string(),
+                retryable: over_limit(error).is_some(),
+            })
+            .collect();
+        let chosen = context_picker::choose_retries(&issues, result.files.len())?;
+        retry = asked
+            .iter()
+            .zip(chosen)
+            .filter(|(_, chosen)| *chosen)
+            .filter_map(|(error, _)| over_limit(error).cloned())
+            .collect();
+    } else if !asked.is_empty() {
+        // Display errors, then prompt user
+        eprintln!("\n⚠️  Context Processing Issues Detected:");
+        eprintln!("=====================================");
+
+        eprintln!("\nErrors ({}):", asked.len());
+        for error in &asked {
+            eprintln!("  • {}", error);
         }
-        ErrorHandlingMode::Ignore => {
-            // Log warnings and continue
-            if !result.warnings.is_empty() {
-                eprintln!("\n⚠️  Warnings:");
-                for warning in &result.warnings {
-                    eprintln!("  • {}", warning);
-                }
-            }
-            if !result.errors.is_empty() {
-                eprintln!("\n⚠️  Errors (ignored):");
-                for error in &result.errors {
-                    eprintln!("  • {}", error);
-                }
-            }
-            Ok(true)
+
+        eprintln!("\nSuccessfully processed {} file(s).", result.files.len());
+        eprintln!("\nDo you want to continue with the available files? (y/n): ");
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+
+        if input != "y" && input != "yes" {
+            anyhow::bail!("Context processing aborted by user.");
         }
     }
+
+    include_with_raised_limits(result, retry, config);
+    Ok(true)
+}
+
+/// Reads `files`, which were left out for a limit, again without the size
+/// and per-directory limits, adding those that can be read to `result`.
+fn include_with_raised_limits(
+    result: &mut ContextResult,
+    files: Vec<DiscoveredFile>,
+    config: &ContextConfig,
+) {
+    if files.is_empty() {
+        return;
+    }
+    let raised = ContextConfig {
+        max_file_size_kb: u64::MAX,
+        max_files_per_directory: usize::MAX,
+        ..config.clone()
+    };
+    let retried = validate_and_read_files(files, &raised);
+    for error in &retried.errors {
+        eprintln!("⚠️  Still leaving out {}", error);
+    }
+    for file in retried.files {
+        eprintln!(
+            "Including {} with the limits raised",
+            file.relative_path.display()
+        );
+        result.errors.retain(|error| error.path() != file.path);
+        result.over_limit.retain(|left_out| left_out.path != file.path);
+        result.files.push(file);
+    }
 }
 
 /// Formats the context result as markdown for inclusion in the LLM prompt.
-pub fn format_context(result: &ContextResult, config: &ContextConfig) -> String {
+///
+/// Also returns a provenance map recording which byte ranges of the output
+/// hold each file's contents.
+pub fn format_context(result: &ContextResult, config: &ContextConfig) -> (String, ProvenanceMap) {
     // Load the static header template at compile time
     const HEADER_TEMPLATE: &str = include_str!("../prompts/context_header.md");
 
     let mut output = String::from(HEADER_TEMPLATE);
 
-    // Build the Notes section dynamically
-    output.push_str("\n\n## Notes\n");
+    output.push_str("\n\n");
+    if config.notes {
+        push_notes(&mut output, config);
+    }
+
+    output.push_str("---\n\n");
+
+    // Generate directory tree
+    output.push_str("## Directory Structure\n\n");
+    output.push_str("```\n");
+    output.push_str(&generate_tree(&result.files, &result.listed));
+    output.push_str("```\n\n");
+
+    output.push_str("---\n\n");
+
+    // Individual files
+    let mut provenance = ProvenanceMap::default();
+    let mut header_start = 0;
+    output.push_str("## Files\n\n");
+    for (index, file) in result.files.iter().enumerate() {
+        if config.citations {
+            output.push_str(&format!(
+                "### [{}] {}\n\n",
+                index + 1,
+                file.relative_path.display()
+            ));
+        } else {
+            output.push_str(&format!("### {}\n\n", file.relative_path.display()));
+        }
+        output.push_str("```\n");
+        provenance.push(header_start, output.len(), Source::ContextHeader);
+        let start = output.len();
+        output.push_str(&file.content);
+        provenance.push(
+            start,
+            output.len(),
+            Source::ContextFile {
+                path: file.relative_path.clone(),
+            },
+        );
+        if !file.content.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str("```\n\n");
+        header_start = start + file.content.len();
+    }
+    if config.citations && !result.files.is_empty() {
+        output.push_str(CITATION_INSTRUCTIONS);
+    }
+    provenance.push(header_start, output.len(), Source::ContextHeader);
+
+    (output, provenance)
+}
+
+/// Asks the model to cite the numbered files, appended after them.
+const CITATION_INSTRUCTIONS: &str = "## Citations\n\n\
+Each file above is numbered, e.g. `### [1] src/main.rs`. When a statement relies on a file, \
+cite its number in square brackets, e.g. [1] or [2, 3], so it can be checked against the source.\n";
+
+/// Appends the Notes section describing the limits applied to the context.
+fn push_notes(output: &mut String, config: &ContextConfig) {
+    output.push_str("## Notes\n");
     output.push_str(&format!(
         "- Maximum file size: {} KB\n",
         config.max_file_size_kb
@@ -382,6 +801,12 @@ pub fn format_context(result: &ContextResult, config: &ContextConfig) -> String
         "- Maximum files per directory: {}\n",
         config.max_files_per_directory
     ));
+    if config.max_line_length > 0 {
+        output.push_str(&format!(
+            "- Maximum line length: {} characters; longer lines are truncated\n",
+            config.max_line_length
+        ));
+    }
     output.push_str(&format!(
         "- Excluded directories: {}\n",
         config.excluded_directories.join(", ")
@@ -391,40 +816,36 @@ pub fn format_context(result: &ContextResult, config: &ContextConfig) -> String
         config.excluded_extensions.join(", ")
     ));
     output.push_str(&format!(
-        "- Recursion depth: {}\n\n",
+        "- Recursion depth: {}\n",
         config
             .recurse_depth
             .map_or("unlimited".to_string(), |d| d.to_string())
     ));
-
-    output.push_str("---\n\n");
-
-    // Generate directory tree
-    output.push_str("## Directory Structure\n\n");
-    output.push_str("```\n");
-    output.push_str(&generate_tree(&result.files));
-    output.push_str("```\n\n");
-
-    output.push_str("---\n\n");
-
-    // Individual files
-    output.push_str("## Files\n\n");
-    for file in &result.files {
-        output.push_str(&format!("### {}\n\n", file.relative_path.display()));
-        output.push_str("```\n");
-        output.push_str(&file.content);
-        if !file.content.ends_with('\n') {
-            output.push('\n');
+    let mut any_depth = config.recurse_depth.is_some();
+    for spec in &config.paths {
+        if let Ok((path, options)) = parse_root(spec)
+            && !options.is_empty()
+        {
+            any_depth |= options.depth.is_some();
+            output.push_str(&format!("- In {}: {}\n", path.display(), options.describe()));
         }
-        output.push_str("```\n\n");
     }
-
-    output
+    if config.list_beyond_depth && any_depth {
+        output.push_str(&format!(
+            "- Deeper files are listed in the tree, marked {}, without their contents\n",
+            LISTED_ONLY_MARK
+        ));
+    }
+    output.push('\n');
 }
 
-/// Generates a tree structure from file paths using termtree.
-fn generate_tree(files: &[FileContent]) -> String {
-    if files.is_empty() {
+/// Marks files in the directory tree whose contents are not included.
+const LISTED_ONLY_MARK: &str = "(contents not included)";
+
+/// Generates a tree structure from file paths using termtree. `listed`
+/// files are marked as having no contents in the prompt.
+fn generate_tree(files: &[FileContent], listed: &[PathBuf]) -> String {
+    if files.is_empty() && listed.is_empty() {
         return String::from("(no files)");
     }
 
@@ -433,7 +854,11 @@ fn generate_tree(files: &[FileContent]) -> String {
 
     for file in files {
         let components: Vec<_> = file.relative_path.components().collect();
-        insert_path(&mut root, &components);
+        insert_path(&mut root, &components, false);
+    }
+    for path in listed {
+        let components: Vec<_> = path.components().collect();
+        insert_path(&mut root, &components, true);
     }
 
     // Convert the HashMap tree to termtree format
@@ -456,11 +881,16 @@ fn generate_tree(files: &[FileContent]) -> String {
 
 #[derive(Debug)]
 enum Node {
-    File,
+    /// A file; `true` if only listed, without its contents.
+    File(bool),
     Directory(HashMap<String, Node>),
 }
 
-fn insert_path(tree: &mut HashMap<String, Node>, components: &[std::path::Component]) {
+fn insert_path(
+    tree: &mut HashMap<String, Node>,
+    components: &[std::path::Component],
+    listed_only: bool,
+) {
     if components.is_empty() {
         return;
     }
@@ -469,7 +899,7 @@ fn insert_path(tree: &mut HashMap<String, Node>, components: &[std::path::Compon
 
     if components.len() == 1 {
         // This is a file
-        tree.insert(name, Node::File);
+        tree.insert(name, Node::File(listed_only));
     } else {
         // This is a directory path
         let subtree = tree
@@ -477,14 +907,15 @@ fn insert_path(tree: &mut HashMap<String, Node>, components: &[std::path::Compon
             .or_insert_with(|| Node::Directory(HashMap::new()));
 
         if let Node::Directory(children) = subtree {
-            insert_path(children, &components[1..]);
+            insert_path(children, &components[1..], listed_only);
         }
     }
 }
 
 fn build_termtree(name: String, node: &Node) -> Tree<String> {
     match node {
-        Node::File => Tree::new(name),
+        Node::File(false) => Tree::new(name),
+        Node::File(true) => Tree::new(format!("{} {}", name, LISTED_ONLY_MARK)),
         Node::Directory(children) => {
             let mut tree = Tree::new(format!("{}/", name));
             let mut child_trees: Vec<_> = children
@@ -509,6 +940,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_is_binary_detection() {
         // Create temp directory with test files
         let temp_dir = std::env::temp_dir().join("claw_test_binary");
@@ -527,4 +959,319 @@ mod tests {
         // Cleanup
         std::fs::remove_dir_all(&temp_dir).unwrap();
     }
+
+    fn config_with(mode: ErrorHandlingMode, policies: ContextErrorPolicies) -> ContextConfig {
+        ContextConfig {
+            paths: Vec::new(),
+            recurse_depth: None,
+            respect_gitignore: true,
+            max_file_size_kb: 1024,
+            max_files_per_directory: 50,
+            max_line_length: 2000,
+            error_handling_mode: mode,
+            error_policies: policies,
+            excluded_directories: Vec::new(),
+            excluded_extensions: Vec::new(),
+            sensitive_paths: Vec::new(),
+            notes: true,
+            list_beyond_depth: false,
+            citations: false,
+        }
+    }
+
+    #[test]
+    fn test_sensitive_pattern_matches_files_and_directories() {
+        let patterns: Vec<String> = ["**/.env*", "**/secrets/**", "id_rsa", "!.env.example"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let matcher = sensitive_matcher(&patterns).unwrap();
+        let pattern = |path: &str| sensitive_pattern(&matcher, Path::new(path));
+
+        assert_eq!(pattern(".env").as_deref(), Some("**/.env*"));
+        assert_eq!(pattern("app/.env.local").as_deref(), Some("**/.env*"));
+        assert_eq!(pattern("config/secrets/db.yaml").as_deref(), Some("**/secrets/**"));
+        assert_eq!(pattern("/home/me/.ssh/id_rsa").as_deref(), Some("id_rsa"));
+        assert_eq!(pattern(".env.example"), None);
+        assert_eq!(pattern("src/main.rs"), None);
+        assert_eq!(pattern("src/environment.rs"), None);
+        assert!(sensitive_matcher(&["a/**/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_root_options() {
+        let (path, options) = parse_root(Path::new("docs:depth=0,ext=md,ext=.txt")).unwrap();
+        assert_eq!(path, PathBuf::from("docs"));
+        assert_eq!(
+            options,
+            RootOptions {
+                depth: Some(0),
+                extensions: vec!["md".to_string(), "txt".to_string()],
+                excluded_directories: Vec::new(),
+            }
+        );
+        assert_eq!(options.describe(), "recursion depth 0; only .md, .txt files");
+
+        // Colons without options stay part of the path
+        for plain in ["src", "notes:draft.md", "C:\\src", ":depth=1"] {
+            let (path, options) = parse_root(Path::new(plain)).unwrap();
+            assert_eq!(path, PathBuf::from(plain));
+            assert!(options.is_empty());
+        }
+
+        assert!(parse_root(Path::new("src:depth=two")).is_err());
+        assert!(parse_root(Path::new("src:depth=")).is_err());
+        assert!(parse_root(Path::new("src:size=2")).is_err());
+    }
+
+    #[test]
+    fn test_roots_have_their_own_depth_and_filters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::create_dir_all(root.join("docs/drafts")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/deep/inner.rs"), "").unwrap();
+        fs::write(root.join("docs/index.md"), "").unwrap();
+        fs::write(root.join("docs/logo.svg"), "").unwrap();
+        fs::write(root.join("docs/guide/intro.md"), "").unwrap();
+        fs::write(root.join("docs/drafts/wip.md"), "").unwrap();
+
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.paths = vec![
+            root.join("src"),
+            PathBuf::from(format!("{}:ext=md,exclude=drafts", root.join("docs").display())),
+        ];
+        config.recurse_depth = Some(0);
+        let names = |config: &ContextConfig| -> Vec<String> {
+            discover_files(config)
+                .unwrap()
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+        assert_eq!(names(&config), vec!["lib.rs", "index.md"]);
+
+        config.paths[1] = PathBuf::from(format!(
+            "{}:depth=1,ext=md,exclude=drafts",
+            root.join("docs").display()
+        ));
+        assert_eq!(names(&config), vec!["lib.rs", "intro.md", "index.md"]);
+    }
+
+    #[test]
+    fn test_truncate_long_lines() {
+        let long = "x".repeat(10);
+        let content = format!("short\n{}\nnext", long);
+        assert_eq!(
+            truncate_long_lines(content.clone(), 4),
+            "shor [... line truncated: 5 characters in total]\n\
+             xxxx [... line truncated: 10 characters in total]\n\
+             next"
+        );
+        assert_eq!(truncate_long_lines(content.clone(), 10), content);
+        assert_eq!(truncate_long_lines(content.clone(), 0), content);
+        assert_eq!(
+            truncate_long_lines("ééé\n".to_string(), 2),
+            "éé [... line truncated: 3 characters in total]\n"
+        );
+    }
+
+    #[test]
+    fn test_notebooks_are_rendered_and_limited_by_rendered_size() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let blob = "A".repeat(4096);
+        let json = format!(
+            r#"{{"cells": [{{"cell_type": "code", "source": "plot()", "outputs": [{{"data": {{"image/png": "{}"}}}}]}}]}}"#,
+            blob
+        );
+        fs::write(root.join("analysis.ipynb"), json).unwrap();
+
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.paths = vec![root.to_path_buf()];
+        config.max_file_size_kb = 1;
+        let result = validate_and_read_files(discover_files(&config).unwrap(), &config);
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.files.len(), 1);
+        assert!(result.files[0].content.contains("plot()"));
+        assert!(!result.files[0].content.contains(&blob));
+    }
+
+    #[test]
+    fn test_over_limit_files_can_be_included_with_raised_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("big.log"), "x".repeat(4096)).unwrap();
+        fs::write(root.join("image.png"), [0u8, 159, 146, 150]).unwrap();
+
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.paths = vec![root.to_path_buf()];
+        config.max_file_size_kb = 1;
+        let mut result = validate_and_read_files(discover_files(&config).unwrap(), &config);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.over_limit.len(), 1);
+
+        let retry = result.over_limit.clone();
+        include_with_raised_limits(&mut result, retry, &config);
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].path, root.join("big.log"));
+        assert!(result.over_limit.is_empty());
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(result.errors[0], ContextError::BinaryFile(_)));
+    }
+
+    #[test]
+    fn test_discover_files_can_include_gitignored_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "coverage/\n").unwrap();
+        fs::create_dir(root.join("coverage")).unwrap();
+        fs::write(root.join("coverage/report.txt"), "92%").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.paths = vec![root.to_path_buf()];
+        config.excluded_directories = vec![".git".to_string()];
+        let names = |config: &ContextConfig| {
+            let mut names: Vec<String> = discover_files(config)
+                .unwrap()
+                .iter()
+                .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(&config), vec!["main.rs"]);
+
+        config.respect_gitignore = false;
+        assert_eq!(names(&config), vec![".gitignore", "main.rs", "report.txt"]);
+    }
+
+    #[test]
+    fn test_files_beyond_depth_are_listed_without_contents() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod deep;").unwrap();
+        fs::write(root.join("src/deep/hidden.rs"), "fn secret_body() {}").unwrap();
+
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.paths = vec![root.to_path_buf()];
+        config.recurse_depth = Some(0);
+        config.list_beyond_depth = true;
+        let result = validate_and_read_files(discover_files(&config).unwrap(), &config);
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.listed.len(), 2);
+
+        let (output, _) = format_context(&result, &config);
+        assert!(output.contains("main.rs\n"));
+        assert!(output.contains("lib.rs (contents not included)"));
+        assert!(output.contains("hidden.rs (contents not included)"));
+        assert!(!output.contains("secret_body"));
+        assert!(output.contains("Deeper files are listed in the tree"));
+
+        config.list_beyond_depth = false;
+        let files = discover_files(&config).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_format_context_numbers_files_for_citations() {
+        let result = ContextResult {
+            files: vec![
+                FileContent {
+                    path: PathBuf::from("a.rs"),
+                    relative_path: PathBuf::from("a.rs"),
+                    content: "fn a() {}".to_string(),
+                },
+                FileContent {
+                    path: PathBuf::from("b.rs"),
+                    relative_path: PathBuf::from("b.rs"),
+                    content: "fn b() {}".to_string(),
+                },
+            ],
+            errors: Vec::new(),
+            listed: Vec::new(),
+            over_limit: Vec::new(),
+        };
+        let mut config = config_with(ErrorHandlingMode::Flexible, ContextErrorPolicies::default());
+        config.citations = true;
+        let (output, _) = format_context(&result, &config);
+        assert!(output.contains("### [1] a.rs\n"));
+        assert!(output.contains("### [2] b.rs\n"));
+        assert!(output.ends_with(CITATION_INSTRUCTIONS));
+
+        config.citations = false;
+        let (output, _) = format_context(&result, &config);
+        assert!(output.contains("### a.rs\n"));
+        assert!(!output.contains("## Citations"));
+    }
+
+    #[test]
+    fn test_error_class_policies_override_mode() {
+        let policies = ContextErrorPolicies {
+            on_file_too_large: Some(ErrorPolicy::Skip),
+            on_permission_denied: Some(ErrorPolicy::Fail),
+            ..Default::default()
+        };
+        let too_large = ContextError::FileTooLarge {
+            path: PathBuf::from("big.log"),
+            size: 2048,
+            limit: 1024,
+        };
+        let denied = ContextError::PermissionDenied(PathBuf::from("secret"));
+        let utf8 = ContextError::Utf8Error(PathBuf::from("latin1.txt"));
+        let binary = ContextError::BinaryFile(PathBuf::from("image.png"));
+
+        let mode = ErrorHandlingMode::Flexible;
+        assert_eq!(too_large.policy(&mode, &policies), ErrorPolicy::Skip);
+        assert_eq!(denied.policy(&mode, &policies), ErrorPolicy::Fail);
+        assert_eq!(utf8.policy(&mode, &policies), ErrorPolicy::Ask);
+        assert_eq!(binary.policy(&ErrorHandlingMode::Strict, &policies), ErrorPolicy::Skip);
+    }
+
+    #[test]
+    fn test_handle_errors_applies_policies() {
+        let mut result = ContextResult {
+            files: Vec::new(),
+            errors: vec![
+                ContextError::BinaryFile(PathBuf::from("image.png")),
+                ContextError::FileTooLarge {
+                    path: PathBuf::from("big.log"),
+                    size: 2048,
+                    limit: 1024,
+                },
+            ],
+            listed: Vec::new(),
+            over_limit: Vec::new(),
+        };
+
+        // Strict mode, but large files are explicitly skipped: nothing to ask or fail
+        let config = config_with(
+            ErrorHandlingMode::Strict,
+            ContextErrorPolicies {
+                on_file_too_large: Some(ErrorPolicy::Skip),
+                ..Default::default()
+            },
+        );
+        assert!(handle_errors(&mut result, &config).unwrap());
+
+        // Ignore mode, but binary files are fatal
+        let config = config_with(
+            ErrorHandlingMode::Ignore,
+            ContextErrorPolicies {
+                on_binary: Some(ErrorPolicy::Fail),
+                ..Default::default()
+            },
+        );
+        let err = handle_errors(&mut result, &config).unwrap_err();
+        assert!(err.to_string().contains("image.png"));
+        assert!(!err.to_string().contains("big.log"));
+    }
 }
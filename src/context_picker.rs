@@ -0,0 +1,188 @@
+//! The checklist Flexible mode shows for context problems on a terminal.
+//!
+//! Each problem is a line. Files left out for `max_file_size_kb` or
+//! `max_files_per_directory` can be checked to read them again with the
+//! limits raised; the rest (binary, unreadable) are listed but left out.
+
+use anyhow::{Context as AnyhowContext, Result, bail};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::io;
+
+/// One context problem in the checklist.
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub description: String,
+    /// Whether the file can be read again with the limits raised.
+    pub retryable: bool,
+}
+
+/// Asks which of `issues` to retry, with `included` files already read.
+/// Returns one flag per issue, or an error if the user aborts.
+pub fn choose_retries(issues: &[Issue], included: usize) -> Result<Vec<bool>> {
+    // Drawn on stderr, so stdout can stay redirected
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    let backend = CrosstermBackend::new(stderr);
+    let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
+
+    let mut picker = Picker::new(issues);
+    let result = run_picker(&mut terminal, &mut picker, included);
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    result?;
+    Ok(picker.retry)
+}
+
+/// What a key press did to the checklist.
+#[derive(Debug, PartialEq)]
+enum PickerAction {
+    Continue,
+    Confirm,
+    Abort,
+}
+
+/// The issues and which of them to retry.
+struct Picker<'a> {
+    issues: &'a [Issue],
+    retry: Vec<bool>,
+    cursor: usize,
+}
+
+impl<'a> Picker<'a> {
+    fn new(issues: &'a [Issue]) -> Self {
+        Picker {
+            issues,
+            retry: vec![false; issues.len()],
+            cursor: 0,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> PickerAction {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor = (self.cursor + 1).min(self.issues.len().saturating_sub(1))
+            }
+            KeyCode::Char(' ') => {
+                if self.issues.get(self.cursor).is_some_and(|issue| issue.retryable) {
+                    self.retry[self.cursor] = !self.retry[self.cursor];
+                }
+            }
+            KeyCode::Char('a') => {
+                let retryable = || self.issues.iter().map(|issue| issue.retryable);
+                let all = retryable()
+                    .zip(&self.retry)
+                    .all(|(retryable, &retry)| !retryable || retry);
+                self.retry = retryable().map(|retryable| retryable && !all).collect();
+            }
+            KeyCode::Enter => return PickerAction::Confirm,
+            KeyCode::Esc | KeyCode::Char('q') => return PickerAction::Abort,
+            _ => {}
+        }
+        PickerAction::Continue
+    }
+}
+
+fn run_picker<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    picker: &mut Picker,
+    included: usize,
+) -> Result<()> {
+    loop {
+        terminal.draw(|f| render_picker(f, picker, included))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match picker.handle_key(key.code) {
+                PickerAction::Continue => {}
+                PickerAction::Confirm => return Ok(()),
+                PickerAction::Abort => bail!("Context processing aborted by user."),
+            }
+        }
+    }
+}
+
+fn render_picker(frame: &mut Frame, picker: &Picker, included: usize) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.area());
+
+    let items: Vec<ListItem> = picker
+        .issues
+        .iter()
+        .zip(&picker.retry)
+        .map(|(issue, &retry)| {
+            let (mark, style) = match (issue.retryable, retry) {
+                (true, true) => ("[x]", Style::default()),
+                (true, false) => ("[ ]", Style::default()),
+                (false, _) => ("   ", Style::default().fg(Color::DarkGray)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", mark), style),
+                Span::styled(issue.description.clone(), style),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Context issues: check files to include with the limits raised ({} file(s) read) ",
+            included
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(picker.cursor));
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    let help = Paragraph::new(" ↑/↓ move  Space toggle  a all/none  Enter continue  Esc abort")
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(help, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(description: &str, retryable: bool) -> Issue {
+        Issue {
+            description: description.to_string(),
+            retryable,
+        }
+    }
+
+    #[test]
+    fn test_only_retryable_issues_can_be_checked() {
+        let issues = [
+            issue("File too large: big.log", true),
+            issue("Binary file skipped: logo.png", false),
+            issue("Too many files in directory: src", true),
+        ];
+        let mut picker = Picker::new(&issues);
+        picker.handle_key(KeyCode::Char(' '));
+        picker.handle_key(KeyCode::Down);
+        picker.handle_key(KeyCode::Char(' '));
+        assert_eq!(picker.retry, vec![true, false, false]);
+
+        picker.handle_key(KeyCode::Char('a'));
+        assert_eq!(picker.retry, vec![true, false, true]);
+        picker.handle_key(KeyCode::Char('a'));
+        assert_eq!(picker.retry, vec![false, false, false]);
+        assert_eq!(picker.handle_key(KeyCode::Esc), PickerAction::Abort);
+    }
+}
@@ -0,0 +1,180 @@
+//! The `date` and `datetime` template filters.
+//!
+//! Both format a timestamp in the local timezone unless given another one,
+//! unlike Tera's built-in `date`, which formats in UTC, so a standup note
+//! written just after midnight gets today's date:
+//!
+//! ```text
+//! {{ now() | date }}                                   2026-10-17
+//! {{ now() | datetime(timezone="America/New_York") }}  2026-10-17 09:30
+//! {{ "2026-10-17T08:00:00Z" | date(format="%A %e %B") }}
+//! ```
+//!
+//! A timestamp can be seconds since the epoch, an RFC 3339 string (what
+//! `now()` returns), `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD`. The last two have
+//! no timezone, so they are formatted as written.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use tera::{Tera, Value};
+
+/// The format `date` uses without a `format` argument.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// The format `datetime` uses without a `format` argument.
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Adds the `date` and `datetime` filters to `tera`, replacing its built-in
+/// `date`.
+pub fn register(tera: &mut Tera) {
+    tera.register_filter("date", |value: &Value, args: &HashMap<String, Value>| {
+        format_filter("date", DATE_FORMAT, value, args)
+    });
+    tera.register_filter(
+        "datetime",
+        |value: &Value, args: &HashMap<String, Value>| {
+            format_filter("datetime", DATETIME_FORMAT, value, args)
+        },
+    );
+}
+
+/// Renders `template` once with `context`, like `Tera::one_off`, with the
+/// date filters available.
+pub fn render_str(template: &str, context: &tera::Context) -> tera::Result<String> {
+    let mut tera = Tera::default();
+    register(&mut tera);
+    tera.render_str(template, context)
+}
+
+/// A parsed timestamp: an instant, or a date and time without a timezone.
+enum Timestamp {
+    Instant(DateTime<FixedOffset>),
+    Naive(NaiveDateTime),
+}
+
+fn format_filter(
+    filter: &str,
+    default_format: &str,
+    value: &Value,
+    args: &HashMap<String, Value>,
+) -> tera::Result<Value> {
+    let format = match args.get("format") {
+        Some(Value::String(format)) => format.as_str(),
+        Some(other) => {
+            return Err(format!(
+                "Filter `{}`: `format` must be a string, got {}",
+                filter, other
+            )
+            .into());
+        }
+        None => default_format,
+    };
+    // Reject bad formats here, since chrono panics on them while formatting
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(format!("Filter `{}`: invalid format \"{}\"", filter, format).into());
+    }
+    let timezone = match args.get("timezone") {
+        Some(Value::String(timezone)) => Some(timezone.as_str()),
+        Some(other) => {
+            return Err(format!(
+                "Filter `{}`: `timezone` must be a string, got {}",
+                filter, other
+            )
+            .into());
+        }
+        None => None,
+    };
+
+    let formatted = match parse_timestamp(value)
+        .ok_or_else(|| format!("Filter `{}`: can't read {} as a date", filter, value))?
+    {
+        Timestamp::Naive(naive) => naive.format(format).to_string(),
+        Timestamp::Instant(instant) => match timezone {
+            None | Some("local") => instant.with_timezone(&Local).format(format).to_string(),
+            Some(name) => {
+                let tz: Tz = name
+                    .parse()
+                    .map_err(|_| format!("Filter `{}`: unknown timezone \"{}\"", filter, name))?;
+                instant.with_timezone(&tz).format(format).to_string()
+            }
+        },
+    };
+    Ok(Value::String(formatted))
+}
+
+fn parse_timestamp(value: &Value) -> Option<Timestamp> {
+    match value {
+        Value::Number(number) => {
+            let instant = Utc.timestamp_opt(number.as_i64()?, 0).single()?;
+            Some(Timestamp::Instant(instant.fixed_offset()))
+        }
+        Value::String(text) => {
+            if let Ok(instant) = DateTime::parse_from_rfc3339(text) {
+                Some(Timestamp::Instant(instant))
+            } else if let Ok(naive) = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+                .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+            {
+                Some(Timestamp::Naive(naive))
+            } else {
+                let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+                Some(Timestamp::Naive(date.and_hms_opt(0, 0, 0)?))
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Context;
+
+    fn render(template: &str) -> tera::Result<String> {
+        render_str(template, &Context::new())
+    }
+
+    #[test]
+    fn test_date_filters_use_the_given_timezone_and_format() {
+        // 03:30 UTC is still the previous day in New York
+        let stamp = "\"2026-10-17T03:30:00Z\"";
+        assert_eq!(
+            render(&format!("{{{{ {} | date(timezone=\"UTC\") }}}}", stamp)).unwrap(),
+            "2026-10-17"
+        );
+        assert_eq!(
+            render(&format!(
+                "{{{{ {} | datetime(timezone=\"America/New_York\") }}}}",
+                stamp
+            ))
+            .unwrap(),
+            "2026-10-16 23:30"
+        );
+        assert_eq!(
+            render("{{ 0 | date(format=\"%d %b %Y\", timezone=\"Asia/Tokyo\") }}").unwrap(),
+            "01 Jan 1970"
+        );
+        assert_eq!(
+            render("{{ \"2026-03-01\" | date(format=\"%A\") }}").unwrap(),
+            "Sunday"
+        );
+        assert_eq!(
+            render("{{ \"2026-03-01 14:05:00\" | datetime }}").unwrap(),
+            "2026-03-01 14:05"
+        );
+    }
+
+    #[test]
+    fn test_date_filters_report_bad_input() {
+        let error = |template: &str| format!("{:?}", render(template).unwrap_err());
+        assert!(error("{{ \"soon\" | date }}").contains("can't read"));
+        assert!(error("{{ 0 | date(timezone=\"Mars/Olympus\") }}").contains("unknown timezone"));
+        assert!(error("{{ 0 | date(format=\"%Q\") }}").contains("invalid format"));
+    }
+
+    #[test]
+    fn test_now_is_dated_in_local_time() {
+        let today = Local::now().format(DATE_FORMAT).to_string();
+        assert_eq!(render("{{ now() | date }}").unwrap(), today);
+    }
+}
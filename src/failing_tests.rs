@@ -0,0 +1,380 @@
+//! The `failing_tests` built-in context section.
+//!
+//! Runs the project's test command (`test_command` in `claw.yaml`, or one
+//! guessed from the project's files) and reports only what a "fix my
+//! failing tests" goal needs: the names of the failing tests, their output,
+//! and the source files their output points at. Output from Rust's libtest,
+//! pytest and `go test` is split per test; for other runners the end of the
+//! output is reported.
+
+use anyhow::{Context as AnyhowContext, Result};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Lines of output kept per failing test, from its end.
+const MAX_LINES_PER_FAILURE: usize = 60;
+
+/// Lines kept from the end of output claw can't split per test.
+const MAX_UNPARSED_LINES: usize = 80;
+
+/// Source files included, in the order the output mentions them.
+const MAX_SOURCE_FILES: usize = 5;
+
+/// A failing test and the output it produced.
+#[derive(Debug, Clone, PartialEq)]
+struct Failure {
+    name: String,
+    output: Vec<String>,
+}
+
+/// Guesses the test command from the files in `dir`.
+fn detect_test_command(dir: &Path) -> Option<&'static str> {
+    [
+        ("Cargo.toml", "cargo test"),
+        ("go.mod", "go test ./..."),
+        ("package.json", "npm test"),
+        ("pyproject.toml", "python3 -m pytest"),
+        ("pytest.ini", "python3 -m pytest"),
+        ("setup.py", "python3 -m pytest"),
+    ]
+    .into_iter()
+    .find(|(file, _)| dir.join(file).exists())
+    .map(|(_, command)| command)
+}
+
+/// Runs the test command in `dir` and describes the failures. Source files
+/// larger than `max_file_size_kb` are left out.
+pub fn report(dir: &Path, test_command: Option<&str>, max_file_size_kb: u64) -> Result<String> {
+    let command = match test_command {
+        Some(command) => command,
+        None => detect_test_command(dir)
+            .context("No test command: set test_command in claw.yaml (e.g. \"cargo test\")")?,
+    };
+    let words = shlex::split(command)
+        .filter(|words| !words.is_empty())
+        .with_context(|| format!("Invalid test_command: {}", command))?;
+    let executable = which::which(&words[0])
+        .with_context(|| format!("'{}' not found in your PATH", words[0]))?;
+
+    eprintln!("Running tests for failing_tests: {}", command);
+    let output = Command::new(executable)
+        .args(&words[1..])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", command))?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if output.status.success() {
+        return Ok(format!("All tests passed (`{}`).", command));
+    }
+
+    let failures = parse_failures(&text);
+    let mut report = format!(
+        "Test command: `{}` ({})\n",
+        command,
+        match output.status.code() {
+            Some(code) => format!("exit status {}", code),
+            None => "terminated by a signal".to_string(),
+        }
+    );
+    if failures.is_empty() {
+        let lines: Vec<&str> = text.trim_end().lines().collect();
+        let start = lines.len().saturating_sub(MAX_UNPARSED_LINES);
+        report.push_str(
+            "\nThe failing tests couldn't be told apart; the end of the output:\n\n```\n",
+        );
+        report.push_str(&lines[start..].join("\n"));
+        report.push_str("\n```\n");
+        return Ok(report);
+    }
+
+    report.push_str(&format!("{} failing test(s)\n", failures.len()));
+    for failure in &failures {
+        report.push_str(&format!("\n### {}\n\n```\n", failure.name));
+        let start = failure.output.len().saturating_sub(MAX_LINES_PER_FAILURE);
+        if start > 0 {
+            report.push_str(&format!("[... {} earlier lines omitted]\n", start));
+        }
+        report.push_str(&failure.output[start..].join("\n"));
+        report.push_str("\n```\n");
+    }
+
+    let sources = source_files(dir, &failures);
+    if !sources.is_empty() {
+        report.push_str("\n## Source files\n");
+    }
+    for path in sources {
+        let full_path = dir.join(&path);
+        let size_kb = fs::metadata(&full_path)
+            .map(|m| m.len() / 1024)
+            .unwrap_or(0);
+        if size_kb > max_file_size_kb {
+            report.push_str(&format!(
+                "\n### {}\n\n(left out: {} KB exceeds max_file_size_kb)\n",
+                path.display(),
+                size_kb
+            ));
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&full_path) else {
+            continue;
+        };
+        report.push_str(&format!("\n### {}\n\n```\n{}", path.display(), content));
+        if !content.ends_with('\n') {
+            report.push('\n');
+        }
+        report.push_str("```\n");
+    }
+    Ok(report)
+}
+
+/// Splits test output into failing tests, trying each runner's format.
+fn parse_failures(output: &str) -> Vec<Failure> {
+    for parse in [parse_libtest, parse_pytest, parse_go_test] {
+        let failures = parse(output);
+        if !failures.is_empty() {
+            return failures;
+        }
+    }
+    Vec::new()
+}
+
+/// Rust's libtest prints `---- name stdout ----` before each failure's output.
+fn parse_libtest(output: &str) -> Vec<Failure> {
+    let mut failures: Vec<Failure> = Vec::new();
+    let mut current: Option<Failure> = None;
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            failures.extend(current.take());
+            current = Some(Failure {
+                name: name.to_string(),
+                output: Vec::new(),
+            });
+        } else if line == "failures:" || line == "successes:" {
+            failures.extend(current.take());
+        } else if let Some(failure) = &mut current {
+            failure.output.push(line.to_string());
+        }
+    }
+    failures.extend(current);
+    for failure in &mut failures {
+        trim_blank_lines(&mut failure.output);
+    }
+    failures
+}
+
+/// pytest prints `___ name ___` before each failure's output, in its
+/// FAILURES section.
+fn parse_pytest(output: &str) -> Vec<Failure> {
+    let header = Regex::new(r"^_{3,} (.+?) _{3,}$").expect("valid pytest header pattern");
+    let mut failures: Vec<Failure> = Vec::new();
+    let mut current: Option<Failure> = None;
+    let mut in_failures = false;
+    for line in output.lines() {
+        if line.starts_with("====") {
+            failures.extend(current.take());
+            in_failures = line.contains(" FAILURES ") || line.contains(" ERRORS ");
+        } else if !in_failures {
+            continue;
+        } else if let Some(captures) = header.captures(line) {
+            failures.extend(current.take());
+            current = Some(Failure {
+                name: captures[1].to_string(),
+                output: Vec::new(),
+            });
+        } else if let Some(failure) = &mut current {
+            failure.output.push(line.to_string());
+        }
+    }
+    failures.extend(current);
+    for failure in &mut failures {
+        trim_blank_lines(&mut failure.output);
+    }
+    failures
+}
+
+/// `go test` prints `--- FAIL: Name (0.00s)` followed by the test's
+/// indented log lines.
+fn parse_go_test(output: &str) -> Vec<Failure> {
+    let mut failures: Vec<Failure> = Vec::new();
+    let mut current: Option<Failure> = None;
+    for line in output.lines() {
+        if let Some(rest) = line.trim_start().strip_prefix("--- FAIL: ") {
+            failures.extend(current.take());
+            let name = rest.split(" (").next().unwrap_or(rest);
+            current = Some(Failure {
+                name: name.to_string(),
+                output: Vec::new(),
+            });
+        } else if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(failure) = &mut current {
+                failure.output.push(line.trim_start().to_string());
+            }
+        } else {
+            failures.extend(current.take());
+        }
+    }
+    failures.extend(current);
+    failures
+}
+
+fn trim_blank_lines(lines: &mut Vec<String>) {
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    let leading = lines
+        .iter()
+        .take_while(|line| line.trim().is_empty())
+        .count();
+    lines.drain(..leading);
+}
+
+/// The files under `dir` that the failures' names and output point at, as
+/// `path:line` or pytest's `path::test`, in order of first mention.
+fn source_files(dir: &Path, failures: &[Failure]) -> Vec<PathBuf> {
+    let location =
+        Regex::new(r"([\w./-]+\.[A-Za-z]+)(?::\d+|::)").expect("valid source location pattern");
+    let mut seen = BTreeSet::new();
+    let mut files = Vec::new();
+    for failure in failures {
+        let texts =
+            std::iter::once(failure.name.as_str()).chain(failure.output.iter().map(String::as_str));
+        for text in texts {
+            for captures in location.captures_iter(text) {
+                let path = PathBuf::from(captures[1].trim_start_matches("./"));
+                if path.is_absolute() || !dir.join(&path).is_file() || !seen.insert(path.clone()) {
+                    continue;
+                }
+                files.push(path);
+                if files.len() == MAX_SOURCE_FILES {
+                    return files;
+                }
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBTEST_OUTPUT: &str = "\
+running 3 tests
+test tests::adds ... ok
+test tests::subtracts ... FAILED
+test tests::divides ... FAILED
+
+failures:
+
+---- tests::subtracts stdout ----
+
+thread 'tests::subtracts' panicked at src/math.rs:20:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+
+---- tests::divides stdout ----
+thread 'tests::divides' panicked at src/math.rs:30:5:
+attempt to divide by zero
+
+
+failures:
+    tests::subtracts
+    tests::divides
+
+test result: FAILED. 1 passed; 2 failed
+";
+
+    #[test]
+    fn test_parse_libtest() {
+        let failures = parse_failures(LIBTEST_OUTPUT);
+        let names: Vec<&str> = failures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["tests::subtracts", "tests::divides"]);
+        assert_eq!(
+            failures[0].output[0],
+            "thread 'tests::subtracts' panicked at src/math.rs:20:9:"
+        );
+        assert_eq!(failures[0].output.last().unwrap(), " right: 2");
+    }
+
+    #[test]
+    fn test_parse_pytest() {
+        let output = "\
+============================= test session starts ==============================
+collected 2 items
+
+tests/test_math.py .F                                                    [100%]
+
+=================================== FAILURES ===================================
+_________________________________ test_divide __________________________________
+
+    def test_divide():
+>       assert divide(4, 2) == 3
+E       assert 2.0 == 3
+
+tests/test_math.py:8: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_divide - assert 2.0 == 3
+";
+        let failures = parse_failures(output);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "test_divide");
+        assert_eq!(
+            failures[0].output.last().unwrap(),
+            "tests/test_math.py:8: AssertionError"
+        );
+    }
+
+    #[test]
+    fn test_parse_go_test() {
+        let output = "\
+--- FAIL: TestDivide (0.00s)
+    math_test.go:12: divide(4, 2) = 2, want 3
+FAIL
+FAIL\texample.com/math\t0.002s
+";
+        let failures = parse_failures(output);
+        assert_eq!(
+            failures,
+            vec![Failure {
+                name: "TestDivide".to_string(),
+                output: vec!["math_test.go:12: divide(4, 2) = 2, want 3".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_source_files_exist_and_are_listed_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/math.rs"), "fn divide() {}").unwrap();
+        let failures = parse_failures(LIBTEST_OUTPUT);
+        assert_eq!(
+            source_files(temp_dir.path(), &failures),
+            vec![PathBuf::from("src/math.rs")]
+        );
+    }
+
+    #[test]
+    fn test_report_runs_the_test_command() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let passed = report(temp_dir.path(), Some("true"), 1024).unwrap();
+        assert!(passed.starts_with("All tests passed"));
+
+        let failed = report(temp_dir.path(), Some("sh -c 'echo boom; exit 1'"), 1024).unwrap();
+        assert!(failed.contains("exit status 1"));
+        assert!(failed.contains("the end of the output"));
+        assert!(failed.contains("boom"));
+    }
+}
@@ -0,0 +1,823 @@
+//! `--context-git`: adds the files changed in git as context, and
+//! `--context-diff`: adds the changes themselves, as unified diffs.
+//!
+//! The scope is one of:
+//!
+//! - `branch:<ref>`: everything the current branch changes since it forked
+//!   from `<ref>`, including uncommitted edits to tracked files
+//! - `<from>..<to>`: the changes between two refs
+//! - `staged`: the changes staged for the next commit
+//! - `working`: the uncommitted changes to tracked files
+//!
+//! In a terminal, `--context-git` shows the changed files with their
+//! diffstat in a checklist so generated or unrelated files can be left out
+//! before any of them are read.
+//!
+//! Changes are read from the repository and diffed with gix, so neither
+//! flag needs `git` installed. The `git_status` and `git_diff_staged`
+//! built-in context sections are read the same way.
+
+use anyhow::{Context as AnyhowContext, Result, bail};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use gix::ObjectId;
+use gix::bstr::ByteSlice;
+use gix::diff::blob::{
+    self, Algorithm, UnifiedDiff,
+    intern::InternedInput,
+    sink::Counter,
+    sources::byte_lines_with_terminator,
+    unified_diff::{ConsumeHunk, ContextSize, DiffLineKind, HunkHeader},
+};
+use gix::index::entry::Mode;
+use gix::status::tree_index::TrackRenames;
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::param_prompt;
+
+/// Which changes `--context-git` takes its files from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitScope {
+    /// Changes since the current branch forked from the given ref.
+    Branch(String),
+    /// Changes between two refs.
+    Range(String, String),
+    Staged,
+    Working,
+}
+
+impl GitScope {
+    /// Parses `branch:<ref>`, `<from>..<to>`, `staged` or `working`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        match spec {
+            "staged" => return Ok(GitScope::Staged),
+            "working" => return Ok(GitScope::Working),
+            _ => {}
+        }
+        if let Some(base) = spec.strip_prefix("branch:")
+            && !base.is_empty()
+        {
+            return Ok(GitScope::Branch(base.to_string()));
+        }
+        if let Some((from, to)) = spec.split_once("..")
+            && !from.is_empty()
+            && !to.is_empty()
+            && !to.starts_with('.')
+        {
+            return Ok(GitScope::Range(from.to_string(), to.to_string()));
+        }
+        bail!(
+            "Invalid git scope '{}': use branch:<ref>, <from>..<to>, staged or working",
+            spec
+        )
+    }
+
+    /// Describes the changes, e.g. for a section heading.
+    pub fn describe(&self) -> String {
+        match self {
+            GitScope::Branch(base) => format!("changes since the branch forked from {}", base),
+            GitScope::Range(from, to) => format!("changes from {} to {}", from, to),
+            GitScope::Staged => "staged changes".to_string(),
+            GitScope::Working => "uncommitted changes".to_string(),
+        }
+    }
+}
+
+/// Where the changed side of a scope is read from.
+enum Target {
+    Tree(ObjectId),
+    Index,
+    Worktree,
+}
+
+/// A file changed in the scope, with its contents before and after; a
+/// side is `None` where the file doesn't exist.
+#[derive(Debug, Clone, PartialEq)]
+struct FileChange {
+    /// Relative to the directory claw runs in, `/`-separated.
+    path: String,
+    old: Option<Vec<u8>>,
+    new: Option<Vec<u8>>,
+}
+
+impl FileChange {
+    /// Lines added and removed, and the hunks of the unified diff with
+    /// three lines of context; `None` for a binary file.
+    fn hunks(&self) -> Option<((u64, u64), String)> {
+        let sides = [&self.old, &self.new];
+        if sides
+            .into_iter()
+            .flatten()
+            .any(|data| content_inspector::inspect(data).is_binary())
+        {
+            return None;
+        }
+        // Lines keep their newline, so a missing one at the end shows
+        let input = InternedInput::new(
+            byte_lines_with_terminator(self.old.as_deref().unwrap_or_default()),
+            byte_lines_with_terminator(self.new.as_deref().unwrap_or_default()),
+        );
+        let sink = UnifiedDiff::new(&input, GitHunks::default(), ContextSize::symmetrical(3));
+        let counter = blob::diff(Algorithm::Histogram, &input, Counter::new(sink));
+        // Writing hunks into a Vec can't fail
+        let hunks = counter.wrapped.unwrap_or_default();
+        Some((
+            (counter.insertions.into(), counter.removals.into()),
+            String::from_utf8_lossy(&hunks).into_owned(),
+        ))
+    }
+
+    /// The change as `git diff` shows it.
+    fn file_diff(&self) -> FileDiff {
+        let side = |data: &Option<Vec<u8>>, prefix: &str| match data {
+            Some(_) => format!("{}{}", prefix, self.path),
+            None => "/dev/null".to_string(),
+        };
+        let (old, new) = (side(&self.old, "a/"), side(&self.new, "b/"));
+        let mut diff = format!("diff --git a/{0} b/{0}\n", self.path);
+        let stat = match self.hunks() {
+            Some((stat, hunks)) => {
+                diff.push_str(&format!("--- {}\n+++ {}\n{}", old, new, hunks));
+                Some(stat)
+            }
+            None => {
+                diff.push_str(&format!("Binary files {} and {} differ\n", old, new));
+                None
+            }
+        };
+        FileDiff {
+            path: self.path.clone(),
+            stat,
+            diff,
+        }
+    }
+}
+
+/// Writes unified diff hunks the way `git diff` does.
+#[derive(Default)]
+struct GitHunks(Vec<u8>);
+
+impl ConsumeHunk for GitHunks {
+    type Out = Vec<u8>;
+
+    fn consume_hunk(
+        &mut self,
+        header: HunkHeader,
+        lines: &[(DiffLineKind, &[u8])],
+    ) -> io::Result<()> {
+        // An empty range names the line before it; a single line has no length
+        let range = |start: u32, len: u32| match len {
+            0 => format!("{},0", start - 1),
+            1 => start.to_string(),
+            _ => format!("{},{}", start, len),
+        };
+        writeln!(
+            self.0,
+            "@@ -{} +{} @@",
+            range(header.before_hunk_start, header.before_hunk_len),
+            range(header.after_hunk_start, header.after_hunk_len)
+        )?;
+        for (kind, line) in lines {
+            self.0.push(match kind {
+                DiffLineKind::Context => b' ',
+                DiffLineKind::Add => b'+',
+                DiffLineKind::Remove => b'-',
+            });
+            self.0.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                self.0
+                    .extend_from_slice(b"\n\\ No newline at end of file\n");
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// A file changed in the scope, with its diffstat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedFile {
+    /// Relative to the directory claw runs in.
+    pub path: PathBuf,
+    /// Lines added and removed; `None` for binary files.
+    pub stat: Option<(u64, u64)>,
+}
+
+impl ChangedFile {
+    fn format_stat(&self) -> String {
+        match self.stat {
+            Some((added, removed)) => format!("+{} -{}", added, removed),
+            None => "binary".to_string(),
+        }
+    }
+}
+
+/// Lists the files changed in `scope` under `dir`. Deleted files are left
+/// out, since there is nothing to read.
+pub fn changed_files(dir: &Path, scope: &GitScope) -> Result<Vec<ChangedFile>> {
+    Ok(file_changes(dir, scope)?
+        .into_iter()
+        .filter(|change| dir.join(&change.path).is_file())
+        .map(|change| ChangedFile {
+            path: PathBuf::from(&change.path),
+            stat: change.hunks().map(|(stat, _)| stat),
+        })
+        .collect())
+}
+
+/// Reads the files changed in `scope` under `dir` from its repository,
+/// in path order. Renames show as a deletion and an addition.
+fn file_changes(dir: &Path, scope: &GitScope) -> Result<Vec<FileChange>> {
+    let repo = discover(dir)?;
+    let workdir = repo
+        .workdir()
+        .context("The git repository has no working tree")?
+        .to_path_buf();
+    let prefix = path_prefix(&workdir, dir)?;
+
+    let (base, target) = match scope {
+        GitScope::Branch(reference) => {
+            let head = repo.head_commit().context("HEAD has no commits yet")?.id;
+            let base = repo
+                .merge_base(commit_id(&repo, reference)?, head)
+                .with_context(|| format!("{} and HEAD have no common ancestor", reference))?;
+            (base.object()?.peel_to_tree()?.id, Target::Worktree)
+        }
+        GitScope::Range(from, to) => (tree_id(&repo, from)?, Target::Tree(tree_id(&repo, to)?)),
+        GitScope::Staged => (repo.head_tree_id_or_empty()?.detach(), Target::Index),
+        GitScope::Working => (repo.head_tree_id_or_empty()?.detach(), Target::Worktree),
+    };
+
+    let index = repo.index_or_empty()?;
+    let to_tree;
+    let new_index: &gix::index::State = match target {
+        Target::Tree(id) => {
+            to_tree = gix::index::State::from(repo.index_from_tree(&id)?);
+            &to_tree
+        }
+        Target::Index | Target::Worktree => &index,
+    };
+
+    // Collect the changed paths first, then read both sides of each
+    let mut paths = BTreeSet::new();
+    let outcome = repo.tree_index_status(
+        &base,
+        new_index,
+        None,
+        TrackRenames::Disabled,
+        |change, _, _| {
+            paths.insert(change.location().to_owned());
+            Ok::<_, Infallible>(gix::diff::index::Action::Continue)
+        },
+    )?;
+    if matches!(target, Target::Worktree) {
+        let status = repo
+            .status(gix::progress::Discard)?
+            .index_worktree_rewrites(None)
+            .index_worktree_submodules(None)
+            .index_worktree_options_mut(|options| options.dirwalk_options = None)
+            .into_index_worktree_iter(Vec::new())?;
+        for item in status {
+            paths.insert(item?.rela_path().to_owned());
+        }
+    }
+
+    let mut changes = Vec::new();
+    for path in paths {
+        let Some(relative) = path.strip_prefix(prefix.as_bytes()) else {
+            continue;
+        };
+        let old = read_blob(&repo, outcome.tree_index.entry_by_path(path.as_bstr()))?;
+        let new = match target {
+            Target::Worktree => match new_index.entry_by_path(path.as_bstr()) {
+                Some(entry) if entry.mode != Mode::COMMIT => {
+                    read_worktree_file(&workdir.join(gix::path::from_bstr(path.as_bstr())))?
+                }
+                _ => None,
+            },
+            Target::Tree(_) | Target::Index => {
+                read_blob(&repo, new_index.entry_by_path(path.as_bstr()))?
+            }
+        };
+        if old != new {
+            changes.push(FileChange {
+                path: String::from_utf8_lossy(relative).into_owned(),
+                old,
+                new,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+fn discover(dir: &Path) -> Result<gix::Repository> {
+    gix::discover(dir).with_context(|| format!("{} isn't in a git repository", dir.display()))
+}
+
+/// The staged changes under `dir`, as `git diff --staged` shows them.
+pub fn staged_diff(dir: &Path) -> Result<String> {
+    Ok(file_changes(dir, &GitScope::Staged)?
+        .iter()
+        .map(|change| change.file_diff().diff)
+        .collect::<String>()
+        .trim_end()
+        .to_string())
+}
+
+/// The repository's status as `git status --short --branch` shows it: the
+/// branch, its upstream and how far apart they are, then a two-letter code
+/// for each changed or untracked path (staged change first, then unstaged),
+/// relative to the top of the working tree.
+pub fn short_status(dir: &Path) -> Result<String> {
+    let repo = discover(dir)?;
+    let mut lines = vec![format!("## {}", branch_summary(&repo)?)];
+
+    let mut codes: std::collections::BTreeMap<gix::bstr::BString, [u8; 2]> = Default::default();
+    let head_tree = repo.head_tree_id_or_empty()?.detach();
+    let index = repo.index_or_empty()?;
+    repo.tree_index_status(
+        &head_tree,
+        &index,
+        None,
+        TrackRenames::Disabled,
+        |change, _, _| {
+            let code = match change {
+                gix::diff::index::ChangeRef::Addition { .. } => b'A',
+                gix::diff::index::ChangeRef::Deletion { .. } => b'D',
+                _ => b'M',
+            };
+            codes
+                .entry(change.location().to_owned())
+                .or_insert([b' '; 2])[0] = code;
+            Ok::<_, Infallible>(gix::diff::index::Action::Continue)
+        },
+    )?;
+    let status = repo
+        .status(gix::progress::Discard)?
+        .index_worktree_rewrites(None)
+        .index_worktree_submodules(None)
+        .into_index_worktree_iter(Vec::new())?;
+    for item in status {
+        let item = item?;
+        use gix::status::index_worktree::iter::Summary;
+        let code = match item.summary() {
+            Some(Summary::Added) => *b"??",
+            Some(Summary::Removed) => *b" D",
+            Some(Summary::Modified | Summary::Renamed | Summary::Copied) => *b" M",
+            Some(Summary::TypeChange) => *b" T",
+            Some(Summary::Conflict) => *b"UU",
+            Some(Summary::IntentToAdd) => *b" A",
+            None => continue,
+        };
+        let mut path = item.rela_path().to_owned();
+        // Like git, an untracked directory is listed once, with a slash
+        if let gix::status::index_worktree::Item::DirectoryContents { entry, .. } = &item
+            && entry.disk_kind == Some(gix::dir::entry::Kind::Directory)
+        {
+            path.push(b'/');
+        }
+        let entry = codes.entry(path).or_insert([b' '; 2]);
+        if code[0] == b' ' {
+            entry[1] = code[1];
+        } else {
+            *entry = code;
+        }
+    }
+
+    for (path, code) in codes {
+        lines.push(format!("{} {}", String::from_utf8_lossy(&code), path));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// The branch part of `git status --branch`, e.g. `main...origin/main
+/// [ahead 1]`.
+fn branch_summary(repo: &gix::Repository) -> Result<String> {
+    let Some(name) = repo.head_name()? else {
+        return Ok("HEAD (no branch)".to_string());
+    };
+    let branch = name.shorten().to_string();
+    let Some(head) = repo.head_id().ok().map(|id| id.detach()) else {
+        return Ok(format!("No commits yet on {}", branch));
+    };
+    let upstream = repo
+        .branch_remote_tracking_ref_name(name.as_ref(), gix::remote::Direction::Fetch)
+        .and_then(Result::ok);
+    let Some(upstream) = upstream else {
+        return Ok(branch);
+    };
+    let mut summary = format!("{}...{}", branch, upstream.shorten());
+    let Ok(Some(mut reference)) = repo.try_find_reference(upstream.as_ref()) else {
+        return Ok(format!("{} [gone]", summary));
+    };
+    let upstream_id = reference.peel_to_id()?.detach();
+    let count = |from: ObjectId, hidden: ObjectId| -> Result<usize> {
+        Ok(repo.rev_walk([from]).with_hidden([hidden]).all()?.count())
+    };
+    let (ahead, behind) = (count(head, upstream_id)?, count(upstream_id, head)?);
+    let apart: Vec<String> = [("ahead", ahead), ("behind", behind)]
+        .into_iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(word, n)| format!("{} {}", word, n))
+        .collect();
+    if !apart.is_empty() {
+        summary.push_str(&format!(" [{}]", apart.join(", ")));
+    }
+    Ok(summary)
+}
+
+/// `dir` relative to the working tree, as the `/`-separated prefix of the
+/// paths under it ("" at the top).
+fn path_prefix(workdir: &Path, dir: &Path) -> Result<String> {
+    let canonical = |path: &Path| {
+        fs::canonicalize(path).with_context(|| format!("Failed to resolve {}", path.display()))
+    };
+    let (workdir, dir) = (canonical(workdir)?, canonical(dir)?);
+    let relative = dir.strip_prefix(&workdir).unwrap_or(Path::new(""));
+    Ok(relative
+        .components()
+        .map(|component| format!("{}/", component.as_os_str().to_string_lossy()))
+        .collect())
+}
+
+fn commit_id(repo: &gix::Repository, spec: &str) -> Result<ObjectId> {
+    Ok(revision(repo, spec)?.peel_to_commit()?.id)
+}
+
+fn tree_id(repo: &gix::Repository, spec: &str) -> Result<ObjectId> {
+    Ok(revision(repo, spec)?.peel_to_tree()?.id)
+}
+
+fn revision<'repo>(repo: &'repo gix::Repository, spec: &str) -> Result<gix::Object<'repo>> {
+    Ok(repo
+        .rev_parse_single(spec)
+        .with_context(|| format!("Unknown git revision '{}'", spec))?
+        .object()?)
+}
+
+/// The contents of an index entry's blob; `None` without an entry, or for
+/// a submodule.
+fn read_blob(repo: &gix::Repository, entry: Option<&gix::index::Entry>) -> Result<Option<Vec<u8>>> {
+    match entry {
+        Some(entry) if entry.mode != Mode::COMMIT => {
+            Ok(Some(repo.find_blob(entry.id)?.take_data()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// A working tree file's contents, or a symlink's target as git stores it;
+/// `None` if it was deleted.
+fn read_worktree_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    let read = || -> io::Result<Vec<u8>> {
+        if fs::symlink_metadata(path)?.file_type().is_symlink() {
+            Ok(fs::read_link(path)?.as_os_str().as_encoded_bytes().to_vec())
+        } else {
+            fs::read(path)
+        }
+    };
+    match read() {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// One file's part of the diff section.
+#[derive(Debug, Clone, PartialEq)]
+struct FileDiff {
+    path: String,
+    /// Lines added and removed, or `None` for a binary file.
+    stat: Option<(u64, u64)>,
+    diff: String,
+}
+
+/// Diffs the changes in `scope` under `dir` and formats them as a context
+/// section, with each file's diff in its own fenced block.
+pub fn diff_section(dir: &Path, scope: &GitScope) -> Result<String> {
+    let files: Vec<FileDiff> = file_changes(dir, scope)?
+        .iter()
+        .map(FileChange::file_diff)
+        .collect();
+    Ok(format_diff_section(&scope.describe(), &files))
+}
+
+fn format_diff_section(description: &str, files: &[FileDiff]) -> String {
+    let mut output = format!("## Git Diff ({})\n\n", description);
+    if files.is_empty() {
+        output.push_str("No changes.\n");
+        return output;
+    }
+    output.push_str(&format!("{} file(s) changed.\n", files.len()));
+    for file in files {
+        let stat = match file.stat {
+            Some((added, removed)) => format!("+{} -{}", added, removed),
+            None => "binary".to_string(),
+        };
+        let fence = fence_for(&file.diff);
+        output.push_str(&format!(
+            "\n### {} ({})\n\n{}diff\n{}{}\n",
+            file.path, stat, fence, file.diff, fence
+        ));
+    }
+    output
+}
+
+/// A code fence longer than any run of backticks in `text`, so the text
+/// can't close it early.
+pub fn fence_for(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Lets the user deselect changed files in a checklist when running in a
+/// terminal. Otherwise every file is kept.
+pub fn pick_files(files: Vec<ChangedFile>) -> Result<Vec<ChangedFile>> {
+    if files.is_empty() || !param_prompt::is_interactive() {
+        return Ok(files);
+    }
+
+    // The checklist is drawn on stderr, so stdout can stay redirected
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    let backend = CrosstermBackend::new(stderr);
+    let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
+
+    let mut checklist = Checklist::new(files);
+    let result = run_checklist(&mut terminal, &mut checklist);
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    result?;
+    let total = checklist.files.len();
+    let picked = checklist.into_selected();
+    if picked.len() < total {
+        eprintln!(
+            "Including {} of {} changed files as context",
+            picked.len(),
+            total
+        );
+    }
+    Ok(picked)
+}
+
+/// What a key press did to the checklist.
+#[derive(Debug, PartialEq)]
+enum ChecklistAction {
+    Continue,
+    Confirm,
+    Cancel,
+}
+
+/// The changed files and which of them are selected.
+struct Checklist {
+    files: Vec<ChangedFile>,
+    selected: Vec<bool>,
+    cursor: usize,
+}
+
+impl Checklist {
+    fn new(files: Vec<ChangedFile>) -> Self {
+        let selected = vec![true; files.len()];
+        Checklist {
+            files,
+            selected,
+            cursor: 0,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> ChecklistAction {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor = (self.cursor + 1).min(self.files.len().saturating_sub(1))
+            }
+            KeyCode::Char(' ') => self.selected[self.cursor] = !self.selected[self.cursor],
+            KeyCode::Char('a') => {
+                let all = self.selected.iter().all(|&s| s);
+                self.selected.iter_mut().for_each(|s| *s = !all);
+            }
+            KeyCode::Enter => return ChecklistAction::Confirm,
+            KeyCode::Esc | KeyCode::Char('q') => return ChecklistAction::Cancel,
+            _ => {}
+        }
+        ChecklistAction::Continue
+    }
+
+    fn into_selected(self) -> Vec<ChangedFile> {
+        self.files
+            .into_iter()
+            .zip(self.selected)
+            .filter_map(|(file, selected)| selected.then_some(file))
+            .collect()
+    }
+}
+
+fn run_checklist<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    checklist: &mut Checklist,
+) -> Result<()> {
+    loop {
+        terminal.draw(|f| render_checklist(f, checklist))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match checklist.handle_key(key.code) {
+                ChecklistAction::Continue => {}
+                ChecklistAction::Confirm => return Ok(()),
+                ChecklistAction::Cancel => bail!("Cancelled choosing the changed files"),
+            }
+        }
+    }
+}
+
+fn render_checklist(frame: &mut Frame, checklist: &Checklist) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.area());
+
+    let stat_width = checklist
+        .files
+        .iter()
+        .map(|file| file.format_stat().len())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = checklist
+        .files
+        .iter()
+        .zip(&checklist.selected)
+        .map(|(file, &selected)| {
+            let (mark, style) = if selected {
+                ("[x]", Style::default())
+            } else {
+                ("[ ]", Style::default().fg(Color::DarkGray))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", mark), style),
+                Span::styled(
+                    format!("{:>width$}  ", file.format_stat(), width = stat_width),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(file.path.display().to_string(), style),
+            ]))
+        })
+        .collect();
+    let count = checklist.selected.iter().filter(|&&s| s).count();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Changed files to include ({} of {}) ",
+            count,
+            checklist.files.len()
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(checklist.cursor));
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    let help = Paragraph::new(" ↑/↓ move  Space toggle  a all/none  Enter confirm  Esc cancel")
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(help, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!(
+            GitScope::parse("branch:main").unwrap(),
+            GitScope::Branch("main".to_string())
+        );
+        assert_eq!(GitScope::parse("staged").unwrap(), GitScope::Staged);
+        assert_eq!(
+            GitScope::parse("v1.0..HEAD").unwrap(),
+            GitScope::Range("v1.0".to_string(), "HEAD".to_string())
+        );
+        assert!(GitScope::parse("branch:").is_err());
+        assert!(GitScope::parse("main").is_err());
+        assert!(GitScope::parse("main...feature").is_err());
+        assert!(GitScope::parse("..HEAD").is_err());
+    }
+
+    fn change(path: &str, old: Option<&str>, new: Option<&str>) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            old: old.map(|text| text.as_bytes().to_vec()),
+            new: new.map(|text| text.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_file_diffs_and_format() {
+        let changes = [
+            change(
+                "src/my file.rs",
+                Some("let a = 1;\ndone\n"),
+                Some("let a = 2;\n// ```\ndone\n"),
+            ),
+            change("old.txt", Some("gone\n"), None),
+            change("notes.md", Some("a\nb"), Some("a\nc")),
+            FileChange {
+                path: "logo.png".to_string(),
+                old: None,
+                new: Some(vec![0x89, b'P', b'N', b'G', 0, 0]),
+            },
+        ];
+        let files: Vec<FileDiff> = changes.iter().map(FileChange::file_diff).collect();
+        assert_eq!(
+            files[0].diff,
+            "diff --git a/src/my file.rs b/src/my file.rs\n\
+             --- a/src/my file.rs\n\
+             +++ b/src/my file.rs\n\
+             @@ -1,2 +1,3 @@\n\
+             -let a = 1;\n\
+             +let a = 2;\n\
+             +// ```\n \
+             done\n"
+        );
+        assert_eq!(files[0].stat, Some((2, 1)));
+        assert!(
+            files[1]
+                .diff
+                .contains("--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n")
+        );
+        assert_eq!(files[1].stat, Some((0, 1)));
+        assert!(files[2].diff.ends_with(
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n"
+        ));
+        assert!(
+            files[3]
+                .diff
+                .ends_with("Binary files /dev/null and b/logo.png differ\n")
+        );
+        assert_eq!(files[3].stat, None);
+
+        let section = format_diff_section("staged changes", &files);
+        assert!(section.starts_with("## Git Diff (staged changes)\n\n4 file(s) changed.\n"));
+        assert!(section.contains("### src/my file.rs (+2 -1)\n\n````diff\ndiff --git"));
+        assert!(section.contains("### old.txt (+0 -1)\n\n```diff\n"));
+        assert!(section.contains("### logo.png (binary)"));
+        assert_eq!(
+            format_diff_section("staged changes", &[]),
+            "## Git Diff (staged changes)\n\nNo changes.\n"
+        );
+    }
+
+    fn changed(paths: &[&str]) -> Vec<ChangedFile> {
+        paths
+            .iter()
+            .map(|path| ChangedFile {
+                path: PathBuf::from(path),
+                stat: Some((1, 0)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_checklist_deselects_files() {
+        let mut checklist = Checklist::new(changed(&["a.rs", "b.lock", "c.rs"]));
+        checklist.handle_key(KeyCode::Down);
+        checklist.handle_key(KeyCode::Char(' '));
+        assert_eq!(
+            checklist.handle_key(KeyCode::Enter),
+            ChecklistAction::Confirm
+        );
+        let picked: Vec<PathBuf> = checklist
+            .into_selected()
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+        assert_eq!(picked, vec![PathBuf::from("a.rs"), PathBuf::from("c.rs")]);
+
+        let mut checklist = Checklist::new(changed(&["a.rs"]));
+        checklist.handle_key(KeyCode::Char('a'));
+        assert!(checklist.into_selected().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Parsing of the goal arguments given after `--`.
+//!
+//! Each argument is one shell word, so quoting is the shell's job:
+//! `--title="A title"` and `--title "A title"` both arrive as whole values.
+//!
+//! - `--key=value` takes everything after the first `=` verbatim, including
+//!   spaces, further `=` signs and leading dashes; `--key=` is an empty value
+//! - `--key value` takes the next word as the value unless it starts with
+//!   `--`, so negative numbers and dashes work: `--offset -5`
+//! - `--flag` on its own is `true`, and `--no-flag` is `false`
+//! - A value starting with `--` must use the `=` form: `--pattern=--x`
+//!
+//! Later arguments replace earlier ones with the same key.
+
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/// Prefix that turns a boolean flag off, e.g. `--no-verbose`.
+const NEGATION_PREFIX: &str = "no-";
+
+/// Parses goal arguments into a map of parameter names to values.
+pub fn parse(args: &[String]) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    let mut words = args.iter().peekable();
+
+    while let Some(arg) = words.next() {
+        let Some(key_part) = arg.strip_prefix("--") else {
+            if arg.starts_with('-') && arg.len() > 1 {
+                bail!(
+                    "Invalid goal argument: '{}'. Goal arguments use two dashes, e.g. '-{}'.",
+                    arg,
+                    arg
+                );
+            }
+            bail!(
+                "Invalid goal argument: '{}'. All goal arguments must be flags starting with '--'.",
+                arg
+            );
+        };
+
+        // Handles --key=value
+        if let Some((key, value)) = key_part.split_once('=') {
+            check_key(key, arg)?;
+            map.insert(key.to_string(), value.to_string());
+            continue;
+        }
+        check_key(key_part, arg)?;
+
+        // Handles --no-flag, which takes no value
+        if let Some(flag) = key_part.strip_prefix(NEGATION_PREFIX) {
+            check_key(flag, arg)?;
+            if let Some(next) = words.peek()
+                && !next.starts_with("--")
+            {
+                bail!(
+                    "Unexpected value '{}' after '{}', which sets '{}' to false. \
+                     Use '--{}={}' for a parameter named '{}'.",
+                    next,
+                    arg,
+                    flag,
+                    key_part,
+                    next,
+                    key_part
+                );
+            }
+            map.insert(flag.to_string(), "false".to_string());
+            continue;
+        }
+
+        // Handles --key value, or --flag (boolean) when no value follows
+        match words.next_if(|next| !next.starts_with("--")) {
+            Some(value) => map.insert(key_part.to_string(), value.to_string()),
+            None => map.insert(key_part.to_string(), "true".to_string()),
+        };
+    }
+    Ok(map)
+}
+
+/// Rejects empty parameter names, as in `--` or `--=value`.
+fn check_key(key: &str, arg: &str) -> Result<()> {
+    if key.is_empty() {
+        bail!(
+            "Invalid goal argument: '{}'. A parameter name is missing after '--'.",
+            arg
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_words(words: &[&str]) -> Result<HashMap<String, String>> {
+        parse(&words.iter().map(|w| w.to_string()).collect::<Vec<_>>())
+    }
+
+    fn get(map: &HashMap<String, String>, key: &str) -> Option<String> {
+        map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_key_equals_value() {
+        let map = parse_words(&["--title=A title with spaces", "--expr=a=b", "--empty="]).unwrap();
+        assert_eq!(get(&map, "title").as_deref(), Some("A title with spaces"));
+        assert_eq!(get(&map, "expr").as_deref(), Some("a=b"));
+        assert_eq!(get(&map, "empty").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_key_space_value() {
+        let map = parse_words(&["--lang", "rust", "--title", "Two words"]).unwrap();
+        assert_eq!(get(&map, "lang").as_deref(), Some("rust"));
+        assert_eq!(get(&map, "title").as_deref(), Some("Two words"));
+    }
+
+    #[test]
+    fn test_values_starting_with_a_dash() {
+        let map = parse_words(&[
+            "--offset",
+            "-5",
+            "--ratio=-0.5",
+            "--sep",
+            "-",
+            "--opt",
+            "-x",
+        ])
+        .unwrap();
+        assert_eq!(get(&map, "offset").as_deref(), Some("-5"));
+        assert_eq!(get(&map, "ratio").as_deref(), Some("-0.5"));
+        assert_eq!(get(&map, "sep").as_deref(), Some("-"));
+        assert_eq!(get(&map, "opt").as_deref(), Some("-x"));
+
+        let map = parse_words(&["--pattern=--verbose"]).unwrap();
+        assert_eq!(get(&map, "pattern").as_deref(), Some("--verbose"));
+    }
+
+    #[test]
+    fn test_boolean_flags() {
+        let map = parse_words(&["--verbose", "--dry", "--lang", "go"]).unwrap();
+        assert_eq!(get(&map, "verbose").as_deref(), Some("true"));
+        assert_eq!(get(&map, "dry").as_deref(), Some("true"));
+        assert_eq!(get(&map, "lang").as_deref(), Some("go"));
+
+        let map = parse_words(&["--no-color", "--no-cache"]).unwrap();
+        assert_eq!(get(&map, "color").as_deref(), Some("false"));
+        assert_eq!(get(&map, "cache").as_deref(), Some("false"));
+        assert!(!map.contains_key("no-color"));
+    }
+
+    #[test]
+    fn test_negation_with_equals_is_a_plain_key() {
+        let map = parse_words(&["--no-reply=yes"]).unwrap();
+        assert_eq!(get(&map, "no-reply").as_deref(), Some("yes"));
+    }
+
+    #[test]
+    fn test_negation_rejects_a_value() {
+        let error = parse_words(&["--no-color", "red"]).unwrap_err();
+        assert!(error.to_string().contains("'--no-color=red'"));
+    }
+
+    #[test]
+    fn test_later_arguments_win() {
+        let map =
+            parse_words(&["--scope=api", "--verbose", "--scope", "web", "--no-verbose"]).unwrap();
+        assert_eq!(get(&map, "scope").as_deref(), Some("web"));
+        assert_eq!(get(&map, "verbose").as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let error = parse_words(&["rust"]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("must be flags starting with '--'")
+        );
+
+        let error = parse_words(&["-lang", "rust"]).unwrap_err();
+        assert!(error.to_string().contains("use two dashes, e.g. '--lang'"));
+
+        for words in [&["--"][..], &["--=value"], &["--no-"]] {
+            let error = parse_words(words).unwrap_err();
+            assert!(error.to_string().contains("parameter name is missing"));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(parse(&[]).unwrap().is_empty());
+    }
+}
@@ -12,14 +12,22 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
+use indexmap::IndexMap;
+use std::collections::BTreeMap;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-use crate::config::DiscoveredGoal;
+use crate::config::{ContextScript, DiscoveredGoal};
+use crate::history;
+use crate::prompt_review;
+use crate::script_cache::{self, CachedScript};
+use crate::theme::{Palette, TuiTheme};
 
 /// Represents which panel is currently active.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,12 +45,24 @@ enum AppMode {
     ViewMode,
 }
 
+/// Which tab is shown in view mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewTab {
+    /// The goal's prompt.yaml
+    Prompt,
+    /// The last cached output of each context script
+    Scripts,
+}
+
 /// Control flow result from input handling.
 enum ControlFlow {
     /// Continue running the event loop
     Continue,
     /// User selected a goal, exit and return the goal name
     Select,
+    /// User wants to edit the selected goal's prompt.yaml, which needs the
+    /// terminal handed over to the editor
+    Edit,
     /// User wants to quit the application
     Quit,
 }
@@ -67,18 +87,32 @@ struct GoalBrowserApp {
     view_content: Option<String>,
     /// Cached path being viewed (for display)
     view_path: Option<String>,
+    /// Which tab is shown in view mode
+    view_tab: ViewTab,
+    /// Last outputs of the viewed goal's context scripts
+    scripts_content: Option<String>,
+    /// Result of the last open/reveal action, shown in the help footer
+    status: Option<String>,
+    /// The configured theme, resolved to dark or light if `auto`
+    theme: TuiTheme,
+    /// Whether high contrast was toggled on over the configured theme
+    high_contrast: bool,
 }
 
 impl GoalBrowserApp {
-    /// Creates a new GoalBrowserApp from a list of discovered goals.
-    fn new(goals: Vec<DiscoveredGoal>) -> Self {
+    /// Creates a new GoalBrowserApp from a list of discovered goals, drawn
+    /// with `theme`.
+    fn new(goals: Vec<DiscoveredGoal>, theme: TuiTheme) -> Self {
         let mut local_goals = Vec::new();
         let mut global_goals = Vec::new();
 
         for goal in goals {
             match goal.source {
                 crate::config::GoalSource::Local => local_goals.push(goal),
-                crate::config::GoalSource::Global => global_goals.push(goal),
+                // Managed goals are shared like global ones, and marked as such
+                crate::config::GoalSource::Global | crate::config::GoalSource::Managed => {
+                    global_goals.push(goal)
+                }
             }
         }
 
@@ -99,9 +133,32 @@ impl GoalBrowserApp {
             view_scroll: 0,
             view_content: None,
             view_path: None,
+            view_tab: ViewTab::Prompt,
+            scripts_content: None,
+            status: None,
+            theme: theme.resolve(),
+            high_contrast: false,
         }
     }
 
+    /// The palette to draw with: high contrast while toggled on.
+    fn palette(&self) -> Palette {
+        if self.high_contrast {
+            TuiTheme::HighContrast.palette()
+        } else {
+            self.theme.palette()
+        }
+    }
+
+    /// Toggles high contrast on or off over the configured theme.
+    fn toggle_high_contrast(&mut self) {
+        self.high_contrast = !self.high_contrast;
+        self.status = Some(format!(
+            "High contrast {}",
+            if self.high_contrast { "on" } else { "off" }
+        ));
+    }
+
     /// Returns the currently selected goal, if any.
     fn get_selected_goal(&self) -> Option<&DiscoveredGoal> {
         match self.active_panel {
@@ -166,19 +223,87 @@ impl GoalBrowserApp {
             // Load the prompt.yaml content
             let loaded = crate::config::find_and_load_goal(&goal.name)?;
 
-            // Read the actual prompt.yaml file
-            let prompt_path = loaded.directory.join("prompt.yaml");
+            // Read the actual prompt.yaml (or prompt.md) file
+            let prompt_path = crate::config::goal_definition_path(&loaded.directory);
             let content = std::fs::read_to_string(&prompt_path)
                 .with_context(|| format!("Failed to read {}", prompt_path.display()))?;
 
+            let scripts = format_script_outputs(
+                &loaded.config.context_scripts,
+                &script_cache::load(&goal.name),
+                history::now_secs(),
+            );
+
             self.view_content = Some(content);
             self.view_path = Some(prompt_path.display().to_string());
+            self.scripts_content = Some(scripts);
+            self.view_tab = ViewTab::Prompt;
             self.view_scroll = 0;
             self.mode = AppMode::ViewMode;
         }
         Ok(())
     }
 
+    /// Returns the directory and definition file of the selected goal.
+    fn selected_goal_paths(&self) -> Result<Option<(PathBuf, PathBuf)>> {
+        let Some(goal) = self.get_selected_goal() else {
+            return Ok(None);
+        };
+        let loaded = crate::config::find_and_load_goal(&goal.name)?;
+        let prompt_path = crate::config::goal_definition_path(&loaded.directory);
+        Ok(Some((loaded.directory, prompt_path)))
+    }
+
+    /// Rescans the goals after an edit, keeping the selection and, in view
+    /// mode, the viewed goal and scroll position.
+    fn reload(&mut self) -> Result<()> {
+        let selected = self.get_selected_goal_name();
+        let panel = self.active_panel;
+        let (mode, tab, scroll) = (self.mode, self.view_tab, self.view_scroll);
+
+        let reloaded = Self::new(crate::config::find_all_goals()?, self.theme);
+        self.local_goals = reloaded.local_goals;
+        self.global_goals = reloaded.global_goals;
+        self.local_selected = self.local_selected.min(self.local_goals.len().saturating_sub(1));
+        self.global_selected = self.global_selected.min(self.global_goals.len().saturating_sub(1));
+        if let Some(name) = selected {
+            let goals = match panel {
+                Panel::Local => &self.local_goals,
+                Panel::Global => &self.global_goals,
+            };
+            if let Some(index) = goals.iter().position(|g| g.name == name) {
+                match panel {
+                    Panel::Local => self.local_selected = index,
+                    Panel::Global => self.global_selected = index,
+                }
+            }
+        }
+
+        if mode == AppMode::ViewMode {
+            self.enter_view_mode()?;
+            self.view_tab = tab;
+            self.view_scroll = scroll;
+        }
+        Ok(())
+    }
+
+    /// Switches between the prompt and script output tabs in view mode.
+    fn toggle_view_tab(&mut self) {
+        self.view_tab = match self.view_tab {
+            ViewTab::Prompt => ViewTab::Scripts,
+            ViewTab::Scripts => ViewTab::Prompt,
+        };
+        self.view_scroll = 0;
+    }
+
+    /// Returns the text shown in the current view mode tab.
+    fn current_view_content(&self) -> Option<&String> {
+        match self.view_tab {
+            ViewTab::Prompt => self.view_content.as_ref(),
+            ViewTab::Scripts => self.scripts_content.as_ref(),
+        }
+    }
+
     /// Scrolls up in view mode.
     fn scroll_up(&mut self) {
         if self.view_scroll > 0 {
@@ -203,10 +328,48 @@ impl GoalBrowserApp {
     }
 }
 
+/// Formats each context script's last cached output, with its age, for the
+/// Scripts tab.
+fn format_script_outputs(
+    scripts: &IndexMap<String, ContextScript>,
+    cached: &BTreeMap<String, CachedScript>,
+    now: u64,
+) -> String {
+    if scripts.is_empty() {
+        return "This goal has no context scripts.".to_string();
+    }
+
+    let mut text = String::new();
+    for (name, script) in scripts {
+        text.push_str(&format!("── {} ── `{}`\n", name, script.command()));
+        match cached.get(name) {
+            Some(entry) => {
+                let status = if entry.succeeded { "✓" } else { "✗ failed" };
+                text.push_str(&format!(
+                    "{} last run {}\n",
+                    status,
+                    script_cache::format_age(entry.recorded_at, now)
+                ));
+                if entry.output.is_empty() {
+                    text.push_str("(no output)\n");
+                } else {
+                    text.push_str(&entry.output);
+                    text.push('\n');
+                }
+            }
+            None => text.push_str("Not run yet. Run or dry-run the goal to record its output.\n"),
+        }
+        text.push('\n');
+    }
+    text
+}
+
 /// Entry point for the goal browser TUI.
 ///
-/// Takes a list of discovered goals and returns the name of the selected goal.
-pub fn run_goal_browser(goals: Vec<DiscoveredGoal>) -> Result<String> {
+/// Takes a list of discovered goals and the configured `tui_theme`, and
+/// returns the name of the selected goal.
+#[allow(clippy::needless_match)]
+pub fn run_goal_browser(goals: Vec<DiscoveredGoal>, theme: TuiTheme) -> Result<String> {
     // Set up terminal
     enable_raw_mode().context("Failed to enable raw mode")?;
     let mut stdout = io::stdout();
@@ -215,7 +378,7 @@ pub fn run_goal_browser(goals: Vec<DiscoveredGoal>) -> Result<String> {
     let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
 
     // Initialize app state
-    let mut app = GoalBrowserApp::new(goals);
+    let mut app = GoalBrowserApp::new(goals, theme);
 
     // Run main event loop
     let result = run_app(&mut terminal, &mut app);
@@ -234,7 +397,7 @@ pub fn run_goal_browser(goals: Vec<DiscoveredGoal>) -> Result<String> {
 }
 
 /// Main application event loop.
-fn run_app<B: ratatui::backend::Backend>(
+fn run_app<B: ratatui::backend::Backend + io::Write>(
     terminal: &mut Terminal<B>,
     app: &mut GoalBrowserApp,
 ) -> Result<String> {
@@ -246,6 +409,7 @@ fn run_app<B: ratatui::backend::Backend>(
             if key.kind == KeyEventKind::Press {
                 match handle_input(key, app)? {
                     ControlFlow::Continue => {}
+                    ControlFlow::Edit => edit_selected_goal(terminal, app)?,
                     ControlFlow::Select => {
                         return app
                             .get_selected_goal_name()
@@ -260,6 +424,86 @@ fn run_app<B: ratatui::backend::Backend>(
     }
 }
 
+/// Suspends the browser to edit the selected goal's prompt.yaml in
+/// `$EDITOR`, then reloads the goals so the fix shows up right away.
+fn edit_selected_goal<B: ratatui::backend::Backend + io::Write>(
+    terminal: &mut Terminal<B>,
+    app: &mut GoalBrowserApp,
+) -> Result<()> {
+    if app.get_selected_goal().is_some_and(|g| g.config.managed) {
+        app.status = Some("Managed goals are read-only".to_string());
+        return Ok(());
+    }
+    let prompt_path = match app.selected_goal_paths() {
+        Ok(Some((_, prompt_path))) => prompt_path,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            app.status = Some(format!("{:#}", e));
+            return Ok(());
+        }
+    };
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    let edited = run_editor(&prompt_path);
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)
+        .context("Failed to enter alternate screen")?;
+    terminal.clear().context("Failed to redraw the terminal")?;
+
+    app.status = Some(match edited.and_then(|_| app.reload()) {
+        Ok(()) => format!("Reloaded {}", prompt_path.display()),
+        Err(e) => format!("{:#}", e),
+    });
+    Ok(())
+}
+
+/// Opens `path` in `$VISUAL` / `$EDITOR` and waits for it to exit.
+fn run_editor(path: &Path) -> Result<()> {
+    let (program, args) = prompt_review::editor_command()?;
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", program))?;
+    if !status.success() {
+        anyhow::bail!("The editor exited with an error");
+    }
+    Ok(())
+}
+
+/// Shows `directory` in the platform's file manager, without waiting for it.
+fn reveal(directory: &Path) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run '{}' to open {}", opener, directory.display()))?;
+    Ok(())
+}
+
+/// Reveals the selected goal's directory, reporting the outcome in the footer.
+fn reveal_selected_goal(app: &mut GoalBrowserApp) {
+    app.status = match app.selected_goal_paths() {
+        Ok(Some((directory, _))) => Some(match reveal(&directory) {
+            Ok(()) => format!("Opened {}", directory.display()),
+            Err(e) => format!("{:#}", e),
+        }),
+        Ok(None) => None,
+        Err(e) => Some(format!("{:#}", e)),
+    };
+}
+
 /// Main UI rendering function.
 fn render_ui(frame: &mut Frame, app: &GoalBrowserApp) {
     match app.mode {
@@ -269,9 +513,8 @@ fn render_ui(frame: &mut Frame, app: &GoalBrowserApp) {
 }
 
 /// Renders the ASCII art logo.
-fn render_logo(area: Rect, frame: &mut Frame) {
-    let bright_orange = Color::Rgb(255, 165, 0);
-    let soft_neon_green = Color::Rgb(144, 238, 144);
+fn render_logo(area: Rect, frame: &mut Frame, palette: &Palette) {
+    let (letters, underscores) = palette.logo;
 
     let logo_text = r#"________/\\\\\\\\\__/\\\_________________/\\\\\\\\\_____/\\\______________/\\\_
  _____/\\\////////__\/\\\_______________/\\\\\\\\\\\\\__\/\\\_____________\/\\\_
@@ -290,9 +533,9 @@ fn render_logo(area: Rect, frame: &mut Frame) {
                 .chars()
                 .map(|ch| {
                     if ch == '_' {
-                        Span::styled(ch.to_string(), Style::default().fg(soft_neon_green))
+                        Span::styled(ch.to_string(), underscores)
                     } else {
-                        Span::styled(ch.to_string(), Style::default().fg(bright_orange))
+                        Span::styled(ch.to_string(), letters)
                     }
                 })
                 .collect();
@@ -307,6 +550,7 @@ fn render_logo(area: Rect, frame: &mut Frame) {
 /// Renders the selection mode (dual-panel view).
 fn render_selection_mode(frame: &mut Frame, app: &GoalBrowserApp) {
     let area = frame.area();
+    let palette = app.palette();
 
     // Create vertical layout: logo + main area + help footer
     let chunks = Layout::default()
@@ -323,7 +567,7 @@ fn render_selection_mode(frame: &mut Frame, app: &GoalBrowserApp) {
     let help_area = chunks[2];
 
     // Render logo
-    render_logo(logo_area, frame);
+    render_logo(logo_area, frame, &palette);
 
     // Determine which panels to show
     let show_local = !app.local_goals.is_empty();
@@ -352,16 +596,16 @@ fn render_selection_mode(frame: &mut Frame, app: &GoalBrowserApp) {
 
     // Render panels
     if show_local && show_global {
-        render_goal_panel(frame, panels[0], &app.local_goals, app.local_selected, "Local Goals", app.active_panel == Panel::Local);
-        render_goal_panel(frame, panels[1], &app.global_goals, app.global_selected, "Global Goals", app.active_panel == Panel::Global);
+        render_goal_panel(frame, panels[0], &app.local_goals, app.local_selected, "Local Goals", app.active_panel == Panel::Local, &palette);
+        render_goal_panel(frame, panels[1], &app.global_goals, app.global_selected, "Global Goals", app.active_panel == Panel::Global, &palette);
     } else if show_local {
-        render_goal_panel(frame, panels[0], &app.local_goals, app.local_selected, "Local Goals", true);
+        render_goal_panel(frame, panels[0], &app.local_goals, app.local_selected, "Local Goals", true, &palette);
     } else if show_global {
-        render_goal_panel(frame, panels[0], &app.global_goals, app.global_selected, "Global Goals", true);
+        render_goal_panel(frame, panels[0], &app.global_goals, app.global_selected, "Global Goals", true, &palette);
     }
 
     // Render help footer
-    render_help_footer(frame, help_area);
+    render_help_footer(frame, help_area, app.status.as_deref(), &palette);
 }
 
 /// Renders a single goal panel.
@@ -372,6 +616,7 @@ fn render_goal_panel(
     selected: usize,
     title: &str,
     is_active: bool,
+    palette: &Palette,
 ) {
     // Create list items from goals
     let items: Vec<ListItem> = goals
@@ -384,20 +629,23 @@ fn render_goal_panel(
                 .as_deref()
                 .unwrap_or("No description");
 
-            // Format: {name} ({folder_name}) -- {description}
+            // Format: {name} ({folder_name}) -- {description}, with a lock
+            // for read-only managed goals
             let content = format!(
-                "{} ({}) -- {}",
-                goal.config.name, goal.name, description
+                "{}{} ({}) -- {}",
+                if goal.config.managed { "🔒 " } else { "" },
+                goal.config.name,
+                goal.name,
+                description
             );
 
             // Highlight selected item
-            let style = if i == selected {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(if is_active { Color::Cyan } else { Color::DarkGray })
-                    .add_modifier(Modifier::BOLD)
+            let style = if i != selected {
+                palette.text
+            } else if is_active {
+                palette.selected
             } else {
-                Style::default().fg(Color::White)
+                palette.selected_inactive
             };
 
             ListItem::new(content).style(style)
@@ -410,43 +658,60 @@ fn render_goal_panel(
             .title(title)
             .borders(Borders::ALL)
             .border_style(if is_active {
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                palette.border
             } else {
-                Style::default().fg(Color::DarkGray)
+                palette.border_inactive
             }),
     );
 
     frame.render_widget(list, area);
 }
 
-/// Renders the help footer with keybindings.
-fn render_help_footer(frame: &mut Frame, area: Rect) {
-    let orange = Color::Rgb(255, 165, 0);
+/// Renders the help footer with keybindings, titled with `status` if set.
+fn render_help_footer(frame: &mut Frame, area: Rect, status: Option<&str>, palette: &Palette) {
+    let key = palette.key;
     let help_text = vec![
         Line::from(vec![
-            Span::styled("↑/↓ or j/k", Style::default().fg(orange)),
+            Span::styled("↑/↓ or j/k", key),
             Span::raw(": Navigate  "),
-            Span::styled("Tab", Style::default().fg(orange)),
+            Span::styled("Tab", key),
             Span::raw(": Switch Panel  "),
-            Span::styled("v", Style::default().fg(orange)),
+            Span::styled("v", key),
             Span::raw(": View  "),
-            Span::styled("Enter", Style::default().fg(orange)),
+            Span::styled("o/O", key),
+            Span::raw(": Edit/Reveal  "),
+            Span::styled("c", key),
+            Span::raw(": Contrast  "),
+            Span::styled("Enter", key),
             Span::raw(": Select  "),
-            Span::styled("Esc/q", Style::default().fg(orange)),
+            Span::styled("Esc/q", key),
             Span::raw(": Quit"),
         ]),
     ];
 
     let help = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title("Help"))
-        .style(Style::default().fg(Color::DarkGray));
+        .block(help_block(status, palette))
+        .style(palette.muted);
 
     frame.render_widget(help, area);
 }
 
+/// The help footer's block, titled with the last action's outcome if any.
+fn help_block<'a>(status: Option<&'a str>, palette: &Palette) -> Block<'a> {
+    let block = Block::default().borders(Borders::ALL);
+    match status {
+        Some(status) => block.title(Span::styled(
+            format!(" {} ", status),
+            palette.status,
+        )),
+        None => block.title("Help"),
+    }
+}
+
 /// Renders the view mode (prompt.yaml preview).
 fn render_view_mode(frame: &mut Frame, app: &GoalBrowserApp) {
     let area = frame.area();
+    let palette = app.palette();
 
     // Create vertical layout: header + content area + help footer
     let chunks = Layout::default()
@@ -469,14 +734,14 @@ fn render_view_mode(frame: &mut Frame, app: &GoalBrowserApp) {
                 Block::default()
                     .title("Viewing")
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
+                    .border_style(palette.border),
             )
-            .style(Style::default().fg(Color::White));
+            .style(palette.text);
         frame.render_widget(header, header_area);
     }
 
     // Render content
-    if let Some(content) = &app.view_content {
+    if let Some(content) = app.current_view_content() {
         let lines: Vec<&str> = content.lines().collect();
         let total_lines = lines.len();
 
@@ -496,39 +761,62 @@ fn render_view_mode(frame: &mut Frame, app: &GoalBrowserApp) {
             .block(
                 Block::default()
                     .title(format!(
-                        "Content (line {}/{}) - Use ↑/↓ to scroll, Esc to exit",
+                        "{} (line {}/{}) - Use ↑/↓ to scroll, Esc to exit",
+                        match app.view_tab {
+                            ViewTab::Prompt => "[Prompt] Scripts",
+                            ViewTab::Scripts => "Prompt [Scripts]",
+                        },
                         scroll + 1,
                         total_lines
                     ))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
+                    .border_style(palette.border),
             )
             .wrap(Wrap { trim: false })
-            .style(Style::default().fg(Color::White));
+            .style(palette.text);
 
         frame.render_widget(paragraph, content_area);
     }
 
     // Render help footer for view mode
-    let orange = Color::Rgb(255, 165, 0);
+    let key = palette.key;
     let help_text = vec![Line::from(vec![
-        Span::styled("↑/↓ or j/k", Style::default().fg(orange)),
+        Span::styled("↑/↓ or j/k", key),
         Span::raw(": Scroll  "),
-        Span::styled("PgUp/PgDn", Style::default().fg(orange)),
+        Span::styled("PgUp/PgDn", key),
         Span::raw(": Page  "),
-        Span::styled("Esc/q", Style::default().fg(orange)),
+        Span::styled("Tab", key),
+        Span::raw(": Prompt/Scripts  "),
+        Span::styled("o/O", key),
+        Span::raw(": Edit/Reveal  "),
+        Span::styled("c", key),
+        Span::raw(": Contrast  "),
+        Span::styled("Esc/q", key),
         Span::raw(": Back"),
     ])];
 
     let help = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title("Help"))
-        .style(Style::default().fg(Color::DarkGray));
+        .block(help_block(app.status.as_deref(), &palette))
+        .style(palette.muted);
 
     frame.render_widget(help, help_area);
 }
 
 /// Handles keyboard input and updates application state.
 fn handle_input(key: KeyEvent, app: &mut GoalBrowserApp) -> Result<ControlFlow> {
+    app.status = None;
+    match key.code {
+        KeyCode::Char('o') => return Ok(ControlFlow::Edit),
+        KeyCode::Char('O') => {
+            reveal_selected_goal(app);
+            return Ok(ControlFlow::Continue);
+        }
+        KeyCode::Char('c') => {
+            app.toggle_high_contrast();
+            return Ok(ControlFlow::Continue);
+        }
+        _ => {}
+    }
     match app.mode {
         AppMode::Selection => handle_selection_input(key, app),
         AppMode::ViewMode => handle_view_input(key, app),
@@ -568,9 +856,14 @@ fn handle_view_input(key: KeyEvent, app: &mut GoalBrowserApp) -> Result<ControlF
             app.mode = AppMode::Selection;
             app.view_content = None;
             app.view_path = None;
+            app.scripts_content = None;
             app.view_scroll = 0;
             Ok(ControlFlow::Continue)
         }
+        KeyCode::Tab => {
+            app.toggle_view_tab();
+            Ok(ControlFlow::Continue)
+        }
         KeyCode::Up | KeyCode::Char('k') => {
             app.scroll_up();
             Ok(ControlFlow::Continue)
@@ -595,7 +888,7 @@ fn handle_view_input(key: KeyEvent, app: &mut GoalBrowserApp) -> Result<ControlF
 mod tests {
     use super::*;
     use crate::config::{GoalSource, PromptConfig};
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
 
     fn create_test_goal(name: &str, source: GoalSource) -> DiscoveredGoal {
         DiscoveredGoal {
@@ -605,9 +898,11 @@ mod tests {
                 name: format!("{} Display Name", name),
                 description: Some(format!("{} description", name)),
                 parameters: Vec::new(),
-                context_scripts: HashMap::new(),
+                context_scripts: IndexMap::new(),
                 prompt: "test prompt".to_string(),
+                ..Default::default()
             },
+            collection: None,
         }
     }
 
@@ -618,7 +913,7 @@ mod tests {
             create_test_goal("local2", GoalSource::Local),
         ];
 
-        let app = GoalBrowserApp::new(goals);
+        let app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         assert_eq!(app.local_goals.len(), 2);
         assert_eq!(app.global_goals.len(), 0);
@@ -633,7 +928,7 @@ mod tests {
             create_test_goal("global2", GoalSource::Global),
         ];
 
-        let app = GoalBrowserApp::new(goals);
+        let app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         assert_eq!(app.local_goals.len(), 0);
         assert_eq!(app.global_goals.len(), 2);
@@ -648,7 +943,7 @@ mod tests {
             create_test_goal("global1", GoalSource::Global),
         ];
 
-        let app = GoalBrowserApp::new(goals);
+        let app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         assert_eq!(app.local_goals.len(), 1);
         assert_eq!(app.global_goals.len(), 1);
@@ -662,7 +957,7 @@ mod tests {
             create_test_goal("local2", GoalSource::Local),
         ];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
         assert_eq!(app.local_selected, 0);
 
         app.move_up();
@@ -676,7 +971,7 @@ mod tests {
             create_test_goal("local2", GoalSource::Local),
         ];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
         app.local_selected = 1;
 
         app.move_down();
@@ -691,7 +986,7 @@ mod tests {
             create_test_goal("local3", GoalSource::Local),
         ];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
         assert_eq!(app.local_selected, 0);
 
         app.move_down();
@@ -711,7 +1006,7 @@ mod tests {
             create_test_goal("global1", GoalSource::Global),
         ];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
         assert_eq!(app.active_panel, Panel::Local);
 
         app.toggle_panel();
@@ -725,7 +1020,7 @@ mod tests {
     fn test_toggle_panel_with_only_local() {
         let goals = vec![create_test_goal("local1", GoalSource::Local)];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
         assert_eq!(app.active_panel, Panel::Local);
 
         app.toggle_panel();
@@ -739,7 +1034,7 @@ mod tests {
             create_test_goal("global1", GoalSource::Global),
         ];
 
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         let selected = app.get_selected_goal().unwrap();
         assert_eq!(selected.name, "local1");
@@ -752,7 +1047,7 @@ mod tests {
     #[test]
     fn test_scroll_up_at_top() {
         let goals = vec![create_test_goal("local1", GoalSource::Local)];
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         app.view_scroll = 0;
         app.scroll_up();
@@ -762,7 +1057,7 @@ mod tests {
     #[test]
     fn test_scroll_down() {
         let goals = vec![create_test_goal("local1", GoalSource::Local)];
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         app.view_scroll = 0;
         app.scroll_down();
@@ -775,7 +1070,7 @@ mod tests {
     #[test]
     fn test_page_up_and_down() {
         let goals = vec![create_test_goal("local1", GoalSource::Local)];
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         app.view_scroll = 20;
         app.page_up(10);
@@ -788,10 +1083,71 @@ mod tests {
     #[test]
     fn test_page_up_underflow() {
         let goals = vec![create_test_goal("local1", GoalSource::Local)];
-        let mut app = GoalBrowserApp::new(goals);
+        let mut app = GoalBrowserApp::new(goals, TuiTheme::Dark);
 
         app.view_scroll = 5;
         app.page_up(10);
         assert_eq!(app.view_scroll, 0); // Should not underflow
     }
+
+    #[test]
+    fn test_format_script_outputs() {
+        let scripts = IndexMap::from([
+            ("diff".to_string(), "git diff".into()),
+            ("todo".to_string(), "grep -r TODO".into()),
+        ]);
+        let cached = BTreeMap::from([(
+            "diff".to_string(),
+            CachedScript {
+                recorded_at: 0,
+                succeeded: true,
+                output: "+ line".to_string(),
+            },
+        )]);
+
+        let text = format_script_outputs(&scripts, &cached, 7200);
+        assert!(text.contains("── diff ── `git diff`\n✓ last run 2h ago\n+ line\n"));
+        assert!(text.contains("── todo ── `grep -r TODO`\nNot run yet."));
+    }
+
+    #[test]
+    fn test_toggle_view_tab_resets_scroll() {
+        let mut app = GoalBrowserApp::new(vec![create_test_goal("a", GoalSource::Local)], TuiTheme::Dark);
+        app.view_content = Some("prompt".to_string());
+        app.scripts_content = Some("scripts".to_string());
+        app.view_scroll = 5;
+
+        app.toggle_view_tab();
+        assert_eq!(app.view_scroll, 0);
+        assert_eq!(app.current_view_content().map(String::as_str), Some("scripts"));
+    }
+
+    #[test]
+    fn test_open_keys_in_both_modes() {
+        let mut app = GoalBrowserApp::new(vec![create_test_goal("a", GoalSource::Local)], TuiTheme::Dark);
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        assert!(matches!(handle_input(key('o'), &mut app).unwrap(), ControlFlow::Edit));
+
+        // Any key clears the last action's status
+        app.mode = AppMode::ViewMode;
+        app.status = Some("Reloaded prompt.yaml".to_string());
+        assert!(matches!(handle_input(key('o'), &mut app).unwrap(), ControlFlow::Edit));
+        assert_eq!(app.status, None);
+    }
+
+    #[test]
+    fn test_contrast_key_toggles_high_contrast_in_both_modes() {
+        let mut app = GoalBrowserApp::new(vec![create_test_goal("a", GoalSource::Local)], TuiTheme::Light);
+        let key = KeyEvent::from(KeyCode::Char('c'));
+        assert_eq!(app.palette(), TuiTheme::Light.palette());
+
+        handle_input(key, &mut app).unwrap();
+        assert_eq!(app.palette(), TuiTheme::HighContrast.palette());
+        assert_eq!(app.status.as_deref(), Some("High contrast on"));
+
+        app.mode = AppMode::ViewMode;
+        handle_input(key, &mut app).unwrap();
+        assert_eq!(app.palette(), TuiTheme::Light.palette());
+        assert_eq!(app.mode, AppMode::ViewMode);
+    }
 }
@@ -0,0 +1,236 @@
+//! Lock files that keep runs of an `exclusive` goal from overlapping.
+//!
+//! A goal with `exclusive: true` (or `wait`) takes `.claw/locks/<goal>.lock`
+//! in its repository for the whole run. The file is created only if it
+//! doesn't exist, and holds the owner's process ID, so a second run can tell
+//! a live owner from one that crashed and left its lock behind. Outside a
+//! repository with a `.claw/` directory, the lock lives in the global config
+//! directory, keyed by the working directory.
+
+use anyhow::{Context as AnyhowContext, Result};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::ConfigPaths;
+use crate::digest;
+use crate::history;
+
+/// How often a queued run checks whether the lock was released.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long an unreadable lock file is left alone, since its owner may not
+/// have written it yet.
+const UNREADABLE_GRACE: Duration = Duration::from_secs(2);
+
+/// A held goal lock, released when dropped.
+#[derive(Debug)]
+pub struct GoalLock {
+    path: PathBuf,
+}
+
+impl Drop for GoalLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The run holding a lock, as written in its lock file.
+#[derive(Debug, PartialEq)]
+struct Holder {
+    pid: u32,
+    /// Seconds since the Unix epoch.
+    since: u64,
+}
+
+/// Takes the lock of `goal` in the current repository. If another run holds
+/// it, waits for that run to finish when `wait` is set, and fails otherwise.
+pub fn acquire(goal: &str, wait: bool) -> Result<GoalLock> {
+    let path = lock_path(goal)?;
+    acquire_at(&path, goal, wait)
+}
+
+/// The directory locks are kept in, in `.claw/` or the global config.
+pub const LOCKS_DIR: &str = "locks";
+
+/// Where the lock of `goal` lives: `.claw/locks/` in the repository, or
+/// the global `locks/` directory outside one.
+fn lock_path(goal: &str) -> Result<PathBuf> {
+    // Collection goals are named `collection/goal`
+    let name: String = goal
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let paths = ConfigPaths::new()?;
+    if let Some(local) = paths.local {
+        return Ok(local.join(LOCKS_DIR).join(format!("{}.lock", name)));
+    }
+    let Some(global) = paths.global else {
+        anyhow::bail!(
+            "Goal '{}' is exclusive, but there's no .claw directory here or global \
+             config directory to keep its lock in.",
+            goal
+        );
+    };
+    let cwd = std::env::current_dir()?;
+    let key = digest::sha256_hex(cwd.to_string_lossy().as_bytes());
+    Ok(global.join(LOCKS_DIR).join(format!("{}-{}.lock", name, &key[..12])))
+}
+
+fn acquire_at(path: &Path, goal: &str, wait: bool) -> Result<GoalLock> {
+    let dir = path.parent().context("Lock file has no directory")?;
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    // Lock files are per machine, never to be committed
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        let _ = fs::write(&gitignore, "*\n");
+    }
+
+    let mut announced = false;
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                let contents = format!("{}\n{}\n", std::process::id(), history::now_secs());
+                file.write_all(contents.as_bytes())
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                return Ok(GoalLock {
+                    path: path.to_path_buf(),
+                });
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", path.display()));
+            }
+        }
+
+        // The owner may be releasing the lock right now; try again then
+        let Ok(contents) = fs::read_to_string(path) else {
+            continue;
+        };
+        match parse_holder(&contents) {
+            Some(holder) if process_alive(holder.pid) => {
+                if !wait {
+                    anyhow::bail!(
+                        "Goal '{}' is exclusive and already running here ({}).\n\
+                         Try again once it finishes, or remove {} if that run is gone.",
+                        goal,
+                        describe(&holder),
+                        path.display()
+                    );
+                }
+                if !announced {
+                    eprintln!(
+                        "⏳ Waiting for the other run of '{}' to finish ({})...",
+                        goal,
+                        describe(&holder)
+                    );
+                    announced = true;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            None if modified_within(path, UNREADABLE_GRACE) => thread::sleep(POLL_INTERVAL),
+            // Left behind by a run that died, or unreadable: take it over
+            _ => {
+                eprintln!("⚠️  Removing a stale lock of '{}': {}", goal, path.display());
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove stale lock {}", path.display()))?;
+            }
+        }
+    }
+}
+
+fn parse_holder(contents: &str) -> Option<Holder> {
+    let mut lines = contents.lines();
+    let pid = lines.next()?.trim().parse().ok()?;
+    let since = lines.next()?.trim().parse().ok()?;
+    Some(Holder { pid, since })
+}
+
+fn modified_within(path: &Path, duration: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < duration))
+}
+
+fn describe(holder: &Holder) -> String {
+    format!(
+        "pid {}, started {}s ago",
+        holder.pid,
+        history::now_secs().saturating_sub(holder.since)
+    )
+}
+
+/// Whether a process with this ID is running. Where that can't be told,
+/// assumes it is, so a live run's lock is never taken over.
+fn process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Path::new("/proc").join(pid.to_string()).exists()
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        std::process::Command::new("ps")
+            .args(["-p", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(true)
+    }
+    #[cfg(not(unix))]
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_refused_while_held_and_released_on_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("locks/notes.lock");
+
+        let lock = acquire_at(&path, "notes", false).unwrap();
+        let holder = parse_holder(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+
+        let err = acquire_at(&path, "notes", false).unwrap_err();
+        assert!(err.to_string().contains("already running here"));
+
+        drop(lock);
+        assert!(!path.exists());
+        acquire_at(&path, "notes", false).unwrap();
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.lock");
+        // No process has the largest ID
+        fs::write(&path, format!("{}\n0\n", u32::MAX)).unwrap();
+
+        let _lock = acquire_at(&path, "notes", false).unwrap();
+        let holder = parse_holder(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+    }
+
+    #[test]
+    fn test_parse_holder() {
+        assert_eq!(
+            parse_holder("42\n1700000000\n"),
+            Some(Holder {
+                pid: 42,
+                since: 1700000000
+            })
+        );
+        assert_eq!(parse_holder("42\n"), None);
+    }
+}
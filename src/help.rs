@@ -1,13 +1,18 @@
-use crate::config::{GoalParameter, LoadedGoal, ParameterType};
+use crate::config::{GoalExample, GoalParameter, LoadedGoal, ParameterType};
+use crate::markdown;
 
-/// Formats help text for a goal with parameters.
-pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
+/// Formats help text for a goal with parameters. With `styled`, the markdown
+/// in descriptions is rendered for a terminal.
+pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str, styled: bool) -> String {
     let mut output = String::new();
 
     // Header
     output.push_str(&format!("Goal: {} ({})\n", goal.config.name, goal_name));
     if let Some(desc) = &goal.config.description {
-        output.push_str(&format!("Description: {}\n", desc));
+        output.push_str(&format_description(desc, styled));
+    }
+    if let Some(requirement) = &goal.config.requires_claw {
+        output.push_str(&format!("Requires claw: {}\n", requirement));
     }
     output.push('\n');
 
@@ -20,6 +25,10 @@ pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
         ));
         output.push_str("This goal didn't define any parameters.\n");
         output.push_str("Parameters are passed after '--' as --key value or --key=value.\n");
+        if !goal.config.examples.is_empty() {
+            output.push('\n');
+            output.push_str(&format_examples(&goal.config.examples, goal_name));
+        }
         output.push_str("\nTo see this help again, run:\n");
         output.push_str(&format!("  claw {} --explain\n", goal_name));
         return output;
@@ -44,7 +53,7 @@ pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
     if !required.is_empty() {
         output.push_str("Required Parameters:\n");
         for param in &required {
-            output.push_str(&format_parameter(param));
+            output.push_str(&format_parameter(param, styled));
             output.push('\n');
         }
     }
@@ -53,7 +62,7 @@ pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
     if !optional.is_empty() {
         output.push_str("Optional Parameters:\n");
         for param in &optional {
-            output.push_str(&format_parameter(param));
+            output.push_str(&format_parameter(param, styled));
             output.push('\n');
         }
     }
@@ -65,9 +74,16 @@ pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
         "  -d, --recurse_depth <num>  Maximum recursion depth when scanning directories\n",
     );
     output.push_str("  -e, --explain              Show this help information\n");
+    if !goal.config.examples.is_empty() {
+        output.push_str("      --example <N>          Run example N from the list below\n");
+    }
     output.push('\n');
 
-    // Show usage examples
+    // Show the goal's own examples, or usage with placeholders
+    if !goal.config.examples.is_empty() {
+        output.push_str(&format_examples(&goal.config.examples, goal_name));
+        return output;
+    }
     output.push_str("Usage Examples:\n");
     output.push_str(&format!("  claw {} --", goal_name));
     for param in &required {
@@ -89,8 +105,62 @@ pub fn format_goal_help(goal: &LoadedGoal, goal_name: &str) -> String {
     output
 }
 
+/// Formats a goal's examples as a numbered list of descriptions and commands.
+fn format_examples(examples: &[GoalExample], goal_name: &str) -> String {
+    let mut output = String::from("Examples:\n");
+    for (i, example) in examples.iter().enumerate() {
+        output.push_str(&format!("  {}. {}\n", i + 1, example.description));
+        output.push_str(&format!("     {}\n", example_command(example, goal_name)));
+    }
+    output.push_str(&format!(
+        "\nRun an example with: claw {} --example <N>\n",
+        goal_name
+    ));
+    output
+}
+
+/// The command line that runs `example`, e.g. `claw review -- --scope=api`.
+pub fn example_command(example: &GoalExample, goal_name: &str) -> String {
+    let mut command = format!("claw {}", goal_name);
+    for path in &example.context {
+        command.push_str(&format!(
+            " --context {}",
+            quote(&path.display().to_string())
+        ));
+    }
+    // Invalid values are reported when the example is run
+    let args = example.template_args().unwrap_or_default();
+    if !args.is_empty() {
+        command.push_str(" --");
+        for arg in &args {
+            let (name, value) = arg.split_once('=').unwrap_or((arg, ""));
+            command.push_str(&format!(" {}={}", name, quote(value)));
+        }
+    }
+    command
+}
+
+/// Quotes a word for the shell if it needs it.
+fn quote(word: &str) -> String {
+    shlex::try_quote(word)
+        .map(|quoted| quoted.into_owned())
+        .unwrap_or_else(|_| word.to_string())
+}
+
+/// Formats the goal's description, on its own lines below the label when
+/// its rendered markdown takes more than one.
+fn format_description(desc: &str, styled: bool) -> String {
+    if !styled {
+        return format!("Description: {}\n", desc);
+    }
+    match markdown::render(desc, 66).as_slice() {
+        [line] => format!("Description: {}\n", line),
+        _ => format!("Description:\n{}", markdown::render_indented(desc, 2, 76)),
+    }
+}
+
 /// Formats a single parameter for display.
-fn format_parameter(param: &GoalParameter) -> String {
+fn format_parameter(param: &GoalParameter, styled: bool) -> String {
     let mut output = String::new();
 
     // Parameter name and type
@@ -106,7 +176,18 @@ fn format_parameter(param: &GoalParameter) -> String {
     }
     output.push('\n');
 
+    if let Some(choices) = &param.choices {
+        output.push_str(&format!("      Choices: {}\n", choices.join(", ")));
+    }
+    if let Some(var) = &param.env {
+        output.push_str(&format!("      From environment: ${}\n", var));
+    }
+
     // Description with proper indentation
+    if styled {
+        output.push_str(&markdown::render_indented(&param.description, 6, 70));
+        return output;
+    }
     let description_lines = wrap_text(&param.description, 70);
     for line in description_lines {
         output.push_str("      ");
@@ -118,7 +199,7 @@ fn format_parameter(param: &GoalParameter) -> String {
 }
 
 /// Formats a parameter type for display.
-fn format_type(param_type: &ParameterType) -> String {
+pub fn format_type(param_type: &ParameterType) -> String {
     match param_type {
         ParameterType::String => "string".to_string(),
         ParameterType::Number => "number".to_string(),
@@ -127,6 +208,7 @@ fn format_type(param_type: &ParameterType) -> String {
 }
 
 /// Wraps text to a maximum width, breaking on word boundaries.
+#[allow(clippy::int_plus_one)]
 fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
     let mut lines = Vec::new();
     let mut current_line = String::new();
@@ -157,8 +239,8 @@ fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::PromptConfig;
-    use std::collections::HashMap;
+    use crate::config::{GoalSource, PromptConfig};
+    use indexmap::IndexMap;
     use std::path::PathBuf;
 
     fn create_test_param(
@@ -174,6 +256,10 @@ mod tests {
             required,
             param_type,
             default: default.map(|s| s.to_string()),
+            ui: None,
+            choices: None,
+            choices_script: None,
+            env: None,
         }
     }
 
@@ -184,13 +270,15 @@ mod tests {
                 name: "Test Goal".to_string(),
                 description: Some("A test goal".to_string()),
                 parameters: Vec::new(),
-                context_scripts: HashMap::new(),
+                context_scripts: IndexMap::new(),
                 prompt: "test".to_string(),
+                ..Default::default()
             },
             directory: PathBuf::from("/test"),
+            source: GoalSource::Local,
         };
 
-        let help = format_goal_help(&goal, "test-goal");
+        let help = format_goal_help(&goal, "test-goal", false);
         assert!(help.contains("Test Goal"));
         assert!(help.contains("A test goal"));
         assert!(help.contains("didn't define any parameters"));
@@ -209,13 +297,15 @@ mod tests {
                     Some(ParameterType::String),
                     None,
                 )],
-                context_scripts: HashMap::new(),
+                context_scripts: IndexMap::new(),
                 prompt: "test".to_string(),
+                ..Default::default()
             },
             directory: PathBuf::from("/test"),
+            source: GoalSource::Local,
         };
 
-        let help = format_goal_help(&goal, "test-goal");
+        let help = format_goal_help(&goal, "test-goal", false);
         assert!(help.contains("Required Parameters"));
         assert!(help.contains("--scope"));
         assert!(help.contains("The scope of the review"));
@@ -234,18 +324,72 @@ mod tests {
                     Some(ParameterType::String),
                     Some("markdown"),
                 )],
-                context_scripts: HashMap::new(),
+                context_scripts: IndexMap::new(),
                 prompt: "test".to_string(),
+                ..Default::default()
             },
             directory: PathBuf::from("/test"),
+            source: GoalSource::Local,
         };
 
-        let help = format_goal_help(&goal, "test-goal");
+        let help = format_goal_help(&goal, "test-goal", false);
         assert!(help.contains("Optional Parameters"));
         assert!(help.contains("--format"));
         assert!(help.contains("default: \"markdown\""));
     }
 
+    #[test]
+    fn test_goal_examples_replace_placeholders() {
+        let examples: Vec<GoalExample> = serde_yaml::from_str(
+            "- description: Review the API layer\n  args: { scope: api, depth: 2 }\n  context: [src/api]\n- description: Review everything\n  args: { scope: all files }\n",
+        )
+        .unwrap();
+        let goal = LoadedGoal {
+            config: PromptConfig {
+                name: "Test Goal".to_string(),
+                parameters: vec![create_test_param("scope", "Scope", true, None, None)],
+                examples,
+                prompt: "test".to_string(),
+                ..Default::default()
+            },
+            directory: PathBuf::from("/test"),
+            source: GoalSource::Local,
+        };
+
+        let help = format_goal_help(&goal, "review", false);
+        assert!(!help.contains("<value>"));
+        assert!(help.contains(
+            "  1. Review the API layer\n     claw review --context src/api -- --depth=2 --scope=api\n"
+        ));
+        assert!(help.contains("  2. Review everything\n     claw review -- --scope='all files'\n"));
+        assert!(help.contains("claw review --example <N>"));
+    }
+
+    #[test]
+    fn test_styled_help_renders_markdown_descriptions() {
+        let goal = LoadedGoal {
+            config: PromptConfig {
+                name: "Test Goal".to_string(),
+                description: Some("Checks:\n\n- **bugs**\n- style".to_string()),
+                parameters: vec![create_test_param(
+                    "scope",
+                    "Passed to `git diff`",
+                    true,
+                    None,
+                    None,
+                )],
+                prompt: "test".to_string(),
+                ..Default::default()
+            },
+            directory: PathBuf::from("/test"),
+            source: GoalSource::Local,
+        };
+
+        let help = format_goal_help(&goal, "test-goal", true);
+        assert!(help.contains("Description:\n  Checks:\n\n  • \x1b[1mbugs\x1b[0m\n  • style\n"));
+        assert!(help.contains("      Passed to \x1b[36mgit diff\x1b[0m\n"));
+    }
+
     #[test]
     fn test_wrap_text() {
         let text = "This is a long description that should be wrapped at the specified width";
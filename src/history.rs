@@ -0,0 +1,530 @@
+//! Run history store.
+//!
+//! Each goal run is recorded as a small JSON file under the global
+//! `~/.config/claw/history/` directory, with its arguments, outcome and, when
+//! the response was captured (e.g. with `--tee`), the response text. Runs
+//! interrupted with Ctrl-C are recorded with whatever output arrived.
+//!
+//! A run is identified by its file name without `.json`, e.g.
+//! `1718000000-review`; `claw bundle` packs one up for sharing. Runs also
+//! keep the prompt as sent, the goal's definition and a manifest of the
+//! context files, so the bundle shows exactly what the model was given.
+//! Secrets are masked before a run is recorded, and only the newest
+//! `history_limit` runs are kept.
+
+use anyhow::{Context as AnyhowContext, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tera::Tera;
+
+use crate::config::ConfigPaths;
+use crate::digest;
+use crate::provenance::{ProvenanceMap, Source};
+use crate::redact::{self, REDACTED};
+
+/// How many runs history keeps when `history_limit` isn't set.
+pub const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+/// How a recorded run ended.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Completed,
+    Failed,
+    Interrupted,
+}
+
+/// Whether a recorded argument was given by the user or filled in from the
+/// parameter's default.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgSource {
+    /// Given on the command line or answered at a prompt.
+    Supplied,
+    Defaulted,
+}
+
+/// A single recorded goal run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// CLI name of the goal that was run.
+    pub goal: String,
+    /// Validated template arguments, including defaults.
+    pub args: HashMap<String, String>,
+    /// Where each argument came from. Missing in runs recorded before this
+    /// was tracked.
+    #[serde(default)]
+    pub arg_sources: Option<BTreeMap<String, ArgSource>>,
+    /// Unix timestamp (seconds) when the run started.
+    pub started_at: u64,
+    /// Unix timestamp (seconds) when the run ended.
+    pub finished_at: u64,
+    pub status: RunStatus,
+    /// The LLM response, if it was captured.
+    #[serde(default)]
+    pub response: Option<String>,
+    /// The prompt as sent. Missing in runs recorded before this was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// The goal's `prompt.yaml` or `prompt.md` when the run started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_definition: Option<GoalDefinition>,
+    /// The files included with `--context`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_files: Vec<ContextFileRecord>,
+    /// The context files the response cited, with `cite_context` on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+    /// The claw version that made the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claw_version: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Masks the values of `secret_args`, the arguments of parameters marked
+    /// `ui.password`, wherever they appear in the entry, and anything else
+    /// that looks like a secret in its arguments, prompt and response.
+    pub fn mask_secrets(&mut self, secret_args: &[String]) {
+        let secrets: Vec<String> = secret_args
+            .iter()
+            .filter_map(|name| self.args.get(name))
+            .filter(|value| !value.is_empty())
+            .cloned()
+            .collect();
+        let mask = |text: &str| {
+            let text = secrets
+                .iter()
+                .fold(text.to_string(), |text, secret| text.replace(secret, REDACTED));
+            redact::redact(&text)
+        };
+        for (name, value) in &mut self.args {
+            *value = if secret_args.contains(name) {
+                REDACTED.to_string()
+            } else {
+                mask(value)
+            };
+        }
+        self.prompt = self.prompt.as_deref().map(mask);
+        self.response = self.response.as_deref().map(mask);
+    }
+}
+
+/// The file that defined a goal, as recorded with a run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoalDefinition {
+    /// `prompt.yaml` or `prompt.md`.
+    pub file: String,
+    pub content: String,
+}
+
+/// A file included with `--context`, as recorded with a run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextFileRecord {
+    pub path: PathBuf,
+    /// SHA-256 of the file's text as it appeared in the prompt, in hex.
+    pub sha256: String,
+    pub bytes: usize,
+}
+
+/// Lists the context files in a rendered prompt, with a hash of the text of
+/// each that went into it.
+pub fn context_manifest(prompt: &str, provenance: &ProvenanceMap) -> Vec<ContextFileRecord> {
+    let mut texts: IndexMap<&Path, String> = IndexMap::new();
+    for entry in &provenance.entries {
+        if let Source::ContextFile { path } = &entry.source
+            && let Some(text) = prompt.get(entry.start..entry.end)
+        {
+            texts.entry(path).or_default().push_str(text);
+        }
+    }
+    texts
+        .into_iter()
+        .map(|(path, text)| ContextFileRecord {
+            path: path.to_path_buf(),
+            sha256: digest::sha256_hex(text.as_bytes()),
+            bytes: text.len(),
+        })
+        .collect()
+}
+
+/// A `[n]` citation in a response, mapped back to the context file it names.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Citation {
+    /// The number cited, as the context files were numbered in the prompt.
+    pub source: usize,
+    /// The cited file; `None` if no context file had the number.
+    pub path: Option<PathBuf>,
+}
+
+/// Finds the `[n]` and `[n, m]` citations in a response and maps them to
+/// the context files, numbered from 1 in the order they were sent. Each
+/// number is listed once, in order.
+pub fn resolve_citations(response: &str, context_files: &[ContextFileRecord]) -> Vec<Citation> {
+    let pattern = Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").expect("valid citation pattern");
+    let numbers: BTreeSet<usize> = pattern
+        .captures_iter(response)
+        .flat_map(|captures| {
+            captures[1]
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect::<Vec<usize>>()
+        })
+        .collect();
+    numbers
+        .into_iter()
+        .map(|source| Citation {
+            source,
+            path: source
+                .checked_sub(1)
+                .and_then(|index| context_files.get(index))
+                .map(|file| file.path.clone()),
+        })
+        .collect()
+}
+
+/// Returns the current time as seconds since the Unix epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns the history directory inside the global config directory, if any.
+pub fn history_dir() -> Result<Option<PathBuf>> {
+    Ok(ConfigPaths::new()?.global.map(|dir| dir.join("history")))
+}
+
+/// Writes an entry to the history directory and returns the file path,
+/// then removes the oldest runs beyond the newest `limit`.
+pub fn record(entry: &HistoryEntry, limit: usize) -> Result<Option<PathBuf>> {
+    let Some(dir) = history_dir()? else {
+        return Ok(None);
+    };
+    let path = write_entry(&dir, entry)?;
+    prune(&dir, limit)?;
+    Ok(Some(path))
+}
+
+/// Loads every entry in the history directory. Files that can't be parsed
+/// are skipped.
+pub fn load_all() -> Result<Vec<HistoryEntry>> {
+    match history_dir()? {
+        Some(dir) => load_entries(&dir),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Loads every entry in the history directory with its run ID, oldest first.
+pub fn load_runs() -> Result<Vec<(String, HistoryEntry)>> {
+    match history_dir()? {
+        Some(dir) => load_runs_in(&dir),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn load_entries(dir: &Path) -> Result<Vec<HistoryEntry>> {
+    Ok(load_runs_in(dir)?
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect())
+}
+
+fn load_runs_in(dir: &Path) -> Result<Vec<(String, HistoryEntry)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for file in fs::read_dir(dir)
+        .with_context(|| format!("Failed to read history directory {}", dir.display()))?
+    {
+        let path = file?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Ok(json) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&json) {
+            entries.push((run_id(&path), entry));
+        }
+    }
+    entries.sort_by(|(a_id, a), (b_id, b)| (a.started_at, a_id).cmp(&(b.started_at, b_id)));
+    Ok(entries)
+}
+
+/// Returns the ID of the run recorded in `path`: its file name without `.json`.
+pub fn run_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Returns the responses of the last `n` completed runs of `goal`, oldest
+/// first. Runs without a captured response are skipped.
+pub fn recent_responses(entries: &[HistoryEntry], goal: &str, n: usize) -> Vec<String> {
+    let responses: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.goal == goal && entry.status == RunStatus::Completed)
+        .filter_map(|entry| entry.response.clone())
+        .filter(|response| !response.trim().is_empty())
+        .collect();
+    responses[responses.len().saturating_sub(n)..].to_vec()
+}
+
+/// Registers the `history(goal="...", n=3)` template function, which returns
+/// the last `n` saved responses of a goal (default 1), oldest first.
+pub fn register_tera_function(tera: &mut Tera) {
+    tera.register_function("history", |args: &HashMap<String, tera::Value>| {
+        let goal = args
+            .get("goal")
+            .and_then(tera::Value::as_str)
+            .ok_or_else(|| tera::Error::msg("history() requires a `goal` argument"))?;
+        let n = match args.get("n") {
+            Some(value) => value
+                .as_u64()
+                .ok_or_else(|| tera::Error::msg("history(): `n` must be a positive integer"))?
+                as usize,
+            None => 1,
+        };
+        let entries = load_all().map_err(|e| tera::Error::msg(format!("{:#}", e)))?;
+        Ok(tera::to_value(recent_responses(&entries, goal, n))?)
+    });
+}
+
+/// Removes the oldest runs in `dir` beyond the newest `keep`, going by the
+/// start time in their names.
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let mut runs: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read history directory {}", dir.display()))?
+        .filter_map(|file| file.ok().map(|file| file.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let started_at = run_id(&path)
+                .split('-')
+                .next()
+                .and_then(|secs| secs.parse().ok())
+                .unwrap_or_default();
+            (started_at, path)
+        })
+        .collect();
+    if runs.len() <= keep {
+        return Ok(());
+    }
+    runs.sort();
+    for (_, path) in &runs[..runs.len() - keep] {
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to remove old history entry {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Writes an entry as `<started_at>-<goal>.json` inside `dir`.
+fn write_entry(dir: &Path, entry: &HistoryEntry) -> Result<PathBuf> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create history directory {}", dir.display()))?;
+
+    // Disambiguate runs of the same goal that start within the same second
+    let mut path = dir.join(format!("{}-{}.json", entry.started_at, entry.goal));
+    let mut suffix = 1;
+    while path.exists() {
+        path = dir.join(format!("{}-{}-{}.json", entry.started_at, entry.goal, suffix));
+        suffix += 1;
+    }
+
+    let json = serde_json::to_string_pretty(entry).context("Failed to serialize history entry")?;
+    fs::write(&path, json)
+        .with_context(|| format!("Failed to write history entry {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_entry_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = HistoryEntry {
+            goal: "review".to_string(),
+            args: HashMap::from([("scope".to_string(), "auth".to_string())]),
+            arg_sources: Some(BTreeMap::from([(
+                "scope".to_string(),
+                ArgSource::Supplied,
+            )])),
+            started_at: 100,
+            finished_at: 105,
+            status: RunStatus::Interrupted,
+            response: Some("partial".to_string()),
+            prompt: None,
+            goal_definition: None,
+            context_files: Vec::new(),
+            citations: Vec::new(),
+            claw_version: None,
+        };
+
+        let first = write_entry(temp_dir.path(), &entry).unwrap();
+        let second = write_entry(temp_dir.path(), &entry).unwrap();
+        assert_ne!(first, second);
+
+        let loaded: HistoryEntry =
+            serde_json::from_str(&fs::read_to_string(&first).unwrap()).unwrap();
+        assert_eq!(loaded.status, RunStatus::Interrupted);
+        assert_eq!(loaded.response.as_deref(), Some("partial"));
+        assert!(
+            fs::read_to_string(&first)
+                .unwrap()
+                .contains("\"status\": \"interrupted\"")
+        );
+    }
+
+    #[test]
+    fn test_mask_secrets_hides_password_arguments() {
+        let mut entry = HistoryEntry {
+            goal: "deploy".to_string(),
+            args: HashMap::from([
+                ("password".to_string(), "hunter2".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ]),
+            arg_sources: None,
+            started_at: 100,
+            finished_at: 105,
+            status: RunStatus::Completed,
+            response: Some("Logged in with hunter2".to_string()),
+            prompt: Some("Deploy prod with hunter2 and API_TOKEN=abc".to_string()),
+            goal_definition: None,
+            context_files: Vec::new(),
+            citations: Vec::new(),
+            claw_version: None,
+        };
+        entry.mask_secrets(&["password".to_string()]);
+        assert_eq!(entry.args["password"], REDACTED);
+        assert_eq!(entry.args["env"], "prod");
+        assert_eq!(
+            entry.prompt.as_deref(),
+            Some("Deploy prod with [REDACTED] and API_TOKEN=[REDACTED]")
+        );
+        assert_eq!(entry.response.as_deref(), Some("Logged in with [REDACTED]"));
+    }
+
+    #[test]
+    fn test_prune_keeps_the_newest_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["100-a", "300-b", "200-a", "90-c"] {
+            fs::write(temp_dir.path().join(format!("{}.json", name)), "{}").unwrap();
+        }
+        prune(temp_dir.path(), 2).unwrap();
+        let mut left: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|file| run_id(&file.unwrap().path()))
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["200-a", "300-b"]);
+    }
+
+    #[test]
+    fn test_resolve_citations() {
+        let record = |path: &str| ContextFileRecord {
+            path: PathBuf::from(path),
+            sha256: String::new(),
+            bytes: 0,
+        };
+        let files = vec![record("src/a.rs"), record("src/b.rs")];
+        let citations = resolve_citations("Uses a lock [2]. See [1, 2] and [7]; not [x].", &files);
+        assert_eq!(
+            citations,
+            vec![
+                Citation {
+                    source: 1,
+                    path: Some(PathBuf::from("src/a.rs")),
+                },
+                Citation {
+                    source: 2,
+                    path: Some(PathBuf::from("src/b.rs")),
+                },
+                Citation {
+                    source: 7,
+                    path: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_manifest_hashes_sent_text() {
+        let prompt = "Files:\nfn a() {}\nDone";
+        let mut provenance = ProvenanceMap::default();
+        provenance.push(0, 7, Source::ContextHeader);
+        provenance.push(
+            7,
+            17,
+            Source::ContextFile {
+                path: PathBuf::from("src/a.rs"),
+            },
+        );
+        provenance.push(17, 21, Source::Separator);
+
+        let files = context_manifest(prompt, &provenance);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, PathBuf::from("src/a.rs"));
+        assert_eq!(files[0].bytes, 10);
+        assert_eq!(
+            files[0].sha256,
+            digest::sha256_hex(b"fn a() {}\n")
+        );
+    }
+
+    #[test]
+    fn test_load_entries_accepts_old_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("1-review.json"),
+            r#"{"goal":"review","args":{},"started_at":1,"finished_at":2,"status":"completed"}"#,
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("2-broken.json"), "{").unwrap();
+
+        let entries = load_entries(temp_dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].arg_sources.is_none());
+    }
+
+    #[test]
+    fn test_recent_responses_keeps_last_completed_runs() {
+        let entry = |goal: &str, started_at, status, response: Option<&str>| HistoryEntry {
+            goal: goal.to_string(),
+            args: HashMap::new(),
+            arg_sources: None,
+            started_at,
+            finished_at: started_at,
+            status,
+            response: response.map(str::to_string),
+            prompt: None,
+            goal_definition: None,
+            context_files: Vec::new(),
+            citations: Vec::new(),
+            claw_version: None,
+        };
+        let entries = vec![
+            entry("standup", 1, RunStatus::Completed, Some("monday")),
+            entry("standup", 2, RunStatus::Completed, Some("tuesday")),
+            entry("review", 3, RunStatus::Completed, Some("lgtm")),
+            entry("standup", 4, RunStatus::Interrupted, Some("half")),
+            entry("standup", 5, RunStatus::Completed, None),
+            entry("standup", 6, RunStatus::Completed, Some("thursday")),
+        ];
+
+        assert_eq!(
+            recent_responses(&entries, "standup", 2),
+            vec!["tuesday", "thursday"]
+        );
+        assert_eq!(recent_responses(&entries, "standup", 10).len(), 3);
+        assert!(recent_responses(&entries, "deploy", 3).is_empty());
+    }
+}
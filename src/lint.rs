@@ -0,0 +1,328 @@
+//! Style checks for goal prompts.
+//!
+//! Each rule inspects a goal's `PromptConfig` and reports findings with a
+//! severity that can be tuned (or turned off) through `prompt_lint` in
+//! `claw.yaml`.
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::PromptConfig;
+
+/// Default maximum prompt line length before `long-line` fires.
+const DEFAULT_MAX_LINE_LENGTH: usize = 120;
+
+/// The fields of `Claw`, the run details every prompt can use.
+const CLAW_FIELDS: &[&str] = &["version", "goal", "invoked_at", "run_id"];
+
+/// Words that ask the model to keep things short.
+const BREVITY_WORDS: &[&str] = &["brief", "concise", "short", "succinct", "terse"];
+
+/// Words that ask the model to be thorough.
+const EXHAUSTIVE_WORDS: &[&str] = &["exhaustive", "comprehensive", "thorough", "in-depth"];
+
+/// Phrases that indicate the prompt tells the model how to shape its answer.
+const OUTPUT_FORMAT_HINTS: &[&str] = &[
+    "format",
+    "output",
+    "respond with",
+    "respond in",
+    "structure your response",
+    "provide a",
+    "return a",
+];
+
+/// How seriously a lint finding should be taken.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The rule is disabled.
+    Off,
+    Info,
+    Warning,
+    /// Error findings make `claw prompt-lint` exit with a failure.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Off => write!(f, "off"),
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The lint rules claw knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    LongLine,
+    UndefinedPlaceholder,
+    ConflictingInstructions,
+    MissingOutputFormat,
+}
+
+impl Rule {
+    /// The identifier used in reports and in `claw.yaml`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::LongLine => "long-line",
+            Rule::UndefinedPlaceholder => "undefined-placeholder",
+            Rule::ConflictingInstructions => "conflicting-instructions",
+            Rule::MissingOutputFormat => "missing-output-format",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self {
+            Rule::LongLine => Severity::Warning,
+            Rule::UndefinedPlaceholder => Severity::Error,
+            Rule::ConflictingInstructions => Severity::Warning,
+            Rule::MissingOutputFormat => Severity::Info,
+        }
+    }
+}
+
+/// Prompt lint settings from `claw.yaml`.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct LintConfig {
+    /// Maximum line length in the prompt template (default: 120).
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+
+    /// Per-rule severity overrides keyed by rule id, e.g. `long-line: off`.
+    #[serde(default)]
+    pub severities: HashMap<String, Severity>,
+}
+
+impl LintConfig {
+    fn severity(&self, rule: Rule) -> Severity {
+        self.severities
+            .get(rule.id())
+            .copied()
+            .unwrap_or_else(|| rule.default_severity())
+    }
+}
+
+/// A single problem found in a goal's prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintFinding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// 1-based line number in the prompt template, when the finding is local.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.severity, self.rule.id())?;
+        if let Some(line) = self.line {
+            write!(f, " line {}", line)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Runs every enabled rule against a goal's prompt configuration.
+pub fn lint_prompt(goal: &PromptConfig, config: &LintConfig) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let mut report = |rule: Rule, line: Option<usize>, message: String| {
+        let severity = config.severity(rule);
+        if severity != Severity::Off {
+            findings.push(LintFinding {
+                rule,
+                severity,
+                line,
+                message,
+            });
+        }
+    };
+
+    // long-line
+    let max_len = config.max_line_length.unwrap_or(DEFAULT_MAX_LINE_LENGTH);
+    for (i, line) in goal.prompt.lines().enumerate() {
+        let len = line.chars().count();
+        if len > max_len {
+            report(
+                Rule::LongLine,
+                Some(i + 1),
+                format!("line is {} characters (max {})", len, max_len),
+            );
+        }
+    }
+
+    // undefined-placeholder
+    let placeholder = Regex::new(r"\b(Args|Context|Claw)\.([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    for (i, line) in goal.prompt.lines().enumerate() {
+        for caps in placeholder.captures_iter(line) {
+            let namespace = &caps[1];
+            let name = &caps[2];
+            let defined = match namespace {
+                // Goals without parameter definitions accept arbitrary args
+                "Args" => {
+                    goal.parameters.is_empty() || goal.parameters.iter().any(|p| p.name == name)
+                }
+                "Claw" => CLAW_FIELDS.contains(&name),
+                _ => {
+                    goal.context_scripts.contains_key(name)
+                        || goal.context_builtin.iter().any(|b| b.name() == name)
+                }
+            };
+            if !defined {
+                let source = match namespace {
+                    "Args" => "parameters",
+                    "Claw" => "claw's run details",
+                    _ => "context_scripts or context_builtin",
+                };
+                report(
+                    Rule::UndefinedPlaceholder,
+                    Some(i + 1),
+                    format!("{}.{} is not defined in {}", namespace, name, source),
+                );
+            }
+        }
+    }
+
+    // conflicting-instructions
+    let lowered = goal.prompt.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let brevity = BREVITY_WORDS.iter().find(|w| words.contains(w));
+    let exhaustive = EXHAUSTIVE_WORDS.iter().find(|w| words.contains(w));
+    if let (Some(short), Some(long)) = (brevity, exhaustive) {
+        report(
+            Rule::ConflictingInstructions,
+            None,
+            format!(
+                "prompt asks for both \"{}\" and \"{}\" responses; pick one",
+                short, long
+            ),
+        );
+    }
+
+    // missing-output-format
+    if !OUTPUT_FORMAT_HINTS
+        .iter()
+        .any(|hint| lowered.contains(hint))
+    {
+        report(
+            Rule::MissingOutputFormat,
+            None,
+            "prompt does not describe the expected output format".to_string(),
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GoalParameter;
+
+    fn goal_with_prompt(prompt: &str) -> PromptConfig {
+        PromptConfig {
+            name: "Test".to_string(),
+            prompt: prompt.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn rules(findings: &[LintFinding]) -> Vec<Rule> {
+        findings.iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn test_clean_prompt_has_no_findings() {
+        let goal = goal_with_prompt("Summarize the diff. Format the answer as a bullet list.");
+        let findings = lint_prompt(&goal, &LintConfig::default());
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn test_long_line() {
+        let goal = goal_with_prompt(&format!("Output a list.\n{}", "x".repeat(30)));
+        let config = LintConfig {
+            max_line_length: Some(20),
+            ..Default::default()
+        };
+        let findings = lint_prompt(&goal, &config);
+        assert_eq!(rules(&findings), vec![Rule::LongLine]);
+        assert_eq!(findings[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_undefined_placeholders() {
+        let mut goal =
+            goal_with_prompt("Output {{ Args.scope }} {{ Args.missing }} {{ Context.diff }}");
+        goal.parameters = vec![GoalParameter {
+            name: "scope".to_string(),
+            description: "Scope".to_string(),
+            required: true,
+            param_type: None,
+            default: None,
+            ui: None,
+            choices: None,
+            choices_script: None,
+            env: None,
+        }];
+        let findings = lint_prompt(&goal, &LintConfig::default());
+
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].message.contains("Args.missing"));
+        assert!(findings[1].message.contains("Context.diff"));
+        assert_eq!(findings[0].severity, Severity::Error);
+
+        let goal = goal_with_prompt("{{ Claw.run_id }} {{ Claw.user }}");
+        let undefined: Vec<_> = lint_prompt(&goal, &LintConfig::default())
+            .into_iter()
+            .filter(|finding| finding.rule == Rule::UndefinedPlaceholder)
+            .collect();
+        assert_eq!(undefined.len(), 1);
+        assert!(undefined[0].message.contains("Claw.user"));
+    }
+
+    #[test]
+    fn test_builtin_context_is_defined() {
+        let mut goal = goal_with_prompt("Status:\n{{ Context.git_status }}");
+        goal.context_builtin = vec![crate::config::ContextBuiltin::GitStatus];
+        let findings = lint_prompt(&goal, &LintConfig::default());
+        assert!(!rules(&findings).contains(&Rule::UndefinedPlaceholder));
+    }
+
+    #[test]
+    fn test_args_allowed_without_parameter_definitions() {
+        let goal = goal_with_prompt("Output {{ Args.anything }}");
+        let findings = lint_prompt(&goal, &LintConfig::default());
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_instructions() {
+        let goal = goal_with_prompt("Be brief. Be exhaustive. Output markdown.");
+        let findings = lint_prompt(&goal, &LintConfig::default());
+        assert_eq!(rules(&findings), vec![Rule::ConflictingInstructions]);
+    }
+
+    #[test]
+    fn test_missing_output_format_and_severity_override() {
+        let goal = goal_with_prompt("Tell me about Rust.");
+        let findings = lint_prompt(&goal, &LintConfig::default());
+        assert_eq!(rules(&findings), vec![Rule::MissingOutputFormat]);
+        assert_eq!(findings[0].severity, Severity::Info);
+
+        let config = LintConfig {
+            severities: HashMap::from([("missing-output-format".to_string(), Severity::Off)]),
+            ..Default::default()
+        };
+        assert!(lint_prompt(&goal, &config).is_empty());
+    }
+}
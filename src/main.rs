@@ -1,54 +1,374 @@
+mod alias;
+mod audit;
+mod builtins;
+mod bundled_goals;
 mod cli;
+mod collections;
 mod commands;
 mod config;
 mod context;
+mod context_picker;
+mod dates;
+mod digest;
+mod failing_tests;
+mod git_context;
+mod goal_args;
+// The interactive browser is wired up but currently disabled in `main`.
+#[allow(dead_code)]
 mod goal_browser;
+mod goal_lock;
 mod help;
+mod history;
+mod lint;
+mod markdown;
+mod middleware;
+mod batch;
+mod matrix;
+mod notebook;
+mod param_prompt;
+mod patch;
+mod policy;
+mod prefetch;
+mod prompt_core;
+mod prompt_review;
+mod provenance;
+mod providers;
+mod repo_map;
+mod redact;
+mod response;
+mod run_view;
+mod run_status;
 mod runner;
+mod script_cache;
+mod shell_history;
+mod todos;
+mod theme;
+mod tokens;
+mod trust;
 mod validation;
+mod version;
 
 use anyhow::{Context as AnyhowContext, Result};
 use clap::Parser;
-use cli::{Cli, Subcommands};
-use std::collections::HashMap;
-use tera::{Context, Tera};
+use cli::{Cli, CollectionAction, HooksAction, Subcommands};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 fn main() -> Result<()> {
-    config::ensure_global_config_exists()?;
+    // Expand a command alias from claw.yaml; a config that fails to load is
+    // reported once the profile is known, below
+    let mut args: Vec<String> = std::env::args().collect();
+    if let Ok(config) = config::find_and_load_claw_config(None)
+        && let Some(aliases) = &config.aliases
+    {
+        args = alias::expand(args, aliases)?;
+    }
+    let mut cli = Cli::parse_from(args);
+    if cli.porcelain {
+        param_prompt::disable_interaction();
+    }
+    if !cli.no_setup && !config::first_run_setup_disabled() {
+        config::ensure_global_config_exists()?;
+    }
+
+    // Load the main claw configuration (cascading), with the chosen profile
+    let mut claw_config = config::find_and_load_claw_config(cli.profile.as_deref())?;
+
+    let common = match &cli.command {
+        Some(Subcommands::DryRun { common, .. } | Subcommands::Session { common, .. }) => common,
+        _ => &cli.run_args.common,
+    };
+    if common.quiet_context {
+        claw_config.context_notes = Some(false);
+    }
+    if common.no_script_cache {
+        claw_config.script_cache_disabled = true;
+    }
+    if common.no_scripts {
+        claw_config.scripts_disabled = true;
+    }
+    if common.list_beyond_depth {
+        claw_config.list_beyond_depth = Some(true);
+    }
+    if common.cite_context {
+        claw_config.cite_context = Some(true);
+    }
 
-    // Load the main claw configuration (cascading)
-    let claw_config = config::find_and_load_claw_config()?;
+    // Add the files changed in git, after the user has picked among them
+    let common = match &mut cli.command {
+        Some(Subcommands::DryRun { common, .. } | Subcommands::Session { common, .. }) => common,
+        _ => &mut cli.run_args.common,
+    };
+    if let Some(scope) = &common.context_git {
+        let scope = git_context::GitScope::parse(scope)?;
+        let files = git_context::changed_files(&std::env::current_dir()?, &scope)?;
+        if files.is_empty() {
+            eprintln!("No changed files found for --context-git.");
+        }
+        let files = git_context::pick_files(files)?;
+        common.context.extend(files.into_iter().map(|file| file.path));
+    }
+    let diff_scope = common
+        .context_diff
+        .as_deref()
+        .map(git_context::GitScope::parse)
+        .transpose()?;
 
-    let cli = Cli::parse();
+    if cli.run_args.fail_on_empty_response {
+        claw_config.fail_on_empty_response = Some(true);
+    }
+    if cli.run_args.live {
+        claw_config.live_view = Some(true);
+    }
+    if let Some(destination) = &cli.run_args.json_status {
+        claw_config.json_status = Some(destination.clone());
+    }
+    if cli.run_args.page {
+        claw_config.page_response = Some(true);
+    }
+    if let Some(profiles) = &cli.run_args.fan_out {
+        claw_config.fan_out = Some(profiles.clone());
+    }
+    if let Some(llm) = &cli.llm {
+        claw_config.llm_command = Some(llm.clone());
+        claw_config.receiver_type = Some(config::ReceiverType::Generic);
+        claw_config.receiver_from_cli = true;
+    }
+    if let Some(receiver) = cli.receiver {
+        claw_config.receiver_type = Some(receiver.into());
+        claw_config.receiver_from_cli = true;
+    }
+    if let Some(template) = &cli.prompt_template {
+        claw_config.prompt_arg_template = template.clone();
+        claw_config.receiver_from_cli = true;
+    }
 
     match cli.command {
         Some(Subcommands::Add {
             name,
             local,
             global,
+            resume,
         }) => {
-            commands::add::handle_add_command(&name, local, global, &claw_config)?;
+            commands::add::handle_add_command(&name, local, global, resume, &claw_config)?;
+        }
+        Some(Subcommands::List {
+            local,
+            global,
+            search,
+            collection,
+        }) => {
+            commands::list::handle_list_command(
+                local,
+                global,
+                search.as_deref(),
+                collection.as_deref(),
+            )?;
+        }
+        Some(Subcommands::PromptLint { goal_name }) => {
+            commands::prompt_lint::handle_prompt_lint_command(goal_name.as_deref(), &claw_config)?;
+        }
+        Some(Subcommands::Trust { revoke }) => {
+            commands::trust::handle_trust_command(revoke)?;
+        }
+        Some(Subcommands::Schema { kind }) => {
+            commands::schema::handle_schema_command(kind)?;
+        }
+        Some(Subcommands::Hooks { action }) => match action {
+            HooksAction::Install { hooks, force } => {
+                commands::hooks::handle_install_command(&hooks, force)?;
+            }
+            HooksAction::Uninstall { hooks } => {
+                commands::hooks::handle_uninstall_command(&hooks)?;
+            }
+            HooksAction::Status => commands::hooks::handle_status_command()?,
+        },
+        Some(Subcommands::Collection { action }) => match action {
+            CollectionAction::Install {
+                path,
+                local,
+                global,
+            } => {
+                commands::collection::handle_install_command(&path, local, global)?;
+            }
+            CollectionAction::Uninstall { name } => {
+                commands::collection::handle_uninstall_command(&name)?;
+            }
+            CollectionAction::Enable { name } => {
+                commands::collection::handle_enable_command(&name, true)?;
+            }
+            CollectionAction::Disable { name } => {
+                commands::collection::handle_enable_command(&name, false)?;
+            }
+            CollectionAction::List => commands::collection::handle_list_command()?,
+        },
+        Some(Subcommands::Index) => {
+            commands::index::handle_index_command(&claw_config)?;
+        }
+        Some(Subcommands::Bundle { run_id, output }) => {
+            commands::bundle::handle_bundle_command(&run_id, output.as_deref())?;
         }
-        Some(Subcommands::List { local, global }) => {
-            commands::list::handle_list_command(local, global)?;
+        Some(Subcommands::Batch { plan, jobs }) => {
+            run_batch(&claw_config, &plan, jobs)?;
+        }
+        Some(Subcommands::Test {
+            goal_name,
+            update_snapshots,
+        }) => {
+            commands::test::handle_test_command(
+                goal_name.as_deref(),
+                update_snapshots,
+                &claw_config,
+            )?;
+        }
+        Some(Subcommands::Daemon { once }) => {
+            prefetch::run_daemon(once, &claw_config)?;
+        }
+        Some(Subcommands::Alias) => {
+            commands::alias::handle_alias_command(&claw_config);
+        }
+        Some(Subcommands::UpgradeGoals { yes }) => {
+            commands::upgrade_goals::handle_upgrade_goals_command(yes)?;
+        }
+        Some(Subcommands::DiffGoal { goal_name }) => {
+            commands::diff_goal::handle_diff_goal_command(&goal_name)?;
+        }
+        Some(Subcommands::Docs { goal_names, output }) => {
+            commands::docs::handle_docs_command(&output, &goal_names)?;
+        }
+        Some(Subcommands::Fix {
+            install_hook: Some(shell),
+            ..
+        }) => {
+            commands::fix::handle_install_hook_command(shell)?;
+        }
+        Some(Subcommands::Fix {
+            uninstall_hook: Some(shell),
+            ..
+        }) => {
+            commands::fix::handle_uninstall_hook_command(shell)?;
+        }
+        Some(Subcommands::Fix {
+            goal,
+            yes,
+            no_rerun,
+            ..
+        }) => {
+            if let Some(template_args) = commands::fix::triage_args(!no_rerun, yes)? {
+                let goal_name = goal
+                    .or_else(|| claw_config.triage_goal.clone())
+                    .unwrap_or_else(|| commands::fix::DEFAULT_TRIAGE_GOAL.to_string());
+                let receiver_options = runner::ReceiverOptions {
+                    page: claw_config.page_response.unwrap_or(false) && !cli.porcelain,
+                    ..Default::default()
+                };
+                let context = ContextRequest {
+                    template_args: &template_args,
+                    ..Default::default()
+                };
+                run_goal(&goal_name, &claw_config, &context, &receiver_options)?;
+            }
+        }
+        Some(Subcommands::Stats { goal }) => {
+            commands::stats::handle_stats_command(goal.as_deref())?;
+        }
+        Some(Subcommands::Completions { shell }) => {
+            commands::completions::handle_completions_command(shell);
+        }
+        Some(Subcommands::Complete { words }) => {
+            commands::completions::handle_complete_command(&words)?;
+        }
+        Some(Subcommands::Ping) => {
+            commands::ping::handle_ping_command(&claw_config)?;
         }
         Some(Subcommands::Pass) => {
+            config::find_and_load_policy()?.check_receiver(&claw_config)?;
+            trust::ensure_local_config_trusted()?;
             runner::run_pass_through(&claw_config)?;
         }
         Some(Subcommands::DryRun {
             goal_name,
             output,
+            pretty,
+            provenance,
+            check,
             common,
         }) => {
+            if check {
+                let goal_names = match goal_name {
+                    Some(goal_name) => vec![goal_name],
+                    None => {
+                        let mut names: Vec<String> =
+                            config::find_all_goals()?.into_iter().map(|g| g.name).collect();
+                        names.sort();
+                        names.dedup();
+                        names
+                    }
+                };
+                let args = goal_args::parse(&common.template_args)?;
+                return commands::dry_run::handle_check_command(&goal_names, &args, &claw_config);
+            }
+            let goal_name = goal_name.context("A goal name is required")?;
+
             let rendered_prompt = render_goal_prompt(
                 &goal_name,
                 &claw_config,
-                &common.template_args,
-                &common.context,
-                common.recurse_depth,
+                &ContextRequest::new(&common, diff_scope.as_ref()),
             )?;
 
-            commands::dry_run::handle_dry_run_command(output.as_ref(), &rendered_prompt)?;
+            commands::dry_run::handle_dry_run_command(
+                output.as_ref(),
+                &rendered_prompt.text,
+                pretty && !cli.porcelain,
+            )?;
+
+            if provenance && let Some(output) = &output {
+                let sidecar = provenance::sidecar_path(output);
+                rendered_prompt.provenance.write_to(&sidecar)?;
+                eprintln!("Provenance map written to {}", sidecar.display());
+            }
+
+            // The prompt went to a file, so its size doesn't get in the way
+            if output.is_some() {
+                let tokenizer = tokens::Tokenizer::for_config(&claw_config);
+                eprintln!(
+                    "Prompt size: {} ({})",
+                    tokens::format_estimate(tokenizer.estimate(&rendered_prompt.text)),
+                    tokenizer
+                );
+            }
+        }
+        Some(Subcommands::Session {
+            goal_name,
+            save,
+            common,
+        }) => {
+            config::find_and_load_policy()?.check_receiver(&claw_config)?;
+            trust::ensure_local_config_trusted()?;
+            let lock = lock_exclusive_goal(&goal_name)?;
+            let rendered = render_goal_prompt(
+                &goal_name,
+                &claw_config,
+                &ContextRequest::new(&common, diff_scope.as_ref()),
+            )?;
+            runner::install_interrupt_handler()?;
+            let result = commands::session::handle_session_command(
+                rendered.receiver_config.as_ref().unwrap_or(&claw_config),
+                &rendered.text,
+                save.as_deref(),
+                |config, prompt, capture| {
+                    send_session_turn(&goal_name, config, &rendered, prompt, capture)
+                },
+            );
+            if runner::was_interrupted() {
+                eprintln!("\nInterrupted.");
+                drop(lock);
+                std::process::exit(130);
+            }
+            result?;
         }
         None => {
             if let Some(goal_name) = cli.run_args.goal_name {
@@ -56,21 +376,58 @@ fn main() -> Result<()> {
                 if cli.run_args.explain {
                     // Show goal-specific help
                     let goal = config::find_and_load_goal(&goal_name)?;
-                    let help_text = help::format_goal_help(&goal, &goal_name);
+                    let help_text = help::format_goal_help(
+                        &goal,
+                        &goal_name,
+                        std::io::stdout().is_terminal(),
+                    );
                     println!("{}", help_text);
                     return Ok(());
                 }
 
-                run_goal(
-                    &goal_name,
-                    &claw_config,
-                    &cli.run_args.common.template_args,
-                    &cli.run_args.common.context,
-                    cli.run_args.common.recurse_depth,
-                )?;
+                if let Some(matrix_file) = &cli.run_args.matrix {
+                    let output_dir = cli
+                        .run_args
+                        .matrix_dir
+                        .clone()
+                        .unwrap_or_else(|| std::path::PathBuf::from("."));
+                    return run_matrix(
+                        &goal_name,
+                        &claw_config,
+                        matrix_file,
+                        &output_dir,
+                        cli.run_args.jobs,
+                        &ContextRequest::new(&cli.run_args.common, diff_scope.as_ref()),
+                    );
+                }
+
+                let (template_args, context) = match cli.run_args.example {
+                    Some(number) => example_invocation(
+                        &goal_name,
+                        number,
+                        &cli.run_args.common.template_args,
+                        &cli.run_args.common.context,
+                    )?,
+                    None => (
+                        cli.run_args.common.template_args.clone(),
+                        cli.run_args.common.context.clone(),
+                    ),
+                };
+
+                let receiver_options = runner::ReceiverOptions {
+                    tee: cli.run_args.tee.clone(),
+                    page: claw_config.page_response.unwrap_or(false) && !cli.porcelain,
+                    ..Default::default()
+                };
+                let context = ContextRequest {
+                    template_args: &template_args,
+                    context_paths: &context,
+                    ..ContextRequest::new(&cli.run_args.common, diff_scope.as_ref())
+                };
+                run_goal(&goal_name, &claw_config, &context, &receiver_options)?;
             } else {
-                println!("No goal given");
-                commands::list::handle_list_command(false, false)?;
+                eprintln!("No goal given");
+                commands::list::handle_list_command(false, false, None, None)?;
                 // No goal was provided, so enter interactive mode.
                 //                let goals = config::find_all_goals()?;
                 //                if goals.is_empty() {
@@ -78,7 +435,7 @@ fn main() -> Result<()> {
                 //                }
                 //
                 //                // Use the new goal browser TUI
-                //                let selected_goal_name = goal_browser::run_goal_browser(goals)?;
+                //                let selected_goal_name = goal_browser::run_goal_browser(goals, claw_config.tui_theme.unwrap_or_default())?;
                 //
                 //                run_goal(&selected_goal_name, &claw_config, &Vec::new(), &Vec::new(), None)?;
             }
@@ -88,41 +445,35 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// Parses goal arguments into a HashMap.
-/// Supports formats: `--key=value`, `--key value`, and `--flag` (boolean).
-fn parse_goal_args(args: &[String]) -> Result<HashMap<String, String>> {
-    let mut map = HashMap::new();
-    let mut i = 0;
-
-    while i < args.len() {
-        let arg = &args[i];
-        if !arg.starts_with("--") {
-            anyhow::bail!(
-                "Invalid goal argument: '{}'. All goal arguments must be flags starting with '--'.",
-                arg
-            );
-        }
+/// Returns the template arguments and context paths of a goal's example
+/// `number` (1-based), followed by those given on the command line so they
+/// take precedence.
+fn example_invocation(
+    goal_name: &str,
+    number: usize,
+    template_args: &[String],
+    context_paths: &[std::path::PathBuf],
+) -> Result<(Vec<String>, Vec<std::path::PathBuf>)> {
+    let goal = config::find_and_load_goal(goal_name)?;
+    let examples = &goal.config.examples;
+    let example = number
+        .checked_sub(1)
+        .and_then(|i| examples.get(i))
+        .with_context(|| match examples.len() {
+            0 => format!("Goal '{}' has no examples", goal_name),
+            n => format!(
+                "Goal '{}' has no example {}; it has {} (see `claw {} --explain`)",
+                goal_name, number, n, goal_name
+            ),
+        })?;
 
-        let key_part = &arg[2..]; // Remove the "--"
-        if let Some((key, value)) = key_part.split_once('=') {
-            // Handles --key=value
-            map.insert(key.to_string(), value.to_string());
-            i += 1;
-        } else {
-            // Handles --key value or --flag (boolean)
-            i += 1;
-            if i >= args.len() || args[i].starts_with("--") {
-                // This is a boolean flag (no value provided)
-                map.insert(key_part.to_string(), "true".to_string());
-            } else {
-                // This has a value
-                let value = &args[i];
-                map.insert(key_part.to_string(), value.to_string());
-                i += 1;
-            }
-        }
-    }
-    Ok(map)
+    let mut args = example
+        .template_args()
+        .with_context(|| format!("Example {} of goal '{}' is invalid", number, goal_name))?;
+    args.extend(template_args.iter().cloned());
+    let mut context = example.context.clone();
+    context.extend(context_paths.iter().cloned());
+    Ok((args, context))
 }
 
 /// Renders a goal's prompt with all context, scripts, and file context applied.
@@ -133,75 +484,272 @@ fn parse_goal_args(args: &[String]) -> Result<HashMap<String, String>> {
 /// - Parsing and validating template arguments
 /// - Executing context scripts
 /// - Rendering the prompt template with Tera
+/// - Appending sections from configured context providers
 /// - Adding file context if specified
 ///
 /// # Arguments
 /// * `goal_name` - Name of the goal to render
 /// * `claw_config` - Configuration for context settings
-/// * `template_args` - Template arguments from command line
-/// * `context_paths` - File paths to include as context
-/// * `recurse_depth` - Directory recursion depth
+/// * `context` - Template arguments and the context asked for on the command line
 ///
 /// # Returns
-/// * `Ok(String)` - The fully rendered prompt
+/// * `Ok(RenderedPrompt)` - The fully rendered prompt and its provenance map
 /// * `Err` - If any step fails (goal not found, validation errors, script failures, etc.)
 fn render_goal_prompt(
     goal_name: &str,
     claw_config: &config::ClawConfig,
-    template_args: &[String],
-    context_paths: &[std::path::PathBuf],
-    recurse_depth: Option<usize>,
-) -> Result<String> {
+    context: &ContextRequest,
+) -> Result<RenderedPrompt> {
+    let ContextRequest {
+        template_args,
+        context_paths,
+        recurse_depth,
+        no_gitignore,
+        repo_map,
+        todo_paths,
+        diff_scope,
+        shell_history,
+    } = *context;
+    let claw = prompt_core::ClawMeta::new(goal_name, history::now_secs());
     let goal = config::find_and_load_goal(goal_name)?;
+    let policy = config::find_and_load_policy()?;
+    policy.check_goal(goal_name, &goal, claw_config)?;
+    let receiver_config = claw_config.for_goal(&goal.config)?;
+    if let Some(receiver_config) = &receiver_config {
+        policy.check_receiver(receiver_config)?;
+    }
+    trust::ensure_goal_trusted(&goal, claw_config)?;
 
     // Parse template args into HashMap
-    let parsed_args = parse_goal_args(template_args)?;
+    let mut parsed_args = goal_args::parse(template_args)?;
 
-    // Validate parameters against the goal's parameter definitions
+    // Validate parameters against the goal's parameter definitions, taking
+    // missing ones from their environment variables, then asking for any
+    // still missing required ones when running in a terminal
     let validator =
         validation::ParameterValidator::new(&goal.config.parameters, goal_name.to_string());
+    validator.apply_env(&mut parsed_args, |var| std::env::var(var).ok());
+    let missing = validator.get_missing_required(&parsed_args);
+    if !missing.is_empty() && param_prompt::is_interactive() {
+        param_prompt::prompt_for_parameters(&missing, &mut parsed_args)?;
+    }
     let template_args = validator.validate(&parsed_args)?;
+    let arg_sources = template_args
+        .keys()
+        .map(|name| {
+            let source = if parsed_args.contains_key(name) {
+                history::ArgSource::Supplied
+            } else {
+                history::ArgSource::Defaulted
+            };
+            (name.clone(), source)
+        })
+        .collect();
 
-    // Create a Tera context with Args for rendering context scripts
-    let mut context = Context::new();
-    context.insert("Args", &template_args);
+    // Render the context scripts through Tera to substitute Args variables;
+    // prefetched ones use the output `claw daemon` stored instead
+    let mut rendered_scripts =
+        prompt_core::render_context_scripts(&goal.config, &template_args)?;
+    let prefetched = if claw_config.scripts_disabled {
+        HashMap::new()
+    } else {
+        prefetch::take_prefetched(
+            &std::env::current_dir()?,
+            goal_name,
+            &goal,
+            &mut rendered_scripts,
+        )
+    };
 
-    // Render the context scripts through Tera to substitute Args variables
-    let mut tera = Tera::default();
-    let mut rendered_scripts = HashMap::new();
-    for (name, script_template) in &goal.config.context_scripts {
-        tera.add_raw_template(name, script_template)
-            .with_context(|| format!("Failed to add context script template '{}'", name))?;
-        let rendered_script = tera
-            .render(name, &context)
-            .map_err(|e| anyhow::anyhow!("Failed to render context script '{}': {}", name, e))?;
-        rendered_scripts.insert(name.clone(), rendered_script);
+    // Gather built-in sections, then execute the rendered context scripts;
+    // a script with the same name as a built-in replaces it
+    let mut script_outputs = builtins::collect(&goal.config.context_builtin, claw_config)?;
+    script_outputs.extend(prefetched);
+    let env = prompt_core::render_env(
+        &claw_config.env.clone().unwrap_or_default(),
+        &goal.config.env,
+        &template_args,
+    )?;
+    let working_dir = runner::existing_working_dir(prompt_core::render_working_dir(
+        &goal.config,
+        &template_args,
+        &std::env::current_dir()?,
+    )?)?;
+    let mut script_timings = Vec::new();
+    if claw_config.scripts_disabled {
+        // --no-scripts: mark where each script's output would go
+        script_outputs.extend(
+            rendered_scripts
+                .keys()
+                .map(|name| (name.clone(), prompt_core::script_placeholder(name))),
+        );
+    } else {
+        let script_options = goal
+            .config
+            .context_scripts
+            .iter()
+            .map(|(name, script)| {
+                let options = runner::ScriptOptions {
+                    cwd: working_dir.clone(),
+                    ..runner::ScriptOptions::for_script(claw_config, script, &env)
+                };
+                (name.clone(), options)
+            })
+            .collect();
+        let (script_results, durations) =
+            runner::execute_context_scripts(&rendered_scripts, &script_options);
+        script_timings = durations
+            .iter()
+            .map(|(name, duration)| run_status::ScriptTiming::new(name, *duration))
+            .collect();
+        if claw_config.record_history.unwrap_or(true)
+            && let Err(e) = script_cache::record(goal_name, &script_results)
+        {
+            eprintln!("⚠️  Warning: Failed to cache context script output: {:#}", e);
+        }
+        for (name, result) in script_results {
+            script_outputs.insert(name, result?);
+        }
     }
 
-    // Execute the rendered context scripts
-    let script_outputs = runner::execute_context_scripts(&rendered_scripts)?;
-    context.insert("Context", &script_outputs);
-
     // Now render the main prompt with both Args and Context
-    let mut tera = Tera::new(&format!("{}/**/*", goal.directory.display()))
-        .context("Failed to create Tera instance")?;
-    tera.add_raw_template("prompt", &goal.config.prompt)
-        .context("Failed to add raw template")?;
-    let mut rendered_prompt = tera
-        .render("prompt", &context)
-        .map_err(|e| anyhow::anyhow!("Failed to render prompt for goal '{}': {}", goal_name, e))?;
+    let mut tera = prompt_core::prompt_templates(&goal.config, &goal)?;
+    history::register_tera_function(&mut tera);
+    let mut rendered_prompt =
+        prompt_core::render_prompt(&tera, &claw, &template_args, &script_outputs)?;
+    let mut provenance =
+        provenance::attribute_template(&rendered_prompt, &goal.config.prompt, &script_outputs);
+
+    // Append sections contributed by external context providers
+    if !goal.config.providers.is_empty() {
+        let providers = providers::create_providers(
+            &goal.config.providers,
+            claw_config.context_providers.as_deref().unwrap_or_default(),
+            claw_config.script_timeout_secs.map(Duration::from_secs),
+        )?;
+        let request = providers::ProviderRequest {
+            goal: goal_name.to_string(),
+            args: template_args.clone(),
+            cwd: std::env::current_dir()?,
+        };
+        let sections = providers::collect_sections(&providers, &request)?;
+        if !sections.is_empty() {
+            append_part(
+                &mut rendered_prompt,
+                &mut provenance,
+                "\n\n",
+                provenance::Source::Separator,
+            );
+            for section in sections {
+                let formatted = providers::format_sections(std::slice::from_ref(&section));
+                append_part(
+                    &mut rendered_prompt,
+                    &mut provenance,
+                    &formatted,
+                    provenance::Source::Provider {
+                        title: section.title,
+                    },
+                );
+            }
+        }
+    }
+
+    // Add the repository map, from `claw index` if it was run here
+    if repo_map {
+        let cwd = std::env::current_dir()?;
+        let index = repo_map::load(&cwd).unwrap_or_else(|| {
+            eprintln!("Building a repository map; run `claw index` to cache it.");
+            repo_map::build(
+                &cwd,
+                claw_config.excluded_directories.as_deref().unwrap_or_default(),
+                claw_config.excluded_extensions.as_deref().unwrap_or_default(),
+            )
+        });
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            &repo_map::format(&index),
+            provenance::Source::RepoMap,
+        );
+    }
+
+    // Add the TODO/FIXME/HACK comments under the --context-todos paths
+    if !todo_paths.is_empty() {
+        let todos = todos::scan(
+            todo_paths,
+            !no_gitignore && goal.config.respect_gitignore.unwrap_or(true),
+            claw_config.excluded_directories.as_deref().unwrap_or_default(),
+            claw_config.excluded_extensions.as_deref().unwrap_or_default(),
+            claw_config.max_file_size_kb.unwrap_or(1024),
+        )?;
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            &todos::format(&todos),
+            provenance::Source::Todos,
+        );
+    }
+
+    // Add the changes in git as unified diffs
+    if let Some(scope) = diff_scope {
+        let section = git_context::diff_section(&std::env::current_dir()?, scope)?;
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            &section,
+            provenance::Source::GitDiff,
+        );
+    }
+
+    // Add the last commands of the shell history, secrets redacted
+    if let Some(count) = shell_history {
+        let section = shell_history::section(count)?;
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            &section,
+            provenance::Source::ShellHistory,
+        );
+    }
 
     // Process file context if --context parameter was provided
     if !context_paths.is_empty() {
         let context_config = context::ContextConfig {
             paths: context_paths.to_vec(),
             recurse_depth,
+            respect_gitignore: !no_gitignore && goal.config.respect_gitignore.unwrap_or(true),
             max_file_size_kb: claw_config.max_file_size_kb.unwrap_or(1024),
             max_files_per_directory: claw_config.max_files_per_directory.unwrap_or(50),
+            max_line_length: claw_config.max_line_length.unwrap_or(2000),
             error_handling_mode: claw_config
                 .error_handling_mode
                 .clone()
                 .unwrap_or(config::ErrorHandlingMode::Flexible),
+            error_policies: claw_config.context_errors.clone().unwrap_or_default(),
             excluded_directories: claw_config.excluded_directories.clone().unwrap_or_else(|| {
                 vec![
                     ".git".to_string(),
@@ -213,44 +761,963 @@ fn render_goal_prompt(
                 .excluded_extensions
                 .clone()
                 .unwrap_or_else(|| vec!["exe".to_string(), "bin".to_string(), "so".to_string()]),
+            sensitive_paths: claw_config.sensitive_paths.clone().unwrap_or_default(),
+            notes: claw_config.context_notes.unwrap_or(true),
+            list_beyond_depth: claw_config.list_beyond_depth.unwrap_or(false),
+            citations: claw_config.cite_context.unwrap_or(false),
         };
 
         let files = context::discover_files(&context_config)?;
-        let result = context::validate_and_read_files(files, &context_config);
+        let files = context::confirm_sensitive_files(files, &context_config)?;
+        let mut result = context::validate_and_read_files(files, &context_config);
 
         // Handle errors based on mode
-        context::handle_errors(&result, &context_config.error_handling_mode)?;
+        context::handle_errors(&mut result, &context_config)?;
 
         // Format and append to prompt
-        let context_section = context::format_context(&result, &context_config);
-        rendered_prompt.push_str("\n\n");
+        let (context_section, context_provenance) =
+            context::format_context(&result, &context_config);
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        provenance.extend_shifted(context_provenance, rendered_prompt.len());
         rendered_prompt.push_str(&context_section);
     }
 
-    Ok(rendered_prompt)
+    // Everything gathered counts towards the policy's cap, not just files.
+    // Script and built-in output counts in full, since template filters can
+    // reshape it past what the provenance attributes to it
+    let script_bytes: u64 = script_outputs.values().map(|output| output.len() as u64).sum();
+    let context_bytes = provenance.context_bytes() - provenance.script_bytes() + script_bytes;
+    policy.check_context_size(context_bytes)?;
+
+    // Ask for marked sections last, so the instructions follow all context
+    if !goal.config.outputs.is_empty() {
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            "\n\n",
+            provenance::Source::Separator,
+        );
+        append_part(
+            &mut rendered_prompt,
+            &mut provenance,
+            &response::output_instructions(&goal.config.outputs),
+            provenance::Source::OutputInstructions,
+        );
+    }
+
+    let definition_path = config::goal_definition_path(&goal.directory);
+    let goal_definition = std::fs::read_to_string(&definition_path)
+        .ok()
+        .map(|content| history::GoalDefinition {
+            file: definition_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            content,
+        });
+
+    let secret_args = goal
+        .config
+        .parameters
+        .iter()
+        .filter(|param| param.ui.as_ref().is_some_and(|ui| ui.password))
+        .map(|param| param.name.clone())
+        .collect();
+
+    Ok(RenderedPrompt {
+        template_bytes: (rendered_prompt.len() as u64).saturating_sub(context_bytes),
+        text: rendered_prompt,
+        provenance,
+        args: template_args,
+        arg_sources,
+        secret_args,
+        claw,
+        receiver_config,
+        response_template: goal.config.response_template,
+        post_process: goal.config.post_process,
+        expects: goal.config.expects,
+        assert_response: goal.config.assert_response,
+        outputs: goal.config.outputs,
+        model_options: goal.config.model_options,
+        env,
+        working_dir,
+        goal_definition,
+        script_timings,
+    })
+}
+
+/// The template arguments and command-line context a goal is rendered with,
+/// from `CommonGoalArgs`.
+#[derive(Debug, Clone, Copy, Default)]
+struct ContextRequest<'a> {
+    template_args: &'a [String],
+    /// File paths to include as context.
+    context_paths: &'a [std::path::PathBuf],
+    /// Directory recursion depth.
+    recurse_depth: Option<usize>,
+    /// Include files ignored by `.gitignore`, whatever the goal says.
+    no_gitignore: bool,
+    /// Add a map of the working directory's files and symbols.
+    repo_map: bool,
+    /// Paths to scan for TODO/FIXME/HACK comments to add.
+    todo_paths: &'a [std::path::PathBuf],
+    /// The git changes to add as unified diffs.
+    diff_scope: Option<&'a git_context::GitScope>,
+    /// How many recent shell commands to add.
+    shell_history: Option<usize>,
+}
+
+impl<'a> ContextRequest<'a> {
+    fn new(
+        common: &'a cli::CommonGoalArgs,
+        diff_scope: Option<&'a git_context::GitScope>,
+    ) -> Self {
+        Self {
+            template_args: &common.template_args,
+            context_paths: &common.context,
+            recurse_depth: common.recurse_depth,
+            no_gitignore: common.no_gitignore,
+            repo_map: common.repo_map,
+            todo_paths: &common.context_todos,
+            diff_scope,
+            shell_history: common.context_shell_history,
+        }
+    }
+}
+
+/// A fully rendered prompt along with the provenance of each byte range.
+struct RenderedPrompt {
+    text: String,
+    provenance: provenance::ProvenanceMap,
+    /// Bytes of `text` that aren't context: the goal's template text and
+    /// what claw adds around it.
+    template_bytes: u64,
+    /// The validated template arguments the prompt was rendered with.
+    args: HashMap<String, String>,
+    /// Whether each argument was supplied or filled in from a default.
+    arg_sources: BTreeMap<String, history::ArgSource>,
+    /// The arguments of parameters marked `ui.password`, masked in history.
+    secret_args: Vec<String>,
+    /// The run's details the templates see as `Claw`.
+    claw: prompt_core::ClawMeta,
+    /// The config with the goal's own receiver, if it sets one.
+    receiver_config: Option<config::ClawConfig>,
+    /// The goal's template for wrapping the captured response, if any.
+    response_template: Option<String>,
+    /// Commands the response is piped through before it is shown.
+    post_process: Vec<String>,
+    /// What the goal expects the response to contain, if declared.
+    expects: Option<config::ResponseFormat>,
+    /// Checks the captured response must pass, if declared.
+    assert_response: Option<response::ResponseAssertions>,
+    /// Named artifacts to split the captured response into.
+    outputs: BTreeMap<String, String>,
+    /// The goal's generation settings, passed on by the receiver.
+    model_options: config::ModelOptions,
+    /// Environment variables for the LLM command, already rendered.
+    env: BTreeMap<String, String>,
+    /// The directory the LLM command runs in, if the goal sets one.
+    working_dir: Option<std::path::PathBuf>,
+    /// The goal's definition file, recorded with the run.
+    goal_definition: Option<history::GoalDefinition>,
+    /// How long each context script that ran took.
+    script_timings: Vec<run_status::ScriptTiming>,
+}
+
+impl RenderedPrompt {
+    /// The context bytes in `prompt`, this prompt as it is sent after an edit
+    /// or with follow-ups: everything but its template text.
+    fn context_bytes_in(&self, prompt: &str) -> u64 {
+        (prompt.len() as u64).saturating_sub(self.template_bytes)
+    }
+}
+
+/// Appends `part` to the prompt, attributing its bytes to `source`.
+fn append_part(
+    prompt: &mut String,
+    provenance: &mut provenance::ProvenanceMap,
+    part: &str,
+    source: provenance::Source,
+) {
+    let start = prompt.len();
+    prompt.push_str(part);
+    provenance.push(start, prompt.len(), source);
 }
 
 fn run_goal(
     goal_name: &str,
     claw_config: &config::ClawConfig,
-    template_args: &[String],
-    context_paths: &[std::path::PathBuf],
-    recurse_depth: Option<usize>,
+    context: &ContextRequest,
+    receiver_options: &runner::ReceiverOptions,
 ) -> Result<()> {
-    let rendered_prompt = render_goal_prompt(
+    let started = Instant::now();
+    let prepare = || -> Result<_> {
+        // Fail before running any scripts if the receiver isn't allowed here
+        config::find_and_load_policy()?.check_receiver(claw_config)?;
+        trust::ensure_local_config_trusted()?;
+        let lock = lock_exclusive_goal(goal_name)?;
+
+        let mut rendered = render_goal_prompt(goal_name, claw_config, context)?;
+
+        // Let the user look over a large prompt before it's sent
+        let review = prompt_review::confirm_large_prompt(
+            goal_name,
+            &rendered.text,
+            &rendered.provenance,
+            claw_config.confirm_prompt_kb.unwrap_or(512),
+            tokens::Tokenizer::for_config(claw_config),
+        )?;
+        if let prompt_review::Review::Edited(text) = review {
+            rendered.text = text;
+            rendered.provenance = provenance::ProvenanceMap::default();
+        }
+        Ok((lock, rendered))
+    };
+    let (lock, rendered) = match prepare() {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            let error = Err(e);
+            write_json_status(
+                goal_name,
+                claw_config,
+                None,
+                history::RunStatus::Failed,
+                1,
+                &error,
+                started,
+            );
+            return error;
+        }
+    };
+
+    runner::install_interrupt_handler()?;
+    let outcome = send_rendered_prompt(goal_name, claw_config, &rendered, receiver_options);
+
+    // Let scripts and CI see the LLM's own exit code
+    let llm_exit_code = if claw_config.propagate_exit_code.unwrap_or(true)
+        && let Err(e) = &outcome.result
+    {
+        e.downcast_ref::<runner::LlmExitError>()
+            .and_then(runner::LlmExitError::code)
+    } else {
+        None
+    };
+    let exit_code = match (outcome.status, &outcome.result) {
+        (history::RunStatus::Interrupted, _) => 130,
+        (_, Ok(())) => 0,
+        (_, Err(_)) => llm_exit_code.unwrap_or(1),
+    };
+    write_json_status(
         goal_name,
         claw_config,
-        template_args,
-        context_paths,
-        recurse_depth,
-    )?;
+        Some(&rendered),
+        outcome.status,
+        exit_code,
+        &outcome.result,
+        started,
+    );
+
+    if outcome.status == history::RunStatus::Interrupted {
+        eprintln!("\nInterrupted.");
+        if let Some(path) = outcome.history_path {
+            eprintln!("The partial run was recorded in {}", path.display());
+        }
+        drop(lock);
+        std::process::exit(130);
+    }
+
+    if let (Some(code), Err(e)) = (llm_exit_code, &outcome.result) {
+        eprintln!("Error: {:?}", e);
+        drop(lock);
+        std::process::exit(code);
+    }
+
+    outcome.result
+}
+
+/// Writes the `--json-status` summary of a run, if it was asked for.
+/// `rendered` is `None` when the run failed before its prompt was ready.
+fn write_json_status(
+    goal_name: &str,
+    claw_config: &config::ClawConfig,
+    rendered: Option<&RenderedPrompt>,
+    status: history::RunStatus,
+    exit_code: i32,
+    result: &Result<()>,
+    started: Instant,
+) {
+    let Some(destination) = &claw_config.json_status else {
+        return;
+    };
+    let receiver_config = rendered
+        .and_then(|rendered| rendered.receiver_config.as_ref())
+        .unwrap_or(claw_config);
+    let prompt = rendered.map_or("", |rendered| rendered.text.as_str());
+    let report = run_status::RunReport {
+        goal: goal_name.to_string(),
+        run_id: rendered.map(|rendered| rendered.claw.run_id.clone()),
+        status,
+        exit_code,
+        duration_ms: started.elapsed().as_millis(),
+        prompt_bytes: prompt.len(),
+        prompt_tokens: tokens::Tokenizer::for_config(receiver_config).estimate(prompt),
+        receiver: receiver_config.receiver_label(),
+        scripts: rendered.map_or_else(Vec::new, |rendered| rendered.script_timings.clone()),
+        error: result.as_ref().err().map(|e| format!("{:#}", e)),
+    };
+    if let Err(e) = run_status::write(&report, destination) {
+        eprintln!("⚠️  Warning: {:#}", e);
+    }
+}
+
+/// Takes the lock of an `exclusive` goal for the rest of the run, waiting
+/// for it if the goal says `exclusive: wait`. Not every exit path unwinds,
+/// so drop the lock before `std::process::exit`.
+fn lock_exclusive_goal(goal_name: &str) -> Result<Option<goal_lock::GoalLock>> {
+    // A goal that doesn't load fails when it's rendered
+    let Ok(goal) = config::find_and_load_goal(goal_name) else {
+        return Ok(None);
+    };
+    match goal.config.exclusive {
+        Some(exclusive) if exclusive.is_enabled() => {
+            goal_lock::acquire(goal_name, exclusive.waits()).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
 
+/// The result of sending one rendered prompt to the LLM.
+struct RunOutcome {
+    status: history::RunStatus,
+    result: Result<()>,
+    /// Where the run was recorded, if history is enabled.
+    history_path: Option<std::path::PathBuf>,
+}
+
+/// Sends a rendered prompt to the configured receiver, records the run in
+/// history, applies an expected diff and the goal's response template to
+/// captured output. The response is captured for the goal's
+/// `assert_response`, `outputs`, `expects` and `post_process` even without
+/// `--tee`.
+///
+/// The Ctrl-C handler must already be installed.
+fn send_rendered_prompt(
+    goal_name: &str,
+    claw_config: &config::ClawConfig,
+    rendered: &RenderedPrompt,
+    receiver_options: &runner::ReceiverOptions,
+) -> RunOutcome {
+    let claw_config = rendered.receiver_config.as_ref().unwrap_or(claw_config);
     // Check for large prompt warning
-    runner::check_prompt_size_warning(&rendered_prompt, &claw_config.prompt_arg_template);
+    runner::check_prompt_size_warning(&rendered.text, &claw_config.prompt_arg_template);
+
+    if rendered.response_template.is_some() && receiver_options.tee.is_none() {
+        eprintln!(
+            "⚠️  Note: goal '{}' defines a response_template, which is only applied \
+             to a response captured with --tee FILE.",
+            goal_name
+        );
+    }
+
+    // The goal's checks, artifacts, diff and post-processing need the whole
+    // response, which a tmux session or several fanned-out receivers don't
+    // hand back
+    let capturable =
+        claw_config.fan_out.is_none() && claw_config.receiver_type != Some(config::ReceiverType::Tmux);
+    let mut declared = Vec::new();
+    if rendered.assert_response.is_some() {
+        declared.push("assert_response");
+    }
+    if !rendered.outputs.is_empty() {
+        declared.push("outputs");
+    }
+    if rendered.expects == Some(config::ResponseFormat::UnifiedDiff) {
+        declared.push("expects: unified-diff");
+    }
+    if !rendered.post_process.is_empty() {
+        declared.push("post_process");
+    }
+    if claw_config.fail_on_empty_response.unwrap_or(false) {
+        declared.push("fail_on_empty_response");
+    }
+    if !declared.is_empty() && !capturable {
+        eprintln!(
+            "⚠️  Note: goal '{}' declares {}, which is skipped with fan_out or the tmux receiver.",
+            goal_name,
+            declared.join(", ")
+        );
+    }
+    let post_process = !rendered.post_process.is_empty() && capturable;
+
+    let receiver_options = &runner::ReceiverOptions {
+        model_options: rendered.model_options.clone(),
+        env: rendered.env.clone(),
+        cwd: rendered.working_dir.clone(),
+        ..receiver_options.clone()
+    };
+    // Without --tee, the response is captured in a temporary file for them
+    let temp_capture = if !declared.is_empty() && capturable && receiver_options.tee.is_none() {
+        match tempfile::NamedTempFile::new() {
+            Ok(file) => Some(file),
+            Err(e) => {
+                return RunOutcome {
+                    status: history::RunStatus::Failed,
+                    result: Err(anyhow::Error::new(e)
+                        .context("Failed to create a file to capture the response in")),
+                    history_path: None,
+                };
+            }
+        }
+    } else {
+        None
+    };
+    let tee = receiver_options
+        .tee
+        .clone()
+        .or_else(|| temp_capture.as_ref().map(|file| file.path().to_path_buf()));
+    // With post-processing, the raw response is captured without being shown
+    let send_options = &if post_process {
+        runner::ReceiverOptions {
+            tee,
+            background: true,
+            page: false,
+            ..receiver_options.clone()
+        }
+    } else {
+        runner::ReceiverOptions {
+            tee,
+            ..receiver_options.clone()
+        }
+    };
+
+    // Create receiver and send prompt, in the live view if asked for and
+    // the LLM's output can be captured
+    let live = claw_config.live_view.unwrap_or(false)
+        && !receiver_options.background
+        && !post_process
+        && claw_config.receiver_type != Some(config::ReceiverType::Tmux)
+        && claw_config.fan_out.is_none()
+        && std::io::stdout().is_terminal();
+    // Recorded as invoked, so the history ID matches `Claw.run_id`
+    let started_at = rendered.claw.invoked_at;
+    // With an audit log required by the policy, each prompt is logged
+    // before it is sent, and not sent if it can't be
+    let policy = config::find_and_load_policy();
+    let audit_receivers = audit_receivers(claw_config);
+    let send = |prompt: &str| {
+        let policy = match &policy {
+            Ok(policy) => policy,
+            Err(e) => anyhow::bail!("Failed to load the repository policy: {:#}", e),
+        };
+        // Edited prompts and retries are held to the context cap too
+        policy.check_context_size(rendered.context_bytes_in(prompt))?;
+        if let Some(path) = &policy.audit_log {
+            let record = audit::AuditRecord::new(
+                goal_name,
+                &rendered.claw.run_id,
+                audit_receivers.clone(),
+                prompt,
+                history::context_manifest(prompt, &rendered.provenance),
+            );
+            audit::append(path, &record)
+                .context("Not sending the prompt: this repository's policy requires an audit log")?;
+        }
+        if live {
+            run_view::run(
+                goal_name,
+                claw_config,
+                prompt,
+                &rendered.provenance,
+                send_options,
+            )
+        } else {
+            runner::create_receiver(claw_config, send_options).send_prompt(prompt)
+        }
+    };
+    let mut result = send(&rendered.text);
+
+    // Ask again, with the problems listed, while the response fails its
+    // assertions
+    if let (Some(assertions), Some(path)) = (&rendered.assert_response, &send_options.tee) {
+        let retries = assertions.retries.unwrap_or(2);
+        let mut attempts = 1;
+        while result.is_ok() && !runner::was_interrupted() {
+            let response = std::fs::read_to_string(path).unwrap_or_default();
+            let failures = assertions.check(&response);
+            if failures.is_empty() {
+                break;
+            }
+            if attempts > retries {
+                result = Err(anyhow::anyhow!(
+                    "The response failed its assertions after {} attempt(s): {}",
+                    attempts,
+                    failures.join("; ")
+                ));
+                break;
+            }
+            eprintln!(
+                "⚠️  The response failed its assertions ({}); asking again ({}/{})",
+                failures.join("; "),
+                attempts,
+                retries
+            );
+            attempts += 1;
+            let prompt = format!(
+                "{}{}",
+                rendered.text,
+                response::corrective_instructions(&failures)
+            );
+            result = send(&prompt);
+        }
+    }
+
+    // An empty answer counts as a failure when asked to gate on output
+    if result.is_ok()
+        && !runner::was_interrupted()
+        && claw_config.fail_on_empty_response.unwrap_or(false)
+        && let Some(path) = &send_options.tee
+        && std::fs::read_to_string(path).map_or(true, |response| response.trim().is_empty())
+    {
+        result = Err(anyhow::anyhow!(
+            "The LLM returned an empty response (fail_on_empty_response)"
+        ));
+    }
+
+    // Pipe the response through the goal's commands and show what comes out
+    if post_process
+        && result.is_ok()
+        && !runner::was_interrupted()
+        && let Some(path) = &send_options.tee
+    {
+        match response::post_process_file(path, &rendered.post_process) {
+            Ok(processed) => {
+                if !receiver_options.background {
+                    print!("{}", processed);
+                    let _ = std::io::stdout().flush();
+                }
+            }
+            Err(e) => result = Err(e),
+        }
+    }
+
+    let status = if runner::was_interrupted() {
+        history::RunStatus::Interrupted
+    } else if result.is_ok() {
+        history::RunStatus::Completed
+    } else {
+        history::RunStatus::Failed
+    };
+
+    // Whatever was teed before an interrupt is the partial transcript
+    let response = send_options
+        .tee
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok());
+    let context_files = history::context_manifest(&rendered.text, &rendered.provenance);
+    let citations = match &response {
+        Some(response) if claw_config.cite_context.unwrap_or(false) => {
+            history::resolve_citations(response, &context_files)
+        }
+        _ => Vec::new(),
+    };
+    report_citations(&citations);
+
+    let mut history_path = None;
+    if claw_config.record_history.unwrap_or(true) {
+        let mut entry = history::HistoryEntry {
+            goal: goal_name.to_string(),
+            args: rendered.args.clone(),
+            arg_sources: Some(rendered.arg_sources.clone()),
+            started_at,
+            finished_at: history::now_secs(),
+            status,
+            response,
+            prompt: Some(rendered.text.clone()),
+            goal_definition: rendered.goal_definition.clone(),
+            context_files,
+            citations,
+            claw_version: Some(version::CURRENT.to_string()),
+        };
+        entry.mask_secrets(&rendered.secret_args);
+        let limit = claw_config
+            .history_limit
+            .unwrap_or(history::DEFAULT_HISTORY_LIMIT);
+        match history::record(&entry, limit) {
+            Ok(path) => history_path = path,
+            Err(e) => eprintln!("⚠️  Warning: Failed to record run history: {:#}", e),
+        }
+    }
+
+    // Apply a proposed diff from the raw response, before any template wraps it.
+    // Concurrent matrix runs can't share the terminal to confirm, so they skip this.
+    if status == history::RunStatus::Completed
+        && rendered.expects == Some(config::ResponseFormat::UnifiedDiff)
+        && let Some(path) = &send_options.tee
+    {
+        if receiver_options.background {
+            eprintln!(
+                "⚠️  Note: not applying the diff in {} while other runs are in progress.",
+                path.display()
+            );
+        } else {
+            result = patch::apply_response_file(path, rendered.working_dir.as_deref());
+        }
+    }
+
+    // Split the raw response into the goal's named artifacts
+    if result.is_ok()
+        && status == history::RunStatus::Completed
+        && !rendered.outputs.is_empty()
+        && let Some(path) = &send_options.tee
+    {
+        result = response::write_outputs(path, &rendered.outputs);
+    }
+
+    // Wrap the saved response in the goal's template once the run completed
+    if result.is_ok()
+        && status == history::RunStatus::Completed
+        && let (Some(template), Some(path)) = (&rendered.response_template, &receiver_options.tee)
+    {
+        result = response::apply_to_file(path, template, &rendered.claw, &rendered.args);
+    }
+
+    RunOutcome {
+        status,
+        result,
+        history_path,
+    }
+}
+
+/// Sends one turn of a `claw session`, `prompt`, the way a run sends its
+/// prompt: reviewed when large, audited and recorded in history. The
+/// response is captured in `capture`, and the goal's response handling
+/// (`response_template`, `outputs`, `post_process` and the like) is left out
+/// so the conversation keeps the raw response.
+fn send_session_turn(
+    goal_name: &str,
+    session_config: &config::ClawConfig,
+    rendered: &RenderedPrompt,
+    prompt: &str,
+    capture: Option<&std::path::Path>,
+) -> Result<()> {
+    // Follow-ups that resend the conversation start with the goal's prompt,
+    // so its context files are still where the provenance says
+    let provenance = if prompt.starts_with(rendered.text.trim_end()) {
+        rendered.provenance.clone()
+    } else {
+        provenance::ProvenanceMap::default()
+    };
+    let mut turn = RenderedPrompt {
+        text: prompt.to_string(),
+        provenance,
+        template_bytes: rendered.template_bytes,
+        args: rendered.args.clone(),
+        arg_sources: rendered.arg_sources.clone(),
+        secret_args: rendered.secret_args.clone(),
+        claw: prompt_core::ClawMeta::new(goal_name, history::now_secs()),
+        receiver_config: None,
+        response_template: None,
+        post_process: Vec::new(),
+        expects: None,
+        assert_response: None,
+        outputs: BTreeMap::new(),
+        model_options: rendered.model_options.clone(),
+        env: rendered.env.clone(),
+        working_dir: rendered.working_dir.clone(),
+        goal_definition: rendered.goal_definition.clone(),
+        script_timings: Vec::new(),
+    };
+
+    let review = prompt_review::confirm_large_prompt(
+        goal_name,
+        &turn.text,
+        &turn.provenance,
+        session_config.confirm_prompt_kb.unwrap_or(512),
+        tokens::Tokenizer::for_config(session_config),
+    )?;
+    if let prompt_review::Review::Edited(text) = review {
+        turn.text = text;
+        turn.provenance = provenance::ProvenanceMap::default();
+    }
 
-    // Create receiver and send prompt
-    let receiver = runner::create_receiver(claw_config);
-    receiver.send_prompt(&rendered_prompt)?;
+    let receiver_options = runner::ReceiverOptions {
+        tee: capture.map(std::path::Path::to_path_buf),
+        page: false,
+        ..Default::default()
+    };
+    send_rendered_prompt(goal_name, session_config, &turn, &receiver_options).result
+}
+
+/// Names every receiver a prompt may be sent to, for the audit log.
+fn audit_receivers(claw_config: &config::ClawConfig) -> Vec<String> {
+    let receivers = if claw_config.fan_out.is_some() {
+        claw_config.fan_out_receivers()
+    } else {
+        claw_config.receiver_chain()
+    };
+    match receivers {
+        Ok(receivers) => receivers.into_iter().map(|(label, _)| label).collect(),
+        Err(_) => vec![claw_config.receiver_label()],
+    }
+}
+
+/// Lists the context files the response cited, and citations of numbers
+/// that no context file had.
+fn report_citations(citations: &[history::Citation]) {
+    if citations.is_empty() {
+        return;
+    }
+    eprintln!("Cited sources:");
+    for citation in citations {
+        match &citation.path {
+            Some(path) => eprintln!("  [{}] {}", citation.source, path.display()),
+            None => eprintln!("  [{}] ⚠️  no context file has this number", citation.source),
+        }
+    }
+}
 
+/// Runs a goal once per entry of a matrix file, writing each response to
+/// its own file in `output_dir`. Up to `jobs` entries run at the same time.
+fn run_matrix(
+    goal_name: &str,
+    claw_config: &config::ClawConfig,
+    matrix_file: &std::path::Path,
+    output_dir: &std::path::Path,
+    jobs: usize,
+    context: &ContextRequest,
+) -> Result<()> {
+    config::find_and_load_policy()?.check_receiver(claw_config)?;
+    trust::ensure_local_config_trusted()?;
+
+    let lock = lock_exclusive_goal(goal_name)?;
+
+    if !config::find_and_load_goal(goal_name)?.config.outputs.is_empty() {
+        anyhow::bail!(
+            "Goal '{}' declares outputs, so every matrix entry would write the same files. \
+             Run it without --matrix.",
+            goal_name
+        );
+    }
+
+    let entries = matrix::load_matrix(matrix_file)?;
+    let output_paths = matrix::output_paths(output_dir, goal_name, &entries);
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    // Render every entry up front, so scripts and interactive parameter
+    // prompts run one at a time before any LLM is started
+    let mut rendered = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let mut args = context.template_args.to_vec();
+        args.extend(entry.template_args());
+        let context = ContextRequest {
+            template_args: &args,
+            ..*context
+        };
+        let prompt = render_goal_prompt(goal_name, claw_config, &context)
+        .with_context(|| format!("Failed to render matrix entry ({})", entry.label()))?;
+        rendered.push(prompt);
+    }
+
+    runner::install_interrupt_handler()?;
+    let jobs = jobs.clamp(1, entries.len());
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Option<RunOutcome>>> =
+        Mutex::new((0..entries.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= entries.len() || runner::was_interrupted() {
+                        break;
+                    }
+                    eprintln!(
+                        "[{}/{}] Running {} ({})",
+                        i + 1,
+                        entries.len(),
+                        goal_name,
+                        entries[i].label()
+                    );
+                    let receiver_options = runner::ReceiverOptions {
+                        tee: Some(output_paths[i].clone()),
+                        background: jobs > 1,
+                        ..Default::default()
+                    };
+                    let outcome =
+                        send_rendered_prompt(goal_name, claw_config, &rendered[i], &receiver_options);
+                    outcomes.lock().unwrap()[i] = Some(outcome);
+                }
+            });
+        }
+    });
+
+    println!("\nMatrix results for '{}':", goal_name);
+    let mut failures = 0;
+    for ((entry, path), outcome) in entries
+        .iter()
+        .zip(&output_paths)
+        .zip(outcomes.into_inner().unwrap())
+    {
+        match outcome {
+            Some(RunOutcome { result: Ok(()), .. }) => {
+                println!("  ✓ {} -> {}", entry.label(), path.display());
+            }
+            Some(RunOutcome {
+                result: Err(e), ..
+            }) => {
+                failures += 1;
+                println!("  ✗ {}: {:#}", entry.label(), e);
+            }
+            None => {
+                failures += 1;
+                println!("  - {}: not run", entry.label());
+            }
+        }
+    }
+
+    if runner::was_interrupted() {
+        eprintln!("\nInterrupted.");
+        drop(lock);
+        std::process::exit(130);
+    }
+    if failures > 0 {
+        anyhow::bail!("{} of {} matrix entries failed", failures, entries.len());
+    }
+    Ok(())
+}
+
+/// Runs every goal invocation in a batch plan, up to `jobs` at a time, and
+/// prints a summary table. A run that fails to render or send doesn't stop
+/// the others.
+fn run_batch(
+    claw_config: &config::ClawConfig,
+    plan_file: &std::path::Path,
+    jobs: Option<usize>,
+) -> Result<()> {
+    config::find_and_load_policy()?.check_receiver(claw_config)?;
+    trust::ensure_local_config_trusted()?;
+
+    let plan = batch::load_plan(plan_file)?;
+    let runs = &plan.runs;
+
+    // Exclusive goals are locked for the whole batch, before any run starts
+    let mut locks = Vec::new();
+    let mut locked_goals = std::collections::BTreeSet::new();
+    for run in runs {
+        if locked_goals.insert(run.goal.as_str())
+            && let Some(lock) = lock_exclusive_goal(&run.goal)?
+        {
+            locks.push(lock);
+        }
+    }
+
+    // Render every run up front, so scripts and interactive parameter
+    // prompts run one at a time before any LLM is started
+    let mut rendered = Vec::with_capacity(runs.len());
+    for run in runs {
+        let started = Instant::now();
+        let template_args = run.template_args();
+        let context = ContextRequest {
+            template_args: &template_args,
+            context_paths: &run.context,
+            ..Default::default()
+        };
+        let prompt = render_goal_prompt(&run.goal, claw_config, &context);
+        if let Err(e) = &prompt {
+            eprintln!("✗ Failed to render {} ({}): {:#}", run.goal, run.args_label(), e);
+        }
+        rendered.push(prompt.map_err(|e| (format!("{:#}", e), started.elapsed())));
+    }
+
+    runner::install_interrupt_handler()?;
+    let jobs = jobs.or(plan.jobs).unwrap_or(1).clamp(1, runs.len());
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(batch::RunResult, Duration)>> = Mutex::new(
+        rendered
+            .iter()
+            .map(|prompt| match prompt {
+                Ok(_) => (batch::RunResult::NotRun, Duration::ZERO),
+                Err((error, elapsed)) => (batch::RunResult::Failed(error.clone()), *elapsed),
+            })
+            .collect(),
+    );
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= runs.len() || runner::was_interrupted() {
+                        break;
+                    }
+                    let Ok(prompt) = &rendered[i] else {
+                        continue;
+                    };
+                    let run = &runs[i];
+                    eprintln!(
+                        "[{}/{}] Running {} ({})",
+                        i + 1,
+                        runs.len(),
+                        run.goal,
+                        run.args_label()
+                    );
+                    if let Some(dir) = run.output.parent()
+                        && let Err(e) = std::fs::create_dir_all(dir)
+                    {
+                        results.lock().unwrap()[i] = (
+                            batch::RunResult::Failed(format!(
+                                "Failed to create {}: {}",
+                                dir.display(),
+                                e
+                            )),
+                            Duration::ZERO,
+                        );
+                        continue;
+                    }
+                    let receiver_options = runner::ReceiverOptions {
+                        tee: Some(run.output.clone()),
+                        background: jobs > 1,
+                        ..Default::default()
+                    };
+                    let started = Instant::now();
+                    let outcome =
+                        send_rendered_prompt(&run.goal, claw_config, prompt, &receiver_options);
+                    let result = match outcome.result {
+                        Ok(()) => batch::RunResult::Succeeded,
+                        Err(e) => batch::RunResult::Failed(format!("{:#}", e)),
+                    };
+                    results.lock().unwrap()[i] = (result, started.elapsed());
+                }
+            });
+        }
+    });
+
+    let results = results.into_inner().unwrap();
+    let failures = results
+        .iter()
+        .filter(|(result, _)| !matches!(result, batch::RunResult::Succeeded))
+        .count();
+    println!(
+        "\nBatch results ({} runs, {} failed):",
+        runs.len(),
+        failures
+    );
+    print!("{}", batch::format_summary(runs, &results));
+
+    if runner::was_interrupted() {
+        eprintln!("\nInterrupted.");
+        drop(locks);
+        std::process::exit(130);
+    }
+    if failures > 0 {
+        anyhow::bail!("{} of {} batch runs failed", failures, runs.len());
+    }
     Ok(())
 }
@@ -0,0 +1,282 @@
+//! Renders the markdown in goal descriptions for a terminal: bold, emphasis,
+//! code spans, headings, lists and code blocks, wrapped to a width.
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const CODE: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders `text` as lines of at most `width` visible characters (longer
+/// words and code blocks aside), styled with ANSI escapes.
+pub fn render(text: &str, width: usize) -> Vec<String> {
+    let mut renderer = Renderer {
+        width,
+        ..Renderer::default()
+    };
+    for event in Parser::new(text) {
+        renderer.event(event);
+    }
+    renderer.end_line();
+    renderer.lines
+}
+
+/// Renders `text` like [`render`], with each line indented by `indent`
+/// spaces and ending in a newline.
+pub fn render_indented(text: &str, indent: usize, width: usize) -> String {
+    render(text, width)
+        .into_iter()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("{}{}\n", " ".repeat(indent), line)
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Renderer {
+    width: usize,
+    lines: Vec<String>,
+    /// The line being filled, once something has been put on it.
+    line: Option<String>,
+    line_len: usize,
+    line_has_words: bool,
+    /// The word being built, as runs of text with the style each has.
+    word: Vec<(String, String)>,
+    word_len: usize,
+    styles: Vec<&'static str>,
+    /// The next number of each open list; `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    /// How far each open list item's text is indented.
+    indents: Vec<usize>,
+    in_code_block: bool,
+    /// A finished block wants a blank line before the next one.
+    gap: bool,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Paragraph) => self.start_block(),
+            Event::End(TagEnd::Paragraph) => self.end_block(),
+            Event::Start(Tag::Heading { .. }) => {
+                self.start_block();
+                self.styles.push(BOLD);
+                self.styles.push(UNDERLINE);
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                self.styles.truncate(self.styles.len().saturating_sub(2));
+                self.end_block();
+            }
+            Event::Start(Tag::List(first)) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.end_line();
+                }
+                self.lists.push(first);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.end_block();
+                }
+            }
+            Event::Start(Tag::Item) => self.start_item(),
+            Event::End(TagEnd::Item) => {
+                self.end_line();
+                self.indents.pop();
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.start_block();
+                self.in_code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.in_code_block = false;
+                self.gap = true;
+            }
+            Event::Start(Tag::Strong) => self.styles.push(BOLD),
+            Event::Start(Tag::Emphasis) => self.styles.push(ITALIC),
+            Event::Start(Tag::Link { .. }) => self.styles.push(UNDERLINE),
+            Event::End(TagEnd::Strong | TagEnd::Emphasis | TagEnd::Link) => {
+                self.styles.pop();
+            }
+            Event::Code(code) => {
+                // Code spans don't break across lines
+                self.styles.push(CODE);
+                for c in code.chars() {
+                    self.push_char(c);
+                }
+                self.styles.pop();
+            }
+            Event::Text(text) if self.in_code_block => self.code_block(&text),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        self.end_word();
+                    } else {
+                        self.push_char(c);
+                    }
+                }
+            }
+            Event::SoftBreak => self.end_word(),
+            Event::HardBreak => self.end_line(),
+            Event::Rule => {
+                self.start_block();
+                let indent = self.indent();
+                let rule = "─".repeat(self.width.saturating_sub(indent));
+                self.lines.push(format!("{}{}", " ".repeat(indent), rule));
+                self.gap = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn indent(&self) -> usize {
+        self.indents.last().copied().unwrap_or(0)
+    }
+
+    fn start_block(&mut self) {
+        self.end_line();
+        if self.gap && !self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.gap = false;
+    }
+
+    fn end_block(&mut self) {
+        self.end_line();
+        self.gap = true;
+    }
+
+    /// Starts a list item's first line with its bullet or number.
+    fn start_item(&mut self) {
+        self.end_line();
+        let marker = match self.lists.last_mut() {
+            Some(Some(number)) => {
+                *number += 1;
+                format!("{}. ", *number - 1)
+            }
+            _ => "• ".to_string(),
+        };
+        let indent = self.indent();
+        self.line_len = indent + marker.chars().count();
+        self.line = Some(format!("{}{}", " ".repeat(indent), marker));
+        self.line_has_words = false;
+        self.indents.push(self.line_len);
+    }
+
+    fn code_block(&mut self, text: &str) {
+        let indent = " ".repeat(self.indent() + 2);
+        for line in text.lines() {
+            self.lines.push(format!("{}{}{}{}", indent, CODE, line, RESET));
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        let style = self.styles.concat();
+        match self.word.last_mut() {
+            Some((run_style, run)) if *run_style == style => run.push(c),
+            _ => self.word.push((style, c.to_string())),
+        }
+        self.word_len += 1;
+    }
+
+    /// Puts the word being built on the line, moving to a new line when it
+    /// doesn't fit.
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        if self.line_has_words && self.line_len + 1 + self.word_len > self.width {
+            self.break_line();
+        }
+        let indent = self.indent();
+        let line = self.line.get_or_insert_with(|| " ".repeat(indent));
+        if self.line_has_words {
+            line.push(' ');
+            self.line_len += 1;
+        } else if self.line_len == 0 {
+            self.line_len = indent;
+        }
+        for (style, run) in self.word.drain(..) {
+            if style.is_empty() {
+                line.push_str(&run);
+            } else {
+                line.push_str(&format!("{}{}{}", style, run, RESET));
+            }
+        }
+        self.line_len += self.word_len;
+        self.word_len = 0;
+        self.line_has_words = true;
+    }
+
+    fn end_line(&mut self) {
+        self.end_word();
+        self.break_line();
+    }
+
+    fn break_line(&mut self) {
+        if let Some(line) = self.line.take() {
+            self.lines.push(line);
+        }
+        self.line_len = 0;
+        self.line_has_words = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strips the ANSI escapes, leaving what the terminal shows.
+    fn plain(lines: &[String]) -> Vec<String> {
+        let escape = regex::Regex::new("\x1b\\[[0-9;]*m").unwrap();
+        lines
+            .iter()
+            .map(|line| escape.replace_all(line, "").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_styles_inline_markup() {
+        let lines = render("Reviews **staged** changes with `git diff`.", 80);
+        assert_eq!(
+            lines,
+            vec![format!(
+                "Reviews {BOLD}staged{RESET} changes with {CODE}git diff{RESET}."
+            )]
+        );
+    }
+
+    #[test]
+    fn test_render_wraps_paragraphs_and_list_items() {
+        let text = "Checks the code for bugs and style problems.\n\n\
+                    - first item that is long enough to wrap\n- second";
+        assert_eq!(
+            plain(&render(text, 24)),
+            vec![
+                "Checks the code for bugs",
+                "and style problems.",
+                "",
+                "• first item that is",
+                "  long enough to wrap",
+                "• second",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_numbers_nested_lists_and_keeps_code_blocks() {
+        let text = "1. one\n   - inner\n2. two\n\n```\nlet x = 1;\n```";
+        assert_eq!(
+            plain(&render(text, 80)),
+            vec!["1. one", "   • inner", "2. two", "", "  let x = 1;"]
+        );
+    }
+}
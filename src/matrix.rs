@@ -0,0 +1,183 @@
+//! Matrix runs: one goal, many parameter sets.
+//!
+//! `--matrix params.yaml` takes a YAML list of parameter maps and runs the
+//! goal once per entry, writing each response to its own file:
+//!
+//! ```yaml
+//! - component: api
+//!   version: 1.2
+//! - component: web
+//! ```
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single parameter set from a matrix file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixEntry {
+    pub params: BTreeMap<String, String>,
+}
+
+impl MatrixEntry {
+    /// Returns the entry as template arguments, e.g. `--component=api`.
+    pub fn template_args(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|(key, value)| format!("--{}={}", key, value))
+            .collect()
+    }
+
+    /// A short human-readable description, e.g. `component=api, version=1.2`.
+    pub fn label(&self) -> String {
+        self.params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A file-name-safe slug built from the entry's values.
+    fn slug(&self) -> String {
+        let joined = self
+            .params
+            .values()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("-");
+        joined
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+/// Loads and validates a matrix file.
+pub fn load_matrix(path: &Path) -> Result<Vec<MatrixEntry>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read matrix file {}", path.display()))?;
+    let raw: Vec<BTreeMap<String, Value>> = serde_yaml::from_str(&content).with_context(|| {
+        format!(
+            "Matrix file {} must be a YAML list of parameter maps",
+            path.display()
+        )
+    })?;
+
+    if raw.is_empty() {
+        anyhow::bail!("Matrix file {} contains no entries", path.display());
+    }
+
+    raw.into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let params = entry
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = scalar_to_string(&value).with_context(|| {
+                        format!(
+                            "Entry {} of {}: parameter '{}' must be a string, number or boolean",
+                            i + 1,
+                            path.display(),
+                            key
+                        )
+                    })?;
+                    Ok((key, value))
+                })
+                .collect::<Result<_>>()?;
+            Ok(MatrixEntry { params })
+        })
+        .collect()
+}
+
+/// Converts a YAML scalar to the string a template argument would hold.
+pub fn scalar_to_string(value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => anyhow::bail!("unsupported value {:?}", value),
+    }
+}
+
+/// Returns one distinct output file per entry, named `<goal>-<values>.md`.
+pub fn output_paths(dir: &Path, goal_name: &str, entries: &[MatrixEntry]) -> Vec<PathBuf> {
+    let mut used = HashSet::new();
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let slug = entry.slug();
+            let mut stem = if slug.is_empty() {
+                format!("{}-{}", goal_name, i + 1)
+            } else {
+                format!("{}-{}", goal_name, slug)
+            };
+            if !used.insert(stem.clone()) {
+                stem = format!("{}-{}", stem, i + 1);
+                used.insert(stem.clone());
+            }
+            dir.join(format!("{}.md", stem))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_matrix_converts_scalars() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("matrix.yaml");
+        fs::write(
+            &path,
+            "- component: api\n  version: 1.2\n  draft: true\n- component: web\n",
+        )
+        .unwrap();
+
+        let entries = load_matrix(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].template_args(),
+            vec!["--component=api", "--draft=true", "--version=1.2"]
+        );
+        assert_eq!(entries[1].label(), "component=web");
+    }
+
+    #[test]
+    fn test_load_matrix_rejects_nested_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("matrix.yaml");
+        fs::write(&path, "- component: [api, web]\n").unwrap();
+
+        let err = load_matrix(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("parameter 'component'"));
+    }
+
+    #[test]
+    fn test_output_paths_are_unique() {
+        let entry = |value: &str| MatrixEntry {
+            params: BTreeMap::from([("component".to_string(), value.to_string())]),
+        };
+        let entries = vec![entry("api/v1"), entry("api/v1"), entry("")];
+
+        let paths = output_paths(Path::new("out"), "notes", &entries);
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("out/notes-api_v1.md"),
+                PathBuf::from("out/notes-api_v1-2.md"),
+                PathBuf::from("out/notes-3.md"),
+            ]
+        );
+    }
+}
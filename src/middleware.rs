@@ -0,0 +1,212 @@
+//! Layers around a receiver's `send_prompt`, from `middlewares` in
+//! claw.yaml: behaviors every receiver can use, such as logging, masking
+//! secrets, metering and retrying, without each receiver implementing them.
+
+use anyhow::Result;
+use std::time::Instant;
+
+use crate::config::{ClawConfig, ReceiverMiddleware};
+use crate::redact;
+use crate::runner::{PromptReceiver, RetrySettings};
+use crate::tokens::{self, Tokenizer};
+
+/// One layer around sending a prompt. It may change the prompt, act before
+/// and after passing it on to `next`, or pass it on more than once.
+pub trait Middleware {
+    fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()>;
+}
+
+/// A receiver with layers around it, the first one outermost.
+pub struct LayeredReceiver {
+    receiver: Box<dyn PromptReceiver>,
+    layers: Vec<Box<dyn Middleware>>,
+}
+
+impl LayeredReceiver {
+    pub fn new(receiver: Box<dyn PromptReceiver>) -> Self {
+        Self {
+            receiver,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Adds `layer` inside the layers added before it.
+    pub fn layer(mut self, layer: impl Middleware + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    fn send_from(&self, layer: usize, prompt: &str) -> Result<()> {
+        match self.layers.get(layer) {
+            Some(middleware) => {
+                middleware.handle(prompt, &|prompt| self.send_from(layer + 1, prompt))
+            }
+            None => self.receiver.send_prompt(prompt),
+        }
+    }
+}
+
+impl PromptReceiver for LayeredReceiver {
+    fn send_prompt(&self, prompt: &str) -> Result<()> {
+        self.send_from(0, prompt)
+    }
+
+    fn name(&self) -> &str {
+        self.receiver.name()
+    }
+}
+
+/// Wraps `receiver` in the layers `config` lists in `middlewares`, with
+/// `retry` innermost when it isn't listed.
+pub fn wrap(receiver: Box<dyn PromptReceiver>, config: &ClawConfig) -> Box<dyn PromptReceiver> {
+    let mut kinds = config.middlewares.clone().unwrap_or_default();
+    if !kinds.contains(&ReceiverMiddleware::Retry) {
+        kinds.push(ReceiverMiddleware::Retry);
+    }
+    let label = config.receiver_label();
+    let mut layered = LayeredReceiver::new(receiver);
+    for kind in kinds {
+        layered = match kind {
+            ReceiverMiddleware::Log => layered.layer(Log {
+                label: label.clone(),
+            }),
+            ReceiverMiddleware::Redact => layered.layer(Redact),
+            ReceiverMiddleware::Retry => {
+                layered.layer(Retry::new(&label, RetrySettings::from_config(config)))
+            }
+            ReceiverMiddleware::Meter => layered.layer(Meter {
+                label: label.clone(),
+                tokenizer: Tokenizer::for_config(config),
+                price_per_million: config.prompt_token_price,
+            }),
+        };
+    }
+    Box::new(layered)
+}
+
+/// Notes on stderr when the prompt is sent and how the receiver fared.
+pub struct Log {
+    label: String,
+}
+
+impl Middleware for Log {
+    fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()> {
+        eprintln!("→ Sending the prompt ({} bytes) to {}", prompt.len(), self.label);
+        let started = Instant::now();
+        let result = next(prompt);
+        let elapsed = started.elapsed().as_secs_f64();
+        match &result {
+            Ok(()) => eprintln!("← {} finished in {:.1}s", self.label, elapsed),
+            Err(e) => eprintln!("← {} failed after {:.1}s: {:#}", self.label, elapsed, e),
+        }
+        result
+    }
+}
+
+/// Masks anything that looks like a secret in the prompt before passing it
+/// on.
+pub struct Redact;
+
+impl Middleware for Redact {
+    fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()> {
+        let redacted = redact::redact(prompt);
+        let masked =
+            redacted.matches(redact::REDACTED).count() - prompt.matches(redact::REDACTED).count();
+        if masked > 0 {
+            eprintln!("Masked {} secret(s) in the prompt before sending it.", masked);
+        }
+        next(&redacted)
+    }
+}
+
+/// Passes the prompt on again when the LLM command fails, per `retries` and
+/// `backoff_ms`.
+pub struct Retry {
+    label: String,
+    settings: RetrySettings,
+}
+
+impl Retry {
+    pub fn new(label: &str, settings: RetrySettings) -> Self {
+        Self {
+            label: label.to_string(),
+            settings,
+        }
+    }
+}
+
+impl Middleware for Retry {
+    fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()> {
+        self.settings.run(&self.label, || next(prompt))
+    }
+}
+
+/// Reports the prompt's estimated size in tokens once it was sent, with its
+/// cost when a price is set.
+pub struct Meter {
+    label: String,
+    tokenizer: Tokenizer,
+    price_per_million: Option<f64>,
+}
+
+impl Middleware for Meter {
+    fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()> {
+        let result = next(prompt);
+        if result.is_ok() {
+            let count = self.tokenizer.estimate(prompt);
+            let cost = self
+                .price_per_million
+                .map(|price| format!(" (≈ ${:.4})", count as f64 * price / 1_000_000.0))
+                .unwrap_or_default();
+            eprintln!(
+                "Sent {} to {}{}",
+                tokens::format_estimate(count),
+                self.label,
+                cost
+            );
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records every prompt it is sent.
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl PromptReceiver for Recorder {
+        fn send_prompt(&self, prompt: &str) -> Result<()> {
+            self.0.borrow_mut().push(prompt.to_string());
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "Recorder"
+        }
+    }
+
+    /// Appends its tag to the prompt on the way in.
+    struct Tag(&'static str);
+
+    impl Middleware for Tag {
+        fn handle(&self, prompt: &str, next: &dyn Fn(&str) -> Result<()>) -> Result<()> {
+            next(&format!("{} {}", prompt, self.0))
+        }
+    }
+
+    #[test]
+    fn test_layers_run_outermost_first() {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let receiver = LayeredReceiver::new(Box::new(Recorder(sent.clone())))
+            .layer(Tag("outer"))
+            .layer(Redact)
+            .layer(Tag("inner"));
+
+        receiver.send_prompt("TOKEN=abc").unwrap();
+        assert_eq!(*sent.borrow(), vec!["TOKEN=[REDACTED] outer inner"]);
+    }
+}
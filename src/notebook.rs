@@ -0,0 +1,138 @@
+//! Rendering Jupyter notebooks as context.
+//!
+//! A `.ipynb` file is JSON, and most of its bytes are usually cell outputs:
+//! plots as base64 images, HTML tables, long logs. Included as-is, a notebook
+//! buries its few lines of code in noise. Instead, claw renders the cells in
+//! the "percent" format used by jupytext and most editors, with outputs and
+//! attachments dropped.
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct Notebook {
+    #[serde(default)]
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    source: Source,
+    #[serde(default)]
+    outputs: Vec<Value>,
+}
+
+/// Cell source, stored either as one string or as a list of lines.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::Text(String::new())
+    }
+}
+
+impl Source {
+    fn text(&self) -> String {
+        match self {
+            Source::Text(text) => text.clone(),
+            Source::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+/// Returns true if `path` looks like a Jupyter notebook.
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Renders a notebook's cells as readable text, without outputs.
+pub fn render(json: &str) -> Result<String> {
+    let notebook: Notebook = serde_json::from_str(json).context("Not a valid Jupyter notebook")?;
+
+    let language = notebook
+        .metadata
+        .pointer("/kernelspec/language")
+        .or_else(|| notebook.metadata.pointer("/language_info/name"))
+        .and_then(Value::as_str)
+        .unwrap_or("python");
+
+    let mut output = format!("# Jupyter notebook ({}), outputs omitted\n", language);
+    for cell in &notebook.cells {
+        let source = cell.source.text();
+        let source = source.trim_end();
+        output.push('\n');
+        match cell.cell_type.as_str() {
+            "code" => {
+                output.push_str("# %%");
+                if !cell.outputs.is_empty() {
+                    output.push_str(&format!(" ({} output(s) omitted)", cell.outputs.len()));
+                }
+                output.push('\n');
+                if !source.is_empty() {
+                    output.push_str(source);
+                    output.push('\n');
+                }
+            }
+            cell_type => {
+                output.push_str(&format!("# %% [{}]\n", cell_type));
+                for line in source.lines() {
+                    if line.is_empty() {
+                        output.push_str("#\n");
+                    } else {
+                        output.push_str(&format!("# {}\n", line));
+                    }
+                }
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_drops_outputs_and_keeps_cells() {
+        let json = r##"{
+            "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+            "nbformat": 4,
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "\n", "Load the data."]},
+                {"cell_type": "code", "metadata": {}, "execution_count": 1,
+                 "source": "import pandas as pd\ndf = pd.read_csv('data.csv')",
+                 "outputs": [{"output_type": "display_data", "data": {"image/png": "iVBORw0KGgoAAAANSUhEUg"}}]},
+                {"cell_type": "code", "metadata": {}, "source": [], "outputs": []}
+            ]
+        }"##;
+
+        let rendered = render(json).unwrap();
+        assert_eq!(
+            rendered,
+            "# Jupyter notebook (python), outputs omitted\n\
+             \n# %% [markdown]\n# # Analysis\n#\n# Load the data.\n\
+             \n# %% (1 output(s) omitted)\nimport pandas as pd\ndf = pd.read_csv('data.csv')\n\
+             \n# %%\n"
+        );
+        assert!(!rendered.contains("iVBOR"));
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_notebooks() {
+        assert!(render("not json").is_err());
+        assert!(is_notebook(Path::new("analysis.IPYNB")));
+        assert!(!is_notebook(Path::new("analysis.py")));
+    }
+}
@@ -0,0 +1,271 @@
+//! Interactive collection of goal parameters.
+//!
+//! When a goal is run from a terminal without some of its required
+//! parameters, claw asks for them instead of failing. Each parameter's
+//! optional `ui:` hints control how the value is collected.
+
+use anyhow::{Context as AnyhowContext, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::collections::HashMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::{GoalParameter, ParameterUi, SliderHint};
+
+/// Set by `--porcelain`, which must never wait for input.
+static INTERACTION_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes `is_interactive` false for the rest of the run, even in a terminal.
+pub fn disable_interaction() {
+    INTERACTION_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns true if claw can ask the user for values interactively.
+pub fn is_interactive() -> bool {
+    !INTERACTION_DISABLED.load(Ordering::Relaxed)
+        && io::stdin().is_terminal()
+        && io::stderr().is_terminal()
+}
+
+/// Prompts for each of `params` on the terminal and inserts the answers into `args`.
+pub fn prompt_for_parameters(
+    params: &[GoalParameter],
+    args: &mut HashMap<String, String>,
+) -> Result<()> {
+    if params.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!("This goal needs a few more parameters:");
+    let stdin = io::stdin();
+    for param in params {
+        let value = prompt_for_parameter(param, &mut stdin.lock())?;
+        args.insert(param.name.clone(), value);
+    }
+    eprintln!();
+    Ok(())
+}
+
+/// Collects a single parameter value, honoring its UI hints.
+fn prompt_for_parameter(param: &GoalParameter, input: &mut impl BufRead) -> Result<String> {
+    let ui = param.ui.clone().unwrap_or_default();
+
+    loop {
+        eprint!("{}", format_label(param, &ui));
+        io::stderr().flush()?;
+
+        let value = if ui.password {
+            read_hidden_line()
+        } else if ui.multiline {
+            read_multiline(input)
+        } else {
+            read_line(input)
+        }
+        .with_context(|| format!("No value given for --{}", param.name))?;
+
+        if value.is_empty() {
+            eprintln!("  A value is required.");
+            continue;
+        }
+
+        if let Some(choices) = &param.choices
+            && !choices.contains(&value)
+        {
+            eprintln!("  Expected one of: {}", choices.join(", "));
+            continue;
+        }
+
+        if let Some(slider) = &ui.slider
+            && let Err(e) = check_slider(&value, slider)
+        {
+            eprintln!("  {}", e);
+            continue;
+        }
+
+        return Ok(value);
+    }
+}
+
+/// Builds the prompt label shown before reading a value.
+fn format_label(param: &GoalParameter, ui: &ParameterUi) -> String {
+    let mut label = format!("\n--{}: {}\n", param.name, param.description);
+    if let Some(choices) = &param.choices {
+        label.push_str(&format!("  (one of: {})\n", choices.join(", ")));
+    }
+    if let Some(slider) = &ui.slider {
+        label.push_str(&format!("  (a number from {} to {})\n", slider.min, slider.max));
+    }
+    if let Some(placeholder) = &ui.placeholder {
+        label.push_str(&format!("  e.g. {}\n", placeholder));
+    }
+    if ui.multiline && !ui.password {
+        label.push_str("  (finish with an empty line)\n");
+    }
+    label.push_str("> ");
+    label
+}
+
+/// Validates that `value` is a number within the slider's bounds.
+fn check_slider(value: &str, slider: &SliderHint) -> Result<()> {
+    let number: f64 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("'{}' is not a number", value))?;
+    if number < slider.min || number > slider.max {
+        anyhow::bail!(
+            "{} is outside the allowed range {} to {}",
+            number,
+            slider.min,
+            slider.max
+        );
+    }
+    Ok(())
+}
+
+/// Reads a single trimmed line. Fails at end of input, where asking again
+/// would never get an answer.
+fn read_line(input: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        anyhow::bail!("Input ended");
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Reads lines until an empty line or end of input. Fails if the input
+/// ends before any line.
+fn read_multiline(input: &mut impl BufRead) -> Result<String> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            if lines.is_empty() {
+                anyhow::bail!("Input ended");
+            }
+            break;
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line.to_string());
+        eprint!("> ");
+        io::stderr().flush()?;
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Reads a line from the terminal without echoing it.
+fn read_hidden_line() -> Result<String> {
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let result = read_hidden_keys();
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    eprintln!();
+    result
+}
+
+fn read_hidden_keys() -> Result<String> {
+    let mut value = String::new();
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => return Ok(value),
+                KeyCode::Backspace => {
+                    value.pop();
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    anyhow::bail!("Parameter input cancelled");
+                }
+                KeyCode::Char('d')
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && value.is_empty() =>
+                {
+                    anyhow::bail!("Input ended");
+                }
+                KeyCode::Char(c) => value.push(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn param_with_ui(ui: ParameterUi) -> GoalParameter {
+        GoalParameter {
+            name: "notes".to_string(),
+            description: "Release notes".to_string(),
+            required: true,
+            param_type: None,
+            default: None,
+            ui: Some(ui),
+            choices: None,
+            choices_script: None,
+            env: None,
+        }
+    }
+
+    #[test]
+    fn test_multiline_input() {
+        let param = param_with_ui(ParameterUi {
+            multiline: true,
+            ..Default::default()
+        });
+        let mut input = Cursor::new("first line\nsecond line\n\nignored\n");
+
+        let value = prompt_for_parameter(&param, &mut input).unwrap();
+        assert_eq!(value, "first line\nsecond line");
+    }
+
+    #[test]
+    fn test_end_of_input_fails_instead_of_asking_again() {
+        let param = param_with_ui(ParameterUi::default());
+        let err = prompt_for_parameter(&param, &mut Cursor::new("\n")).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "No value given for --notes: Input ended"
+        );
+
+        let param = param_with_ui(ParameterUi {
+            multiline: true,
+            ..Default::default()
+        });
+        assert!(prompt_for_parameter(&param, &mut Cursor::new("")).is_err());
+        let value = prompt_for_parameter(&param, &mut Cursor::new("last line")).unwrap();
+        assert_eq!(value, "last line");
+    }
+
+    #[test]
+    fn test_slider_reprompts_until_in_range() {
+        let param = param_with_ui(ParameterUi {
+            slider: Some(SliderHint { min: 1.0, max: 5.0 }),
+            ..Default::default()
+        });
+        let mut input = Cursor::new("nope\n9\n3\n");
+
+        let value = prompt_for_parameter(&param, &mut input).unwrap();
+        assert_eq!(value, "3");
+    }
+
+    #[test]
+    fn test_label_includes_hints() {
+        let ui = ParameterUi {
+            placeholder: Some("v1.2.0".to_string()),
+            slider: Some(SliderHint {
+                min: 0.0,
+                max: 10.0,
+            }),
+            ..Default::default()
+        };
+        let label = format_label(&param_with_ui(ui.clone()), &ui);
+        assert!(label.contains("--notes"));
+        assert!(label.contains("e.g. v1.2.0"));
+        assert!(label.contains("from 0 to 10"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Applying model-proposed edits (experimental).
+//!
+//! Goals that declare `expects: unified-diff` ask the model for a patch. Once
+//! the response has been captured with `--tee`, claw pulls the diff out of it,
+//! previews the affected files and, after confirmation, applies it to the
+//! working tree with `git apply`. This lets CLIs that only print patches
+//! edit files.
+
+use anyhow::{Context as AnyhowContext, Result};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::param_prompt;
+
+/// Extracts a unified diff from a model response.
+///
+/// Fenced ```` ```diff ```` or ```` ```patch ```` blocks are used if present;
+/// otherwise everything from the first `diff --git` or `--- ` header line on.
+/// Returns `None` if the response contains no diff.
+fn extract_diff(response: &str) -> Option<String> {
+    let mut fenced = String::new();
+    let mut in_block = false;
+    for line in response.lines() {
+        let trimmed = line.trim_start();
+        if in_block {
+            if trimmed.starts_with("```") {
+                in_block = false;
+            } else {
+                fenced.push_str(line);
+                fenced.push('\n');
+            }
+        } else if trimmed == "```diff" || trimmed == "```patch" {
+            in_block = true;
+        }
+    }
+    if !fenced.trim().is_empty() {
+        return Some(fenced);
+    }
+
+    let lines: Vec<&str> = response.lines().collect();
+    let start = lines.iter().enumerate().position(|(i, line)| {
+        line.starts_with("diff --git ")
+            || (line.starts_with("--- ")
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| next.starts_with("+++ ")))
+    })?;
+    let mut diff = lines[start..].join("\n");
+    diff.push('\n');
+    Some(diff)
+}
+
+/// Returns the paths a diff changes, taken from its `+++` headers. A file
+/// the diff deletes has `+++ /dev/null`, so its `---` path is listed instead,
+/// marked as deleted.
+fn changed_files(diff: &str) -> Vec<String> {
+    let header_path = |path: &str, prefix: &str| {
+        let path = path.split('\t').next().unwrap_or(path).trim();
+        path.strip_prefix(prefix).unwrap_or(path).to_string()
+    };
+    let mut files = Vec::new();
+    let mut old_path = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("--- ") {
+            old_path = Some(header_path(path, "a/"));
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            match header_path(path, "b/").as_str() {
+                "/dev/null" => {
+                    if let Some(old_path) = old_path.take() {
+                        files.push(format!("{} (deleted)", old_path));
+                    }
+                }
+                path => files.push(path.to_string()),
+            }
+        }
+    }
+    files
+}
+
+/// Applies the diff found in a captured response file to the working tree,
+/// after a preview and confirmation. The diff applies in `working_dir`, the
+/// directory the LLM ran in, if the goal sets one; otherwise in the current
+/// directory.
+pub fn apply_response_file(path: &Path, working_dir: Option<&Path>) -> Result<()> {
+    let response = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read captured response {}", path.display()))?;
+    let dir = match working_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir()?,
+    };
+    apply_response(&response, &dir, confirm)
+}
+
+/// Extracts the diff from `response`, checks that it applies cleanly in
+/// `dir`, previews it and applies it if `confirm` agrees. `confirm` returns
+/// `None` when it cannot ask, in which case nothing is applied.
+fn apply_response(
+    response: &str,
+    dir: &Path,
+    confirm: impl FnOnce(&[String]) -> Result<Option<bool>>,
+) -> Result<()> {
+    let Some(diff) = extract_diff(response) else {
+        eprintln!("⚠️  Note: the goal expects a unified diff, but the response contains none.");
+        return Ok(());
+    };
+
+    git_apply(&diff, dir, &["--check"]).context("The proposed diff does not apply cleanly")?;
+
+    let files = changed_files(&diff);
+    eprintln!("\n{}", diff.trim_end());
+    match confirm(&files)? {
+        Some(true) => {
+            git_apply(&diff, dir, &[])?;
+            eprintln!("✓ Applied changes to {} file(s).", files.len());
+        }
+        Some(false) => eprintln!("Changes not applied."),
+        None => eprintln!(
+            "Changes not applied: no terminal to confirm. Review the response and apply it with `git apply`."
+        ),
+    }
+    Ok(())
+}
+
+/// Asks on the terminal whether to apply changes to `files`.
+fn confirm(files: &[String]) -> Result<Option<bool>> {
+    if !param_prompt::is_interactive() {
+        return Ok(None);
+    }
+
+    eprintln!("\nThe model proposes changes to:");
+    for file in files {
+        eprintln!("    {}", file);
+    }
+    eprint!("Apply them to the working tree? [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(Some(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes"
+    )))
+}
+
+/// Runs `git apply` on `diff` in `dir`, failing with git's message.
+fn git_apply(diff: &str, dir: &Path, extra_args: &[&str]) -> Result<()> {
+    let git = which::which("git")
+        .context("git not found in your PATH. Applying diffs needs `git apply`.")?;
+    let mut child = Command::new(git)
+        .arg("apply")
+        .args(extra_args)
+        .arg("-")
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run `git apply`")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(diff.as_bytes())
+            .context("Failed to pass the diff to `git apply`")?;
+    }
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for `git apply`")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`git apply` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const DIFF: &str = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1 +1 @@\n-old\n+new\n";
+
+    #[test]
+    fn test_extract_diff_prefers_fenced_blocks() {
+        let response = format!("Here is the fix:\n\n```diff\n{}```\n\nDone.", DIFF);
+        assert_eq!(extract_diff(&response).as_deref(), Some(DIFF));
+
+        let bare = format!("Sure.\n{}", DIFF);
+        assert_eq!(extract_diff(&bare).as_deref(), Some(DIFF));
+
+        assert_eq!(extract_diff("--- just a rule\nno diff here"), None);
+        assert_eq!(changed_files(DIFF), vec!["notes.txt"]);
+
+        let deletion = "--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n";
+        assert_eq!(
+            changed_files(&format!("{}{}", DIFF, deletion)),
+            vec!["notes.txt", "old.txt (deleted)"]
+        );
+    }
+
+    #[test]
+    fn test_apply_response_after_confirmation() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        fs::write(&file, "old\n").unwrap();
+        let response = format!("```diff\n{}```", DIFF);
+
+        apply_response(&response, temp_dir.path(), |_| Ok(Some(false))).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old\n");
+
+        apply_response(&response, temp_dir.path(), |files| {
+            assert_eq!(files, ["notes.txt"]);
+            Ok(Some(true))
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new\n");
+
+        // The diff no longer applies, so nothing is asked
+        let err = apply_response(&response, temp_dir.path(), |_| unreachable!()).unwrap_err();
+        assert!(format!("{:#}", err).contains("does not apply cleanly"));
+    }
+}
@@ -0,0 +1,273 @@
+//! Repository usage policy.
+//!
+//! A repository can ship a `.claw/policy.yaml` that restricts how claw is used
+//! inside it: which receivers and LLM commands may be invoked, whether global
+//! goals may run shell commands, and how much context a prompt may carry.
+//! Violations fail with an error that explains how to comply. It can also
+//! require an audit log of every prompt sent (see `audit`).
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::config::{ClawConfig, GoalSource, LoadedGoal, ReceiverType};
+
+/// Policy settings loaded from `.claw/policy.yaml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Policy {
+    /// Receiver types that may be used in this repository.
+    #[serde(default)]
+    pub allowed_receivers: Option<Vec<ReceiverType>>,
+
+    /// LLM executables that may be invoked in this repository.
+    #[serde(default)]
+    pub allowed_llm_commands: Option<Vec<String>>,
+
+    /// When true, global goals run in safe mode: they may not execute
+    /// context scripts, context providers or `post_process` commands, or
+    /// choose their own LLM command, env or working directory, in this
+    /// repository.
+    #[serde(default)]
+    pub global_goals_safe_mode: bool,
+
+    /// Maximum size in KB of a prompt's context: context script and
+    /// provider output, `--context` files and what the other context flags
+    /// add.
+    #[serde(default)]
+    pub max_context_kb: Option<u64>,
+
+    /// JSONL file every prompt sent is logged to, relative to the
+    /// repository root unless absolute.
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+}
+
+impl Policy {
+    /// Checks that the configured receiver and LLM command, and those of
+    /// its `receiver_fallbacks` and `fan_out`, are allowed.
+    pub fn check_receiver(&self, config: &ClawConfig) -> Result<()> {
+        for (_, config) in config.receiver_chain()? {
+            self.check_single_receiver(&config)?;
+        }
+        for (_, config) in config.fan_out_receivers()? {
+            self.check_single_receiver(&config)?;
+        }
+        Ok(())
+    }
+
+    fn check_single_receiver(&self, config: &ClawConfig) -> Result<()> {
+        let receiver_type = config.receiver_type.clone().unwrap_or_default();
+
+        if let Some(allowed) = &self.allowed_receivers
+            && !allowed.contains(&receiver_type)
+        {
+            let names: Vec<String> = allowed.iter().map(|r| format!("{:?}", r)).collect();
+            anyhow::bail!(
+                "Receiver '{:?}' is not allowed by this repository's policy (.claw/policy.yaml).\n\
+                 Allowed receivers: {}\n\
+                 Set `receiver_type` in .claw/claw.yaml to one of them.",
+                receiver_type,
+                names.join(", ")
+            );
+        }
+
+        if let Some(allowed) = &self.allowed_llm_commands {
+            let command = match receiver_type {
+                ReceiverType::Generic => config.llm_command.clone().unwrap_or_default(),
+                ReceiverType::ClaudeCli => "claude".to_string(),
+                ReceiverType::Tmux => "tmux".to_string(),
+            };
+            if !allowed.contains(&command) {
+                anyhow::bail!(
+                    "LLM command '{}' is not allowed by this repository's policy (.claw/policy.yaml).\n\
+                     Allowed commands: {}\n\
+                     Set `llm_command` in .claw/claw.yaml to one of them.",
+                    command,
+                    allowed.join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a goal may run under this policy.
+    pub fn check_goal(
+        &self,
+        goal_name: &str,
+        goal: &LoadedGoal,
+        claw_config: &ClawConfig,
+    ) -> Result<()> {
+        if self.global_goals_safe_mode
+            && goal.source != GoalSource::Local
+            && goal.config.runs_commands(claw_config)
+        {
+            let kind = if goal.source == GoalSource::Managed {
+                "Managed"
+            } else {
+                "Global"
+            };
+            anyhow::bail!(
+                "{} goal '{}' runs commands of its own (context scripts, providers, tests, \
+                 post_process, or its own LLM command, env or working_dir), which this \
+                 repository's policy (.claw/policy.yaml) forbids for global goals.\n\
+                 Copy it into .claw/goals/{}/ to review and run it as a local goal.",
+                kind,
+                goal_name,
+                goal_name
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the total size of a prompt's context is within the cap.
+    pub fn check_context_size(&self, total_bytes: u64) -> Result<()> {
+        if let Some(limit_kb) = self.max_context_kb
+            && total_bytes > limit_kb.saturating_mul(1024)
+        {
+            anyhow::bail!(
+                "Context is {:.1} KB, which exceeds the {} KB limit set by this repository's \
+                 policy (.claw/policy.yaml).\n\
+                 Narrow the --context paths, lower --recurse_depth, or leave out other \
+                 context (--context-diff, --repo-map, --context-todos, --context-shell-history).",
+                total_bytes as f64 / 1024.0,
+                limit_kb
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ContextBuiltin, PromptConfig};
+    use indexmap::IndexMap;
+    use std::path::PathBuf;
+
+    fn goal_with_scripts(source: GoalSource) -> LoadedGoal {
+        LoadedGoal {
+            config: PromptConfig {
+                name: "Test".to_string(),
+                context_scripts: IndexMap::from([("diff".to_string(), "git diff".into())]),
+                prompt: "test".to_string(),
+                ..Default::default()
+            },
+            directory: PathBuf::from("/test"),
+            source,
+        }
+    }
+
+    #[test]
+    fn test_default_policy_allows_everything() {
+        let policy = Policy::default();
+        assert!(policy.check_receiver(&ClawConfig::default()).is_ok());
+        assert!(
+            policy
+                .check_goal(
+                    "review",
+                    &goal_with_scripts(GoalSource::Global),
+                    &ClawConfig::default()
+                )
+                .is_ok()
+        );
+        assert!(policy.check_context_size(u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_disallowed_receiver_and_command() {
+        let config = ClawConfig::default(); // Generic receiver running "claude"
+
+        let policy = Policy {
+            allowed_receivers: Some(vec![ReceiverType::ClaudeCli]),
+            ..Default::default()
+        };
+        let err = policy.check_receiver(&config).unwrap_err();
+        assert!(err.to_string().contains("Generic"));
+
+        let policy = Policy {
+            allowed_llm_commands: Some(vec!["gemini".to_string()]),
+            ..Default::default()
+        };
+        let err = policy.check_receiver(&config).unwrap_err();
+        assert!(err.to_string().contains("'claude'"));
+    }
+
+    #[test]
+    fn test_global_goals_safe_mode() {
+        let policy = Policy {
+            global_goals_safe_mode: true,
+            ..Default::default()
+        };
+        assert!(
+            policy
+                .check_goal(
+                    "review",
+                    &goal_with_scripts(GoalSource::Global),
+                    &ClawConfig::default()
+                )
+                .is_err()
+        );
+        assert!(
+            policy
+                .check_goal(
+                    "review",
+                    &goal_with_scripts(GoalSource::Local),
+                    &ClawConfig::default()
+                )
+                .is_ok()
+        );
+
+        // A goal's own LLM command is a command it runs too
+        let goal = LoadedGoal {
+            config: PromptConfig {
+                name: "Test".to_string(),
+                llm_command: Some("sh".to_string()),
+                prompt: "test".to_string(),
+                ..Default::default()
+            },
+            directory: PathBuf::from("/test"),
+            source: GoalSource::Global,
+        };
+        assert!(
+            policy
+                .check_goal("review", &goal, &ClawConfig::default())
+                .is_err()
+        );
+
+        // So are the tools a claw.yaml puts in the toolchain report
+        let goal = LoadedGoal {
+            config: PromptConfig {
+                name: "Test".to_string(),
+                context_builtin: vec![ContextBuiltin::Toolchains],
+                prompt: "test".to_string(),
+                ..Default::default()
+            },
+            directory: PathBuf::from("/test"),
+            source: GoalSource::Global,
+        };
+        assert!(
+            policy
+                .check_goal("review", &goal, &ClawConfig::default())
+                .is_ok()
+        );
+        let config = ClawConfig {
+            toolchain_report: Some(vec!["make".to_string()]),
+            ..Default::default()
+        };
+        assert!(policy.check_goal("review", &goal, &config).is_err());
+    }
+
+    #[test]
+    fn test_context_size_cap() {
+        let policy = Policy {
+            max_context_kb: Some(10),
+            ..Default::default()
+        };
+        assert!(policy.check_context_size(10 * 1024).is_ok());
+        // A byte over the limit is over it, not rounded down to 10 KB
+        let err = policy.check_context_size(10 * 1024 + 1).unwrap_err();
+        assert!(err.to_string().starts_with("Context is 10.0 KB"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Background prefetching of slow context scripts.
+//!
+//! A context script declared as `{ run: ..., prefetch: true, every: 900 }`
+//! is run by `claw daemon` every `every` seconds in the directory the daemon
+//! was started in. Renders in that directory use its most recent output,
+//! and say how old it is, instead of waiting for the script. Without a
+//! prefetched output the script runs as usual.
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, ClawConfig, GoalSource, LoadedGoal};
+use crate::history;
+use crate::prompt_core;
+use crate::runner;
+use crate::script_cache;
+use crate::trust;
+
+/// How often the daemon looks for scripts that are due.
+const TICK: Duration = Duration::from_secs(30);
+
+/// Replaces the goal's prefetched scripts in `scripts` with their most
+/// recent successful outputs for `cwd`, which are returned by name.
+/// Scripts without one stay in `scripts` to run now.
+pub fn take_prefetched(
+    cwd: &Path,
+    goal_name: &str,
+    goal: &LoadedGoal,
+    scripts: &mut IndexMap<String, String>,
+) -> HashMap<String, String> {
+    let prefetched: Vec<&String> = goal
+        .config
+        .context_scripts
+        .iter()
+        .filter(|(_, script)| script.prefetch())
+        .map(|(name, _)| name)
+        .collect();
+    if prefetched.is_empty() {
+        return HashMap::new();
+    }
+
+    let cached = script_cache::load_prefetched(cwd, goal_name);
+    let now = history::now_secs();
+    let mut outputs = HashMap::new();
+    for name in prefetched {
+        match cached.get(name) {
+            Some(entry) if entry.succeeded => {
+                eprintln!(
+                    "Using the prefetched output of '{}' from {}",
+                    name,
+                    script_cache::format_age(entry.recorded_at, now)
+                );
+                scripts.shift_remove(name);
+                outputs.insert(name.clone(), entry.output.clone());
+            }
+            _ => eprintln!(
+                "No prefetched output of '{}' yet; running it now (start `claw daemon` to prefetch it)",
+                name
+            ),
+        }
+    }
+    outputs
+}
+
+/// Handles `claw daemon`: runs the prefetched scripts of every goal as they
+/// come due, until interrupted. With `once`, runs the due scripts and exits.
+/// Scripts get `claw.yaml`'s `env` with their goal's, and its timeouts.
+pub fn run_daemon(once: bool, claw_config: &ClawConfig) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    if !once {
+        eprintln!(
+            "Prefetching context scripts for {} (Ctrl-C to stop)",
+            cwd.display()
+        );
+    }
+    let mut skipped = BTreeSet::new();
+    loop {
+        let ran = prefetch_due(&cwd, claw_config, &mut skipped)?;
+        if once {
+            if ran == 0 {
+                println!("Nothing to prefetch: no prefetched script is due.");
+            }
+            return Ok(());
+        }
+        thread::sleep(TICK);
+    }
+}
+
+/// Runs each prefetched script whose output is missing or older than its
+/// interval, one at a time, and returns how many ran. Goals the daemon may
+/// not run scripts for are reported once, in `skipped`.
+fn prefetch_due(
+    cwd: &Path,
+    claw_config: &ClawConfig,
+    skipped: &mut BTreeSet<String>,
+) -> Result<usize> {
+    let env = claw_config.env.clone().unwrap_or_default();
+    let policy = config::find_and_load_policy()?;
+    let mut names: Vec<String> = config::find_all_goals()?
+        .into_iter()
+        .filter(|goal| goal.config.context_scripts.values().any(|s| s.prefetch()))
+        .map(|goal| goal.name)
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let has_local_config = config::ConfigPaths::new()?.has_local_claw_config();
+    let mut ran = 0;
+    for name in names {
+        let Ok(goal) = config::find_and_load_goal(&name) else {
+            continue;
+        };
+        // A local claw.yaml's shell and env apply to global goals' scripts too
+        let local = goal.source == GoalSource::Local || has_local_config;
+        let allowed = if local && !trust::is_local_trusted() {
+            Err("the local .claw directory isn't trusted; run `claw trust`".to_string())
+        } else {
+            policy
+                .check_goal(&name, &goal, claw_config)
+                .map_err(|e| format!("{:#}", e))
+        };
+        if let Err(reason) = allowed {
+            if skipped.insert(name.clone()) {
+                eprintln!("Skipping goal '{}': {}", name, reason);
+            }
+            continue;
+        }
+
+        let cached = script_cache::load_prefetched(cwd, &name);
+        let now = history::now_secs();
+        for (script_name, script) in &goal.config.context_scripts {
+            let due = cached.get(script_name).is_none_or(|entry| {
+                now.saturating_sub(entry.recorded_at) >= script.prefetch_interval()
+            });
+            if !script.prefetch() || !due {
+                continue;
+            }
+            let started = Instant::now();
+            // Prefetched scripts run without arguments, so their env and
+            // working directory do too
+            let result = prompt_core::render_env(&env, &goal.config.env, &HashMap::new())
+                .and_then(|env| {
+                    let command =
+                        prompt_core::render_context_script(script_name, script, &HashMap::new())?;
+                    let working_dir =
+                        prompt_core::render_working_dir(&goal.config, &HashMap::new(), cwd)?;
+                    let options = runner::ScriptOptions {
+                        cwd: runner::existing_working_dir(working_dir)?,
+                        ..runner::ScriptOptions::for_script(claw_config, script, &env)
+                    };
+                    runner::run_script_with(script_name, &command, &options)
+                });
+            match &result {
+                Ok(_) => eprintln!(
+                    "✓ Prefetched {}/{} in {:.1}s",
+                    name,
+                    script_name,
+                    started.elapsed().as_secs_f64()
+                ),
+                Err(e) => eprintln!("✗ {}/{} failed: {:#}", name, script_name, e),
+            }
+            let results = IndexMap::from([(script_name.clone(), result)]);
+            if let Err(e) = script_cache::record_prefetched(cwd, &name, &results) {
+                eprintln!("⚠️  Warning: Failed to store prefetched output: {:#}", e);
+            }
+            ran += 1;
+        }
+    }
+    Ok(ran)
+}
@@ -0,0 +1,262 @@
+//! The prompt-building core: context script and prompt template rendering.
+//!
+//! Nothing here spawns processes or touches the filesystem. A goal's
+//! template files come through [`GoalFiles`], and script output is passed in
+//! already gathered. That keeps prompt rendering testable in memory, e.g.
+//! rendering a goal with made-up context.
+//!
+//! Parameter validation lives in `validation`, which follows the same rules.
+
+use anyhow::{Context as AnyhowContext, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tera::{Context, Tera};
+
+use crate::config::{ContextScript, PromptConfig};
+use crate::dates;
+use crate::version;
+
+/// Name of the goal's prompt template among its templates.
+const PROMPT_TEMPLATE: &str = "prompt";
+
+/// Read access to the template files kept next to a goal's `prompt.yaml`,
+/// which the prompt can `include` or `extend`.
+pub trait GoalFiles {
+    /// Returns each template file's name, relative to the goal directory
+    /// with `/` separators (e.g. `partials/rules.md`), and its contents.
+    fn template_files(&self) -> Result<Vec<(String, String)>>;
+}
+
+/// Template files held in memory, by name.
+impl GoalFiles for BTreeMap<String, String> {
+    fn template_files(&self) -> Result<Vec<(String, String)>> {
+        Ok(self
+            .iter()
+            .map(|(name, content)| (name.clone(), content.clone()))
+            .collect())
+    }
+}
+
+/// Details of the run, available to the prompt and the response template
+/// as `Claw`, e.g. `{{ Claw.goal }}`. They live in their own namespace, so
+/// a goal parameter can't shadow them, and adding one never breaks a goal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClawMeta {
+    /// claw's version.
+    pub version: String,
+    /// The goal being run.
+    pub goal: String,
+    /// When the run was invoked, in seconds since the Unix epoch;
+    /// `{{ Claw.invoked_at | date(format="%Y-%m-%d %H:%M") }}` formats it.
+    pub invoked_at: u64,
+    /// `<invoked_at>-<goal>`, the ID `claw history` and `claw bundle` give
+    /// the run once it's recorded.
+    pub run_id: String,
+}
+
+impl ClawMeta {
+    pub fn new(goal: &str, invoked_at: u64) -> Self {
+        ClawMeta {
+            version: version::CURRENT.to_string(),
+            goal: goal.to_string(),
+            invoked_at,
+            run_id: format!("{}-{}", invoked_at, goal),
+        }
+    }
+}
+
+/// Renders the environment variables of `claw.yaml` (`shared`) and of the
+/// goal, whose values win, substituting the goal's arguments in each value.
+pub fn render_env(
+    shared: &BTreeMap<String, String>,
+    goal: &BTreeMap<String, String>,
+    args: &HashMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut context = Context::new();
+    context.insert("Args", args);
+    let mut env = shared.clone();
+    env.extend(goal.iter().map(|(name, value)| (name.clone(), value.clone())));
+    env.into_iter()
+        .map(|(name, value)| {
+            let value = dates::render_str(&value, &context)
+                .map_err(anyhow::Error::new)
+                .with_context(|| format!("Failed to render env variable '{}'", name))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// Renders the goal's `working_dir` with its arguments and resolves it from
+/// `base`, the directory claw runs in. Whether it exists is for the caller
+/// to check (`runner::existing_working_dir`).
+pub fn render_working_dir(
+    config: &PromptConfig,
+    args: &HashMap<String, String>,
+    base: &Path,
+) -> Result<Option<PathBuf>> {
+    let Some(template) = &config.working_dir else {
+        return Ok(None);
+    };
+    let mut context = Context::new();
+    context.insert("Args", args);
+    let rendered = dates::render_str(template, &context)
+        .map_err(anyhow::Error::new)
+        .context("Failed to render working_dir")?;
+    let rendered = rendered.trim();
+    if rendered.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(base.join(rendered)))
+}
+
+/// Renders each context script's command with the goal's arguments, ready
+/// to be run by the caller.
+pub fn render_context_scripts(
+    config: &PromptConfig,
+    args: &HashMap<String, String>,
+) -> Result<IndexMap<String, String>> {
+    config
+        .context_scripts
+        .iter()
+        .map(|(name, script)| Ok((name.clone(), render_context_script(name, script, args)?)))
+        .collect()
+}
+
+/// What a context script renders as under `--no-scripts`, instead of its
+/// output.
+pub fn script_placeholder(name: &str) -> String {
+    format!("<<script: {}>>", name)
+}
+
+/// Renders one context script's command with the goal's arguments.
+pub fn render_context_script(
+    name: &str,
+    script: &ContextScript,
+    args: &HashMap<String, String>,
+) -> Result<String> {
+    let mut context = Context::new();
+    context.insert("Args", args);
+    dates::render_str(script.command(), &context)
+        .map_err(anyhow::Error::new)
+        .with_context(|| format!("Failed to render context script '{}'", name))
+}
+
+/// Loads a goal's template files along with its prompt. Callers can
+/// register extra template functions before calling [`render_prompt`].
+pub fn prompt_templates(config: &PromptConfig, files: &impl GoalFiles) -> Result<Tera> {
+    let mut tera = Tera::default();
+    dates::register(&mut tera);
+    tera.add_raw_templates(files.template_files()?)
+        .map_err(anyhow::Error::new)
+        .context("Failed to load the goal's templates")?;
+    tera.add_raw_template(PROMPT_TEMPLATE, &config.prompt)
+        .map_err(anyhow::Error::new)
+        .context("Failed to parse the prompt template")?;
+    Ok(tera)
+}
+
+/// Renders the prompt with the validated arguments as `Args`, the script
+/// and built-in output as `Context` and the run's details as `Claw`.
+pub fn render_prompt(
+    tera: &Tera,
+    claw: &ClawMeta,
+    args: &HashMap<String, String>,
+    script_outputs: &HashMap<String, String>,
+) -> Result<String> {
+    let mut context = Context::new();
+    context.insert("Args", args);
+    context.insert("Context", script_outputs);
+    context.insert("Claw", claw);
+    tera.render(PROMPT_TEMPLATE, &context)
+        .map_err(anyhow::Error::new)
+        .with_context(|| format!("Failed to render the prompt for goal '{}'", claw.goal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_working_dir_from_args() {
+        let base = Path::new("/repo");
+        let config = PromptConfig {
+            working_dir: Some("packages/{{ Args.package }}".to_string()),
+            ..Default::default()
+        };
+        let args = HashMap::from([("package".to_string(), "api".to_string())]);
+        assert_eq!(
+            render_working_dir(&config, &args, base).unwrap(),
+            Some(base.join("packages/api"))
+        );
+        assert_eq!(
+            render_working_dir(&PromptConfig::default(), &args, base).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_env_prefers_goal_values() {
+        let shared = BTreeMap::from([
+            ("REGION".to_string(), "eu".to_string()),
+            ("PROFILE".to_string(), "default".to_string()),
+        ]);
+        let goal = BTreeMap::from([("PROFILE".to_string(), "{{ Args.env }}-admin".to_string())]);
+        let args = HashMap::from([("env".to_string(), "staging".to_string())]);
+
+        let env = render_env(&shared, &goal, &args).unwrap();
+        assert_eq!(env["REGION"], "eu");
+        assert_eq!(env["PROFILE"], "staging-admin");
+    }
+
+    #[test]
+    fn test_render_goal_in_memory() {
+        let config = PromptConfig {
+            name: "Review".to_string(),
+            context_scripts: IndexMap::from([(
+                "diff".to_string(),
+                "git diff {{ Args.base }}".into(),
+            )]),
+            prompt: "{% include \"partials/rules.md\" %}\n{{ Context.diff }}".to_string(),
+            ..Default::default()
+        };
+        let args = HashMap::from([("base".to_string(), "main".to_string())]);
+        let files = BTreeMap::from([(
+            "partials/rules.md".to_string(),
+            "Review against {{ Args.base }}.".to_string(),
+        )]);
+
+        let scripts = render_context_scripts(&config, &args).unwrap();
+        assert_eq!(scripts["diff"], "git diff main");
+
+        let tera = prompt_templates(&config, &files).unwrap();
+        let claw = ClawMeta::new("review", 1700000000);
+        let outputs = HashMap::from([("diff".to_string(), "+ fn new()".to_string())]);
+        assert_eq!(
+            render_prompt(&tera, &claw, &args, &outputs).unwrap(),
+            "Review against main.\n+ fn new()"
+        );
+
+        let error = render_prompt(&tera, &claw, &HashMap::new(), &outputs).unwrap_err();
+        assert!(format!("{:#}", error).contains("Failed to render the prompt for goal 'review'"));
+    }
+
+    #[test]
+    fn test_claw_namespace_is_separate_from_args() {
+        let config = PromptConfig {
+            prompt: "{{ Claw.goal }} {{ Claw.run_id }} {{ Args.goal }} \
+                     {{ Claw.invoked_at | date(format=\"%Y-%m-%d\") }}"
+                .to_string(),
+            ..Default::default()
+        };
+        let tera = prompt_templates(&config, &BTreeMap::new()).unwrap();
+        let args = HashMap::from([("goal".to_string(), "mine".to_string())]);
+        let claw = ClawMeta::new("review", 1700000000);
+        assert_eq!(
+            render_prompt(&tera, &claw, &args, &HashMap::new()).unwrap(),
+            "review 1700000000-review mine 2023-11-14"
+        );
+        assert_eq!(claw.version, version::CURRENT);
+    }
+}
@@ -0,0 +1,299 @@
+//! Confirmation before sending large prompts.
+//!
+//! A stray `--context .` or a runaway script can turn a prompt into
+//! megabytes that cost real money to send. Above `confirm_prompt_kb`, claw
+//! shows what the prompt is made of (each section's size and first lines)
+//! in the pager and asks whether to send it, edit it first, or abort.
+
+use anyhow::{Context as AnyhowContext, Result, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::Command;
+
+use crate::commands::dry_run;
+use crate::history;
+use crate::param_prompt;
+use crate::provenance::{ProvenanceMap, Source};
+use crate::tokens::{self, Tokenizer};
+
+/// Lines shown from the start of each section.
+const PREVIEW_LINES: usize = 2;
+
+/// Preview lines are cut to this many characters.
+const PREVIEW_WIDTH: usize = 100;
+
+/// Everything in the prompt from one source.
+#[derive(Debug, PartialEq)]
+struct Section {
+    label: String,
+    bytes: usize,
+    /// The first non-empty lines of the section's first span.
+    preview: Vec<String>,
+}
+
+/// What to do with a prompt after review.
+pub enum Review {
+    /// Send the prompt unchanged.
+    Send,
+    /// Send this edited prompt instead.
+    Edited(String),
+}
+
+/// Asks before sending `prompt` if it's larger than `threshold_kb` and
+/// claw can ask (stdin and stderr are terminals). Fails if the user aborts.
+pub fn confirm_large_prompt(
+    goal_name: &str,
+    prompt: &str,
+    provenance: &ProvenanceMap,
+    threshold_kb: u64,
+    tokenizer: Tokenizer,
+) -> Result<Review> {
+    let threshold = threshold_kb as usize * 1024;
+    if threshold == 0 || prompt.len() <= threshold || !param_prompt::is_interactive() {
+        return Ok(Review::Send);
+    }
+
+    let unknown = ProvenanceMap::default();
+    let mut edited: Option<String> = None;
+    loop {
+        let text = edited.as_deref().unwrap_or(prompt);
+        // Edits invalidate the provenance, so show the prompt as one section
+        let sections = summarize(
+            text,
+            if edited.is_some() {
+                &unknown
+            } else {
+                provenance
+            },
+        );
+        dry_run::page_output(&format_summary(
+            goal_name,
+            text,
+            &sections,
+            threshold_kb,
+            tokenizer,
+        ))?;
+
+        eprint!("Send this prompt? [y]es / [e]dit / [n]o: ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => {
+                return Ok(match edited {
+                    Some(text) => Review::Edited(text),
+                    None => Review::Send,
+                });
+            }
+            "e" | "edit" => edited = Some(edit(text)?),
+            "" | "n" | "no" | "q" => bail!("Aborted; the prompt was not sent."),
+            other => eprintln!("  Unknown answer '{}'.", other),
+        }
+    }
+}
+
+/// Returns the program and arguments of `$VISUAL` / `$EDITOR` (default `vi`).
+pub fn editor_command() -> Result<(String, Vec<String>)> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = shlex::split(&editor).context("Could not parse $EDITOR")?;
+    if words.is_empty() {
+        bail!("$EDITOR is empty");
+    }
+    let program = words.remove(0);
+    Ok((program, words))
+}
+
+/// Opens `text` in `$VISUAL` / `$EDITOR` (default `vi`) and returns the result.
+fn edit(text: &str) -> Result<String> {
+    let (program, args) = editor_command()?;
+
+    let path = std::env::temp_dir().join(format!(
+        "claw-prompt-{}-{}.md",
+        std::process::id(),
+        history::now_secs()
+    ));
+    fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", program));
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    if !status?.success() {
+        bail!("The editor exited with an error; the prompt was not sent.");
+    }
+    edited.with_context(|| format!("Failed to read back {}", path.display()))
+}
+
+/// Describes a prompt source for the summary.
+fn label(source: &Source) -> Option<String> {
+    Some(match source {
+        Source::Template { .. } => "Prompt template".to_string(),
+        Source::Script { name } => format!("Context script: {}", name),
+        Source::Provider { title } => format!("Provider: {}", title),
+        Source::RepoMap => "Repository map".to_string(),
+        Source::Todos => "TODO comments".to_string(),
+        Source::GitDiff => "Git diff".to_string(),
+        Source::ShellHistory => "Shell history".to_string(),
+        Source::ContextFile { path } => format!("File: {}", path.display()),
+        Source::ContextHeader => "Context headings and notes".to_string(),
+        Source::OutputInstructions => "Output instructions".to_string(),
+        Source::Separator => return None,
+    })
+}
+
+/// Groups the prompt's bytes by source, largest first. Text without
+/// provenance is counted as "Other".
+fn summarize(prompt: &str, provenance: &ProvenanceMap) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut add = |label: String, span: &str| {
+        let i = *index.entry(label.clone()).or_insert_with(|| {
+            sections.push(Section {
+                label,
+                bytes: 0,
+                preview: preview(span),
+            });
+            sections.len() - 1
+        });
+        sections[i].bytes += span.len();
+    };
+
+    let mut covered = 0;
+    for entry in &provenance.entries {
+        let Some(span) = prompt.get(entry.start..entry.end) else {
+            continue;
+        };
+        covered += span.len();
+        if let Some(label) = label(&entry.source) {
+            add(label, span);
+        }
+    }
+    if provenance.entries.is_empty() {
+        add("Prompt".to_string(), prompt);
+    } else if covered < prompt.len() {
+        let other = Section {
+            label: "Other".to_string(),
+            bytes: prompt.len() - covered,
+            preview: Vec::new(),
+        };
+        sections.push(other);
+    }
+
+    sections.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    sections
+}
+
+fn preview(span: &str) -> Vec<String> {
+    span.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .take(PREVIEW_LINES)
+        .map(|line| {
+            if line.chars().count() > PREVIEW_WIDTH {
+                let cut: String = line.chars().take(PREVIEW_WIDTH).collect();
+                format!("{}...", cut)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Formats a size in bytes as KB or MB.
+fn format_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+fn format_summary(
+    goal_name: &str,
+    prompt: &str,
+    sections: &[Section],
+    threshold_kb: u64,
+    tokenizer: Tokenizer,
+) -> String {
+    let mut output = format!(
+        "The prompt for '{}' is {} ({}, {}), over the {} KB confirmation threshold\n\
+         (confirm_prompt_kb in claw.yaml).\n\n",
+        goal_name,
+        format_size(prompt.len()),
+        tokens::format_estimate(tokenizer.estimate(prompt)),
+        tokenizer,
+        threshold_kb
+    );
+    for section in sections {
+        output.push_str(&format!(
+            "{:>9}  {}\n",
+            format_size(section.bytes),
+            section.label
+        ));
+        for line in &section.preview {
+            output.push_str(&format!("{:>9}  │ {}\n", "", line));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_summarize_groups_by_source_largest_first() {
+        let prompt = "Review this.\n\nfn main() {}\nfn other() {}\n\n---\nDone.";
+        let mut provenance = ProvenanceMap::default();
+        provenance.push(0, 12, Source::Template { line: Some(1) });
+        provenance.push(12, 14, Source::Separator);
+        provenance.push(
+            14,
+            41,
+            Source::ContextFile {
+                path: PathBuf::from("src/main.rs"),
+            },
+        );
+        provenance.push(41, 48, Source::Template { line: Some(3) });
+
+        let sections = summarize(prompt, &provenance);
+        assert_eq!(
+            sections,
+            vec![
+                Section {
+                    label: "File: src/main.rs".to_string(),
+                    bytes: 27,
+                    preview: vec!["fn main() {}".to_string(), "fn other() {}".to_string()],
+                },
+                Section {
+                    label: "Prompt template".to_string(),
+                    bytes: 19,
+                    preview: vec!["Review this.".to_string()],
+                },
+                Section {
+                    label: "Other".to_string(),
+                    bytes: 3,
+                    preview: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let sections = summarize("a".repeat(2048).as_str(), &ProvenanceMap::default());
+        let summary = format_summary("review", &"a".repeat(2048), &sections, 1, Tokenizer::Cl100k);
+        assert!(summary.starts_with("The prompt for 'review' is 2.0 KB ("));
+        assert!(summary.contains("over the 1 KB confirmation threshold"));
+        assert!(summary.contains("   2.0 KB  Prompt\n"));
+        assert!(summary.contains(&format!("│ {}...\n", "a".repeat(100))));
+    }
+}
@@ -0,0 +1,326 @@
+//! Provenance tracking for rendered prompts.
+//!
+//! A provenance map records which byte ranges of a rendered prompt came from
+//! which source (a template line, a context script, a provider, a context
+//! file). It is written as a JSON sidecar next to `dry-run --output` so that
+//! "where did this sentence come from" can be answered for very large prompts.
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a span of the rendered prompt came from.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Source {
+    /// Text produced by a line of the goal's prompt template.
+    /// `line` is 1-based; it is `None` when the line could not be matched.
+    Template { line: Option<usize> },
+    /// Output of a context script inserted through `{{ Context.<name> }}`.
+    Script { name: String },
+    /// A section returned by an external context provider.
+    Provider { title: String },
+    /// The map of files and symbols added by `--repo-map`.
+    RepoMap,
+    /// The TODO/FIXME/HACK comments added by `--context-todos`.
+    Todos,
+    /// The unified diffs added by `--context-diff`.
+    GitDiff,
+    /// The commands added by `--context-shell-history`.
+    ShellHistory,
+    /// The contents of a file included via `--context`.
+    ContextFile { path: PathBuf },
+    /// Headings, notes and tree generated by claw around context files.
+    ContextHeader,
+    /// Instructions claw adds so the response can be split into the goal's outputs.
+    OutputInstructions,
+    /// Whitespace claw inserts between prompt parts.
+    Separator,
+}
+
+impl Source {
+    /// Returns true for context gathered for the prompt, as opposed to the
+    /// goal's own template text and the instructions claw adds around it.
+    pub fn is_context(&self) -> bool {
+        !matches!(
+            self,
+            Source::Template { .. } | Source::OutputInstructions | Source::Separator
+        )
+    }
+}
+
+/// A single attributed byte range in the rendered prompt.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProvenanceEntry {
+    /// Inclusive start byte offset.
+    pub start: usize,
+    /// Exclusive end byte offset.
+    pub end: usize,
+    pub source: Source,
+}
+
+/// Ordered, non-overlapping byte-range attributions for a rendered prompt.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProvenanceMap {
+    pub entries: Vec<ProvenanceEntry>,
+}
+
+impl ProvenanceMap {
+    /// Records that `start..end` came from `source`, merging with the previous
+    /// entry when it is adjacent and from the same source.
+    pub fn push(&mut self, start: usize, end: usize, source: Source) {
+        if start >= end {
+            return;
+        }
+        if let Some(last) = self.entries.last_mut()
+            && last.end == start
+            && last.source == source
+        {
+            last.end = end;
+            return;
+        }
+        self.entries.push(ProvenanceEntry { start, end, source });
+    }
+
+    /// Total bytes attributed to context (see `Source::is_context`).
+    pub fn context_bytes(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.source.is_context())
+            .map(|entry| (entry.end - entry.start) as u64)
+            .sum()
+    }
+
+    /// Total bytes attributed to context scripts and built-ins.
+    pub fn script_bytes(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.source, Source::Script { .. }))
+            .map(|entry| (entry.end - entry.start) as u64)
+            .sum()
+    }
+
+    /// Appends entries from another map, shifting them by `offset` bytes.
+    pub fn extend_shifted(&mut self, other: ProvenanceMap, offset: usize) {
+        for entry in other.entries {
+            self.push(entry.start + offset, entry.end + offset, entry.source);
+        }
+    }
+
+    /// Writes the map as pretty JSON to `path`.
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize provenance map")?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write provenance map to {}", path.display()))
+    }
+}
+
+/// Returns the sidecar path used for a given prompt output file,
+/// e.g. `prompt.md` becomes `prompt.md.provenance.json`.
+pub fn sidecar_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_os_string();
+    name.push(".provenance.json");
+    PathBuf::from(name)
+}
+
+/// Attributes the output of a rendered Tera template back to its sources.
+///
+/// Tera does not expose a source map, so this works backwards: spans that
+/// match a context script's output are attributed to that script, and every
+/// remaining line is matched against the literal text of the template lines.
+pub fn attribute_template(
+    rendered: &str,
+    template: &str,
+    script_outputs: &HashMap<String, String>,
+) -> ProvenanceMap {
+    // 1. Locate script outputs, longest first so nested matches don't win.
+    let mut scripts: Vec<(&String, &String)> = script_outputs
+        .iter()
+        .filter(|(_, output)| !output.is_empty())
+        .collect();
+    scripts.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+
+    let mut script_spans: Vec<(usize, usize, String)> = Vec::new();
+    for (name, output) in scripts {
+        for (start, _) in rendered.match_indices(output.as_str()) {
+            let end = start + output.len();
+            let overlaps = script_spans.iter().any(|(s, e, _)| start < *e && *s < end);
+            if !overlaps {
+                script_spans.push((start, end, name.clone()));
+            }
+        }
+    }
+    script_spans.sort_by_key(|(start, _, _)| *start);
+
+    // 2. Attribute everything else line by line to template lines.
+    let fragments: Vec<Option<String>> = template.lines().map(literal_fragment).collect();
+    let mut map = ProvenanceMap::default();
+    let mut cursor = 0;
+    let mut next_template_line = 0;
+
+    let mut attribute_gap = |map: &mut ProvenanceMap, from: usize, to: usize| {
+        let mut line_start = from;
+        while line_start < to {
+            let line_end = rendered[line_start..to]
+                .find('\n')
+                .map(|i| line_start + i + 1)
+                .unwrap_or(to);
+            let line = &rendered[line_start..line_end];
+            let matched = match_template_line(line, &fragments, next_template_line);
+            if let Some(index) = matched {
+                next_template_line = index;
+            }
+            map.push(
+                line_start,
+                line_end,
+                Source::Template {
+                    line: matched.map(|i| i + 1),
+                },
+            );
+            line_start = line_end;
+        }
+    };
+
+    for (start, end, name) in script_spans {
+        attribute_gap(&mut map, cursor, start);
+        map.push(start, end, Source::Script { name });
+        cursor = end;
+    }
+    attribute_gap(&mut map, cursor, rendered.len());
+
+    map
+}
+
+/// Returns the longest literal (non-Tera) fragment of a template line.
+fn literal_fragment(line: &str) -> Option<String> {
+    let mut fragments = Vec::new();
+    let mut current = String::new();
+    let mut rest = line;
+    while let Some(open) = rest.find('{') {
+        let tag_close = match rest[open + 1..].chars().next() {
+            Some('{') => "}}",
+            Some('%') => "%}",
+            Some('#') => "#}",
+            _ => {
+                // A lone brace is literal text
+                current.push_str(&rest[..open + 1]);
+                rest = &rest[open + 1..];
+                continue;
+            }
+        };
+        current.push_str(&rest[..open]);
+        fragments.push(std::mem::take(&mut current));
+        rest = match rest[open + 2..].find(tag_close) {
+            Some(close) => &rest[open + 2 + close + 2..],
+            None => "",
+        };
+    }
+    current.push_str(rest);
+    fragments.push(current);
+
+    fragments
+        .into_iter()
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .max_by_key(|f| f.len())
+}
+
+/// Finds the template line whose literal text appears in `rendered_line`,
+/// preferring lines at or after `from` so repeated text keeps document order.
+fn match_template_line(
+    rendered_line: &str,
+    fragments: &[Option<String>],
+    from: usize,
+) -> Option<usize> {
+    let matches = |i: &usize| {
+        fragments[*i]
+            .as_deref()
+            .is_some_and(|f| rendered_line.contains(f))
+    };
+    (from..fragments.len())
+        .find(matches)
+        .or_else(|| (0..from).find(matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_template_lines_and_scripts() {
+        let template = "Review this:\n{{ Context.diff }}\nBe concise.";
+        let rendered = "Review this:\n+added line\nBe concise.";
+        let scripts = HashMap::from([("diff".to_string(), "+added line".to_string())]);
+
+        let map = attribute_template(rendered, template, &scripts);
+
+        let sources: Vec<(&str, &Source)> = map
+            .entries
+            .iter()
+            .map(|e| (&rendered[e.start..e.end], &e.source))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("Review this:\n", &Source::Template { line: Some(1) }),
+                (
+                    "+added line",
+                    &Source::Script {
+                        name: "diff".to_string()
+                    }
+                ),
+                ("\n", &Source::Template { line: None }),
+                ("Be concise.", &Source::Template { line: Some(3) }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literal_fragment_strips_tags() {
+        assert_eq!(
+            literal_fragment("Scope: {{ Args.scope }} (required)"),
+            Some("(required)".to_string())
+        );
+        assert_eq!(literal_fragment("{% if x %}"), None);
+        assert_eq!(literal_fragment("plain {text}"), Some("plain {text}".to_string()));
+    }
+
+    #[test]
+    fn test_push_merges_adjacent_same_source() {
+        let mut map = ProvenanceMap::default();
+        map.push(0, 5, Source::ContextHeader);
+        map.push(5, 9, Source::ContextHeader);
+        map.push(9, 12, Source::Separator);
+        assert_eq!(map.entries.len(), 2);
+        assert_eq!(map.entries[0].end, 9);
+    }
+
+    #[test]
+    fn test_context_bytes_leave_out_template_text() {
+        let mut map = ProvenanceMap::default();
+        map.push(0, 10, Source::Template { line: Some(1) });
+        map.push(
+            10,
+            25,
+            Source::Script {
+                name: "diff".to_string(),
+            },
+        );
+        map.push(25, 27, Source::Separator);
+        map.push(27, 40, Source::GitDiff);
+        map.push(40, 50, Source::OutputInstructions);
+        assert_eq!(map.context_bytes(), 28);
+        assert_eq!(map.script_bytes(), 15);
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("out/prompt.md")),
+            PathBuf::from("out/prompt.md.provenance.json")
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! External context providers.
+//!
+//! Context providers are executables declared in `claw.yaml` that contribute
+//! additional sections to a goal's prompt. claw invokes each provider with a
+//! JSON request on stdin and expects a JSON list of sections on stdout, which
+//! keeps integrations like ticket fetchers or schema dumpers out of the core.
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::ProviderConfig;
+use crate::runner;
+
+/// The request payload sent to a context provider on stdin.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderRequest {
+    /// The CLI name of the goal being rendered.
+    pub goal: String,
+    /// The validated template arguments for the goal.
+    pub args: HashMap<String, String>,
+    /// The directory claw was invoked from.
+    pub cwd: PathBuf,
+}
+
+/// A single section of context returned by a provider.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ContextSection {
+    /// Heading used for the section in the rendered prompt.
+    pub title: String,
+    /// The body of the section, included verbatim.
+    pub content: String,
+}
+
+/// The response payload a context provider writes to stdout.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderResponse {
+    #[serde(default)]
+    sections: Vec<ContextSection>,
+}
+
+/// Defines the contract for contributing context sections to a prompt.
+///
+/// This mirrors `PromptReceiver` on the input side: implementations decide how
+/// the context is fetched, claw only cares about the sections they return.
+pub trait ContextProvider {
+    /// Produces the context sections for the given request.
+    fn provide(&self, request: &ProviderRequest) -> Result<Vec<ContextSection>>;
+
+    /// Returns the name this provider was registered under.
+    fn name(&self) -> &str;
+}
+
+/// Context provider backed by an external executable.
+pub struct ExecutableProvider {
+    name: String,
+    command: String,
+    args: Vec<String>,
+    /// How long the executable may run before it and everything it started
+    /// are killed, as with context scripts.
+    timeout: Option<Duration>,
+}
+
+impl ExecutableProvider {
+    /// Creates a new ExecutableProvider from its configuration entry.
+    pub fn new(config: &ProviderConfig, timeout: Option<Duration>) -> Self {
+        Self {
+            name: config.name.clone(),
+            command: config.command.clone(),
+            args: config.args.clone(),
+            timeout,
+        }
+    }
+}
+
+impl ContextProvider for ExecutableProvider {
+    fn provide(&self, request: &ProviderRequest) -> Result<Vec<ContextSection>> {
+        let executable = which::which(&self.command).with_context(|| {
+            format!(
+                "Context provider '{}' command '{}' not found in your PATH.",
+                self.name, self.command
+            )
+        })?;
+
+        let payload =
+            serde_json::to_vec(request).context("Failed to serialize context provider request")?;
+
+        let mut command = Command::new(&executable);
+        command.args(&self.args);
+        let output = runner::output_within(command, Some(payload), self.timeout)
+            .with_context(|| format!("Failed to run context provider '{}'", self.name))?;
+        let Some(output) = output else {
+            anyhow::bail!(
+                "Context provider '{}' timed out after {}s and was stopped",
+                self.name,
+                self.timeout.unwrap_or_default().as_secs_f64()
+            );
+        };
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Context provider '{}' failed with status {}:\n{}",
+                self.name,
+                output.status,
+                stderr
+            );
+        }
+
+        let response: ProviderResponse = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Context provider '{}' returned invalid JSON", self.name))?;
+
+        Ok(response.sections)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Creates the providers a goal asked for, looked up by name in the configuration.
+/// Each may run for `timeout`, `script_timeout_secs` in `claw.yaml`.
+///
+/// Returns an error if the goal references a provider that is not configured.
+pub fn create_providers(
+    requested: &[String],
+    configured: &[ProviderConfig],
+    timeout: Option<Duration>,
+) -> Result<Vec<Box<dyn ContextProvider>>> {
+    requested
+        .iter()
+        .map(|name| {
+            configured
+                .iter()
+                .find(|p| &p.name == name)
+                .map(|p| Box::new(ExecutableProvider::new(p, timeout)) as Box<dyn ContextProvider>)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Context provider '{}' is not defined in claw.yaml (context_providers)",
+                        name
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Runs each provider in order and collects all returned sections.
+pub fn collect_sections(
+    providers: &[Box<dyn ContextProvider>],
+    request: &ProviderRequest,
+) -> Result<Vec<ContextSection>> {
+    let mut sections = Vec::new();
+    for provider in providers {
+        let provided = provider
+            .provide(request)
+            .with_context(|| format!("Context provider '{}' failed", provider.name()))?;
+        sections.extend(provided);
+    }
+    Ok(sections)
+}
+
+/// Formats provider sections as markdown for inclusion in the LLM prompt.
+pub fn format_sections(sections: &[ContextSection]) -> String {
+    let mut output = String::new();
+    for section in sections {
+        output.push_str(&format!("## {}\n\n", section.title));
+        output.push_str(&section.content);
+        if !section.content.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_provider(name: &str, script: &str) -> ProviderConfig {
+        ProviderConfig {
+            name: name.to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        }
+    }
+
+    fn test_request() -> ProviderRequest {
+        ProviderRequest {
+            goal: "review".to_string(),
+            args: HashMap::from([("ticket".to_string(), "ABC-1".to_string())]),
+            cwd: PathBuf::from("/tmp"),
+        }
+    }
+
+    #[test]
+    fn test_executable_provider_returns_sections() {
+        let config = shell_provider(
+            "echo",
+            r#"cat > /dev/null; echo '{"sections": [{"title": "Ticket", "content": "Fix it"}]}'"#,
+        );
+        let provider = ExecutableProvider::new(&config, None);
+
+        let sections = provider.provide(&test_request()).unwrap();
+        assert_eq!(
+            sections,
+            vec![ContextSection {
+                title: "Ticket".to_string(),
+                content: "Fix it".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_executable_provider_receives_request() {
+        // Echo the goal name back out of the JSON request
+        let config = shell_provider(
+            "reflect",
+            r#"goal=$(sed -n 's/.*"goal":"\([^"]*\)".*/\1/p'); echo "{\"sections\": [{\"title\": \"Goal\", \"content\": \"$goal\"}]}""#,
+        );
+        let provider = ExecutableProvider::new(&config, None);
+
+        let sections = provider.provide(&test_request()).unwrap();
+        assert_eq!(sections[0].content, "review");
+    }
+
+    #[test]
+    fn test_executable_provider_failure() {
+        let config = shell_provider("broken", "cat > /dev/null; echo oops >&2; exit 3");
+        let provider = ExecutableProvider::new(&config, None);
+
+        let err = provider.provide(&test_request()).unwrap_err();
+        assert!(err.to_string().contains("broken"));
+    }
+
+    #[test]
+    fn test_executable_provider_timeout_without_reading_the_request() {
+        // Never reads stdin, so a request bigger than the pipe can't be written
+        let config = shell_provider("stuck", "sleep 10");
+        let provider = ExecutableProvider::new(&config, Some(Duration::from_millis(200)));
+        let request = ProviderRequest {
+            args: HashMap::from([("big".to_string(), "x".repeat(1 << 20))]),
+            ..test_request()
+        };
+
+        let started = std::time::Instant::now();
+        let err = provider.provide(&request).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_create_providers_unknown_name() {
+        let configured = vec![shell_provider("jira", "true")];
+        let result = create_providers(&["schema".to_string()], &configured, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_format_sections() {
+        let sections = vec![ContextSection {
+            title: "Schema".to_string(),
+            content: "CREATE TABLE users;".to_string(),
+        }];
+        let formatted = format_sections(&sections);
+        assert_eq!(formatted, "## Schema\n\nCREATE TABLE users;\n\n");
+    }
+}
@@ -0,0 +1,316 @@
+//! Regex-based repository maps (experimental).
+//!
+//! `claw index` lists every file under the current directory together with
+//! its key symbols (functions, types, classes) and caches the result in
+//! `~/.config/claw/cache/repo_maps/`. `--repo-map` adds a condensed version
+//! to the prompt, so the model sees how a project is laid out without the
+//! full file bodies.
+//!
+//! Symbols are found with per-language patterns rather than a real parser:
+//! they miss some declarations, but need no C toolchain and work on files
+//! that don't compile.
+
+use anyhow::{Context as AnyhowContext, Result};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::config::ConfigPaths;
+use crate::digest;
+use crate::history;
+use crate::script_cache;
+
+/// Files larger than this are listed without symbols.
+const MAX_INDEXED_FILE_BYTES: u64 = 512 * 1024;
+
+/// At most this many symbols are shown per file in the prompt.
+const MAX_SYMBOLS_PER_FILE: usize = 40;
+
+/// A declaration found in a source file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Symbol {
+    /// The declaration keyword, e.g. `fn`, `class` or `struct`.
+    pub kind: String,
+    pub name: String,
+    /// 1-based line of the declaration.
+    pub line: usize,
+}
+
+/// One file of the index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexedFile {
+    /// Path relative to the indexed directory.
+    pub path: PathBuf,
+    /// Number of lines, or `None` if the file wasn't read.
+    pub lines: Option<usize>,
+    pub symbols: Vec<Symbol>,
+}
+
+/// The files and symbols of a directory tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoIndex {
+    pub root: PathBuf,
+    /// Unix timestamp (seconds) when the index was built.
+    pub built_at: u64,
+    pub files: Vec<IndexedFile>,
+}
+
+/// Patterns for one language: the first capture group is the kind, the
+/// second the name.
+struct Language {
+    extensions: &'static [&'static str],
+    patterns: Vec<Regex>,
+}
+
+static LANGUAGES: LazyLock<Vec<Language>> = LazyLock::new(|| {
+    let language = |extensions, patterns: &[&str]| Language {
+        extensions,
+        patterns: patterns.iter().map(|p| Regex::new(p).unwrap()).collect(),
+    };
+    vec![
+        language(
+            &["rs"],
+            &[
+                r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern\s+\S+)\s+)*(fn|struct|enum|trait|mod|type|union)\s+([A-Za-z_][A-Za-z0-9_]*)",
+                r"^\s*(impl)(?:<[^>]*>)?\s+([^{]+?)\s*(?:where\b.*)?\{?\s*$",
+                r"^\s*(macro_rules!)\s*([A-Za-z_][A-Za-z0-9_]*)",
+            ],
+        ),
+        language(
+            &["py"],
+            &[r"^\s*(?:async\s+)?(def|class)\s+([A-Za-z_][A-Za-z0-9_]*)"],
+        ),
+        language(
+            &["js", "jsx", "mjs", "cjs", "ts", "tsx"],
+            &[
+                r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(function|class|interface|type|enum)\s*\*?\s*([A-Za-z_$][A-Za-z0-9_$]*)",
+                r"^\s*(?:export\s+)?(const)\s+([A-Za-z_$][A-Za-z0-9_$]*)\s*=\s*(?:async\s*)?(?:\([^)]*\)|[A-Za-z_$][A-Za-z0-9_$]*)\s*=>",
+            ],
+        ),
+        language(
+            &["go"],
+            &[
+                r"^(func)\s+(?:\([^)]*\)\s*)?([A-Za-z_][A-Za-z0-9_]*)",
+                r"^(type)\s+([A-Za-z_][A-Za-z0-9_]*)",
+            ],
+        ),
+        language(
+            &["java", "kt", "cs", "scala", "swift"],
+            &[
+                r"^\s*(?:(?:public|private|protected|internal|abstract|final|static|sealed|data|open|partial)\s+)*(class|interface|enum|record|object|struct|protocol)\s+([A-Za-z_][A-Za-z0-9_]*)",
+                r"^\s*(?:(?:public|private|protected|internal|override|suspend|static)\s+)*(fun|func)\s+([A-Za-z_][A-Za-z0-9_]*)",
+            ],
+        ),
+        language(
+            &["rb"],
+            &[r"^\s*(def|class|module)\s+([A-Za-z_][A-Za-z0-9_.:!?]*)"],
+        ),
+        language(
+            &["c", "h", "cc", "cpp", "hpp"],
+            &[r"^\s*(?:typedef\s+)?(struct|class|enum|union|namespace)\s+([A-Za-z_][A-Za-z0-9_]*)"],
+        ),
+    ]
+});
+
+/// Finds the declarations in `content`, a file with the given extension.
+fn extract_symbols(extension: &str, content: &str) -> Vec<Symbol> {
+    let Some(language) = LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension))
+    else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let captures = language
+                .patterns
+                .iter()
+                .find_map(|pattern| pattern.captures(line))?;
+            Some(Symbol {
+                kind: captures[1].to_string(),
+                name: captures[2].trim().to_string(),
+                line: i + 1,
+            })
+        })
+        .collect()
+}
+
+/// Indexes the files under `root`, skipping gitignored files and the given
+/// directories and extensions.
+pub fn build(root: &Path, excluded_dirs: &[String], excluded_extensions: &[String]) -> RepoIndex {
+    let excluded_dirs = excluded_dirs.to_vec();
+    let walker = WalkBuilder::new(root)
+        .filter_entry(move |entry| {
+            !(entry.file_type().is_some_and(|t| t.is_dir())
+                && excluded_dirs.contains(&entry.file_name().to_string_lossy().to_string()))
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if excluded_extensions.contains(&extension) {
+            continue;
+        }
+
+        let small = entry
+            .metadata()
+            .is_ok_and(|m| m.len() <= MAX_INDEXED_FILE_BYTES);
+        let content = if small {
+            fs::read_to_string(path).ok()
+        } else {
+            None
+        };
+        files.push(IndexedFile {
+            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            lines: content.as_ref().map(|c| c.lines().count()),
+            symbols: content
+                .map(|c| extract_symbols(&extension, &c))
+                .unwrap_or_default(),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    RepoIndex {
+        root: root.to_path_buf(),
+        built_at: history::now_secs(),
+        files,
+    }
+}
+
+/// The cache file for the index of `root`.
+fn index_path(root: &Path) -> Result<Option<PathBuf>> {
+    let key = digest::sha256_hex(root.to_string_lossy().as_bytes());
+    let key = &key[..16];
+    Ok(ConfigPaths::new()?.global.map(|dir| {
+        dir.join("cache")
+            .join("repo_maps")
+            .join(format!("{}.json", key))
+    }))
+}
+
+/// Saves `index` to the cache and returns where it was written.
+pub fn save(index: &RepoIndex) -> Result<PathBuf> {
+    let path =
+        index_path(&index.root)?.context("No global config directory to store the index in")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string(index).context("Failed to serialize the index")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Loads the cached index of `root`, if `claw index` was run there.
+pub fn load(root: &Path) -> Option<RepoIndex> {
+    let path = index_path(root).ok().flatten()?;
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Formats the index as a prompt section: one line per file, followed by
+/// its symbols.
+pub fn format(index: &RepoIndex) -> String {
+    let mut output = String::from("## Repository Map\n\n");
+    output.push_str(&format!(
+        "Files under the working directory with their main declarations (indexed {}).\n\n```\n",
+        script_cache::format_age(index.built_at, history::now_secs())
+    ));
+    for file in &index.files {
+        match file.lines {
+            Some(lines) => output.push_str(&format!("{} ({} lines)\n", file.path.display(), lines)),
+            None => output.push_str(&format!("{}\n", file.path.display())),
+        }
+        for symbol in file.symbols.iter().take(MAX_SYMBOLS_PER_FILE) {
+            output.push_str(&format!(
+                "  {}: {} {}\n",
+                symbol.line, symbol.kind, symbol.name
+            ));
+        }
+        if file.symbols.len() > MAX_SYMBOLS_PER_FILE {
+            output.push_str(&format!(
+                "  ... {} more\n",
+                file.symbols.len() - MAX_SYMBOLS_PER_FILE
+            ));
+        }
+    }
+    output.push_str("```\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn names(extension: &str, content: &str) -> Vec<String> {
+        extract_symbols(extension, content)
+            .into_iter()
+            .map(|s| format!("{}:{} {}", s.line, s.kind, s.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_symbols_by_language() {
+        let rust = "use std::fs;\n\npub(crate) async fn load() {}\nstruct Config {\n}\nimpl<T> Display for Wrapper<T> {\n    fn fmt(&self) {}\n}\n";
+        assert_eq!(
+            names("rs", rust),
+            vec![
+                "3:fn load",
+                "4:struct Config",
+                "6:impl Display for Wrapper<T>",
+                "7:fn fmt"
+            ]
+        );
+
+        let python = "import os\n\nclass Loader:\n    async def fetch(self):\n        pass\n";
+        assert_eq!(names("py", python), vec!["3:class Loader", "4:def fetch"]);
+
+        let typescript = "export default class App {}\nexport const handler = async (req) => {};\nconst x = 1;\n";
+        assert_eq!(
+            names("ts", typescript),
+            vec!["1:class App", "2:const handler"]
+        );
+
+        let go = "func (s *Server) Start() error {\ntype Server struct {\n";
+        assert_eq!(names("go", go), vec!["1:func Start", "2:type Server"]);
+
+        assert!(names("md", "# fn heading").is_empty());
+    }
+
+    #[test]
+    fn test_build_and_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+        fs::write(root.join("README.md"), "# Demo\n").unwrap();
+        fs::write(root.join("app.exe"), "MZ").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("target/out.rs"), "fn built() {}\n").unwrap();
+
+        let index = build(root, &["target".to_string()], &["exe".to_string()]);
+        let paths: Vec<_> = index.files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("README.md"), PathBuf::from("src/lib.rs")]
+        );
+
+        let map = format(&index);
+        assert!(map.contains("src/lib.rs (1 lines)\n  1: fn run\n"));
+        assert!(map.contains("README.md (1 lines)\n"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Post-processing of captured LLM responses.
+//!
+//! A goal can define a `response_template:` that wraps the model's answer
+//! before it is saved, so artifacts such as PR notes files always share the
+//! same header, frontmatter or footer.
+//!
+//! A goal can also declare several named `outputs:`. claw then asks the model
+//! to start each one with a marker line and splits the captured response
+//! into separate files.
+//!
+//! Goals consumed by other tools can declare `assert_response:` checks. When
+//! a captured response fails them, claw sends the prompt again with the
+//! problems spelled out, a bounded number of times.
+//!
+//! A goal's `post_process:` commands (e.g. `jq .`, `prettier`) each receive
+//! the response on stdin, and the last one's output becomes the response.
+
+use anyhow::{Context as AnyhowContext, Result};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use tera::Context;
+
+use crate::dates;
+use crate::prompt_core::ClawMeta;
+
+/// Renders `template` with the model response and the run's details.
+///
+/// The template can use `{{ Response }}`, `{{ Goal }}`, `{{ Args.<name> }}`
+/// and `{{ Claw.<field> }}`, plus Tera built-ins such as
+/// `{{ now() | date(format="%Y-%m-%d") }}` (see [`crate::dates`]).
+pub fn apply_response_template(
+    template: &str,
+    response: &str,
+    claw: &ClawMeta,
+    args: &HashMap<String, String>,
+) -> Result<String> {
+    let mut context = Context::new();
+    context.insert("Response", response);
+    context.insert("Goal", &claw.goal);
+    context.insert("Args", args);
+    context.insert("Claw", claw);
+
+    dates::render_str(template, &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render response_template: {}", e))
+}
+
+/// Rewrites a captured response file through the goal's response template.
+pub fn apply_to_file(
+    path: &Path,
+    template: &str,
+    claw: &ClawMeta,
+    args: &HashMap<String, String>,
+) -> Result<()> {
+    let response = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read captured response {}", path.display()))?;
+    let wrapped = apply_response_template(template, &response, claw, args)?;
+    fs::write(path, wrapped)
+        .with_context(|| format!("Failed to write response to {}", path.display()))
+}
+
+/// Pipes `response` through each shell command in turn, feeding each one's
+/// output to the next, and returns the last one's output.
+pub fn post_process(response: &str, commands: &[String]) -> Result<String> {
+    let mut text = response.to_string();
+    for command in commands {
+        text = pipe_through(command, text)?;
+    }
+    Ok(text)
+}
+
+/// Rewrites a captured response file through the goal's post_process
+/// commands and returns the result.
+pub fn post_process_file(path: &Path, commands: &[String]) -> Result<String> {
+    let response = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read captured response {}", path.display()))?;
+    let processed = post_process(&response, commands)?;
+    fs::write(path, &processed)
+        .with_context(|| format!("Failed to write response to {}", path.display()))?;
+    Ok(processed)
+}
+
+/// Runs `sh -c command` with `input` on stdin and returns its stdout. Its
+/// stderr stays on the terminal, so the command's own errors show.
+fn pipe_through(command: &str, input: String) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run post_process command `{}`", command))?;
+
+    // Write from another thread, so a command that answers before reading
+    // everything can't fill its stdout pipe while we're blocked on stdin
+    let mut stdin = child.stdin.take().context("post_process command has no stdin")?;
+    // A command may exit without reading its input, e.g. `echo done`
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to wait for post_process command `{}`", command))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        anyhow::bail!("post_process command `{}` failed with {}", command, output.status);
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("post_process command `{}` wrote invalid UTF-8", command))
+}
+
+/// The line that starts the named output section in a response.
+fn output_marker(name: &str) -> String {
+    format!("<<<claw-output:{}>>>", name)
+}
+
+/// Instructions appended to the prompt so the response can be split into
+/// the goal's outputs.
+pub fn output_instructions(outputs: &BTreeMap<String, String>) -> String {
+    let mut text = String::from(
+        "## Response Format\n\n\
+         Structure your response as the sections below. Start each section with its \
+         marker line, exactly as written, followed by the section's content. \
+         Include every section.\n\n",
+    );
+    for (name, path) in outputs {
+        text.push_str(&format!("{}\n(the content for {})\n\n", output_marker(name), path));
+    }
+    text
+}
+
+/// Splits a response into the goal's named outputs.
+///
+/// Text before the first marker is dropped. Fails if a section is missing,
+/// so no file is written from an incomplete response.
+pub fn split_outputs(
+    response: &str,
+    outputs: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let markers: HashMap<String, &String> = outputs
+        .keys()
+        .map(|name| (output_marker(name), name))
+        .collect();
+
+    let mut sections: BTreeMap<String, String> = BTreeMap::new();
+    let mut current: Option<&String> = None;
+    for line in response.lines() {
+        // Tolerate markers wrapped in backticks or followed by other text
+        let marker = line
+            .trim()
+            .trim_start_matches('`')
+            .split_inclusive(">>>")
+            .next()
+            .unwrap_or_default();
+        if let Some(name) = markers.get(marker) {
+            current = Some(name);
+            sections.entry(name.to_string()).or_default();
+            continue;
+        }
+        if let Some(name) = current
+            && let Some(section) = sections.get_mut(name.as_str())
+        {
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+
+    let missing: Vec<&str> = outputs
+        .keys()
+        .filter(|name| !sections.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The response is missing output section(s): {}. No output files were written.",
+            missing.join(", ")
+        );
+    }
+
+    Ok(sections
+        .into_iter()
+        .map(|(name, content)| (name, format!("{}\n", content.trim())))
+        .collect())
+}
+
+/// Splits a captured response file into the goal's outputs and writes each
+/// one to its path.
+pub fn write_outputs(path: &Path, outputs: &BTreeMap<String, String>) -> Result<()> {
+    let response = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read captured response {}", path.display()))?;
+    let sections = split_outputs(&response, outputs)?;
+    for (name, content) in &sections {
+        let target = Path::new(&outputs[name]);
+        if let Some(parent) = target.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(target, content)
+            .with_context(|| format!("Failed to write output '{}' to {}", name, target.display()))?;
+        eprintln!("✓ Wrote {} to {}", name, target.display());
+    }
+    Ok(())
+}
+
+/// Checks a captured response must pass, from a goal's `assert_response`.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseAssertions {
+    /// Text the response must contain, e.g. `"## Summary"`.
+    #[serde(default)]
+    pub contains: Vec<String>,
+
+    /// Text the response must not contain.
+    #[serde(default)]
+    pub not_contains: Vec<String>,
+
+    /// The most words the response may have.
+    #[serde(default)]
+    pub max_words: Option<usize>,
+
+    /// The fewest words the response may have.
+    #[serde(default)]
+    pub min_words: Option<usize>,
+
+    /// How many times to ask again when the response fails (default: 2).
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl ResponseAssertions {
+    /// Returns one description per failed check, e.g. `it must contain
+    /// "## Summary"`.
+    pub fn check(&self, response: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+        for text in &self.contains {
+            if !response.contains(text.as_str()) {
+                failures.push(format!("it must contain \"{}\"", text));
+            }
+        }
+        for text in &self.not_contains {
+            if response.contains(text.as_str()) {
+                failures.push(format!("it must not contain \"{}\"", text));
+            }
+        }
+        let words = response.split_whitespace().count();
+        if let Some(max) = self.max_words
+            && words > max
+        {
+            failures.push(format!("it must be at most {} words long (it had {})", max, words));
+        }
+        if let Some(min) = self.min_words
+            && words < min
+        {
+            failures.push(format!("it must be at least {} words long (it had {})", min, words));
+        }
+        failures
+    }
+}
+
+/// Text appended to the prompt when asking again after a response failed
+/// its assertions.
+pub fn corrective_instructions(failures: &[String]) -> String {
+    let mut text = String::from(
+        "\n\n## Correction\n\nA previous answer to this prompt was rejected because:\n",
+    );
+    for failure in failures {
+        text.push_str(&format!("- {}\n", failure));
+    }
+    text.push_str("\nAnswer again, following all the instructions above and these requirements.\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_process_chains_commands() {
+        let commands = vec!["tr a-z A-Z".to_string(), "sed 's/^/> /'".to_string()];
+        assert_eq!(post_process("hello\n", &commands).unwrap(), "> HELLO\n");
+        assert_eq!(post_process("as is", &[]).unwrap(), "as is");
+
+        let err = post_process("x", &["cat; exit 4".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("`cat; exit 4` failed"));
+    }
+
+    #[test]
+    fn test_response_assertions() {
+        let assertions = ResponseAssertions {
+            contains: vec!["## Summary".to_string()],
+            not_contains: vec!["As an AI".to_string()],
+            max_words: Some(5),
+            min_words: Some(2),
+            retries: None,
+        };
+        assert!(assertions.check("## Summary\nAll good.").is_empty());
+        assert_eq!(
+            assertions.check("As an AI I think this is fine overall"),
+            vec![
+                "it must contain \"## Summary\"",
+                "it must not contain \"As an AI\"",
+                "it must be at most 5 words long (it had 9)",
+            ]
+        );
+        let min_words = ResponseAssertions {
+            min_words: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            min_words.check("## Summary"),
+            vec!["it must be at least 3 words long (it had 2)"]
+        );
+        assert!(
+            corrective_instructions(&["it must contain \"x\"".to_string()])
+                .contains("- it must contain \"x\"\n")
+        );
+    }
+
+    #[test]
+    fn test_response_template_wraps_output() {
+        let template = "---\ngoal: {{ Goal }}\nscope: {{ Args.scope }}\n---\n{{ Response }}";
+        let args = HashMap::from([("scope".to_string(), "auth".to_string())]);
+
+        let wrapped =
+            apply_response_template(
+                template,
+                "Fixed <login> bug",
+                &ClawMeta::new("pr-notes", 0),
+                &args,
+            )
+            .unwrap();
+        assert_eq!(
+            wrapped,
+            "---\ngoal: pr-notes\nscope: auth\n---\nFixed <login> bug"
+        );
+    }
+
+    fn outputs() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("spec".to_string(), "spec.md".to_string()),
+            ("tasks".to_string(), "tasks.md".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_split_outputs() {
+        let response = "Sure, here you go.\n<<<claw-output:spec>>>\n# Spec\n\nDetails\n\n  `<<<claw-output:tasks>>>` (tasks.md)\n- [ ] one\n";
+        let sections = split_outputs(response, &outputs()).unwrap();
+        assert_eq!(sections["spec"], "# Spec\n\nDetails\n");
+        assert_eq!(sections["tasks"], "- [ ] one\n");
+
+        let err = split_outputs("<<<claw-output:spec>>>\nonly spec", &outputs()).unwrap_err();
+        assert!(err.to_string().contains("missing output section(s): tasks"));
+    }
+
+    #[test]
+    fn test_response_template_errors_are_reported() {
+        let err = apply_response_template("{{ Missing }}", "", &ClawMeta::new("g", 0), &HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("response_template"));
+    }
+}
@@ -0,0 +1,58 @@
+//! The JSON summary `--json-status` writes after a run, for CI pipelines
+//! that wrap claw and need more than its exit code.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::history::RunStatus;
+
+/// What a run did and how long it took.
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub goal: String,
+    /// The run's ID, as in history; absent when the prompt never rendered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub status: RunStatus,
+    /// The code claw exits with.
+    pub exit_code: i32,
+    pub duration_ms: u128,
+    pub prompt_bytes: usize,
+    /// The prompt's size in tokens, as estimated for `tokenizer` in claw.yaml.
+    pub prompt_tokens: usize,
+    pub receiver: String,
+    /// Each context script that ran, in order.
+    pub scripts: Vec<ScriptTiming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How long one context script took.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptTiming {
+    pub name: String,
+    pub duration_ms: u128,
+}
+
+impl ScriptTiming {
+    pub fn new(name: &str, duration: Duration) -> ScriptTiming {
+        ScriptTiming {
+            name: name.to_string(),
+            duration_ms: duration.as_millis(),
+        }
+    }
+}
+
+/// Writes `report` as JSON to `destination`: one line on stderr for `-`,
+/// or else a pretty-printed file.
+pub fn write(report: &RunReport, destination: &Path) -> Result<()> {
+    if destination == Path::new("-") {
+        eprintln!("{}", serde_json::to_string(report)?);
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(report)?;
+    std::fs::write(destination, format!("{}\n", json))
+        .with_context(|| format!("Failed to write the run status to {}", destination.display()))
+}
@@ -0,0 +1,628 @@
+//! Live run view: a split-pane TUI shown while the LLM runs.
+//!
+//! The left pane lists what claw did to build the prompt (context scripts,
+//! providers, context files, prompt size) and the state of the LLM process;
+//! the right pane streams everything the LLM writes. The run can be
+//! cancelled, and its transcript saved or copied, without leaving the view.
+
+use anyhow::{Context as AnyhowContext, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::ClawConfig;
+use crate::history;
+use crate::provenance::{ProvenanceMap, Source};
+use crate::runner::{self, ReceiverOptions};
+use crate::tokens::{self, Tokenizer};
+
+/// State of the LLM process as shown in the left pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunState {
+    Running,
+    Cancelling,
+    Finished,
+    Failed,
+}
+
+/// Control flow result from input handling.
+enum ControlFlow {
+    Continue,
+    Close,
+}
+
+/// Application state for the run view.
+struct RunViewApp {
+    goal_name: String,
+    /// Label and detail for each step claw took to build the prompt.
+    phases: Vec<(String, String)>,
+    state: RunState,
+    started_at: Instant,
+    /// How long the LLM ran, once it is done.
+    elapsed: Option<Duration>,
+    /// Everything the LLM has written so far.
+    transcript: Vec<u8>,
+    /// First visible output line, when not following the end.
+    scroll: usize,
+    /// Whether the output pane keeps showing the latest lines.
+    follow: bool,
+    /// Feedback for the last key pressed, e.g. where the transcript was saved.
+    status: Option<String>,
+}
+
+impl RunViewApp {
+    fn new(goal_name: &str, phases: Vec<(String, String)>) -> Self {
+        Self {
+            goal_name: goal_name.to_string(),
+            phases,
+            state: RunState::Running,
+            started_at: Instant::now(),
+            elapsed: None,
+            transcript: Vec::new(),
+            scroll: 0,
+            follow: true,
+            status: None,
+        }
+    }
+
+    fn transcript_text(&self) -> String {
+        String::from_utf8_lossy(&self.transcript).into_owned()
+    }
+
+    fn finish(&mut self, succeeded: bool) {
+        self.elapsed = Some(self.started_at.elapsed());
+        if self.state != RunState::Cancelling {
+            self.state = if succeeded {
+                RunState::Finished
+            } else {
+                RunState::Failed
+            };
+        }
+    }
+
+    fn scroll_up(&mut self, lines: usize, total: usize, height: usize) {
+        if self.follow {
+            self.scroll = total.saturating_sub(height);
+            self.follow = false;
+        }
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    fn scroll_down(&mut self, lines: usize, total: usize, height: usize) {
+        if self.follow {
+            return;
+        }
+        let max_scroll = total.saturating_sub(height);
+        self.scroll = (self.scroll + lines).min(max_scroll);
+        if self.scroll == max_scroll {
+            self.follow = true;
+        }
+    }
+
+    /// The first output line to show in a pane `height` lines tall.
+    fn first_visible_line(&self, total: usize, height: usize) -> usize {
+        let max_scroll = total.saturating_sub(height);
+        if self.follow {
+            max_scroll
+        } else {
+            self.scroll.min(max_scroll)
+        }
+    }
+}
+
+/// Summarizes how the prompt was built, from its provenance map.
+fn phases(
+    prompt: &str,
+    provenance: &ProvenanceMap,
+    tokenizer: Tokenizer,
+) -> Vec<(String, String)> {
+    let mut scripts = BTreeSet::new();
+    let mut providers = BTreeSet::new();
+    let mut files = BTreeSet::new();
+    for entry in &provenance.entries {
+        match &entry.source {
+            Source::Script { name } => {
+                scripts.insert(name.as_str());
+            }
+            Source::Provider { title } => {
+                providers.insert(title.as_str());
+            }
+            Source::ContextFile { path } => {
+                files.insert(path);
+            }
+            _ => {}
+        }
+    }
+
+    let list = |names: BTreeSet<&str>| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.into_iter().collect::<Vec<_>>().join(", ")
+        }
+    };
+    let mut phases = vec![
+        ("Context scripts".to_string(), list(scripts)),
+        ("Providers".to_string(), list(providers)),
+        ("Context files".to_string(), files.len().to_string()),
+    ];
+    phases.push((
+        "Prompt".to_string(),
+        format!(
+            "{:.1} KB, {} lines, {} ({})",
+            prompt.len() as f64 / 1024.0,
+            prompt.lines().count(),
+            tokens::format_estimate(tokenizer.estimate(prompt)),
+            tokenizer
+        ),
+    ));
+    phases
+}
+
+/// Splits LLM output into lines of at most `width` characters for display.
+///
+/// Terminal escape sequences are dropped, and a carriage return keeps only
+/// the text after it, the way a progress line would look in a terminal.
+fn display_lines(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let text = strip_escapes(text);
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        let line = line
+            .rsplit('\r')
+            .next()
+            .unwrap_or(line)
+            .replace('\t', "    ");
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(width) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    // A trailing newline doesn't start a visible line
+    if text.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+/// Removes ANSI escape sequences (colors, cursor movement) from `text`.
+fn strip_escapes(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    output
+}
+
+/// Encodes `data` as standard base64, for the OSC 52 clipboard sequence.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// Copies `text` to the clipboard through the terminal (OSC 52), which works
+/// locally and over SSH in most modern terminals.
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Writes the transcript to `<goal>-<timestamp>.transcript.md` in the
+/// current directory.
+fn save_transcript(app: &RunViewApp) -> Result<PathBuf> {
+    let path = PathBuf::from(format!(
+        "{}-{}.transcript.md",
+        app.goal_name,
+        history::now_secs()
+    ));
+    fs::write(&path, &app.transcript)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Sends `prompt` to the configured LLM while showing the live run view,
+/// and returns the LLM's result once the view is closed.
+///
+/// The Ctrl-C handler must already be installed; inside the view, Ctrl-C
+/// cancels the run like `c`.
+pub fn run(
+    goal_name: &str,
+    claw_config: &ClawConfig,
+    prompt: &str,
+    provenance: &ProvenanceMap,
+    receiver_options: &ReceiverOptions,
+) -> Result<()> {
+    let (sender, output) = mpsc::channel();
+    let (done_sender, done) = mpsc::channel();
+    let options = ReceiverOptions {
+        stream: Some(sender),
+        ..receiver_options.clone()
+    };
+    let mut app = RunViewApp::new(
+        goal_name,
+        phases(prompt, provenance, Tokenizer::for_config(claw_config)),
+    );
+
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
+
+    let (view_result, run_result) = thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            let receiver = runner::create_receiver(claw_config, &options);
+            let result = receiver.send_prompt(prompt);
+            let _ = done_sender.send(result.is_ok());
+            result
+        });
+        let view_result = run_app(&mut terminal, &mut app, &output, &done);
+        if view_result.is_err() {
+            // Don't leave the LLM running behind a broken view
+            runner::cancel();
+        }
+        let run_result = worker
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("LLM thread panicked")));
+        (view_result, run_result)
+    });
+
+    // Restore terminal
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    terminal.show_cursor().context("Failed to show cursor")?;
+
+    view_result?;
+    run_result
+}
+
+/// Main event loop; returns when the user closes the view after the run, or
+/// once a cancelled run has stopped.
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut RunViewApp,
+    output: &Receiver<Vec<u8>>,
+    done: &Receiver<bool>,
+) -> Result<()> {
+    loop {
+        // The LLM's output has been fully read by the time it reports done
+        let finished = done.try_recv().ok();
+        while let Ok(chunk) = output.try_recv() {
+            app.transcript.extend_from_slice(&chunk);
+        }
+        if let Some(succeeded) = finished {
+            app.finish(succeeded);
+            if app.state == RunState::Cancelling {
+                return Ok(());
+            }
+        }
+
+        let size = terminal.size()?;
+        let (_, output_area, _) = layout(Rect::new(0, 0, size.width, size.height));
+        let width = output_area.width.saturating_sub(2) as usize;
+        let height = output_area.height.saturating_sub(2) as usize;
+        let total = display_lines(&app.transcript_text(), width).len();
+        terminal.draw(|f| render_ui(f, app))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && let ControlFlow::Close = handle_input(key, app, total, height)?
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Splits the screen into the phases pane, the output pane and the footer.
+fn layout(area: Rect) -> (Rect, Rect, Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(area);
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(chunks[0]);
+    (panes[0], panes[1], chunks[1])
+}
+
+/// Handles a key press; `total` and `height` describe the output pane.
+fn handle_input(
+    key: KeyEvent,
+    app: &mut RunViewApp,
+    total: usize,
+    height: usize,
+) -> Result<ControlFlow> {
+    let running = app.elapsed.is_none();
+    app.status = None;
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if running {
+                runner::cancel();
+                app.state = RunState::Cancelling;
+            } else {
+                return Ok(ControlFlow::Close);
+            }
+        }
+        KeyCode::Char('c') if running => {
+            runner::cancel();
+            app.state = RunState::Cancelling;
+        }
+        KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => {
+            if running {
+                app.status = Some("Still running. Press c to cancel.".to_string());
+            } else {
+                return Ok(ControlFlow::Close);
+            }
+        }
+        KeyCode::Char('s') => {
+            app.status = Some(match save_transcript(app) {
+                Ok(path) => format!("Saved transcript to {}", path.display()),
+                Err(e) => format!("{:#}", e),
+            });
+        }
+        KeyCode::Char('y') => {
+            let text = app.transcript_text();
+            app.status = Some(match copy_to_clipboard(&text) {
+                Ok(()) => format!(
+                    "Copied {} characters to the clipboard",
+                    text.chars().count()
+                ),
+                Err(e) => format!("Failed to copy: {:#}", e),
+            });
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_up(1, total, height),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_down(1, total, height),
+        KeyCode::PageUp => app.scroll_up(height.max(1), total, height),
+        KeyCode::PageDown => app.scroll_down(height.max(1), total, height),
+        KeyCode::End | KeyCode::Char('G') => app.follow = true,
+        _ => {}
+    }
+    Ok(ControlFlow::Continue)
+}
+
+/// Renders the phases pane, the output pane and the help footer.
+fn render_ui(frame: &mut Frame, app: &RunViewApp) {
+    let (phases_area, output_area, help_area) = layout(frame.area());
+    render_phases(frame, app, phases_area);
+    render_output(frame, app, output_area);
+    render_help_footer(frame, app, help_area);
+}
+
+fn render_phases(frame: &mut Frame, app: &RunViewApp, area: Rect) {
+    let orange = Color::Rgb(255, 165, 0);
+    let mut lines = Vec::new();
+    for (label, detail) in &app.phases {
+        lines.push(Line::from(Span::styled(
+            format!("✓ {}", label),
+            Style::default().fg(Color::Green),
+        )));
+        lines.push(Line::from(format!("  {}", detail)));
+    }
+
+    let elapsed = app
+        .elapsed
+        .unwrap_or_else(|| app.started_at.elapsed())
+        .as_secs();
+    let (symbol, state, color) = match app.state {
+        RunState::Running => ("▶", "running", orange),
+        RunState::Cancelling => ("■", "cancelling", Color::Yellow),
+        RunState::Finished => ("✓", "finished", Color::Green),
+        RunState::Failed => ("✗", "failed", Color::Red),
+    };
+    lines.push(Line::from(Span::styled(
+        format!("{} LLM", symbol),
+        Style::default().fg(color),
+    )));
+    lines.push(Line::from(format!("  {} ({}s)", state, elapsed)));
+    lines.push(Line::from(format!(
+        "  {:.1} KB received",
+        app.transcript.len() as f64 / 1024.0
+    )));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!("claw: {}", app.goal_name))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_output(frame: &mut Frame, app: &RunViewApp, area: Rect) {
+    let width = area.width.saturating_sub(2) as usize;
+    let height = area.height.saturating_sub(2) as usize;
+    let lines = display_lines(&app.transcript_text(), width);
+    let first = app.first_visible_line(lines.len(), height);
+
+    let visible: Vec<Line> = lines
+        .into_iter()
+        .skip(first)
+        .take(height)
+        .map(Line::from)
+        .collect();
+    let title = if app.follow {
+        "LLM output".to_string()
+    } else {
+        format!("LLM output (line {}) - End to follow", first + 1)
+    };
+    let paragraph = Paragraph::new(visible)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_help_footer(frame: &mut Frame, app: &RunViewApp, area: Rect) {
+    let orange = Color::Rgb(255, 165, 0);
+    let line = match &app.status {
+        Some(status) => Line::from(Span::raw(status.clone())),
+        None => Line::from(vec![
+            Span::styled("↑/↓ or j/k", Style::default().fg(orange)),
+            Span::raw(": Scroll  "),
+            Span::styled("c", Style::default().fg(orange)),
+            Span::raw(": Cancel  "),
+            Span::styled("s", Style::default().fg(orange)),
+            Span::raw(": Save transcript  "),
+            Span::styled("y", Style::default().fg(orange)),
+            Span::raw(": Copy  "),
+            Span::styled("Esc/q", Style::default().fg(orange)),
+            Span::raw(": Close"),
+        ]),
+    };
+
+    let help = Paragraph::new(vec![line])
+        .block(Block::default().borders(Borders::ALL).title("Help"))
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(help, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_phases_summarize_provenance() {
+        let mut provenance = ProvenanceMap::default();
+        provenance.push(
+            0,
+            5,
+            Source::Script {
+                name: "diff".to_string(),
+            },
+        );
+        provenance.push(5, 10, Source::Template { line: Some(1) });
+        provenance.push(
+            10,
+            15,
+            Source::Script {
+                name: "branch".to_string(),
+            },
+        );
+        provenance.push(
+            15,
+            20,
+            Source::ContextFile {
+                path: PathBuf::from("src/main.rs"),
+            },
+        );
+
+        let phases = phases("line one\nline two", &provenance, Tokenizer::Cl100k);
+        assert_eq!(
+            phases[0],
+            ("Context scripts".to_string(), "branch, diff".to_string())
+        );
+        assert_eq!(phases[1], ("Providers".to_string(), "none".to_string()));
+        assert_eq!(phases[2], ("Context files".to_string(), "1".to_string()));
+        assert_eq!(
+            phases[3],
+            (
+                "Prompt".to_string(),
+                "0.0 KB, 2 lines, ~5 tokens (cl100k)".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_lines_wraps_and_cleans_output() {
+        let text = "\x1b[32mgreen\x1b[0m text\nloading 10%\rloading 100%\nabcdefgh\n";
+        assert_eq!(
+            display_lines(text, 5),
+            vec!["green", " text", "loadi", "ng 10", "0%", "abcde", "fgh"]
+        );
+        assert!(display_lines("", 10).len() == 1);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_scrolling_stops_and_resumes_following() {
+        let mut app = RunViewApp::new("review", Vec::new());
+        assert_eq!(app.first_visible_line(100, 10), 90);
+
+        app.scroll_up(5, 100, 10);
+        assert!(!app.follow);
+        assert_eq!(app.first_visible_line(120, 10), 85);
+
+        app.scroll_down(100, 100, 10);
+        assert!(app.follow);
+        assert_eq!(app.first_visible_line(120, 10), 110);
+    }
+}
@@ -1,9 +1,19 @@
 use anyhow::{Context as AnyhowContext, Result};
-use std::collections::HashMap;
-use std::io::Write;
-use std::process::{Command, Stdio};
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-use crate::config::{ClawConfig, ReceiverType};
+use crate::config::{
+    ArgOverflow, ClawConfig, ContextScript, ModelOptions, ReceiverType, ScriptShell,
+};
+use crate::{digest, history, middleware, script_cache};
 
 /// Creates a PromptReceiver based on the provided configuration.
 ///
@@ -13,16 +23,53 @@ use crate::config::{ClawConfig, ReceiverType};
 ///
 /// # Arguments
 /// * `config` - The claw configuration containing receiver settings
+/// * `options` - Per-invocation receiver options (e.g. `--tee`)
 ///
 /// # Returns
 /// A boxed trait object implementing PromptReceiver
 ///
+/// With `receiver_fallbacks`, the receiver tries each fallback profile in
+/// turn when the ones before it fail. With `fan_out`, it sends the prompt to
+/// each listed profile at once instead.
+///
 /// # Panics
-/// Panics if receiver_type is Generic but llm_command is not specified
-pub fn create_receiver(config: &ClawConfig) -> Box<dyn PromptReceiver> {
+/// Panics if receiver_type is Generic but llm_command is not specified, or
+/// if receiver_type is Tmux but tmux_target is not specified
+pub fn create_receiver(config: &ClawConfig, options: &ReceiverOptions) -> Box<dyn PromptReceiver> {
+    if config
+        .fan_out
+        .as_ref()
+        .is_some_and(|profiles| !profiles.is_empty())
+    {
+        return Box::new(FanOutReceiver {
+            config: config.clone(),
+            options: options.clone(),
+        });
+    }
+    if config
+        .receiver_fallbacks
+        .as_ref()
+        .is_some_and(|fallbacks| !fallbacks.is_empty())
+    {
+        return Box::new(FallbackReceiver {
+            config: config.clone(),
+            options: options.clone(),
+        });
+    }
+    create_single_receiver(config, options)
+}
+
+/// Creates the receiver for the config's own receiver settings, wrapped in
+/// its `middlewares`.
+fn create_single_receiver(
+    config: &ClawConfig,
+    options: &ReceiverOptions,
+) -> Box<dyn PromptReceiver> {
     let receiver_type = config.receiver_type.clone().unwrap_or(ReceiverType::Generic);
 
-    match receiver_type {
+    let model_option_flags = config.model_option_flags.clone().unwrap_or_default();
+
+    let receiver: Box<dyn PromptReceiver> = match receiver_type {
         ReceiverType::Generic => {
             let llm_command = config.llm_command.clone().unwrap_or_else(|| {
                 panic!(
@@ -30,15 +77,88 @@ pub fn create_receiver(config: &ClawConfig) -> Box<dyn PromptReceiver> {
                      Either set llm_command in your config or use receiver_type: ClaudeCli"
                 )
             });
-            Box::new(GenericReceiver::new(
-                llm_command,
-                config.prompt_arg_template.clone(),
-            ))
+            Box::new(
+                GenericReceiver::new(llm_command, config.prompt_arg_template.clone())
+                    .with_options(options.clone())
+                    .with_model_option_flags(model_option_flags)
+                    .with_arg_overflow(ArgOverflowSettings::from_config(config)),
+            )
         }
-        ReceiverType::ClaudeCli => {
-            Box::new(ClaudeCliReceiver::new(config.prompt_arg_template.clone()))
+        ReceiverType::ClaudeCli => Box::new(
+            ClaudeCliReceiver::new(config.prompt_arg_template.clone())
+                .with_options(options.clone())
+                .with_model_option_flags(model_option_flags)
+                .with_arg_overflow(ArgOverflowSettings::from_config(config)),
+        ),
+        ReceiverType::Tmux => {
+            let target = config.tmux_target.clone().unwrap_or_else(|| {
+                panic!(
+                    "tmux_target is required when using Tmux receiver type. \
+                     Set tmux_target in your config to the pane running your LLM, e.g. \"llm:0.1\""
+                )
+            });
+            Box::new(TmuxReceiver::new(target).with_options(options.clone()))
         }
-    }
+    };
+    middleware::wrap(receiver, config)
+}
+
+/// Set by the Ctrl-C handler; checked while waiting on the LLM process.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// How long the LLM process gets to exit on its own after Ctrl-C before it is killed.
+const INTERRUPT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Installs a Ctrl-C handler that records the interrupt instead of exiting.
+///
+/// The LLM process shares our terminal, so it receives the same SIGINT and
+/// can wind down on its own; claw stays alive long enough to collect its
+/// output and record the run. Install this only around the LLM call, so
+/// Ctrl-C still aborts claw immediately while scripts run or prompts wait.
+pub fn install_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst))
+        .context("Failed to install Ctrl-C handler")
+}
+
+/// Returns true if Ctrl-C was pressed while the handler was installed.
+pub fn was_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Set when the run is cancelled from the live run view.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Stops the running LLM process right away and records the run as
+/// interrupted. Unlike Ctrl-C, the process gets no signal to wind down on
+/// its own, so it is killed without a grace period.
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Per-invocation options that change how a receiver handles the LLM process.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiverOptions {
+    /// File that receives a copy of everything the LLM writes to stdout.
+    pub tee: Option<PathBuf>,
+    /// Detach the LLM from the terminal: its stdin is closed and its output
+    /// only goes to the tee file. Used when several goals run concurrently.
+    pub background: bool,
+    /// Receives every chunk the LLM writes to stdout or stderr, for the live
+    /// run view. Both streams are captured instead of sharing the terminal.
+    pub stream: Option<Sender<Vec<u8>>>,
+    /// Keep a copy of the LLM's stdout and open it in `$PAGER` once the LLM
+    /// finishes, if it is longer than the terminal.
+    pub page: bool,
+    /// The goal's generation settings, which each receiver passes on in its
+    /// own way.
+    pub model_options: ModelOptions,
+    /// Environment variables set for the LLM command, from `env` in the goal
+    /// and `claw.yaml`.
+    pub env: BTreeMap<String, String>,
+    /// The directory the LLM command runs in, from the goal's `working_dir`,
+    /// instead of claw's.
+    pub cwd: Option<PathBuf>,
 }
 
 /// Defines the contract for sending rendered prompts to different targets.
@@ -65,19 +185,430 @@ pub trait PromptReceiver {
     /// Returns a human-readable name for this receiver type.
     ///
     /// Used for logging and error messages.
+    #[allow(dead_code)]
     fn name(&self) -> &str;
 }
 
+/// Sends the prompt with the first receiver in the config's
+/// [`receiver_chain`](ClawConfig::receiver_chain) that succeeds, reporting
+/// each failure and which receiver handled the prompt.
+pub struct FallbackReceiver {
+    config: ClawConfig,
+    options: ReceiverOptions,
+}
+
+impl PromptReceiver for FallbackReceiver {
+    fn send_prompt(&self, prompt: &str) -> Result<()> {
+        let chain = self.config.receiver_chain()?;
+        let mut failures = Vec::new();
+        for (i, (label, config)) in chain.iter().enumerate() {
+            let error = match create_single_receiver(config, &self.options).send_prompt(prompt) {
+                Ok(()) => {
+                    if i > 0 {
+                        eprintln!("✓ The prompt was handled by fallback {}.", label);
+                    }
+                    return Ok(());
+                }
+                Err(error) => error,
+            };
+            // Ctrl-C stops the run, not just this receiver
+            if was_interrupted() {
+                return Err(error);
+            }
+            failures.push(format!("{}: {:#}", label, error));
+            match chain.get(i + 1) {
+                Some((next, _)) => {
+                    eprintln!("⚠️  {} failed: {:#}", label, error);
+                    eprintln!("   Falling back to {}...", next);
+                }
+                None => {
+                    return Err(error.context(format!(
+                        "Every receiver failed:\n  - {}",
+                        failures.join("\n  - ")
+                    )));
+                }
+            }
+        }
+        unreachable!("the receiver chain always holds the configured receiver")
+    }
+
+    fn name(&self) -> &str {
+        "Fallback"
+    }
+}
+
+/// How often a receiver runs a failing LLM command again, from `retries`
+/// and `backoff_ms`.
+#[derive(Debug, Clone, Default)]
+pub struct RetrySettings {
+    retries: u32,
+    backoff: Duration,
+}
+
+/// The longest wait between two attempts, however many retries are made.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+impl RetrySettings {
+    pub fn from_config(config: &ClawConfig) -> Self {
+        Self {
+            retries: config.retries.unwrap_or(0),
+            backoff: Duration::from_millis(config.backoff_ms.unwrap_or(1000)),
+        }
+    }
+
+    /// The wait before retry `retry` (1 for the first): the backoff doubled
+    /// for each earlier retry, then reduced by a random part of up to half,
+    /// so concurrent runs don't retry in lockstep.
+    fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(retry - 1))
+            .min(MAX_BACKOFF);
+        let jitter = delay.mul_f64(random_fraction() / 2.0);
+        delay - jitter
+    }
+
+    /// Calls `send` until it succeeds, it fails in a way retrying can't fix,
+    /// or the retries are used up. The last error lists every attempt's.
+    pub fn run(&self, command: &str, mut send: impl FnMut() -> Result<()>) -> Result<()> {
+        let mut failures = Vec::new();
+        for attempt in 1.. {
+            let error = match send() {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            if was_interrupted() || error.downcast_ref::<LlmExitError>().is_none() {
+                return Err(error);
+            }
+            failures.push(format!("attempt {}: {:#}", attempt, error));
+            if attempt > self.retries {
+                if attempt == 1 {
+                    return Err(error);
+                }
+                return Err(error.context(format!(
+                    "'{}' failed {} times:\n  - {}",
+                    command,
+                    attempt,
+                    failures.join("\n  - ")
+                )));
+            }
+            let delay = self.delay(attempt);
+            eprintln!(
+                "⚠️  {:#}; retrying in {:.1}s ({}/{})",
+                error,
+                delay.as_secs_f64(),
+                attempt,
+                self.retries
+            );
+            sleep_unless_interrupted(delay);
+            if was_interrupted() {
+                return Err(error);
+            }
+        }
+        unreachable!("the attempts only end by returning")
+    }
+}
+
+/// A random number in `[0, 1)`, from the standard library's randomly seeded
+/// hasher, which is good enough for jitter.
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos()),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Sleeps for `duration`, waking early if Ctrl-C is pressed.
+fn sleep_unless_interrupted(duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while !was_interrupted() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        thread::sleep(left.min(Duration::from_millis(50)));
+    }
+}
+
+/// How a receiver handles a prompt too long to pass as an argument, from
+/// `prompt_arg_overflow`, `prompt_stdin_template` and `max_prompt_arg_kb`.
+#[derive(Debug, Clone, Default)]
+pub struct ArgOverflowSettings {
+    mode: ArgOverflow,
+    stdin_template: Option<String>,
+    /// Overrides the limits detected from the OS.
+    max_bytes: Option<usize>,
+}
+
+impl ArgOverflowSettings {
+    pub fn from_config(config: &ClawConfig) -> Self {
+        Self {
+            mode: config.prompt_arg_overflow.unwrap_or_default(),
+            stdin_template: config.prompt_stdin_template.clone(),
+            max_bytes: config.max_prompt_arg_kb.map(|kb| kb as usize * 1024),
+        }
+    }
+}
+
+/// Room left below the OS limits for what claw doesn't count, such as the
+/// pointers to each argument.
+const ARG_LIMIT_HEADROOM: usize = 4096;
+
+/// The longest single argument and the longest command line (arguments and
+/// environment together) the OS accepts, in bytes.
+fn os_arg_limits() -> (usize, usize) {
+    if cfg!(target_os = "linux") {
+        // MAX_ARG_STRLEN caps each argument; ARG_MAX is a quarter of the
+        // default 8 MB stack
+        (128 * 1024 - 1, 2 * 1024 * 1024)
+    } else if cfg!(target_os = "macos") {
+        (1024 * 1024, 1024 * 1024)
+    } else if cfg!(windows) {
+        (32 * 1024 - 1, 32 * 1024 - 1)
+    } else {
+        (256 * 1024, 256 * 1024)
+    }
+}
+
+/// Returns whether the OS would accept `args` as a command line alongside
+/// the current environment, or, with `max_bytes`, whether the prompt fits.
+fn fits_on_command_line(prompt: &str, args: &[String], max_bytes: Option<usize>) -> bool {
+    if let Some(max_bytes) = max_bytes {
+        return prompt.len() <= max_bytes;
+    }
+    let (max_arg, max_total) = os_arg_limits();
+    let environment: usize = std::env::vars_os()
+        .map(|(key, value)| key.len() + value.len() + 2)
+        .sum();
+    let arguments: usize = args.iter().map(|arg| arg.len() + 1).sum();
+    args.iter().all(|arg| arg.len() <= max_arg)
+        && arguments + environment + ARG_LIMIT_HEADROOM <= max_total
+}
+
+/// Sends the prompt to each profile in the config's `fan_out` at the same
+/// time, then prints their responses side by side (one after the other when
+/// the terminal is too narrow or stdout isn't one). With `--tee`, each
+/// response is also saved next to the tee file, e.g. `review.claude.md`, and
+/// the tee file gets all of them under a heading each.
+pub struct FanOutReceiver {
+    config: ClawConfig,
+    options: ReceiverOptions,
+}
+
+/// Columns narrower than this are printed one after the other instead.
+const MIN_COLUMN_WIDTH: usize = 40;
+
+/// Separates the columns of side-by-side responses.
+const COLUMN_SEPARATOR: &str = " │ ";
+
+impl FanOutReceiver {
+    /// Where the response of profile `name` is written: next to the tee
+    /// file, or in the temp directory when there is none.
+    fn output_path(&self, name: &str) -> PathBuf {
+        match &self.options.tee {
+            Some(tee) => {
+                let stem = tee.file_stem().unwrap_or_default().to_string_lossy();
+                let extension = tee
+                    .extension()
+                    .map_or("md".to_string(), |e| e.to_string_lossy().to_string());
+                tee.with_file_name(format!("{}.{}.{}", stem, name, extension))
+            }
+            None => std::env::temp_dir().join(format!(
+                "claw-fan-out-{}-{}.md",
+                std::process::id(),
+                name
+            )),
+        }
+    }
+}
+
+impl PromptReceiver for FanOutReceiver {
+    fn send_prompt(&self, prompt: &str) -> Result<()> {
+        let receivers = self.config.fan_out_receivers()?;
+        if let Some((name, _)) = receivers
+            .iter()
+            .find(|(_, config)| config.receiver_type == Some(ReceiverType::Tmux))
+        {
+            anyhow::bail!(
+                "Profile '{}' in fan_out uses the tmux receiver, whose response stays in \
+                 its pane and can't be compared",
+                name
+            );
+        }
+        let names: Vec<&str> = receivers.iter().map(|(name, _)| name.as_str()).collect();
+        eprintln!("Sending the prompt to {}...", names.join(", "));
+
+        let results: Vec<(Result<()>, Duration)> = thread::scope(|scope| {
+            let handles: Vec<_> = receivers
+                .iter()
+                .map(|(name, config)| {
+                    let options = ReceiverOptions {
+                        tee: Some(self.output_path(name)),
+                        background: true,
+                        stream: None,
+                        page: false,
+                        ..self.options.clone()
+                    };
+                    scope.spawn(move || {
+                        let started = Instant::now();
+                        let result = create_single_receiver(config, &options).send_prompt(prompt);
+                        let elapsed = started.elapsed();
+                        match &result {
+                            Ok(()) => eprintln!(
+                                "✓ {} answered in {:.1}s",
+                                name,
+                                elapsed.as_secs_f64()
+                            ),
+                            Err(e) => eprintln!("✗ {} failed: {:#}", name, e),
+                        }
+                        (result, elapsed)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| (Err(anyhow::anyhow!("panicked")), Duration::ZERO))
+                })
+                .collect()
+        });
+
+        let mut columns = Vec::new();
+        let mut failures = Vec::new();
+        for ((name, config), (result, elapsed)) in receivers.iter().zip(results) {
+            let path = self.output_path(name);
+            let title = format!(
+                "{} ({}, {:.1}s)",
+                name,
+                config.receiver_label(),
+                elapsed.as_secs_f64()
+            );
+            let body = match result {
+                Ok(()) => std::fs::read_to_string(&path).unwrap_or_default(),
+                Err(e) => {
+                    failures.push(format!("{}: {:#}", name, e));
+                    format!("(failed: {:#})", e)
+                }
+            };
+            if self.options.tee.is_none() {
+                let _ = std::fs::remove_file(&path);
+            }
+            columns.push((title, body));
+        }
+
+        let width = crossterm::terminal::size().map_or(0, |(cols, _)| cols as usize);
+        let column_width = width
+            .saturating_sub(COLUMN_SEPARATOR.chars().count() * (columns.len() - 1))
+            / columns.len();
+        let comparison = if io::stdout().is_terminal() && column_width >= MIN_COLUMN_WIDTH {
+            format_columns(&columns, column_width)
+        } else {
+            format_sections(&columns)
+        };
+        print!("{}", comparison);
+        io::stdout().flush()?;
+
+        if let Some(tee) = &self.options.tee {
+            std::fs::write(tee, format_markdown_sections(&columns))
+                .with_context(|| format!("Failed to write {}", tee.display()))?;
+        }
+
+        if was_interrupted() {
+            anyhow::bail!("The fan-out run was interrupted");
+        }
+        if failures.len() == receivers.len() {
+            anyhow::bail!("Every fan-out receiver failed:\n  - {}", failures.join("\n  - "));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "FanOut"
+    }
+}
+
+/// Lays `(title, body)` pairs out side by side in columns of `width`
+/// characters, wrapping long lines.
+fn format_columns(columns: &[(String, String)], width: usize) -> String {
+    let wrapped: Vec<Vec<String>> = columns
+        .iter()
+        .map(|(title, body)| {
+            let mut lines = wrap_line(title, width);
+            lines.push("─".repeat(width));
+            lines.extend(body.lines().flat_map(|line| wrap_line(line, width)));
+            lines
+        })
+        .collect();
+    let height = wrapped.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut output = String::new();
+    for row in 0..height {
+        let cells: Vec<String> = wrapped
+            .iter()
+            .map(|lines| {
+                let cell = lines.get(row).map_or("", String::as_str);
+                format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+            })
+            .collect();
+        output.push_str(cells.join(COLUMN_SEPARATOR).trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// Splits `line` into pieces of at most `width` characters.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().filter(|c| *c != '\t').collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars.chunks(width).map(|chunk| chunk.iter().collect()).collect()
+}
+
+/// Prints `(title, body)` pairs one after the other, for narrow terminals
+/// and pipes.
+fn format_sections(columns: &[(String, String)]) -> String {
+    let mut output = String::new();
+    for (title, body) in columns {
+        output.push_str(&format!("── {} ──\n{}", title, body));
+        if !body.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// The responses as markdown, one heading per receiver, for the tee file.
+fn format_markdown_sections(columns: &[(String, String)]) -> String {
+    columns
+        .iter()
+        .map(|(title, body)| format!("## {}\n\n{}\n", title, body.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Generic receiver that executes arbitrary CLI commands.
 ///
 /// Supports two modes of operation:
 /// 1. **Argument mode**: If `prompt_arg_template` contains `{{prompt}}`,
-///    the prompt is passed as a command-line argument.
+///    the prompt is passed as a command-line argument. A prompt too long for
+///    the command line goes to stdin instead, unless `prompt_arg_overflow`
+///    is `fail`.
 /// 2. **Stdin mode**: If `{{prompt}}` is NOT present in the template,
 ///    the prompt is piped to the command's stdin.
 pub struct GenericReceiver {
     llm_command: String,
     prompt_arg_template: String,
+    options: ReceiverOptions,
+    model_option_flags: BTreeMap<String, String>,
+    arg_overflow: ArgOverflowSettings,
 }
 
 impl GenericReceiver {
@@ -86,9 +617,55 @@ impl GenericReceiver {
         Self {
             llm_command,
             prompt_arg_template,
+            options: ReceiverOptions::default(),
+            model_option_flags: BTreeMap::new(),
+            arg_overflow: ArgOverflowSettings::default(),
         }
     }
 
+    /// Applies per-invocation options to this receiver.
+    pub fn with_options(mut self, options: ReceiverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the flag templates used to pass model options to the command.
+    pub fn with_model_option_flags(mut self, flags: BTreeMap<String, String>) -> Self {
+        self.model_option_flags = flags;
+        self
+    }
+
+    /// Sets how a prompt too long for the command line is handled.
+    pub fn with_arg_overflow(mut self, arg_overflow: ArgOverflowSettings) -> Self {
+        self.arg_overflow = arg_overflow;
+        self
+    }
+
+    /// Returns the arguments that pass the goal's model options, from the
+    /// `model_option_flags` templates. Options without a template are left
+    /// out with a note.
+    fn model_option_args(&self) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        for (name, value) in self.options.model_options.values() {
+            let Some(template) = self.model_option_flags.get(name) else {
+                eprintln!(
+                    "⚠️  Note: model_options.{} is not passed to '{}'. Add a flag for it to \
+                     model_option_flags in claw.yaml, e.g. {}: \"--{} {{{{value}}}}\".",
+                    name,
+                    self.llm_command,
+                    name,
+                    name.replace('_', "-")
+                );
+                continue;
+            };
+            let words = shlex::split(template).with_context(|| {
+                format!("Could not parse model_option_flags.{} from your config.", name)
+            })?;
+            args.extend(words.iter().map(|word| word.replace("{{value}}", &value)));
+        }
+        Ok(args)
+    }
+
     /// Sends the prompt via command-line arguments (when {{prompt}} is in template).
     fn send_via_argument(&self, prompt: &str) -> Result<()> {
         // Find the full path to the executable
@@ -103,34 +680,72 @@ impl GenericReceiver {
         let template_args = shlex::split(&self.prompt_arg_template)
             .context("Could not parse 'prompt_arg_template' from your config.")?;
 
-        // Build the command
-        let mut command = Command::new(&llm_executable);
-        for arg in template_args {
-            // Substitute the placeholder with the real prompt
-            if arg.contains("{{prompt}}") {
-                command.arg(arg.replace("{{prompt}}", prompt));
-            } else {
-                command.arg(arg);
-            }
+        // Substitute the placeholder with the real prompt
+        let model_args = self.model_option_args()?;
+        let mut args: Vec<String> = template_args
+            .iter()
+            .map(|arg| arg.replace("{{prompt}}", prompt))
+            .collect();
+        args.extend(model_args.iter().cloned());
+
+        if !fits_on_command_line(prompt, &args, self.arg_overflow.max_bytes) {
+            return self.send_overflow_via_stdin(prompt, &llm_executable, &model_args);
         }
 
         // Run the command interactively
-        let status = command.status().with_context(|| {
-            format!(
-                "Failed to execute LLM command: '{}'",
-                llm_executable.display()
-            )
-        })?;
+        let mut command = Command::new(&llm_executable);
+        command.args(&args);
+        match self.run_command(command, &llm_executable, None) {
+            // The OS may still refuse a command line just under our estimate
+            Err(e)
+                if e.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::ArgumentListTooLong) =>
+            {
+                self.send_overflow_via_stdin(prompt, &llm_executable, &model_args)
+            }
+            result => result,
+        }
+    }
 
-        if !status.success() {
+    /// Sends a prompt that is too long for the command line via stdin, with
+    /// `prompt_stdin_template` or the template without its `{{prompt}}`
+    /// arguments, unless `prompt_arg_overflow` is `fail`.
+    fn send_overflow_via_stdin(
+        &self,
+        prompt: &str,
+        llm_executable: &Path,
+        model_args: &[String],
+    ) -> Result<()> {
+        let size_kb = prompt.len().div_ceil(1024);
+        if self.arg_overflow.mode == ArgOverflow::Fail {
             anyhow::bail!(
-                "LLM command '{}' exited with non-zero status: {}",
-                llm_executable.display(),
-                status
+                "The prompt ({} KB) is too long to pass to '{}' as an argument on this system. \
+                 Set prompt_arg_overflow: stdin in claw.yaml to send long prompts on stdin, \
+                 or remove {{{{prompt}}}} from prompt_arg_template.",
+                size_kb,
+                self.llm_command
             );
         }
 
-        Ok(())
+        let template_args = match &self.arg_overflow.stdin_template {
+            Some(template) => shlex::split(template)
+                .context("Could not parse 'prompt_stdin_template' from your config.")?,
+            None => shlex::split(&self.prompt_arg_template)
+                .context("Could not parse 'prompt_arg_template' from your config.")?
+                .into_iter()
+                .filter(|arg| !arg.contains("{{prompt}}"))
+                .collect(),
+        };
+        eprintln!(
+            "⚠️  Note: the prompt ({} KB) is too long to pass as an argument; sending it to '{}' \
+             on stdin instead (see prompt_arg_overflow in claw.yaml).",
+            size_kb, self.llm_command
+        );
+
+        let mut command = Command::new(llm_executable);
+        command.args(&template_args);
+        command.args(model_args);
+        self.run_command(command, llm_executable, Some(prompt))
     }
 
     /// Sends the prompt via stdin (when {{prompt}} is NOT in template).
@@ -147,51 +762,274 @@ impl GenericReceiver {
         let template_args = shlex::split(&self.prompt_arg_template)
             .context("Could not parse 'prompt_arg_template' from your config.")?;
 
-        // Build the command with stdin piped
-        let mut child = Command::new(&llm_executable)
-            .args(&template_args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .with_context(|| {
-                format!(
-                    "Failed to spawn LLM command: '{}'",
-                    llm_executable.display()
-                )
-            })?;
+        let mut command = Command::new(&llm_executable);
+        command.args(&template_args);
+        command.args(self.model_option_args()?);
 
-        // Write prompt to stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(prompt.as_bytes()).with_context(|| {
-                format!(
-                    "Failed to pass prompt to LLM via stdin. Check if '{}' supports stdin input, or try using {{{{prompt}}}} in prompt_arg_template.",
-                    self.llm_command
-                )
-            })?;
-            // stdin is automatically closed when dropped
+        self.run_command(command, &llm_executable, Some(prompt))
+    }
+
+    /// Spawns the LLM command, optionally piping `stdin_prompt` to it and
+    /// teeing its stdout, then waits for it to finish.
+    fn run_command(
+        &self,
+        mut command: Command,
+        llm_executable: &Path,
+        stdin_prompt: Option<&str>,
+    ) -> Result<()> {
+        let streaming = self.options.stream.is_some();
+        command.envs(&self.options.env);
+        if let Some(dir) = &self.options.cwd {
+            command.current_dir(dir);
+        }
+        if stdin_prompt.is_some() {
+            command.stdin(Stdio::piped());
+        } else if self.options.background || streaming {
+            command.stdin(Stdio::null());
+        }
+
+        let tee_file = match &self.options.tee {
+            Some(path) => Some(File::create(path).with_context(|| {
+                format!("Failed to create tee file at {}", path.display())
+            })?),
+            None => None,
+        };
+        // Paging needs the output after it has been shown, so capture it
+        // while echoing, as for --tee
+        let page = self.options.page
+            && !self.options.background
+            && !streaming
+            && io::stdout().is_terminal();
+        if tee_file.is_some() || streaming || page {
+            command.stdout(Stdio::piped());
+        }
+        if streaming {
+            command.stderr(Stdio::piped());
         }
 
-        // Wait for the command to complete
-        let status = child.wait().with_context(|| {
+        let mut child = command.spawn().with_context(|| {
+            format!(
+                "Failed to spawn LLM command: '{}'",
+                llm_executable.display()
+            )
+        })?;
+
+        // Start copying output before writing stdin, so a chatty LLM can't
+        // fill the stdout pipe while we're blocked on a large prompt.
+        let tee_handle = child.stdout.take().map(|stdout| {
+            let echo = !self.options.background && !streaming;
+            spawn_tee(stdout, tee_file, echo, page, self.options.stream.clone())
+        });
+        let stderr_handle = match (child.stderr.take(), &self.options.stream) {
+            (Some(stderr), Some(stream)) => Some(spawn_forward(stderr, stream.clone())),
+            _ => None,
+        };
+
+        // Feed the prompt from another thread while waiting, so Ctrl-C and
+        // cancelling still work while a huge prompt is being written, and an
+        // LLM that exits early is reported by its exit status
+        let (status, fed) = thread::scope(|scope| {
+            let feeder = match (stdin_prompt, child.stdin.take()) {
+                (Some(prompt), Some(stdin)) => {
+                    Some(scope.spawn(move || feed_stdin(stdin, prompt.as_bytes())))
+                }
+                _ => None,
+            };
+            let status = wait_for_child(&mut child);
+            let fed = feeder.map_or(Ok(()), |feeder| {
+                feeder
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("the stdin writer panicked")))
+            });
+            (status, fed)
+        });
+        let status = status.with_context(|| {
             format!(
                 "Failed to wait for LLM command: '{}'",
                 llm_executable.display()
             )
         })?;
 
+        let mut captured = Vec::new();
+        if let Some(handle) = tee_handle {
+            captured = handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Tee thread panicked"))?
+                .context("Failed to tee LLM output")?;
+        }
+        if let Some(handle) = stderr_handle {
+            let _ = handle.join();
+        }
+
+        if was_interrupted() {
+            // The LLM may have been killed mid-render; don't leave the terminal raw
+            let _ = crossterm::terminal::disable_raw_mode();
+            anyhow::bail!("LLM command '{}' was interrupted", llm_executable.display());
+        }
+
+        // An LLM that failed before reading its whole prompt is reported by
+        // its exit status below, not by the broken pipe
+        if let Err(e) = fed
+            && (status.success() || e.kind() != io::ErrorKind::BrokenPipe)
+        {
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to pass prompt to LLM via stdin. Check if '{}' supports stdin input, or try using {{{{prompt}}}} in prompt_arg_template.",
+                    self.llm_command
+                )
+            });
+        }
+
         if !status.success() {
-            anyhow::bail!(
-                "LLM command '{}' exited with non-zero status: {}",
-                llm_executable.display(),
-                status
-            );
+            return Err(LlmExitError {
+                command: llm_executable.display().to_string(),
+                status,
+            }
+            .into());
         }
 
+        if page {
+            page_response(&String::from_utf8_lossy(&captured))?;
+        }
         Ok(())
     }
 }
 
+/// Opens a response that doesn't fit on the screen in `$PAGER`, which
+/// keeps its ANSI colors with the default `less -R`.
+fn page_response(response: &str) -> Result<()> {
+    let rows = crossterm::terminal::size().map_or(usize::MAX, |(_, rows)| rows as usize);
+    if response.lines().count() < rows {
+        return Ok(());
+    }
+    crate::commands::dry_run::page_output(response)
+}
+
+/// The LLM command exited unsuccessfully. Kept as a distinct error so
+/// `run_goal` can exit with the same code.
+#[derive(Debug)]
+pub struct LlmExitError {
+    command: String,
+    status: ExitStatus,
+}
+
+impl LlmExitError {
+    /// The LLM's exit code, if it exited normally rather than by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+}
+
+impl std::fmt::Display for LlmExitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LLM command '{}' exited with non-zero status: {}",
+            self.command, self.status
+        )
+    }
+}
+
+impl std::error::Error for LlmExitError {}
+
+/// How much of a prompt is written to the LLM's stdin at a time.
+const STDIN_CHUNK: usize = 64 * 1024;
+
+/// Writes `prompt` to the LLM's stdin in chunks, then closes it. Stops
+/// early once the run is interrupted or cancelled, so the LLM isn't fed a
+/// prompt nobody is waiting for.
+fn feed_stdin(mut stdin: ChildStdin, prompt: &[u8]) -> io::Result<()> {
+    for chunk in prompt.chunks(STDIN_CHUNK) {
+        if was_interrupted() {
+            return Ok(());
+        }
+        stdin.write_all(chunk)?;
+    }
+    stdin.flush()
+}
+
+/// Waits for the child to exit, killing it if it outlives the grace period
+/// after a Ctrl-C.
+fn wait_for_child(child: &mut Child) -> io::Result<ExitStatus> {
+    let mut interrupted_at = None;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+
+        if interrupted_at.is_none() && was_interrupted() {
+            interrupted_at = Some(Instant::now());
+        }
+        if CANCELLED.load(Ordering::SeqCst)
+            || interrupted_at.is_some_and(|at| at.elapsed() >= INTERRUPT_GRACE_PERIOD)
+        {
+            child.kill()?;
+            return child.wait();
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Copies the child's stdout to `file` as it arrives, echoing it to our stdout
+/// when `echo` is set and passing it on to `stream`, if given. Returns
+/// everything that was read when `capture` is set.
+fn spawn_tee(
+    mut child_stdout: ChildStdout,
+    mut file: Option<File>,
+    echo: bool,
+    capture: bool,
+    stream: Option<Sender<Vec<u8>>>,
+) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let bytes_read = child_stdout.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            let chunk = &buffer[..bytes_read];
+
+            if echo {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(chunk)?;
+                stdout.flush()?;
+            }
+
+            if let Some(file) = &mut file {
+                file.write_all(chunk)?;
+                file.flush()?;
+            }
+
+            if capture {
+                captured.extend_from_slice(chunk);
+            }
+
+            if let Some(stream) = &stream {
+                // The view may already be closed; the output still goes to the file
+                let _ = stream.send(chunk.to_vec());
+            }
+        }
+        Ok(captured)
+    })
+}
+
+/// Passes everything read from `reader` on to `stream`.
+fn spawn_forward(
+    mut reader: impl Read + Send + 'static,
+    stream: Sender<Vec<u8>>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        while let Ok(bytes_read) = reader.read(&mut buffer) {
+            if bytes_read == 0 || stream.send(buffer[..bytes_read].to_vec()).is_err() {
+                break;
+            }
+        }
+    })
+}
+
 impl PromptReceiver for GenericReceiver {
     fn send_prompt(&self, prompt: &str) -> Result<()> {
         if self.prompt_arg_template.contains("{{prompt}}") {
@@ -215,6 +1053,9 @@ impl PromptReceiver for GenericReceiver {
 /// GenericReceiver, supporting both stdin and argument-based modes.
 pub struct ClaudeCliReceiver {
     prompt_arg_template: String,
+    options: ReceiverOptions,
+    model_option_flags: BTreeMap<String, String>,
+    arg_overflow: ArgOverflowSettings,
 }
 
 impl ClaudeCliReceiver {
@@ -222,14 +1063,38 @@ impl ClaudeCliReceiver {
     pub fn new(prompt_arg_template: String) -> Self {
         Self {
             prompt_arg_template,
+            options: ReceiverOptions::default(),
+            model_option_flags: BTreeMap::new(),
+            arg_overflow: ArgOverflowSettings::default(),
         }
     }
+
+    /// Applies per-invocation options to this receiver.
+    pub fn with_options(mut self, options: ReceiverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the flag templates used to pass model options to `claude`.
+    pub fn with_model_option_flags(mut self, flags: BTreeMap<String, String>) -> Self {
+        self.model_option_flags = flags;
+        self
+    }
+
+    /// Sets how a prompt too long for the command line is handled.
+    pub fn with_arg_overflow(mut self, arg_overflow: ArgOverflowSettings) -> Self {
+        self.arg_overflow = arg_overflow;
+        self
+    }
 }
 
 impl PromptReceiver for ClaudeCliReceiver {
     fn send_prompt(&self, prompt: &str) -> Result<()> {
         // Delegate to GenericReceiver with hardcoded "claude" command
-        let generic = GenericReceiver::new("claude".to_string(), self.prompt_arg_template.clone());
+        let generic = GenericReceiver::new("claude".to_string(), self.prompt_arg_template.clone())
+            .with_options(self.options.clone())
+            .with_model_option_flags(self.model_option_flags.clone())
+            .with_arg_overflow(self.arg_overflow.clone());
         generic.send_prompt(prompt)
     }
 
@@ -238,6 +1103,105 @@ impl PromptReceiver for ClaudeCliReceiver {
     }
 }
 
+/// Receiver that pastes the prompt into an existing tmux pane.
+///
+/// The prompt is loaded into a tmux paste buffer and pasted into the target
+/// pane as a bracketed paste, so a multi-line prompt arrives as one message,
+/// then Enter is pressed. This feeds a long-lived interactive LLM session
+/// instead of starting a new process for every goal. The response stays in
+/// the pane, so `--tee` has nothing to capture.
+pub struct TmuxReceiver {
+    target: String,
+    options: ReceiverOptions,
+}
+
+impl TmuxReceiver {
+    /// Creates a new TmuxReceiver for the given pane, e.g. `llm:0.1`.
+    pub fn new(target: String) -> Self {
+        Self {
+            target,
+            options: ReceiverOptions::default(),
+        }
+    }
+
+    /// Applies per-invocation options to this receiver.
+    pub fn with_options(mut self, options: ReceiverOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl PromptReceiver for TmuxReceiver {
+    fn send_prompt(&self, prompt: &str) -> Result<()> {
+        if self.options.tee.is_some() {
+            eprintln!(
+                "⚠️  Note: --tee has no effect with the Tmux receiver; the response stays in the tmux pane."
+            );
+        }
+        if !self.options.model_options.values().is_empty() {
+            eprintln!(
+                "⚠️  Note: model_options have no effect with the Tmux receiver; set them in the LLM session instead."
+            );
+        }
+
+        let tmux = which::which("tmux").context(
+            "tmux not found in your PATH. The Tmux receiver needs tmux to reach the LLM session.",
+        )?;
+        let buffer = format!("claw-{}", std::process::id());
+
+        let mut child = Command::new(&tmux)
+            .args(["load-buffer", "-b", &buffer, "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run `tmux load-buffer`")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(prompt.as_bytes())
+                .context("Failed to write prompt to tmux")?;
+        }
+        let output = child
+            .wait_with_output()
+            .context("Failed to wait for `tmux load-buffer`")?;
+        check_tmux("load-buffer", &output)?;
+
+        run_tmux(
+            &tmux,
+            &["paste-buffer", "-d", "-p", "-b", &buffer, "-t", &self.target],
+        )
+        .with_context(|| format!("Failed to paste the prompt into tmux pane '{}'", self.target))?;
+        run_tmux(&tmux, &["send-keys", "-t", &self.target, "Enter"])?;
+
+        eprintln!("Prompt sent to tmux pane '{}'.", self.target);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "Tmux"
+    }
+}
+
+/// Runs a tmux subcommand, failing with its stderr if it does not succeed.
+fn run_tmux(tmux: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new(tmux)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run `tmux {}`", args[0]))?;
+    check_tmux(args[0], &output)
+}
+
+fn check_tmux(subcommand: &str, output: &std::process::Output) -> Result<()> {
+    if !output.status.success() {
+        anyhow::bail!(
+            "`tmux {}` failed: {}",
+            subcommand,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
 /// Checks if the prompt is large and using {{prompt}} substitution,
 /// and displays a migration warning if appropriate.
 ///
@@ -253,43 +1217,279 @@ pub fn check_prompt_size_warning(prompt: &str, template: &str) {
     }
 }
 
-/// Executes all shell commands defined in the `context_scripts` map.
+/// How a context script is run.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOptions {
+    /// Added to the script's environment.
+    pub env: BTreeMap<String, String>,
+    /// How long the script may run before it and everything it started are
+    /// killed.
+    pub timeout: Option<Duration>,
+    /// The shell the command is run with.
+    pub shell: ScriptShell,
+    /// The directory the script runs in, instead of claw's.
+    pub cwd: Option<PathBuf>,
+    /// How long a successful output is reused for instead of running the
+    /// script again.
+    pub cache_ttl: Option<Duration>,
+}
+
+impl ScriptOptions {
+    /// The options for one of a goal's scripts, with the goal's rendered `env`.
+    pub fn for_script(
+        claw_config: &ClawConfig,
+        script: &ContextScript,
+        env: &BTreeMap<String, String>,
+    ) -> Self {
+        ScriptOptions {
+            env: env.clone(),
+            timeout: claw_config.script_timeout(script),
+            shell: claw_config.script_shell(script),
+            cwd: None,
+            cache_ttl: claw_config.script_cache_ttl(script),
+        }
+    }
+}
+
+/// Checks that a goal's rendered `working_dir`, if it has one, is a directory.
+pub fn existing_working_dir(dir: Option<PathBuf>) -> Result<Option<PathBuf>> {
+    if let Some(dir) = &dir
+        && !dir.is_dir()
+    {
+        anyhow::bail!(
+            "The goal's working_dir {} isn't a directory",
+            dir.display()
+        );
+    }
+    Ok(dir)
+}
+
+/// Executes the shell commands defined in the `context_scripts` map.
 ///
-/// Returns a HashMap where the key is the script name and the value is
-/// the captured standard output of the script. If any script fails,
-/// it returns an error containing the script's stderr.
+/// Returns each script's result keyed by script name: its captured standard
+/// output, or an error containing its stderr. Scripts run one at a time, in
+/// the order they are declared, with their entry in `options` (or the
+/// defaults), and stop at the first failure, so later scripts have no result.
+/// Also returns how long each script that ran took.
 pub fn execute_context_scripts(
-    scripts: &HashMap<String, String>,
-) -> Result<HashMap<String, String>> {
-    let mut outputs = HashMap::new();
+    scripts: &IndexMap<String, String>,
+    options: &HashMap<String, ScriptOptions>,
+) -> (IndexMap<String, Result<String>>, IndexMap<String, Duration>) {
+    let mut results = IndexMap::new();
+    let mut durations = IndexMap::new();
+    let defaults = ScriptOptions::default();
 
     for (name, command_str) in scripts {
-        // We use `sh -c` to ensure that shell features like pipes and globbing
-        // work as expected, which is common for dev tools.
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(command_str)
-            .output()
-            .with_context(|| format!("Failed to execute context script '{}'", name))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!(
-                "Context script '{}' (`{}`) failed with status {}:\n{}",
-                name,
-                command_str,
-                output.status,
-                stderr
-            );
+        let started = Instant::now();
+        let result = run_script_with(name, command_str, options.get(name).unwrap_or(&defaults));
+        durations.insert(name.clone(), started.elapsed());
+        let failed = result.is_err();
+        results.insert(name.clone(), result);
+        if failed {
+            break;
         }
+    }
+
+    (results, durations)
+}
+
+/// Runs a single shell command and returns its trimmed standard output.
+///
+/// `name` is only used to identify the script in error messages.
+pub fn run_script(name: &str, command_str: &str) -> Result<String> {
+    run_script_with(name, command_str, &ScriptOptions::default())
+}
+
+/// Runs a single shell command, as [`run_script`], with `options`. With a
+/// timeout, the command and everything it started are killed once it has
+/// run that long.
+pub fn run_script_with(name: &str, command_str: &str, options: &ScriptOptions) -> Result<String> {
+    let Some(ttl) = options.cache_ttl else {
+        return run_script_uncached(name, command_str, options);
+    };
+    let key = script_cache_key(command_str, options);
+    if let Some((output, recorded_at)) = script_cache::cached_output(&key, ttl) {
+        eprintln!(
+            "Using the cached output of context script '{}' ({})",
+            name,
+            script_cache::format_age(recorded_at, history::now_secs())
+        );
+        return Ok(output);
+    }
+    let output = run_script_uncached(name, command_str, options)?;
+    if let Err(e) = script_cache::store_output(&key, command_str, &output) {
+        eprintln!(
+            "Warning: couldn't cache the output of context script '{}': {:#}",
+            name, e
+        );
+    }
+    Ok(output)
+}
 
-        let stdout = String::from_utf8(output.stdout)
-            .with_context(|| format!("Script output for '{}' was not valid UTF-8", name))?;
+/// Identifies a script run for the output cache: the same command gives
+/// different output in another directory, shell or environment.
+fn script_cache_key(command_str: &str, options: &ScriptOptions) -> String {
+    let cwd = options
+        .cwd
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let mut key = Vec::new();
+    for part in [
+        cwd.to_string_lossy().as_ref(),
+        &options.shell.to_string(),
+        command_str,
+    ] {
+        key.extend_from_slice(part.as_bytes());
+        key.push(0);
+    }
+    for (name, value) in &options.env {
+        key.extend_from_slice(name.as_bytes());
+        key.push(b'=');
+        key.extend_from_slice(value.as_bytes());
+        key.push(0);
+    }
+    digest::sha256_hex(&key)
+}
 
-        outputs.insert(name.clone(), stdout.trim().to_string());
+fn run_script_uncached(name: &str, command_str: &str, options: &ScriptOptions) -> Result<String> {
+    // We use a shell to ensure that shell features like pipes and globbing
+    // work as expected, which is common for dev tools.
+    let mut command = shell_command(&options.shell, command_str)
+        .with_context(|| format!("Can't run context script '{}'", name))?;
+    command.envs(&options.env);
+    if let Some(dir) = &options.cwd {
+        command.current_dir(dir);
+    }
+    let output = match options.timeout {
+        Some(timeout) => output_within(command, None, Some(timeout)),
+        None => command.output().map(Some),
     }
+    .with_context(|| {
+        format!(
+            "Failed to execute context script '{}' with {}",
+            name, options.shell
+        )
+    })?;
+    let Some(output) = output else {
+        anyhow::bail!(
+            "Context script '{}' (`{}`) timed out after {}s and was stopped",
+            name,
+            command_str,
+            options.timeout.unwrap_or_default().as_secs_f64()
+        );
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "Context script '{}' (`{}`) failed with status {}:\n{}",
+            name,
+            command_str,
+            output.status,
+            stderr
+        );
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("Script output for '{}' was not valid UTF-8", name))?;
 
-    Ok(outputs)
+    Ok(stdout.trim().to_string())
+}
+
+/// A command that runs `command_str` with `shell`.
+fn shell_command(shell: &ScriptShell, command_str: &str) -> Result<Command> {
+    let (program, args) = shell.invocation()?;
+    let mut command = Command::new(&program);
+    command.args(args);
+    // cmd doesn't unquote its command line the way Rust quotes arguments,
+    // so the script is passed as written
+    #[cfg(windows)]
+    if *shell == ScriptShell::Named(crate::config::ShellName::Cmd) {
+        std::os::windows::process::CommandExt::raw_arg(&mut command, command_str);
+        return Ok(command);
+    }
+    command.arg(command_str);
+    Ok(command)
+}
+
+/// Runs `command` with its output captured, or returns `None` if it is still
+/// running after `timeout`. It runs in a process group of its own, so that
+/// on timeout the whole group is killed: a `git fetch` or `curl` the shell
+/// started would otherwise keep running and hold the output pipes open.
+/// `input` is written to its stdin from another thread, so a command that
+/// doesn't read it can't block the timeout.
+pub fn output_within(
+    mut command: Command,
+    input: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    // Outside the terminal's foreground group, reading it would stop the script
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // A command that exits without reading all of it is judged by its status
+    let feeder = match (input, child.stdin.take()) {
+        (Some(input), Some(stdin)) => Some(thread::spawn(move || feed_stdin(stdin, &input))),
+        _ => None,
+    };
+    let read_all = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            pipe.read_to_end(&mut bytes).map(|_| bytes)
+        })
+    };
+    let stdout = child.stdout.take().map(|pipe| read_all(Box::new(pipe)));
+    let stderr = child.stderr.take().map(|pipe| read_all(Box::new(pipe)));
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill_process_group(&mut child);
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    if let Some(feeder) = feeder {
+        let _ = feeder.join();
+    }
+
+    let collect = |reader: Option<JoinHandle<io::Result<Vec<u8>>>>| -> io::Result<Vec<u8>> {
+        match reader {
+            Some(reader) => reader.join().unwrap_or_else(|_| Ok(Vec::new())),
+            None => Ok(Vec::new()),
+        }
+    };
+    let stdout = collect(stdout)?;
+    let stderr = collect(stderr)?;
+    Ok(status.map(|status| Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+/// Kills `child` and, on Unix, the rest of the process group it leads.
+fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
 }
 
 pub fn run_pass_through(config: &ClawConfig) -> Result<()> {
@@ -303,6 +1503,11 @@ pub fn run_pass_through(config: &ClawConfig) -> Result<()> {
             )
         })?,
         ReceiverType::ClaudeCli => "claude".to_string(),
+        ReceiverType::Tmux => anyhow::bail!(
+            "`claw pass` is not available with the Tmux receiver: the LLM is already \
+             running in tmux pane '{}'. Attach to it with `tmux attach`.",
+            config.tmux_target.as_deref().unwrap_or_default()
+        ),
     };
 
     let llm_executable = which::which(&llm_command).with_context(|| {
@@ -332,3 +1537,421 @@ pub fn run_pass_through(config: &ClawConfig) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ShellName;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn tee_options(temp_dir: &TempDir) -> (ReceiverOptions, PathBuf) {
+        let path = temp_dir.path().join("response.md");
+        let options = ReceiverOptions {
+            tee: Some(path.clone()),
+            ..Default::default()
+        };
+        (options, path)
+    }
+
+    #[test]
+    fn test_context_scripts_run_in_declared_order() {
+        let scripts = IndexMap::from([
+            ("zeta".to_string(), "echo first".to_string()),
+            ("beta".to_string(), "exit 1".to_string()),
+            ("alpha".to_string(), "echo never".to_string()),
+        ]);
+        let (results, durations) = execute_context_scripts(&scripts, &HashMap::new());
+        let names: Vec<&String> = results.keys().collect();
+        assert_eq!(names, vec!["zeta", "beta"]);
+        assert_eq!(durations.keys().collect::<Vec<_>>(), names);
+        assert_eq!(results["zeta"].as_ref().unwrap(), "first");
+        assert!(results["beta"].is_err());
+    }
+
+    #[test]
+    fn test_context_script_timeout_stops_the_process_group() {
+        let started = Instant::now();
+        let options = ScriptOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        // The background sleep holds stdout open after the shell is killed
+        let err = run_script_with("slow", "sleep 5 & sleep 5; echo done", &options).unwrap_err();
+        assert!(err.to_string().contains("'slow'"));
+        assert!(err.to_string().contains("timed out after 0.2s"));
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        assert_eq!(run_script_with("quick", "echo ok", &options).unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_script_shell_comes_from_the_script_or_config() {
+        let config = ClawConfig {
+            script_shell: Some(ScriptShell::Named(ShellName::Bash)),
+            ..Default::default()
+        };
+        let plain = ContextScript::from("echo $0");
+        let own: ContextScript = serde_yaml::from_str("run: echo $0\nshell: sh").unwrap();
+        assert_eq!(config.script_shell(&plain), ScriptShell::Named(ShellName::Bash));
+        assert_eq!(config.script_shell(&own), ScriptShell::Named(ShellName::Sh));
+        assert_eq!(ClawConfig::default().script_shell(&plain), ScriptShell::default());
+
+        let options = ScriptOptions::for_script(&config, &plain, &BTreeMap::new());
+        assert_eq!(run_script_with("which", "echo $0", &options).unwrap(), "bash");
+    }
+
+    #[test]
+    fn test_script_shell_can_be_a_command_line() {
+        let config: ClawConfig =
+            serde_yaml::from_str("script_shell: \"bash -o pipefail -c\"").unwrap();
+        let shell = config.script_shell(&ContextScript::from("false | true"));
+        assert_eq!(shell, ScriptShell::Command("bash -o pipefail -c".to_string()));
+
+        let options = ScriptOptions {
+            shell,
+            ..Default::default()
+        };
+        assert!(run_script_with("pipe", "false | true", &options).is_err());
+        assert_eq!(run_script_with("arr", "a=(x y); echo ${a[1]}", &options).unwrap(), "y");
+
+        let options = ScriptOptions {
+            shell: ScriptShell::Command("  ".to_string()),
+            ..Default::default()
+        };
+        let err = run_script_with("blank", "true", &options).unwrap_err();
+        assert!(format!("{:#}", err).contains("isn't a command line"));
+    }
+
+    #[test]
+    fn test_tee_in_stdin_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+
+        // `cat` echoes the piped prompt back on stdout
+        let receiver = GenericReceiver::new("cat".to_string(), String::new()).with_options(options);
+        receiver.send_prompt("hello from stdin").unwrap();
+
+        assert_eq!(fs::read_to_string(tee_path).unwrap(), "hello from stdin");
+    }
+
+    #[test]
+    fn test_huge_stdin_prompt_is_fed_while_output_is_read() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut options, tee_path) = tee_options(&temp_dir);
+        options.background = true;
+
+        // `cat` writes as it reads, so neither pipe can be left to fill up
+        let prompt = "0123456789abcdef\n".repeat(2 * 1024 * 1024);
+        let receiver = GenericReceiver::new("cat".to_string(), String::new()).with_options(options);
+        receiver.send_prompt(&prompt).unwrap();
+        assert_eq!(fs::read_to_string(tee_path).unwrap().len(), prompt.len());
+    }
+
+    #[test]
+    fn test_llm_exiting_before_reading_stdin_reports_its_status() {
+        let receiver = GenericReceiver::new("sh".to_string(), "-c 'exit 3'".to_string());
+        let err = receiver.send_prompt(&"x".repeat(4 * 1024 * 1024)).unwrap_err();
+        let exit = err.downcast_ref::<LlmExitError>().expect("the LLM's exit status");
+        assert_eq!(exit.code(), Some(3));
+    }
+
+    #[test]
+    fn test_tee_in_argument_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+
+        let receiver =
+            GenericReceiver::new("echo".to_string(), "{{prompt}}".to_string()).with_options(options);
+        receiver.send_prompt("hello from args").unwrap();
+
+        assert_eq!(fs::read_to_string(tee_path).unwrap(), "hello from args\n");
+    }
+
+    #[test]
+    fn test_model_options_become_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut options, tee_path) = tee_options(&temp_dir);
+        options.model_options = ModelOptions {
+            temperature: Some(0.2),
+            max_tokens: Some(500),
+            top_p: None,
+        };
+
+        // max_tokens has no flag template, so it is left out
+        let receiver = GenericReceiver::new("echo".to_string(), "{{prompt}}".to_string())
+            .with_options(options)
+            .with_model_option_flags(BTreeMap::from([
+                ("temperature".to_string(), "--temp={{value}}".to_string()),
+                ("top_p".to_string(), "--top-p {{value}}".to_string()),
+            ]));
+        receiver.send_prompt("hi").unwrap();
+
+        assert_eq!(fs::read_to_string(tee_path).unwrap(), "hi --temp=0.2\n");
+    }
+
+    #[test]
+    fn test_fallback_receiver_uses_next_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+        let config = ClawConfig {
+            llm_command: Some("claw-test-missing-llm".to_string()),
+            receiver_fallbacks: Some(vec!["broken".to_string(), "local".to_string()]),
+            profiles: BTreeMap::from([
+                (
+                    "broken".to_string(),
+                    serde_yaml::from_str("llm_command: \"false\"").unwrap(),
+                ),
+                (
+                    "local".to_string(),
+                    serde_yaml::from_str("llm_command: cat\nprompt_arg_template: \"\"").unwrap(),
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let chain = config.receiver_chain().unwrap();
+        let labels: Vec<&str> = chain.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "claw-test-missing-llm",
+                "false (profile 'broken')",
+                "cat (profile 'local')"
+            ]
+        );
+
+        create_receiver(&config, &options)
+            .send_prompt("hello fallback")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&tee_path).unwrap(), "hello fallback");
+
+        let config = ClawConfig {
+            receiver_fallbacks: Some(vec!["missing".to_string()]),
+            ..config
+        };
+        assert!(config.receiver_chain().is_err());
+    }
+
+    #[test]
+    fn test_fan_out_receiver_saves_each_response() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+        let config = ClawConfig {
+            fan_out: Some(vec!["upper".to_string(), "plain".to_string()]),
+            profiles: BTreeMap::from([
+                (
+                    "upper".to_string(),
+                    serde_yaml::from_str("llm_command: tr\nprompt_arg_template: \"a-z A-Z\"")
+                        .unwrap(),
+                ),
+                (
+                    "plain".to_string(),
+                    serde_yaml::from_str("llm_command: cat\nprompt_arg_template: \"\"").unwrap(),
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        create_receiver(&config, &options)
+            .send_prompt("hello fan-out")
+            .unwrap();
+        let saved = |name: &str| {
+            fs::read_to_string(tee_path.with_file_name(format!("response.{}.md", name))).unwrap()
+        };
+        assert_eq!(saved("upper"), "HELLO FAN-OUT");
+        assert_eq!(saved("plain"), "hello fan-out");
+        let combined = fs::read_to_string(&tee_path).unwrap();
+        assert!(combined.starts_with("## upper (tr, "));
+        assert!(combined.contains("\n## plain (cat, "));
+
+        let config = ClawConfig {
+            fan_out: Some(vec!["missing".to_string()]),
+            ..config
+        };
+        assert!(config.fan_out_receivers().is_err());
+    }
+
+    #[test]
+    fn test_format_columns() {
+        let columns = vec![
+            ("a".to_string(), "one\ntwo".to_string()),
+            ("b".to_string(), "abcdefg".to_string()),
+        ];
+        assert_eq!(
+            format_columns(&columns, 5),
+            "a     │ b\n───── │ ─────\none   │ abcde\ntwo   │ fg\n"
+        );
+        assert_eq!(format_sections(&columns[..1]), "── a ──\none\ntwo\n\n");
+    }
+
+    #[test]
+    fn test_retry_runs_a_failing_command_again() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+        let marker = temp_dir.path().join("failed-once");
+        let retry = RetrySettings {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+        };
+
+        // Fails the first time only
+        let script = format!(
+            "-c 'cat >/dev/null; [ -e {0} ] && echo ok || {{ touch {0}; exit 1; }}'",
+            marker.display()
+        );
+        let receiver = GenericReceiver::new("sh".to_string(), script).with_options(options.clone());
+        middleware::LayeredReceiver::new(Box::new(receiver))
+            .layer(middleware::Retry::new("sh", retry.clone()))
+            .send_prompt("hi")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&tee_path).unwrap(), "ok\n");
+
+        let receiver = GenericReceiver::new("false".to_string(), String::new()).with_options(options);
+        let error = middleware::LayeredReceiver::new(Box::new(receiver))
+            .layer(middleware::Retry::new("false", retry))
+            .send_prompt("hi")
+            .unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains("'false' failed 3 times"));
+        assert!(message.contains("attempt 3: LLM command"));
+        assert!(error.downcast_ref::<LlmExitError>().is_some());
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_with_jitter() {
+        let retry = RetrySettings {
+            retries: 5,
+            backoff: Duration::from_millis(1000),
+        };
+        for (attempt, full) in [(1, 1000), (2, 2000), (3, 4000)] {
+            let delay = retry.delay(attempt).as_millis();
+            assert!(delay > full / 2 && delay <= full, "{} ms", delay);
+        }
+        assert!(retry.delay(30) <= MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_background_writes_only_to_tee() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut options, tee_path) = tee_options(&temp_dir);
+        options.background = true;
+
+        let receiver =
+            GenericReceiver::new("echo".to_string(), "{{prompt}}".to_string()).with_options(options);
+        receiver.send_prompt("quiet").unwrap();
+
+        assert_eq!(fs::read_to_string(tee_path).unwrap(), "quiet\n");
+    }
+
+    #[test]
+    fn test_long_prompt_falls_back_to_stdin() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+        let prompt = "x".repeat(2048);
+
+        // `wc -c {{prompt}}` becomes `wc -c`, counting the prompt on stdin
+        let receiver = GenericReceiver::new("wc".to_string(), "-c {{prompt}}".to_string())
+            .with_options(options.clone())
+            .with_arg_overflow(ArgOverflowSettings {
+                max_bytes: Some(1024),
+                ..Default::default()
+            });
+        receiver.send_prompt(&prompt).unwrap();
+        assert_eq!(fs::read_to_string(&tee_path).unwrap().trim(), "2048");
+
+        let receiver = GenericReceiver::new("wc".to_string(), "{{prompt}}".to_string())
+            .with_options(options.clone())
+            .with_arg_overflow(ArgOverflowSettings {
+                stdin_template: Some("-c".to_string()),
+                max_bytes: Some(1024),
+                ..Default::default()
+            });
+        receiver.send_prompt(&prompt).unwrap();
+        assert_eq!(fs::read_to_string(&tee_path).unwrap().trim(), "2048");
+
+        let receiver = GenericReceiver::new("wc".to_string(), "-c {{prompt}}".to_string())
+            .with_options(options)
+            .with_arg_overflow(ArgOverflowSettings {
+                mode: ArgOverflow::Fail,
+                max_bytes: Some(1024),
+                ..Default::default()
+            });
+        let error = receiver.send_prompt(&prompt).unwrap_err();
+        assert!(error.to_string().contains("too long to pass to 'wc'"));
+    }
+
+    #[test]
+    fn test_prompt_over_the_os_limit_goes_to_stdin() {
+        let temp_dir = TempDir::new().unwrap();
+        let (options, tee_path) = tee_options(&temp_dir);
+
+        // Longer than any OS allows on a command line
+        let prompt = "y".repeat(4 * 1024 * 1024);
+        assert!(!fits_on_command_line(&prompt, std::slice::from_ref(&prompt), None));
+        assert!(fits_on_command_line("short", &["short".to_string()], None));
+
+        let receiver = GenericReceiver::new("cat".to_string(), "{{prompt}}".to_string())
+            .with_options(options);
+        receiver.send_prompt(&prompt).unwrap();
+        assert_eq!(fs::read_to_string(tee_path).unwrap().len(), prompt.len());
+    }
+
+    #[test]
+    fn test_page_is_skipped_without_a_terminal() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut options, tee_path) = tee_options(&temp_dir);
+        options.page = true;
+
+        // Test output isn't a terminal, so nothing is paged or held back
+        let receiver = GenericReceiver::new("cat".to_string(), String::new()).with_options(options);
+        receiver.send_prompt("line\n".repeat(500).as_str()).unwrap();
+
+        assert_eq!(fs::read_to_string(tee_path).unwrap(), "line\n".repeat(500));
+    }
+
+    #[test]
+    fn test_tmux_receiver_pastes_into_pane() {
+        if which::which("tmux").is_err() {
+            return;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("pane.txt");
+        let session = format!("claw-test-{}", std::process::id());
+
+        let started = Command::new("tmux")
+            .args(["new-session", "-d", "-s", &session])
+            .arg(format!("cat > '{}'", out.display()))
+            .status()
+            .is_ok_and(|s| s.success());
+        if !started {
+            return;
+        }
+
+        let result = TmuxReceiver::new(session.clone()).send_prompt("line one\nline two");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut content = String::new();
+        while Instant::now() < deadline {
+            content = fs::read_to_string(&out).unwrap_or_default();
+            if content.ends_with("line two\n") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        let _ = Command::new("tmux")
+            .args(["kill-session", "-t", &session])
+            .status();
+
+        result.unwrap();
+        assert_eq!(content, "line one\nline two\n");
+    }
+
+    #[test]
+    fn test_tmux_receiver_reports_missing_pane() {
+        if which::which("tmux").is_err() {
+            return;
+        }
+        let receiver = TmuxReceiver::new("claw-no-such-session-xyz".to_string());
+        assert!(receiver.send_prompt("hello").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! Last known outputs of each goal's context scripts.
+//!
+//! Whenever a goal is rendered, the result of each context script it ran is
+//! stored in `~/.config/claw/cache/scripts/<goal>.json`. The goal browser
+//! shows these so you can tell whether a goal's data-gathering still works
+//! before running it.
+//!
+//! Scripts marked `prefetch: true` are run by `claw daemon`, which stores
+//! their outputs per project directory in `cache/prefetch/`.
+//!
+//! Scripts with a cache TTL (`cache_ttl_secs`, or `script_cache_ttl_secs` in
+//! `claw.yaml`) have their successful outputs kept in `~/.cache/claw/scripts/`,
+//! keyed by the rendered command, and reused until they are older than the TTL.
+
+use anyhow::{Context as AnyhowContext, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use directories::BaseDirs;
+
+use crate::config::ConfigPaths;
+use crate::digest;
+use crate::history;
+
+/// The most recent result of one context script.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedScript {
+    /// Unix timestamp (seconds) when the script ran.
+    pub recorded_at: u64,
+    pub succeeded: bool,
+    /// The script's output, or its error message if it failed.
+    pub output: String,
+}
+
+fn cache_dir() -> Result<Option<PathBuf>> {
+    Ok(ConfigPaths::new()?
+        .global
+        .map(|dir| dir.join("cache").join("scripts")))
+}
+
+/// Stores the results of the scripts that just ran for `goal`, keeping the
+/// cached results of any that didn't run.
+pub fn record(goal: &str, results: &IndexMap<String, Result<String>>) -> Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+    match cache_dir()? {
+        Some(dir) => record_in(&dir, goal, results, history::now_secs()),
+        None => Ok(()),
+    }
+}
+
+/// Loads the cached script results for `goal`.
+pub fn load(goal: &str) -> BTreeMap<String, CachedScript> {
+    cache_dir()
+        .ok()
+        .flatten()
+        .map(|dir| load_from(&dir, goal))
+        .unwrap_or_default()
+}
+
+fn record_in(
+    dir: &Path,
+    goal: &str,
+    results: &IndexMap<String, Result<String>>,
+    now: u64,
+) -> Result<()> {
+    let mut cached = load_from(dir, goal);
+    for (name, result) in results {
+        let (succeeded, output) = match result {
+            Ok(output) => (true, output.clone()),
+            Err(e) => (false, format!("{:#}", e)),
+        };
+        cached.insert(
+            name.clone(),
+            CachedScript {
+                recorded_at: now,
+                succeeded,
+                output,
+            },
+        );
+    }
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+    let path = dir.join(format!("{}.json", goal));
+    let json = serde_json::to_string_pretty(&cached).context("Failed to serialize script cache")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn load_from(dir: &Path, goal: &str) -> BTreeMap<String, CachedScript> {
+    fs::read_to_string(dir.join(format!("{}.json", goal)))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Directory of the prefetched script outputs for the project in `cwd`.
+/// They are kept apart from the last-run cache, since they depend on the
+/// directory the scripts ran in.
+fn prefetch_dir(cwd: &Path) -> Result<Option<PathBuf>> {
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    let key = digest::sha256_hex(cwd.to_string_lossy().as_bytes());
+    Ok(ConfigPaths::new()?
+        .global
+        .map(|dir| dir.join("cache").join("prefetch").join(&key[..16])))
+}
+
+/// Stores the results of prefetched scripts of `goal` that ran in `cwd`.
+pub fn record_prefetched(
+    cwd: &Path,
+    goal: &str,
+    results: &IndexMap<String, Result<String>>,
+) -> Result<()> {
+    match prefetch_dir(cwd)? {
+        Some(dir) => record_in(&dir, goal, results, history::now_secs()),
+        None => Ok(()),
+    }
+}
+
+/// Loads the prefetched script results of `goal` for `cwd`.
+pub fn load_prefetched(cwd: &Path, goal: &str) -> BTreeMap<String, CachedScript> {
+    prefetch_dir(cwd)
+        .ok()
+        .flatten()
+        .map(|dir| load_from(&dir, goal))
+        .unwrap_or_default()
+}
+
+/// A context script output kept for reuse until its TTL runs out.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct StoredOutput {
+    /// Unix timestamp (seconds) when the script ran.
+    recorded_at: u64,
+    /// The rendered command, to make the cache files easy to inspect.
+    command: String,
+    output: String,
+}
+
+fn outputs_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.cache_dir().join("claw").join("scripts"))
+}
+
+/// Returns the output stored under `key` and when it was recorded, if it is
+/// younger than `ttl`.
+pub fn cached_output(key: &str, ttl: Duration) -> Option<(String, u64)> {
+    cached_output_in(&outputs_dir()?, key, ttl, history::now_secs())
+}
+
+/// Stores the successful output of `command` under `key`.
+pub fn store_output(key: &str, command: &str, output: &str) -> Result<()> {
+    match outputs_dir() {
+        Some(dir) => store_output_in(&dir, key, command, output, history::now_secs()),
+        None => Ok(()),
+    }
+}
+
+fn cached_output_in(dir: &Path, key: &str, ttl: Duration, now: u64) -> Option<(String, u64)> {
+    let json = fs::read_to_string(dir.join(format!("{}.json", key))).ok()?;
+    let stored: StoredOutput = serde_json::from_str(&json).ok()?;
+    (now.saturating_sub(stored.recorded_at) < ttl.as_secs())
+        .then_some((stored.output, stored.recorded_at))
+}
+
+fn store_output_in(dir: &Path, key: &str, command: &str, output: &str, now: u64) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+    let stored = StoredOutput {
+        recorded_at: now,
+        command: command.to_string(),
+        output: output.to_string(),
+    };
+    let path = dir.join(format!("{}.json", key));
+    let json =
+        serde_json::to_string_pretty(&stored).context("Failed to serialize script output")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Describes how long ago `then` was, e.g. `5m ago`.
+pub fn format_age(then: u64, now: u64) -> String {
+    let secs = now.saturating_sub(then);
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_keeps_scripts_that_did_not_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        let first = IndexMap::from([
+            ("diff".to_string(), Ok("+ added".to_string())),
+            ("branch".to_string(), Ok("main".to_string())),
+        ]);
+        record_in(dir, "review", &first, 100).unwrap();
+
+        let second = IndexMap::from([("diff".to_string(), Err(anyhow::anyhow!("git failed")))]);
+        record_in(dir, "review", &second, 200).unwrap();
+
+        let cached = load_from(dir, "review");
+        assert!(!cached["diff"].succeeded);
+        assert_eq!(cached["diff"].output, "git failed");
+        assert_eq!(cached["diff"].recorded_at, 200);
+        assert_eq!(cached["branch"].output, "main");
+        assert_eq!(cached["branch"].recorded_at, 100);
+    }
+
+    #[test]
+    fn test_stored_output_expires_after_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(cached_output_in(dir, "abc", ttl, 100), None);
+        store_output_in(dir, "abc", "curl example.com", "<html>", 100).unwrap();
+
+        assert_eq!(
+            cached_output_in(dir, "abc", ttl, 159),
+            Some(("<html>".to_string(), 100))
+        );
+        assert_eq!(cached_output_in(dir, "abc", ttl, 160), None);
+        assert_eq!(cached_output_in(dir, "other", ttl, 120), None);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(100, 130), "just now");
+        assert_eq!(format_age(0, 600), "10m ago");
+        assert_eq!(format_age(0, 7200), "2h ago");
+        assert_eq!(format_age(0, 3 * 86400), "3d ago");
+    }
+}
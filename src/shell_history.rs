@@ -0,0 +1,186 @@
+//! The `--context-shell-history N` section: the last commands from the
+//! user's shell history, with secrets masked.
+//!
+//! The history file is `$HISTFILE` if the shell exports it, or the default
+//! file of the shell in `$SHELL` (bash, zsh or fish). Shells write their
+//! history at different times: bash only when a session ends (unless told
+//! to append after each command), so the newest commands of the current
+//! session may be missing.
+
+use anyhow::{Context as AnyhowContext, Result};
+use directories::BaseDirs;
+use std::path::{Path, PathBuf};
+
+use crate::git_context;
+use crate::redact;
+
+/// The shell history formats claw can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// One command per line, as bash writes it (`#<time>` lines skipped).
+    Plain,
+    /// zsh's extended history, `: <time>:<duration>;<command>`, or plain.
+    Zsh,
+    /// fish's YAML-like `- cmd: <command>` entries.
+    Fish,
+}
+
+/// Builds the section with the last `count` commands of the shell history.
+pub fn section(count: usize) -> Result<String> {
+    let (path, format) = history_file()?;
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let commands = last_commands(&String::from_utf8_lossy(&bytes), format, count);
+    Ok(format_section(&path, &commands))
+}
+
+/// Finds the history file and its format.
+fn history_file() -> Result<(PathBuf, Format)> {
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    let shell = Path::new(&shell)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let format = match shell {
+        "zsh" => Format::Zsh,
+        "fish" => Format::Fish,
+        _ => Format::Plain,
+    };
+    if let Some(path) = std::env::var_os("HISTFILE").filter(|path| !path.is_empty()) {
+        return Ok((PathBuf::from(path), format));
+    }
+
+    let home = BaseDirs::new()
+        .map(|dirs| dirs.home_dir().to_path_buf())
+        .context("Could not find your home directory for the shell history")?;
+    let path = match format {
+        Format::Zsh => home.join(".zsh_history"),
+        Format::Fish => home.join(".local/share/fish/fish_history"),
+        Format::Plain => home.join(".bash_history"),
+    };
+    if !path.exists() {
+        anyhow::bail!(
+            "No shell history found at {}. Set HISTFILE to the file your shell writes.",
+            path.display()
+        );
+    }
+    Ok((path, format))
+}
+
+/// The last `count` commands in `history`, oldest first, with the trailing
+/// `claw` invocation that is running now left out.
+fn last_commands(history: &str, format: Format, count: usize) -> Vec<String> {
+    let mut commands: Vec<String> = match format {
+        Format::Plain => history
+            .lines()
+            .filter(|line| !is_bash_timestamp(line))
+            .map(str::to_string)
+            .collect(),
+        Format::Zsh => history.lines().map(|line| zsh_command(line).to_string()).collect(),
+        Format::Fish => history
+            .lines()
+            .filter_map(|line| line.strip_prefix("- cmd: "))
+            .map(fish_unescape)
+            .collect(),
+    };
+    commands.retain(|command| !command.trim().is_empty());
+    if commands
+        .last()
+        .is_some_and(|command| command.split_whitespace().next() == Some("claw"))
+    {
+        commands.pop();
+    }
+    let skip = commands.len().saturating_sub(count);
+    commands.split_off(skip)
+}
+
+/// Undoes fish's escaping of newlines (`\n`) and backslashes (`\\`).
+fn fish_unescape(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn is_bash_timestamp(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The command of a zsh history line, without its extended-history prefix.
+fn zsh_command(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix(": ")
+        && let Some((meta, command)) = rest.split_once(';')
+        && meta.bytes().all(|b| b.is_ascii_digit() || b == b':')
+    {
+        return command;
+    }
+    line
+}
+
+fn format_section(path: &Path, commands: &[String]) -> String {
+    let body: String = commands
+        .iter()
+        .map(|command| redact::redact(command) + "\n")
+        .collect();
+    let fence = git_context::fence_for(&body);
+    format!(
+        "## Recent Shell History (last {} commands from {}, secrets redacted)\n\n{}sh\n{}{}\n",
+        commands.len(),
+        path.display(),
+        fence,
+        body,
+        fence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_commands_in_each_format() {
+        let bash = "#1700000000\nls\ncargo build\n#1700000001\ncargo test\nclaw why --context-shell-history 2\n";
+        assert_eq!(
+            last_commands(bash, Format::Plain, 2),
+            vec!["cargo build", "cargo test"]
+        );
+
+        let zsh = ": 1700000000:0;make\n: 1700000001:3;make test\nplain\n";
+        assert_eq!(
+            last_commands(zsh, Format::Zsh, 5),
+            vec!["make", "make test", "plain"]
+        );
+
+        let fish = "- cmd: npm test\n  when: 1700000000\n- cmd: echo a\\\\nb\n  when: 1700000001\n";
+        assert_eq!(
+            last_commands(fish, Format::Fish, 5),
+            vec!["npm test", "echo a\\nb"]
+        );
+    }
+
+    #[test]
+    fn test_format_section_redacts_commands() {
+        let section = format_section(
+            Path::new("/home/me/.bash_history"),
+            &["export API_TOKEN=abc".to_string(), "make".to_string()],
+        );
+        assert_eq!(
+            section,
+            "## Recent Shell History (last 2 commands from /home/me/.bash_history, secrets redacted)\n\n\
+             ```sh\nexport API_TOKEN=[REDACTED]\nmake\n```\n"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Color palettes for claw's terminal UIs.
+//!
+//! `tui_theme` in `claw.yaml` picks a palette, or `auto` (the default)
+//! guesses the terminal's background from `$COLORFGBG`, which many
+//! terminals set, and falls back to the dark palette. The high-contrast
+//! palette uses only the terminal's own colors, with bold, underline and
+//! reverse video marking what matters, so it reads on any background.
+
+use ratatui::style::{Color, Modifier, Style};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Which palette claw's terminal UIs use.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TuiTheme {
+    /// Dark or light, from the terminal's background.
+    #[default]
+    Auto,
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl TuiTheme {
+    /// Resolves `Auto` from the environment; other themes are kept.
+    pub fn resolve(self) -> TuiTheme {
+        match self {
+            TuiTheme::Auto => match background_from_colorfgbg(std::env::var("COLORFGBG").ok()) {
+                Some(Background::Light) => TuiTheme::Light,
+                _ => TuiTheme::Dark,
+            },
+            theme => theme,
+        }
+    }
+
+    /// The palette of a resolved theme.
+    pub fn palette(self) -> Palette {
+        match self.resolve() {
+            TuiTheme::Light => Palette::light(),
+            TuiTheme::HighContrast => Palette::high_contrast(),
+            TuiTheme::Auto | TuiTheme::Dark => Palette::dark(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Background {
+    Dark,
+    Light,
+}
+
+/// Reads the background from `$COLORFGBG`, e.g. `15;0` (white on black).
+/// Its last field is the background's ANSI color: 7 and 9-15 are light.
+fn background_from_colorfgbg(value: Option<String>) -> Option<Background> {
+    let value = value?;
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(match background {
+        7 | 9..=15 => Background::Light,
+        _ => Background::Dark,
+    })
+}
+
+/// The styles a terminal UI draws with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Regular text.
+    pub text: Style,
+    /// Help text and other secondary text.
+    pub muted: Style,
+    /// Key names in help footers.
+    pub key: Style,
+    /// The selected item of the focused list.
+    pub selected: Style,
+    /// The selected item of a list without focus.
+    pub selected_inactive: Style,
+    /// The border of the focused panel.
+    pub border: Style,
+    /// The border of other panels.
+    pub border_inactive: Style,
+    /// Messages about the last action.
+    pub status: Style,
+    /// The logo's letters and its underscores.
+    pub logo: (Style, Style),
+}
+
+impl Palette {
+    fn dark() -> Self {
+        let orange = Color::Rgb(255, 165, 0);
+        Palette {
+            text: Style::default().fg(Color::White),
+            muted: Style::default().fg(Color::DarkGray),
+            key: Style::default().fg(orange),
+            selected: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            selected_inactive: Style::default()
+                .fg(Color::Black)
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            border_inactive: Style::default().fg(Color::DarkGray),
+            status: Style::default().fg(Color::Yellow),
+            logo: (
+                Style::default().fg(orange),
+                Style::default().fg(Color::Rgb(144, 238, 144)),
+            ),
+        }
+    }
+
+    fn light() -> Self {
+        let burnt_orange = Color::Rgb(176, 84, 0);
+        Palette {
+            text: Style::default().fg(Color::Black),
+            muted: Style::default().fg(Color::Rgb(96, 96, 96)),
+            key: Style::default()
+                .fg(burnt_orange)
+                .add_modifier(Modifier::BOLD),
+            selected: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            selected_inactive: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Rgb(200, 200, 200))
+                .add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            border_inactive: Style::default().fg(Color::Rgb(150, 150, 150)),
+            status: Style::default().fg(Color::Magenta),
+            logo: (
+                Style::default().fg(burnt_orange),
+                Style::default().fg(Color::Rgb(0, 128, 0)),
+            ),
+        }
+    }
+
+    fn high_contrast() -> Self {
+        let plain = Style::default().fg(Color::Reset).bg(Color::Reset);
+        Palette {
+            text: plain,
+            muted: plain,
+            key: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            selected: plain.add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            selected_inactive: plain.add_modifier(Modifier::REVERSED),
+            border: plain.add_modifier(Modifier::BOLD),
+            border_inactive: plain,
+            status: plain.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            logo: (plain.add_modifier(Modifier::BOLD), plain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_from_colorfgbg() {
+        let read = |value: &str| background_from_colorfgbg(Some(value.to_string()));
+        assert_eq!(read("15;0"), Some(Background::Dark));
+        assert_eq!(read("0;15"), Some(Background::Light));
+        assert_eq!(read("0;default;7"), Some(Background::Light));
+        assert_eq!(read("default"), None);
+        assert_eq!(background_from_colorfgbg(None), None);
+    }
+
+    #[test]
+    fn test_explicit_themes_are_kept() {
+        assert_eq!(TuiTheme::Light.resolve(), TuiTheme::Light);
+        assert_eq!(TuiTheme::HighContrast.palette(), Palette::high_contrast());
+        let theme: TuiTheme = serde_yaml::from_str("high-contrast").unwrap();
+        assert_eq!(theme, TuiTheme::HighContrast);
+    }
+}
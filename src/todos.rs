@@ -0,0 +1,177 @@
+//! TODO/FIXME/HACK extraction for `--context-todos`.
+//!
+//! Scans files for comments marked `TODO`, `FIXME` or `HACK` and lists each
+//! with its location and a few lines around it, so backlog-grooming goals
+//! don't need their own grep script.
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Lines shown before and after each marked line.
+const SURROUNDING_LINES: usize = 2;
+
+/// At most this many markers are listed in the prompt.
+const MAX_TODOS: usize = 200;
+
+/// Matches a marker as a whole upper-case word, with an optional `(owner)`
+/// and colon, capturing the marker and the rest of the line.
+static MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(TODO|FIXME|HACK)\b(?:\([^)]*\))?:?\s*(.*)$").unwrap());
+
+/// A marked comment found in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Todo {
+    pub path: PathBuf,
+    /// 1-based line of the marker.
+    pub line: usize,
+    /// `TODO`, `FIXME` or `HACK`.
+    pub marker: String,
+    /// The comment text after the marker.
+    pub text: String,
+    /// The marked line and its neighbours, as (1-based line, text).
+    pub snippet: Vec<(usize, String)>,
+}
+
+/// Finds the marked comments in the given files and directories.
+/// Directories are walked like `--context` ones; files that can't be read
+/// as text, or are larger than `max_file_size_kb`, are skipped.
+pub fn scan(
+    paths: &[PathBuf],
+    respect_gitignore: bool,
+    excluded_dirs: &[String],
+    excluded_extensions: &[String],
+    max_file_size_kb: u64,
+) -> Result<Vec<Todo>> {
+    let mut todos = Vec::new();
+    for path in paths {
+        if !path.exists() {
+            anyhow::bail!("Path does not exist: {}", path.display());
+        }
+        let excluded_dirs = excluded_dirs.to_vec();
+        let walker = WalkBuilder::new(path)
+            .standard_filters(respect_gitignore)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                !(entry.file_type().is_some_and(|t| t.is_dir())
+                    && excluded_dirs.contains(&entry.file_name().to_string_lossy().to_string()))
+            })
+            .build();
+
+        for entry in walker.flatten() {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let extension = entry
+                .path()
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if excluded_extensions.contains(&extension)
+                || entry
+                    .metadata()
+                    .is_ok_and(|m| m.len() > max_file_size_kb * 1024)
+            {
+                continue;
+            }
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                todos.extend(find_todos(entry.path(), &content));
+            }
+        }
+    }
+    todos.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    todos.dedup_by(|a, b| a.path == b.path && a.line == b.line);
+    Ok(todos)
+}
+
+/// Finds the marked comments in one file's content.
+fn find_todos(path: &Path, content: &str) -> Vec<Todo> {
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let captures = MARKER.captures(line)?;
+            let text = captures[2]
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim();
+            let first = i.saturating_sub(SURROUNDING_LINES);
+            let last = (i + SURROUNDING_LINES).min(lines.len() - 1);
+            Some(Todo {
+                path: path.to_path_buf(),
+                line: i + 1,
+                marker: captures[1].to_string(),
+                text: text.to_string(),
+                snippet: (first..=last)
+                    .map(|n| (n + 1, lines[n].to_string()))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Formats the markers as a prompt section.
+pub fn format(todos: &[Todo]) -> String {
+    let mut output = String::from("## TODO/FIXME/HACK Comments\n\n");
+    if todos.is_empty() {
+        output.push_str("No TODO, FIXME or HACK comments were found.\n");
+        return output;
+    }
+    output.push_str(&format!("{} found.\n", todos.len()));
+
+    for todo in todos.iter().take(MAX_TODOS) {
+        output.push_str(&format!(
+            "\n### {}:{} {}",
+            todo.path.display(),
+            todo.line,
+            todo.marker
+        ));
+        if !todo.text.is_empty() {
+            output.push_str(&format!(": {}", todo.text));
+        }
+        output.push_str("\n\n```\n");
+        let width = todo.snippet.last().map_or(1, |(n, _)| n.to_string().len());
+        for (n, line) in &todo.snippet {
+            let pointer = if *n == todo.line { ">" } else { " " };
+            output.push_str(&format!("{} {:>width$} | {}\n", pointer, n, line));
+        }
+        output.push_str("```\n");
+    }
+    if todos.len() > MAX_TODOS {
+        output.push_str(&format!("\n... {} more not shown\n", todos.len() - MAX_TODOS));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_format_todos() {
+        let content = "fn main() {\n    // TODO(ana): handle errors\n    run();\n    /* FIXME leaks */\n}\nlet todo_list = TODOS;\n";
+        let todos = find_todos(Path::new("src/main.rs"), content);
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].line, 2);
+        assert_eq!(todos[0].marker, "TODO");
+        assert_eq!(todos[0].text, "handle errors");
+        assert_eq!(todos[1].text, "leaks");
+        assert_eq!(todos[1].snippet.first().unwrap().0, 2);
+        assert_eq!(todos[1].snippet.last().unwrap().0, 6);
+
+        let section = format(&todos[..1]);
+        assert_eq!(
+            section,
+            "## TODO/FIXME/HACK Comments\n\n1 found.\n\n\
+             ### src/main.rs:2 TODO: handle errors\n\n```\n\
+             \x20 1 | fn main() {\n\
+             > 2 |     // TODO(ana): handle errors\n\
+             \x20 3 |     run();\n\
+             \x20 4 |     /* FIXME leaks */\n```\n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Token estimates for prompts.
+//!
+//! Models count their context in tokens, and different model families split
+//! text differently: OpenAI's `cl100k` and `o200k` encodings merge digits in
+//! groups of three and runs of spaces into one token, while Llama's
+//! SentencePiece vocabulary is smaller and splits every digit. claw doesn't
+//! ship the BPE vocabularies, so it only estimates: text is split the way
+//! each tokenizer pre-tokenizes it, and each piece is costed from the
+//! family's typical characters per token. That tells prompt sizes apart,
+//! but isn't a count to budget a context window to the token.
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::config::{ClawConfig, ReceiverType};
+
+/// The tokenizer family whose token counts are estimated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Tokenizer {
+    /// GPT-4 and GPT-3.5 (`cl100k_base`). Also a fair estimate for Claude.
+    Cl100k,
+    /// GPT-4o and later OpenAI models (`o200k_base`).
+    O200k,
+    /// Llama 2 and other SentencePiece models with a 32k vocabulary.
+    Llama,
+}
+
+impl fmt::Display for Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Tokenizer::Cl100k => "cl100k",
+            Tokenizer::O200k => "o200k",
+            Tokenizer::Llama => "llama",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Average characters per token for each kind of piece.
+struct Rates {
+    /// ASCII words.
+    word: f64,
+    /// Words with non-ASCII letters (accents, CJK, ...).
+    other_word: f64,
+    /// Runs of punctuation and symbols.
+    symbol: f64,
+    /// Digits per token.
+    digits: usize,
+}
+
+impl Tokenizer {
+    /// The tokenizer configured in claw.yaml, or a guess from the receiver
+    /// and LLM command.
+    pub fn for_config(config: &ClawConfig) -> Tokenizer {
+        if let Some(tokenizer) = config.tokenizer {
+            return tokenizer;
+        }
+        if config.receiver_type == Some(ReceiverType::ClaudeCli) {
+            return Tokenizer::Cl100k;
+        }
+        let command = config
+            .llm_command
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        if command.contains("llama") {
+            Tokenizer::Llama
+        } else if ["gpt", "openai", "codex"]
+            .iter()
+            .any(|name| command.contains(name))
+        {
+            Tokenizer::O200k
+        } else {
+            Tokenizer::Cl100k
+        }
+    }
+
+    fn rates(self) -> Rates {
+        match self {
+            Tokenizer::Cl100k => Rates {
+                word: 6.0,
+                other_word: 1.5,
+                symbol: 2.0,
+                digits: 3,
+            },
+            Tokenizer::O200k => Rates {
+                word: 7.0,
+                other_word: 2.5,
+                symbol: 2.5,
+                digits: 3,
+            },
+            Tokenizer::Llama => Rates {
+                word: 4.5,
+                other_word: 1.0,
+                symbol: 1.0,
+                digits: 1,
+            },
+        }
+    }
+
+    /// Estimates the number of tokens in `text`.
+    pub fn estimate(self, text: &str) -> usize {
+        static PIECES: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}+| ?[^\s\p{L}\p{N}]+|\s*[\r\n]+|[ \t]+")
+                .unwrap()
+        });
+
+        let rates = self.rates();
+        let per = |len: usize, rate: f64| (len as f64 / rate).ceil() as usize;
+        PIECES
+            .find_iter(text)
+            .map(|piece| {
+                let piece = piece.as_str();
+                let chars = piece.chars().count();
+                let first = piece.trim_start().chars().next().unwrap_or(' ');
+                if first.is_numeric() {
+                    chars.div_ceil(rates.digits)
+                } else if piece.trim().is_empty() {
+                    if piece.contains('\n') || self != Tokenizer::Llama {
+                        1
+                    } else {
+                        per(chars, 4.0)
+                    }
+                } else if piece.chars().any(char::is_alphabetic) {
+                    if piece.is_ascii() {
+                        per(chars, rates.word)
+                    } else {
+                        per(chars, rates.other_word)
+                    }
+                } else {
+                    per(piece.trim_start().chars().count(), rates.symbol)
+                }
+            })
+            .sum()
+    }
+}
+
+/// Formats a token estimate as e.g. `~850 tokens` or `~12.3k tokens`.
+pub fn format_estimate(count: usize) -> String {
+    if count < 1000 {
+        format!("~{} tokens", count)
+    } else {
+        format!("~{:.1}k tokens", count as f64 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_by_family() {
+        assert_eq!(Tokenizer::Cl100k.estimate("Hello world"), 2);
+        assert_eq!(Tokenizer::Cl100k.estimate(""), 0);
+
+        // Digits: three per token in OpenAI encodings, one per token in Llama
+        assert_eq!(Tokenizer::Cl100k.estimate("1234567"), 3);
+        assert_eq!(Tokenizer::Llama.estimate("1234567"), 7);
+
+        // Non-Latin text costs more tokens in the older, smaller vocabularies
+        let japanese = "日本語のテキストです";
+        assert!(Tokenizer::O200k.estimate(japanese) < Tokenizer::Cl100k.estimate(japanese));
+        assert!(Tokenizer::Cl100k.estimate(japanese) < Tokenizer::Llama.estimate(japanese));
+
+        let code = "fn main() {\n    println!(\"hello, {}\", name);\n}\n";
+        let cl100k = Tokenizer::Cl100k.estimate(code);
+        assert!((12..=24).contains(&cl100k), "{}", cl100k);
+        assert!(Tokenizer::Llama.estimate(code) > cl100k);
+    }
+
+    #[test]
+    fn test_for_config() {
+        let config = |llm_command: &str, tokenizer| ClawConfig {
+            llm_command: Some(llm_command.to_string()),
+            tokenizer,
+            ..Default::default()
+        };
+        assert_eq!(
+            Tokenizer::for_config(&config("claude", None)),
+            Tokenizer::Cl100k
+        );
+        assert_eq!(
+            Tokenizer::for_config(&config("codex", None)),
+            Tokenizer::O200k
+        );
+        assert_eq!(
+            Tokenizer::for_config(&config("llama-cli", None)),
+            Tokenizer::Llama
+        );
+        assert_eq!(
+            Tokenizer::for_config(&config("llama-cli", Some(Tokenizer::O200k))),
+            Tokenizer::O200k
+        );
+    }
+
+    #[test]
+    fn test_format_estimate() {
+        assert_eq!(format_estimate(850), "~850 tokens");
+        assert_eq!(format_estimate(12_345), "~12.3k tokens");
+    }
+}
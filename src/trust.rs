@@ -0,0 +1,388 @@
+//! Trust decisions for repository-local `.claw` directories.
+//!
+//! A cloned repository can ship a `.claw/` whose goals and `claw.yaml` run
+//! arbitrary shell commands. Like direnv, claw asks before running commands
+//! from a local directory it hasn't seen and remembers the answer for that
+//! path and content hash, so any change to the directory asks again.
+
+use anyhow::{Context as AnyhowContext, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::{self, ClawConfig, ConfigPaths, GoalSource, LoadedGoal};
+use crate::digest;
+use crate::goal_lock;
+use crate::param_prompt;
+
+/// A remembered answer for a local directory.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Trusted,
+    Denied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustRecord {
+    /// SHA-256 of the directory contents when the decision was made.
+    hash: String,
+    decision: Decision,
+}
+
+/// Trust decisions keyed by canonical `.claw` path, stored in
+/// `~/.config/claw/trust.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrustStore {
+    #[serde(default)]
+    directories: BTreeMap<PathBuf, TrustRecord>,
+}
+
+impl TrustStore {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read trust store {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse trust store {}", path.display()))
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize trust store")?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write trust store {}", path.display()))
+    }
+}
+
+/// Why claw needs to ask about a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unknown {
+    /// The directory has never been seen.
+    New,
+    /// The directory was trusted, but its contents changed since.
+    Changed,
+}
+
+/// Requires trust for the local `.claw` directory before running a goal's
+/// commands, if the goal is local and runs any (see
+/// `PromptConfig::runs_commands`). Any goal that runs something, global
+/// ones included, also needs a local `claw.yaml` trusted, since its
+/// `script_shell`, `env`, `test_command` and `toolchain_report` apply.
+pub fn ensure_goal_trusted(goal: &LoadedGoal, claw_config: &ClawConfig) -> Result<()> {
+    let runs_commands = goal.config.runs_commands(claw_config);
+    if goal.source == GoalSource::Local && runs_commands {
+        ensure_local_trusted()?;
+    }
+    if runs_commands || !goal.config.context_builtin.is_empty() {
+        ensure_local_config_trusted()?;
+    }
+    Ok(())
+}
+
+/// Requires trust for the local `.claw` directory if it has a `claw.yaml`,
+/// which chooses the LLM command and context providers claw will execute.
+pub fn ensure_local_config_trusted() -> Result<()> {
+    if ConfigPaths::new()?.has_local_claw_config() {
+        ensure_local_trusted()?;
+    }
+    Ok(())
+}
+
+/// Returns true if the local `.claw` directory is currently trusted, without
+/// asking. Used where prompting is impossible, such as shell completion.
+pub fn is_local_trusted() -> bool {
+    let check = || -> Result<bool> {
+        let Some((local, store_path)) = local_and_store()? else {
+            return Ok(false);
+        };
+        let store = TrustStore::load(&store_path)?;
+        let key = canonical(&local)?;
+        let hash = hash_directory(&local)?;
+        Ok(store
+            .directories
+            .get(&key)
+            .is_some_and(|r| r.decision == Decision::Trusted && r.hash == hash))
+    };
+    check().unwrap_or(false)
+}
+
+/// Records a decision for the local `.claw` directory (`claw trust`).
+pub fn set_local_decision(decision: Decision) -> Result<PathBuf> {
+    let (local, store_path) = local_and_store()?.context("No local .claw directory")?;
+    let key = canonical(&local)?;
+    let mut store = TrustStore::load(&store_path)?;
+    store.directories.insert(
+        key.clone(),
+        TrustRecord {
+            hash: hash_directory(&local)?,
+            decision,
+        },
+    );
+    store.save(&store_path)?;
+    Ok(key)
+}
+
+fn ensure_local_trusted() -> Result<()> {
+    let Some((local, store_path)) = local_and_store()? else {
+        return Ok(());
+    };
+    check_trust(&local, &store_path, ask)
+}
+
+/// Checks the stored decision for `local`, calling `ask` when there is none
+/// for its current contents. `ask` returns `None` when it cannot ask.
+fn check_trust(
+    local: &Path,
+    store_path: &Path,
+    ask: impl FnOnce(&Path, Unknown) -> Result<Option<bool>>,
+) -> Result<()> {
+    let key = canonical(local)?;
+    let hash = hash_directory(local)?;
+    let mut store = TrustStore::load(store_path)?;
+
+    let unknown = match store.directories.get(&key) {
+        Some(record) if record.hash == hash => match record.decision {
+            Decision::Trusted => return Ok(()),
+            Decision::Denied => anyhow::bail!(not_trusted_message(&key)),
+        },
+        Some(_) => Unknown::Changed,
+        None => Unknown::New,
+    };
+
+    let Some(trusted) = ask(&key, unknown)? else {
+        anyhow::bail!(not_trusted_message(&key));
+    };
+
+    let decision = if trusted {
+        Decision::Trusted
+    } else {
+        Decision::Denied
+    };
+    store
+        .directories
+        .insert(key.clone(), TrustRecord { hash, decision });
+    store.save(store_path)?;
+
+    if !trusted {
+        anyhow::bail!(not_trusted_message(&key));
+    }
+    Ok(())
+}
+
+/// Asks on the terminal whether to trust `dir`.
+fn ask(dir: &Path, unknown: Unknown) -> Result<Option<bool>> {
+    if !param_prompt::is_interactive() {
+        return Ok(None);
+    }
+
+    match unknown {
+        Unknown::New => eprintln!(
+            "⚠️  claw has not seen this local configuration before:\n    {}",
+            dir.display()
+        ),
+        Unknown::Changed => eprintln!(
+            "⚠️  This local configuration changed since you trusted it:\n    {}",
+            dir.display()
+        ),
+    }
+    eprintln!(
+        "It can make claw run shell commands (context scripts, providers, post_process, \
+         the LLM command)."
+    );
+    eprint!("Review its files. Trust it? [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(Some(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes"
+    )))
+}
+
+fn not_trusted_message(dir: &Path) -> String {
+    format!(
+        "The local configuration at {} is not trusted, so claw won't run its commands.\n\
+         Review its files, then run `claw trust` in this repository to trust it.",
+        dir.display()
+    )
+}
+
+/// Returns the local `.claw` directory, if there is one, and the trust store
+/// path. The store's directory is created when a decision is saved; with no
+/// home directory to put it in, trust can't be remembered, so this fails.
+fn local_and_store() -> Result<Option<(PathBuf, PathBuf)>> {
+    let Some(local) = ConfigPaths::new()?.local else {
+        return Ok(None);
+    };
+    let global = config::global_config_dir_path().context(
+        "Cannot find a home directory to remember trust in, so claw won't run local commands",
+    )?;
+    Ok(Some((local, global.join("trust.json"))))
+}
+
+fn canonical(dir: &Path) -> Result<PathBuf> {
+    fs::canonicalize(dir).with_context(|| format!("Failed to resolve {}", dir.display()))
+}
+
+/// Hashes the relative paths and contents of every file under `dir`, except
+/// those claw writes there itself (see `written_by_claw`). They change with
+/// every run without changing what claw runs.
+fn hash_directory(dir: &Path) -> Result<String> {
+    // A policy that doesn't load fails the run elsewhere
+    let audit_log = config::load_policy(dir)
+        .ok()
+        .and_then(|policy| policy.audit_log);
+    let mut hashed = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("Failed to scan {}", dir.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        if written_by_claw(relative) || is_audit_log(entry.path(), audit_log.as_deref()) {
+            continue;
+        }
+        let content = fs::read(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        hashed.extend_from_slice(relative.to_string_lossy().as_bytes());
+        hashed.push(0);
+        hashed.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        hashed.extend_from_slice(&content);
+    }
+    Ok(digest::sha256_hex(&hashed))
+}
+
+/// Whether `relative` is a goal lock, `locks/<goal>.lock`, or a `claw test`
+/// snapshot, `goals/<goal>/__snapshots__/<case>.md`.
+fn written_by_claw(relative: &Path) -> bool {
+    let parts: Vec<_> = relative.iter().collect();
+    let extension = relative.extension().and_then(|ext| ext.to_str());
+    match parts.as_slice() {
+        [locks, _] => *locks == goal_lock::LOCKS_DIR && extension == Some("lock"),
+        [goals, _, snapshots, _] => {
+            *goals == "goals" && *snapshots == config::SNAPSHOTS_DIR && extension == Some("md")
+        }
+        _ => false,
+    }
+}
+
+/// Whether `path` is the policy's audit log. Only a `.jsonl` one is left out,
+/// so `audit_log` can't be pointed at a script to hide it from the hash.
+fn is_audit_log(path: &Path, audit_log: Option<&Path>) -> bool {
+    audit_log == Some(path) && path.extension().is_some_and(|ext| ext == "jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let local = temp_dir.path().join(".claw");
+        fs::create_dir_all(local.join("goals/review")).unwrap();
+        fs::write(local.join("goals/review/prompt.yaml"), "prompt: hi").unwrap();
+        let store = temp_dir.path().join("trust.json");
+        (temp_dir, local, store)
+    }
+
+    #[test]
+    fn test_decision_is_remembered_until_contents_change() {
+        let (_temp_dir, local, store) = setup();
+        let asked = Cell::new(0);
+        let answer = |answer: bool| {
+            let asked = &asked;
+            move |_: &Path, _: Unknown| {
+                asked.set(asked.get() + 1);
+                Ok(Some(answer))
+            }
+        };
+
+        check_trust(&local, &store, answer(true)).unwrap();
+        check_trust(&local, &store, answer(false)).unwrap();
+        assert_eq!(asked.get(), 1);
+
+        fs::write(local.join("goals/review/prompt.yaml"), "prompt: changed").unwrap();
+        assert!(check_trust(&local, &store, answer(false)).is_err());
+        assert_eq!(asked.get(), 2);
+
+        // A denial is remembered too
+        assert!(check_trust(&local, &store, answer(true)).is_err());
+        assert_eq!(asked.get(), 2);
+    }
+
+    #[test]
+    fn test_changed_directory_is_reported_as_changed() {
+        let (_temp_dir, local, store) = setup();
+        check_trust(&local, &store, |_, unknown| {
+            assert_eq!(unknown, Unknown::New);
+            Ok(Some(true))
+        })
+        .unwrap();
+
+        fs::write(local.join("claw.yaml"), "llm_command: sh").unwrap();
+        check_trust(&local, &store, |_, unknown| {
+            assert_eq!(unknown, Unknown::Changed);
+            Ok(Some(true))
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_files_claw_writes_leave_the_hash_alone() {
+        let (_temp_dir, local, _store) = setup();
+        fs::write(local.join("policy.yaml"), "audit_log: .claw/audit.jsonl\n").unwrap();
+        let hash = hash_directory(&local).unwrap();
+
+        fs::create_dir_all(local.join("locks")).unwrap();
+        fs::write(local.join("locks/review.lock"), "123\n").unwrap();
+        fs::create_dir_all(local.join("goals/review/__snapshots__")).unwrap();
+        fs::write(local.join("goals/review/__snapshots__/default.md"), "hi").unwrap();
+        fs::write(local.join("audit.jsonl"), "{}\n").unwrap();
+        assert_eq!(hash_directory(&local).unwrap(), hash);
+
+        fs::write(local.join("goals/review/check.sh"), "rm -rf /").unwrap();
+        assert_ne!(hash_directory(&local).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_other_files_next_to_claws_still_change_the_hash() {
+        let (_temp_dir, local, _store) = setup();
+        fs::write(local.join("policy.yaml"), "audit_log: .claw/run.sh\n").unwrap();
+        fs::create_dir_all(local.join("goals/review/__snapshots__")).unwrap();
+        let mut hash = hash_directory(&local).unwrap();
+
+        for file in [
+            "goals/review/__snapshots__/check.sh",
+            "locks/review.sh",
+            "locks/.gitignore",
+            "run.sh",
+        ] {
+            let path = local.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "rm -rf /").unwrap();
+            let changed = hash_directory(&local).unwrap();
+            assert_ne!(changed, hash, "{}", file);
+            hash = changed;
+        }
+    }
+
+    #[test]
+    fn test_cannot_ask_is_not_trusted() {
+        let (_temp_dir, local, store) = setup();
+        let err = check_trust(&local, &store, |_, _| Ok(None)).unwrap_err();
+        assert!(err.to_string().contains("claw trust"));
+        assert!(!store.exists());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::config::GoalParameter;
-use anyhow::Result;
+use crate::dates;
+use anyhow::{Context as AnyhowContext, Result};
 use std::collections::HashMap;
 
 /// Validates parameters against a goal's parameter definitions.
@@ -26,6 +27,11 @@ impl<'a> ParameterValidator<'a> {
 
     /// Validates the provided arguments against the goal's parameter definitions.
     /// Returns a HashMap with all parameters (including defaults) if validation succeeds.
+    ///
+    /// Defaults are Tera templates that can use `Args`: the given arguments
+    /// and the defaults of the parameters declared before, e.g.
+    /// `{{ Args.service }}-staging`.
+    #[allow(clippy::collapsible_if)]
     pub fn validate(&self, args: &HashMap<String, String>) -> Result<HashMap<String, String>> {
         // If there are no parameter definitions, accept all arguments as-is
         if self.parameters.is_empty() {
@@ -40,12 +46,32 @@ impl<'a> ParameterValidator<'a> {
             });
         }
 
-        // Build the final parameter map with defaults applied
+        for param in self.parameters {
+            if let (Some(choices), Some(value)) = (&param.choices, args.get(&param.name))
+                && !choices.contains(value)
+            {
+                anyhow::bail!(
+                    "Invalid value '{}' for --{}. Expected one of: {}",
+                    value,
+                    param.name,
+                    choices.join(", ")
+                );
+            }
+        }
+
+        // Build the final parameter map with defaults applied, in
+        // declaration order so a default can use the ones before it
         let mut result = args.clone();
         for param in self.parameters {
             if !result.contains_key(&param.name) {
                 if let Some(default) = &param.default {
-                    result.insert(param.name.clone(), default.clone());
+                    let value = render_default(default, &result).with_context(|| {
+                        format!(
+                            "Failed to work out the default of --{} for goal '{}'",
+                            param.name, self.goal_name
+                        )
+                    })?;
+                    result.insert(param.name.clone(), value);
                 }
             }
         }
@@ -53,6 +79,24 @@ impl<'a> ParameterValidator<'a> {
         Ok(result)
     }
 
+    /// Fills in parameters that weren't given from the environment variables
+    /// they're bound to with `env:`. `lookup` reads a variable; empty values
+    /// count as unset.
+    pub fn apply_env(
+        &self,
+        args: &mut HashMap<String, String>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) {
+        for param in self.parameters {
+            if !args.contains_key(&param.name)
+                && let Some(var) = &param.env
+                && let Some(value) = lookup(var).filter(|value| !value.is_empty())
+            {
+                args.insert(param.name.clone(), value);
+            }
+        }
+    }
+
     /// Returns a list of required parameters that are missing from the provided arguments.
     pub fn get_missing_required(&self, args: &HashMap<String, String>) -> Vec<GoalParameter> {
         self.parameters
@@ -63,6 +107,17 @@ impl<'a> ParameterValidator<'a> {
     }
 }
 
+/// Renders a default that refers to other parameters; plain defaults are
+/// used as written.
+fn render_default(default: &str, args: &HashMap<String, String>) -> Result<String> {
+    if !default.contains("{{") && !default.contains("{%") {
+        return Ok(default.to_string());
+    }
+    let mut context = tera::Context::new();
+    context.insert("Args", args);
+    dates::render_str(default, &context).map_err(anyhow::Error::new)
+}
+
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -76,6 +131,9 @@ impl std::fmt::Display for ValidationError {
             if let Some(param_type) = &param.param_type {
                 write!(f, " <{:?}>", param_type)?;
             }
+            if let Some(var) = &param.env {
+                write!(f, " (or set ${})", var)?;
+            }
             writeln!(f)?;
             writeln!(f, "      {}", param.description)?;
         }
@@ -99,6 +157,10 @@ mod tests {
             required,
             param_type: Some(ParameterType::String),
             default: default.map(|s| s.to_string()),
+            ui: None,
+            choices: None,
+            choices_script: None,
+            env: None,
         }
     }
 
@@ -147,6 +209,39 @@ mod tests {
         assert_eq!(validated.get("format"), Some(&"markdown".to_string()));
     }
 
+    #[test]
+    fn test_default_can_use_earlier_parameters() {
+        let params = vec![
+            create_test_param("service", true, None),
+            create_test_param("env", false, Some("{{ Args.service }}-staging")),
+            create_test_param("url", false, Some("https://{{ Args.env }}.example.com")),
+        ];
+        let validator = ParameterValidator::new(&params, "deploy".to_string());
+        let args = HashMap::from([("service".to_string(), "billing".to_string())]);
+
+        let validated = validator.validate(&args).unwrap();
+        assert_eq!(validated["env"], "billing-staging");
+        assert_eq!(validated["url"], "https://billing-staging.example.com");
+
+        // A given value replaces the default others are built from
+        let args = HashMap::from([
+            ("service".to_string(), "billing".to_string()),
+            ("env".to_string(), "prod".to_string()),
+        ]);
+        assert_eq!(validator.validate(&args).unwrap()["url"], "https://prod.example.com");
+    }
+
+    #[test]
+    fn test_default_using_a_later_parameter_fails() {
+        let params = vec![
+            create_test_param("env", false, Some("{{ Args.service }}-staging")),
+            create_test_param("service", false, Some("billing")),
+        ];
+        let validator = ParameterValidator::new(&params, "deploy".to_string());
+        let err = validator.validate(&HashMap::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("default of --env"));
+    }
+
     #[test]
     fn test_provided_value_overrides_default() {
         let params = vec![create_test_param("format", false, Some("markdown"))];
@@ -158,4 +253,47 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().get("format"), Some(&"json".to_string()));
     }
+
+    #[test]
+    fn test_value_must_be_one_of_choices() {
+        let mut param = create_test_param("format", false, Some("markdown"));
+        param.choices = Some(vec!["markdown".to_string(), "json".to_string()]);
+        let params = vec![param];
+        let validator = ParameterValidator::new(&params, "test-goal".to_string());
+
+        let mut args = HashMap::new();
+        args.insert("format".to_string(), "json".to_string());
+        assert!(validator.validate(&args).is_ok());
+
+        args.insert("format".to_string(), "xml".to_string());
+        let err = validator.validate(&args).unwrap_err();
+        assert!(err.to_string().contains("Expected one of: markdown, json"));
+    }
+
+    #[test]
+    fn test_env_binding_fills_missing_parameters() {
+        let mut ticket = create_test_param("ticket", true, None);
+        ticket.env = Some("JIRA_TICKET".to_string());
+        ticket.choices = Some(vec!["ABC-1".to_string()]);
+        let mut scope = create_test_param("scope", false, None);
+        scope.env = Some("CLAW_SCOPE".to_string());
+        let params = vec![ticket, scope];
+        let validator = ParameterValidator::new(&params, "test-goal".to_string());
+        let lookup = |var: &str| match var {
+            "JIRA_TICKET" => Some("ABC-1".to_string()),
+            "CLAW_SCOPE" => Some(String::new()),
+            _ => None,
+        };
+
+        let mut args = HashMap::new();
+        validator.apply_env(&mut args, lookup);
+        assert_eq!(args.get("ticket"), Some(&"ABC-1".to_string()));
+        assert!(!args.contains_key("scope"));
+        assert!(validator.validate(&args).is_ok());
+
+        // The command line wins, and the value is still validated
+        let mut args = HashMap::from([("ticket".to_string(), "XYZ-9".to_string())]);
+        validator.apply_env(&mut args, lookup);
+        assert!(validator.validate(&args).is_err());
+    }
 }
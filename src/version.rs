@@ -0,0 +1,151 @@
+//! claw's own version, and the `requires_claw` constraints goals place on it.
+//!
+//! Constraints use Cargo's syntax: comma-separated comparators such as
+//! `>=0.5`, `<1.0`, `~0.9.1` or `^0.8`, where a bare version means `^`.
+
+use anyhow::{Result, bail};
+
+/// The version of this build of claw.
+pub const CURRENT: &str = env!("CARGO_PKG_VERSION");
+
+type Version = (u64, u64, u64);
+
+/// A version with possibly missing minor and patch parts, e.g. `0.5`.
+struct Partial {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl Partial {
+    fn parse(text: &str) -> Option<Partial> {
+        // Pre-release and build suffixes are ignored
+        let core = text.split(['-', '+']).next()?;
+        let mut parts = core.split('.');
+        let mut next = || parts.next().map(|part| part.parse::<u64>().ok());
+        let major = next()??;
+        let minor = next().map_or(Some(None), |part| part.map(Some))?;
+        let patch = next().map_or(Some(None), |part| part.map(Some))?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Partial {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    fn lowest(&self) -> Version {
+        (self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    /// The first version past everything this one matches, e.g. `0.6.0`
+    /// for `0.5` and `0.5.2` for `0.5.1`.
+    fn bump_last(&self) -> Version {
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => (self.major, minor, patch + 1),
+            (Some(minor), None) => (self.major, minor + 1, 0),
+            _ => (self.major + 1, 0, 0),
+        }
+    }
+
+    /// The exclusive upper bound of `^`: the leftmost non-zero part may not
+    /// change.
+    fn bump_caret(&self) -> Version {
+        match (self.minor, self.patch) {
+            _ if self.major > 0 => (self.major + 1, 0, 0),
+            (Some(minor), _) if minor > 0 => (0, minor + 1, 0),
+            _ => self.bump_last(),
+        }
+    }
+
+    /// The exclusive upper bound of `~`: only the patch part may change,
+    /// or the minor part if none was given.
+    fn bump_tilde(&self) -> Version {
+        match self.minor {
+            Some(minor) => (self.major, minor + 1, 0),
+            None => (self.major + 1, 0, 0),
+        }
+    }
+}
+
+/// Returns whether `version` satisfies `requirement`, or an error if either
+/// can't be parsed.
+pub fn satisfies(version: &str, requirement: &str) -> Result<bool> {
+    let Some(version) = Partial::parse(version.trim()) else {
+        bail!("Invalid version '{}'", version);
+    };
+    let version = version.lowest();
+
+    for comparator in requirement.split(',') {
+        let comparator = comparator.trim();
+        let (op, rest) = [">=", "<=", ">", "<", "=", "^", "~"]
+            .iter()
+            .find_map(|op| comparator.strip_prefix(op).map(|rest| (*op, rest)))
+            .unwrap_or(("^", comparator));
+        let Some(bound) = Partial::parse(rest.trim()) else {
+            bail!(
+                "Invalid version requirement '{}': expected something like \">=0.5\" or \">=0.5, <2.0\"",
+                requirement
+            );
+        };
+        let matches = match op {
+            ">=" => version >= bound.lowest(),
+            ">" => version >= bound.bump_last(),
+            "<" => version < bound.lowest(),
+            "<=" => version < bound.bump_last(),
+            "=" => version >= bound.lowest() && version < bound.bump_last(),
+            "~" => version >= bound.lowest() && version < bound.bump_tilde(),
+            _ => version >= bound.lowest() && version < bound.bump_caret(),
+        };
+        if !matches {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies_comparators() {
+        let cases = [
+            ("0.9.0", ">=0.5", true),
+            ("0.4.9", ">=0.5", false),
+            ("0.9.0", ">=0.5, <1.0", true),
+            ("1.0.0", ">=0.5, <1.0", false),
+            ("0.5.1", ">0.5", false),
+            ("0.6.0", ">0.5", true),
+            ("0.5.9", "<=0.5", true),
+            ("0.9.3", "=0.9", true),
+            ("0.10.0", "=0.9", false),
+            ("0.9.7", "~0.9.1", true),
+            ("0.10.0", "~0.9.1", false),
+            ("0.8.5", "0.8", true),
+            ("0.9.0", "^0.8", false),
+            ("1.7.0", "^1.2", true),
+            ("2.0.0", "^1.2", false),
+            ("1.0.0-beta.1", ">=1.0", true),
+        ];
+        for (version, requirement, expected) in cases {
+            assert_eq!(
+                satisfies(version, requirement).unwrap(),
+                expected,
+                "{} {}",
+                version,
+                requirement
+            );
+        }
+    }
+
+    #[test]
+    fn test_satisfies_rejects_invalid_requirements() {
+        assert!(satisfies("0.9.0", ">=zero").is_err());
+        assert!(satisfies("0.9.0", ">=0.5.1.2").is_err());
+        assert!(satisfies("0.9.0", "").is_err());
+        assert!(satisfies(CURRENT, &format!(">={}", CURRENT)).unwrap());
+    }
+}
@@ -1,3 +1,6 @@
+// These tests pass argument arrays by reference, which newer clippy flags
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
@@ -28,7 +31,8 @@ fn test_dry_run_with_output_file() {
         .args(&["dry-run", "test_goal", "--output", output_path])
         .assert()
         .success()
-        .stdout(predicate::str::contains(format!(
+        .stdout("")
+        .stderr(predicate::str::contains(format!(
             "Dry run output written to {}",
             output_path
         )));
@@ -52,7 +56,7 @@ fn test_dry_run_with_short_output_flag() {
         .args(&["dry-run", "test_goal", "-o", output_path])
         .assert()
         .success()
-        .stdout(predicate::str::contains(format!(
+        .stderr(predicate::str::contains(format!(
             "Dry run output written to {}",
             output_path
         )));
@@ -93,6 +97,23 @@ fn test_dry_run_with_file_context() {
         .stdout(predicate::str::contains("[package]"));
 }
 
+#[test]
+fn test_dry_run_quiet_context_omits_notes() {
+    claw()
+        .args(["dry-run", "test_goal", "--context", "Cargo.toml"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("## Notes"));
+
+    claw()
+        .args(["dry-run", "test_goal", "--quiet-context", "--context", "Cargo.toml"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("## Notes").not())
+        .stdout(predicate::str::contains("## Directory Structure"))
+        .stdout(predicate::str::contains("[package]"));
+}
+
 #[test]
 fn test_dry_run_nonexistent_goal() {
     claw()
@@ -162,7 +183,7 @@ fn test_dry_run_with_all_features() {
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Dry run output written to"));
+        .stderr(predicate::str::contains("Dry run output written to"));
 
     // Verify file contains all elements
     let contents = fs::read_to_string(&output_file).unwrap();
@@ -219,3 +240,1874 @@ fn test_dry_run_help() {
         .stdout(predicate::str::contains("--output"))
         .stdout(predicate::str::contains("--context"));
 }
+
+#[test]
+fn test_dry_run_writes_provenance_sidecar() {
+    let temp_dir = TempDir::new().unwrap();
+    let output_file = temp_dir.path().join("prompt.md");
+    let output_path = output_file.to_str().unwrap();
+
+    claw()
+        .args([
+            "dry-run",
+            "test_goal",
+            "--context",
+            "Cargo.toml",
+            "--output",
+            output_path,
+            "--provenance",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Provenance map written to"));
+
+    let sidecar = temp_dir.path().join("prompt.md.provenance.json");
+    assert!(sidecar.exists(), "Provenance sidecar should exist");
+    let contents = fs::read_to_string(&sidecar).unwrap();
+    assert!(contents.contains("\"kind\": \"template\""));
+    assert!(contents.contains("\"kind\": \"context_file\""));
+    assert!(contents.contains("Cargo.toml"));
+}
+
+#[test]
+fn test_dry_run_provenance_requires_output() {
+    claw()
+        .args(["dry-run", "test_goal", "--provenance"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_complete_goal_names_and_parameters() {
+    claw()
+        .args(["__complete", "--", "dry-run", "test_"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("test_goal"));
+
+    claw()
+        .args(["__complete", "--", "test-params", "--", "--sc"])
+        .assert()
+        .success()
+        .stdout("--scope\n");
+}
+
+#[test]
+fn test_completions_script() {
+    claw()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("claw __complete"));
+}
+
+#[test]
+fn test_matrix_requires_readable_file() {
+    claw()
+        .args(["test_goal", "--matrix", "does-not-exist.yaml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read matrix file"));
+}
+
+#[test]
+fn test_schema_for_prompt_yaml() {
+    let output = claw().args(["schema", "prompt"]).output().unwrap();
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["title"], "PromptConfig");
+    assert_eq!(schema["required"], serde_json::json!(["name", "prompt"]));
+    assert!(schema["properties"]["parameters"].is_object());
+}
+
+/// Runs claw with a fresh global config whose LLM command is `sh -c <script>`.
+fn claw_with_llm_script(home: &TempDir, script: &str) -> Command {
+    claw()
+        .env("HOME", home.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .arg("list")
+        .assert()
+        .success();
+    let config = home.path().join(".config/claw/claw.yaml");
+    fs::write(
+        &config,
+        format!(
+            "llm_command: sh\nprompt_arg_template: \"-c '{}'\"\nrecord_history: false\n",
+            script
+        ),
+    )
+    .unwrap();
+    claw_in(home)
+}
+
+/// Runs claw with `home` as its home and working directory.
+fn claw_in(home: &TempDir) -> Command {
+    let mut command = claw();
+    command
+        .env("HOME", home.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("CLAW_PROFILE")
+        .current_dir(home.path());
+    command
+}
+
+#[test]
+fn test_llm_exit_code_is_propagated() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3")
+        .arg("test_goal")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("exited with non-zero status"));
+}
+
+#[test]
+fn test_json_status_summarizes_the_run() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3");
+    let goal = home.path().join(".config/claw/goals/status");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Status\n\
+         context_scripts:\n  files: \"echo one two\"\n\
+         prompt: \"Files: {{ Context.files }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["status", "--json-status=status.json"])
+        .assert()
+        .code(3);
+    let status: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(home.path().join("status.json")).unwrap())
+            .unwrap();
+    assert_eq!(status["goal"], "status");
+    assert_eq!(status["status"], "failed");
+    assert_eq!(status["exit_code"], 3);
+    assert_eq!(status["prompt_bytes"], "Files: one two".len());
+    assert_eq!(status["receiver"], "sh");
+    assert_eq!(status["scripts"][0]["name"], "files");
+    assert!(status["scripts"][0]["duration_ms"].is_u64());
+
+    claw_in(&home)
+        .args(["status", "--json-status"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(r#""status":"failed","exit_code":3"#));
+}
+
+#[test]
+fn test_middlewares_wrap_the_receiver() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat > sent.txt");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut yaml = fs::read_to_string(&config).unwrap();
+    yaml.push_str("middlewares: [log, redact, meter]\nprompt_token_price: 3\n");
+    fs::write(&config, yaml).unwrap();
+    let goal = home.path().join(".config/claw/goals/secret");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Secret\nprompt: \"Deploy with API_TOKEN=abc123\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("secret")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("→ Sending the prompt (28 bytes) to sh"))
+        .stderr(predicate::str::contains("Masked 1 secret(s)"))
+        .stderr(predicate::str::contains("Sent ~"))
+        .stderr(predicate::str::contains("(≈ $0.0000)"))
+        .stderr(predicate::str::contains("← sh finished in"));
+    assert_eq!(
+        fs::read_to_string(home.path().join("sent.txt")).unwrap(),
+        "Deploy with API_TOKEN=[REDACTED]"
+    );
+}
+
+#[test]
+fn test_session_resends_the_conversation() {
+    let home = TempDir::new().unwrap();
+    // Each turn's prompt is saved after the ones before it
+    claw_with_llm_script(&home, "cat >> prompts.txt; echo --- >> prompts.txt; echo answer");
+    let goal = home.path().join(".config/claw/goals/chat");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(goal.join("prompt.yaml"), "name: Chat\nprompt: \"Hello\"\n").unwrap();
+    fs::create_dir_all(home.path().join(".claw")).unwrap();
+    fs::write(
+        home.path().join(".claw/policy.yaml"),
+        "audit_log: audit.jsonl\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["session", "chat", "--save", "session.md"])
+        .write_stdin("\nAnd then?\n/exit\n")
+        .assert()
+        .success()
+        .stdout("answer\nanswer\n")
+        .stderr(predicate::str::contains("you> "));
+    let conversation = "Hello\n\n## Assistant\n\nanswer\n\n## User\n\nAnd then?\n";
+    assert_eq!(
+        fs::read_to_string(home.path().join("prompts.txt")).unwrap(),
+        format!("Hello---\n{}---\n", conversation)
+    );
+    assert_eq!(
+        fs::read_to_string(home.path().join("session.md")).unwrap(),
+        format!("{}\n## Assistant\n\nanswer\n", conversation)
+    );
+    // Each turn is audited like a run's prompt
+    let audit = fs::read_to_string(home.path().join("audit.jsonl")).unwrap();
+    assert_eq!(audit.lines().count(), 2);
+}
+
+#[test]
+fn test_fail_on_empty_response() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null")
+        .args(["test_goal", "--tee", "out.md", "--fail-on-empty-response"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("empty response"));
+
+    claw_with_llm_script(&home, "cat >/dev/null; echo answer")
+        .args(["test_goal", "--tee", "out.md", "--fail-on-empty-response"])
+        .assert()
+        .success();
+
+    // Without --tee, the response is captured to check it
+    claw_with_llm_script(&home, "cat >/dev/null")
+        .args(["test_goal", "--fail-on-empty-response"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("empty response"));
+    claw_with_llm_script(&home, "cat >/dev/null; echo answer")
+        .args(["test_goal", "--fail-on-empty-response"])
+        .assert()
+        .success()
+        .stdout("answer\n");
+}
+
+#[test]
+fn test_profile_overrides_top_level_settings() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut yaml = fs::read_to_string(&config).unwrap();
+    yaml.push_str("profiles:\n  offline:\n    prompt_arg_template: \"-c 'cat >/dev/null; exit 4'\"\n");
+    fs::write(&config, yaml).unwrap();
+
+    claw_in(&home)
+        .args(["test_goal", "--profile", "offline"])
+        .assert()
+        .code(4);
+
+    claw_in(&home)
+        .arg("test_goal")
+        .env("CLAW_PROFILE", "offline")
+        .assert()
+        .code(4);
+
+    claw_in(&home)
+        .arg("test_goal")
+        .assert()
+        .code(3);
+
+    claw_in(&home)
+        .args(["test_goal", "--profile", "work"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Available profiles: offline"));
+}
+
+#[test]
+fn test_receiver_flags_override_config_for_one_run() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3");
+
+    claw_in(&home)
+        .args(["test_goal", "--prompt-template", "-c 'cat >/dev/null; exit 5'"])
+        .assert()
+        .code(5);
+
+    claw_in(&home)
+        .args(["test_goal", "--llm", "cat"])
+        .args(["--prompt-template", ""])
+        .assert()
+        .success();
+
+    claw_in(&home)
+        .args(["test_goal", "--receiver", "smoke-signals"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("possible values: generic, claude-cli, tmux"));
+}
+
+#[test]
+fn test_dry_run_check_reports_goals_that_would_fail() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goals = home.path().join(".config/claw/goals");
+    for (name, yaml) in [
+        (
+            "broken_script",
+            "name: broken\nprompt: \"{{ Context.files }}\"\ncontext_scripts:\n  files: \"ls | (\"\n",
+        ),
+        ("undefined_arg", "name: undefined\nprompt: \"Fix {{ Args.ticket }}\"\n"),
+        (
+            "needs_ticket",
+            "name: needs\nprompt: \"Fix {{ Args.ticket }}\"\nparameters:\n  - name: ticket\n    description: Ticket\n    required: true\n",
+        ),
+    ] {
+        fs::create_dir_all(goals.join(name)).unwrap();
+        fs::write(goals.join(name).join("prompt.yaml"), yaml).unwrap();
+    }
+
+    claw_in(&home)
+        .args(["dry-run", "--check", "needs_ticket"])
+        .assert()
+        .success()
+        .stdout("✓ needs_ticket\n");
+
+    claw_in(&home)
+        .args(["dry-run", "--check"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("✗ broken_script: Context script 'files' has a syntax error"))
+        .stdout(predicate::str::contains("✗ undefined_arg: Failed to render the prompt"))
+        .stdout(predicate::str::contains("✓ needs_ticket"))
+        .stderr(predicate::str::contains("2 of"));
+}
+
+#[test]
+fn test_history_function_includes_previous_responses() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let config = home.path().join(".config/claw");
+    fs::create_dir_all(config.join("history")).unwrap();
+    for (started_at, status, response) in [
+        (1, "completed", "Monday: fixed login"),
+        (2, "completed", "Tuesday: wrote docs"),
+        (3, "failed", "Wednesday: broken"),
+    ] {
+        fs::write(
+            config.join(format!("history/{}-standup.json", started_at)),
+            format!(
+                r#"{{"goal":"standup","args":{{}},"started_at":{0},"finished_at":{0},"status":"{1}","response":"{2}"}}"#,
+                started_at, status, response
+            ),
+        )
+        .unwrap();
+    }
+    fs::create_dir_all(config.join("goals/standup")).unwrap();
+    fs::write(
+        config.join("goals/standup/prompt.yaml"),
+        "name: standup\nprompt: \"{% for s in history(goal='standup', n=3) %}[{{ s }}]{% endfor %}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "standup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[Monday: fixed login][Tuesday: wrote docs]",
+        ))
+        .stdout(predicate::str::contains("Wednesday").not());
+}
+
+#[test]
+fn test_goal_requiring_newer_claw_fails_with_upgrade_hint() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goals = home.path().join(".config/claw/goals");
+    for (name, requirement) in [("future", ">=99.0"), ("current", ">=0.1, <99")] {
+        fs::create_dir_all(goals.join(name)).unwrap();
+        fs::write(
+            goals.join(name).join("prompt.yaml"),
+            format!(
+                "name: {}\nrequires_claw: \"{}\"\nprompt: Hello\nnew_feature: {{ nested: true }}\n",
+                name, requirement
+            ),
+        )
+        .unwrap();
+    }
+
+    claw_in(&home)
+        .args(["dry-run", "future"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Goal 'future' requires claw >=99.0"))
+        .stderr(predicate::str::contains("Upgrade claw"));
+
+    claw_in(&home)
+        .args(["dry-run", "current"])
+        .assert()
+        .success()
+        .stdout("Hello");
+}
+
+#[test]
+fn test_managed_goals_are_listed_read_only_and_locked() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let library = home.path().join("company-claw");
+    let write_goal = |dir: &std::path::Path, name: &str, prompt: &str| {
+        fs::create_dir_all(dir.join(name)).unwrap();
+        fs::write(
+            dir.join(name).join("prompt.yaml"),
+            format!("name: {}\nprompt: {}\n", name, prompt),
+        )
+        .unwrap();
+    };
+    write_goal(&library.join("goals"), "review", "Managed review");
+    let global = home.path().join(".config/claw");
+    write_goal(&global.join("goals"), "review", "Personal review");
+    let config = fs::read_to_string(global.join("claw.yaml")).unwrap();
+    fs::write(
+        global.join("claw.yaml"),
+        format!("{}managed_goals: ../../company-claw\n", config),
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Managed Goals ("))
+        .stdout(predicate::str::contains("read-only):"))
+        .stdout(predicate::str::contains("review - review [managed]"));
+
+    // The managed library comes before global goals
+    claw_in(&home)
+        .args(["dry-run", "review"])
+        .assert()
+        .success()
+        .stdout("Managed review");
+
+    claw_in(&home)
+        .args(["add", "review", "--global"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("managed centrally"));
+
+    // A goal marked `managed: true` is locked wherever it lives
+    write_goal(&global.join("goals"), "standup", "Standup");
+    let prompt = global.join("goals/standup/prompt.yaml");
+    let marked = format!("{}managed: true\n", fs::read_to_string(&prompt).unwrap());
+    fs::write(&prompt, marked).unwrap();
+    claw_in(&home)
+        .args(["add", "standup", "--global"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't be changed with claw add"));
+}
+
+#[test]
+fn test_sensitive_paths_are_left_out_without_a_terminal() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let yaml = fs::read_to_string(&config).unwrap();
+    fs::write(
+        &config,
+        format!("{}sensitive_paths: [\"**/.env*\", \"**/secrets/**\"]\n", yaml),
+    )
+    .unwrap();
+    let project = home.path().join("project");
+    fs::create_dir_all(project.join("secrets")).unwrap();
+    fs::write(project.join(".env"), "API_KEY=hunter2").unwrap();
+    fs::write(project.join("secrets/db.yaml"), "password: hunter2").unwrap();
+    fs::write(project.join("main.rs"), "fn main() {}").unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--no-gitignore", "--context", "project"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn main() {}"))
+        .stdout(predicate::str::contains("hunter2").not())
+        .stderr(predicate::str::contains(
+            "Leaving out project/.env: it matches sensitive_paths ('**/.env*')",
+        ))
+        .stderr(predicate::str::contains("project/secrets/db.yaml"));
+}
+
+#[test]
+fn test_snapshot_tests_detect_prompt_drift() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goal = home.path().join(".config/claw/goals/review");
+    fs::create_dir_all(&goal).unwrap();
+    let write_prompt = |instruction: &str| {
+        fs::write(
+            goal.join("prompt.yaml"),
+            format!(
+                "name: Review\nparameters:\n  - name: scope\n    description: Area\n    required: true\ncontext_scripts:\n  diff: git diff\nprompt: |\n  {} {{{{ Args.scope }}}}.\n  {{{{ Context.diff }}}}\n",
+                instruction
+            ),
+        )
+        .unwrap();
+    };
+    write_prompt("Review");
+    fs::write(
+        goal.join("tests.yaml"),
+        "cases:\n  - name: api\n    args: { scope: api }\n    context: { diff: \"fn f() { {{ x }} }\" }\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["test", "review"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("✗ review/api: no snapshot"));
+
+    claw_in(&home)
+        .args(["test", "--update-snapshots"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✎ review/api: wrote"));
+    assert_eq!(
+        fs::read_to_string(goal.join("__snapshots__/api.md")).unwrap(),
+        "Review api.\nfn f() { {{ x }} }\n"
+    );
+
+    // Snapshots aren't loaded as templates, so their braces don't matter
+    claw_in(&home)
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ review/api"));
+
+    write_prompt("Carefully review");
+    claw_in(&home)
+        .arg("test")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("line 1:"))
+        .stdout(predicate::str::contains("- Review api."))
+        .stdout(predicate::str::contains("+ Carefully review api."))
+        .stderr(predicate::str::contains("--update-snapshots"));
+}
+
+#[test]
+fn test_batch_runs_plan_and_summarizes_results() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goals = home.path().join(".config/claw/goals");
+    fs::create_dir_all(goals.join("greet")).unwrap();
+    fs::write(
+        goals.join("greet/prompt.yaml"),
+        "name: Greet\nparameters:\n  - name: who\n    description: Who to greet\n    required: true\nprompt: Hello {{ Args.who }}\n",
+    )
+    .unwrap();
+    fs::write(
+        home.path().join("plan.yaml"),
+        "jobs: 2\nruns:\n  - goal: greet\n    args: { who: world }\n    output: out/world.md\n  - goal: greet\n    output: out/nobody.md\n  - goal: greet\n    args: { who: team }\n    output: out/team.md\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["batch", "plan.yaml"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Batch results (3 runs, 1 failed):"))
+        .stdout(predicate::str::is_match(r"✓\s+greet\s+who=world\s+[\d.]+s\s+out/world.md").unwrap())
+        .stdout(predicate::str::is_match(r"✗\s+greet\s+-\s+[\d.]+s\s+.*who").unwrap())
+        .stderr(predicate::str::contains("1 of 3 batch runs failed"));
+
+    assert_eq!(
+        fs::read_to_string(home.path().join("out/world.md")).unwrap(),
+        "Hello world"
+    );
+    assert_eq!(
+        fs::read_to_string(home.path().join("out/team.md")).unwrap(),
+        "Hello team"
+    );
+}
+
+#[test]
+fn test_assert_response_retries_with_corrections() {
+    let home = TempDir::new().unwrap();
+    // Answers properly only once the prompt carries a correction
+    claw_with_llm_script(
+        &home,
+        "if grep -q Correction; then echo Summary; else echo draft; fi",
+    );
+    let goals = home.path().join(".config/claw/goals");
+    fs::create_dir_all(goals.join("summary")).unwrap();
+    fs::write(
+        goals.join("summary/prompt.yaml"),
+        "name: Summary\nassert_response:\n  contains: [Summary]\nprompt: Summarize\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["summary", "--tee", "out.md"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("asking again (1/2)"));
+    assert_eq!(
+        fs::read_to_string(home.path().join("out.md")).unwrap(),
+        "Summary\n"
+    );
+
+    fs::write(
+        goals.join("summary/prompt.yaml"),
+        "name: Summary\nassert_response:\n  contains: [Details]\n  retries: 1\nprompt: Summarize\n",
+    )
+    .unwrap();
+    claw_in(&home)
+        .args(["summary", "--tee", "out.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed its assertions after 2 attempt(s): it must contain \"Details\"",
+        ));
+
+    // Without --tee, the response is captured for the checks all the same
+    claw_in(&home)
+        .arg("summary")
+        .assert()
+        .failure()
+        .stdout("draft\nSummary\n")
+        .stderr(predicate::str::contains("failed its assertions after 2 attempt(s)"));
+}
+
+#[test]
+fn test_run_goal_example() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goals = home.path().join(".config/claw/goals");
+    fs::create_dir_all(goals.join("greet")).unwrap();
+    fs::write(
+        goals.join("greet/prompt.yaml"),
+        "name: Greet\nparameters:\n  - name: who\n    description: Who to greet\n    required: true\nexamples:\n  - description: Greet the world\n    args: { who: world }\nprompt: Hello {{ Args.who }}\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["greet", "--explain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  1. Greet the world\n     claw greet -- --who=world\n",
+        ));
+    claw_in(&home)
+        .args(["greet", "--example", "1"])
+        .assert()
+        .success()
+        .stdout("Hello world");
+    claw_in(&home)
+        .args(["greet", "--example", "1", "--", "--who", "team"])
+        .assert()
+        .success()
+        .stdout("Hello team");
+    claw_in(&home)
+        .args(["greet", "--example", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no example 2; it has 1"));
+}
+
+#[test]
+fn test_context_todos() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    fs::create_dir_all(home.path().join("src")).unwrap();
+    fs::write(
+        home.path().join("src/lib.rs"),
+        "fn parse() {\n    // FIXME: reject empty input\n}\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-todos", "src"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("## TODO/FIXME/HACK Comments"))
+        .stdout(predicate::str::contains(
+            "### src/lib.rs:2 FIXME: reject empty input",
+        ))
+        .stdout(predicate::str::contains("> 2 |     // FIXME: reject empty input"));
+}
+
+#[test]
+fn test_ping() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo Pong")
+        .arg("ping")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"✓ pong from sh in [\d.]+s").unwrap());
+
+    claw_with_llm_script(&home, "cat >/dev/null")
+        .arg("ping")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("without printing a reply"));
+
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3")
+        .arg("ping")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("sh failed to answer the ping"));
+}
+
+#[test]
+fn test_markdown_goal_with_front_matter() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.md"),
+        "---\nname: Release Notes\nparameters:\n  - name: version\n    description: Version\n    required: true\ncontext_scripts:\n  greeting: \"echo hello\"\n---\n# Notes for {{ Args.version }}\n\n{{ Context.greeting }}: write them.\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "notes", "--", "--version", "1.2"])
+        .assert()
+        .success()
+        .stdout("# Notes for 1.2\n\nhello: write them.\n");
+
+    fs::write(goal.join("prompt.yaml"), "name: Notes\nprompt: hi\n").unwrap();
+    claw_in(&home)
+        .args(["dry-run", "notes", "--", "--version", "1.2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has both prompt.yaml and prompt.md"));
+
+    fs::remove_file(goal.join("prompt.yaml")).unwrap();
+    fs::write(goal.join("prompt.md"), "# Just markdown\n").unwrap();
+    claw_in(&home)
+        .args(["dry-run", "notes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must start with YAML front matter"));
+}
+
+#[test]
+fn test_collection_install_disable_and_list() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let pack = home.path().join("security-pack");
+    fs::create_dir_all(pack.join("goals/threat-model")).unwrap();
+    fs::write(
+        pack.join("collection.yaml"),
+        "name: security-pack\nversion: \"1.0.0\"\ndescription: Security reviews\n",
+    )
+    .unwrap();
+    fs::write(
+        pack.join("goals/threat-model/prompt.yaml"),
+        "name: Threat Model\nprompt: Model the threats.\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["collection", "install", "security-pack", "--global"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed security-pack 1.0.0"));
+    claw_in(&home)
+        .args(["list", "--collection", "security-pack"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "threat-model - Threat Model [security-pack]",
+        ))
+        .stdout(predicate::str::contains("test_goal").not());
+    claw_in(&home)
+        .args(["dry-run", "threat-model"])
+        .assert()
+        .success()
+        .stdout("Model the threats.");
+
+    claw_in(&home)
+        .args(["collection", "disable", "security-pack"])
+        .assert()
+        .success();
+    claw_in(&home)
+        .args(["collection", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("security-pack 1.0.0 [disabled]"));
+    claw_in(&home)
+        .args(["dry-run", "threat-model"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_bundle_recorded_run() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo LGTM");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let content = fs::read_to_string(&config)
+        .unwrap()
+        .replace("record_history: false", "record_history: true");
+    fs::write(&config, content).unwrap();
+    fs::write(home.path().join("notes.txt"), "remember the milk\n").unwrap();
+
+    claw_in(&home)
+        .args(["test_goal", "--context", "notes.txt", "--tee", "out.md"])
+        .assert()
+        .success();
+    claw_in(&home)
+        .args(["bundle", "last", "-o", "bundle.zip"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "prompt, goal definition, 1 context file(s), response",
+        ));
+
+    let file = fs::File::open(home.path().join("bundle.zip")).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    let mut prompt = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("prompt.md").unwrap(), &mut prompt)
+        .unwrap();
+    assert!(prompt.contains("remember the milk"));
+    assert!(archive.by_name("goal/prompt.yaml").is_ok());
+
+    claw_in(&home)
+        .args(["bundle", "1-missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("The most recent runs are"));
+}
+
+#[test]
+fn test_receiver_fallbacks() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; exit 3");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut content = fs::read_to_string(&config).unwrap();
+    content.push_str(
+        "receiver_fallbacks: [backup]\nprofiles:\n  backup:\n    llm_command: cat\n    prompt_arg_template: \"\"\n",
+    );
+    fs::write(&config, content).unwrap();
+
+    claw_in(&home)
+        .args(["test_goal", "--tee", "out.md"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Falling back to cat (profile 'backup')"))
+        .stderr(predicate::str::contains(
+            "The prompt was handled by fallback cat (profile 'backup')",
+        ));
+    assert!(!fs::read_to_string(home.path().join("out.md")).unwrap().is_empty());
+}
+
+#[test]
+fn test_fan_out_compares_profiles() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo from-default");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut content = fs::read_to_string(&config).unwrap();
+    content.push_str(
+        "profiles:\n  a:\n    llm_command: sh\n    prompt_arg_template: \"-c 'cat >/dev/null; echo from-a'\"\n  b:\n    llm_command: sh\n    prompt_arg_template: \"-c 'cat >/dev/null; echo from-b'\"\n",
+    );
+    fs::write(&config, content).unwrap();
+
+    claw_in(&home)
+        .args(["test_goal", "--fan-out", "a,b", "--tee", "out.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from-a"))
+        .stdout(predicate::str::contains("from-b"))
+        .stdout(predicate::str::contains("from-default").not())
+        .stderr(predicate::str::contains("✓ a answered in"));
+    assert_eq!(
+        fs::read_to_string(home.path().join("out.b.md")).unwrap(),
+        "from-b\n"
+    );
+}
+
+#[test]
+fn test_policy_caps_script_context_too() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let goal = home.path().join(".config/claw/goals/big");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Big\n\
+         context_scripts:\n  big: \"printf '%02000d' 0\"\n\
+         prompt: \"{{ Context.big }}\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(home.path().join(".claw")).unwrap();
+    let policy = home.path().join(".claw/policy.yaml");
+
+    fs::write(&policy, "max_context_kb: 1\n").unwrap();
+    claw_in(&home)
+        .args(["dry-run", "big"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Context is 2.0 KB, which exceeds the 1 KB limit",
+        ));
+
+    fs::write(&policy, "max_context_kb: 2\n").unwrap();
+    claw_in(&home).args(["dry-run", "big"]).assert().success();
+
+    // Output reshaped by a filter still counts
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Big\n\
+         context_scripts:\n  big: \"printf '%02000d' 0\"\n\
+         prompt: \"{{ Context.big | replace(from='0', to='1') }}\"\n",
+    )
+    .unwrap();
+    fs::write(&policy, "max_context_kb: 1\n").unwrap();
+    claw_in(&home)
+        .args(["dry-run", "big"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds the 1 KB limit"));
+}
+
+#[test]
+fn test_policy_audit_log_records_each_prompt() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo done");
+    fs::create_dir_all(home.path().join(".claw")).unwrap();
+    fs::write(
+        home.path().join(".claw/policy.yaml"),
+        "audit_log: logs/audit.jsonl\n",
+    )
+    .unwrap();
+    fs::write(home.path().join("notes.txt"), "secret notes\n").unwrap();
+
+    for _ in 0..2 {
+        claw_in(&home)
+            .args(["test_goal", "--context", "notes.txt"])
+            .assert()
+            .success();
+    }
+
+    let log = fs::read_to_string(home.path().join("logs/audit.jsonl")).unwrap();
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["goal"], "test_goal");
+    assert_eq!(lines[0]["receivers"][0], "sh");
+    assert_eq!(lines[0]["prompt_sha256"].as_str().unwrap().len(), 64);
+    assert_eq!(lines[0]["context_files"][0]["path"], "notes.txt");
+    assert!(!log.contains("secret notes"));
+}
+
+#[test]
+fn test_goal_receiver_overrides_claw_yaml() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo from-global");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut content = fs::read_to_string(&config).unwrap();
+    content.push_str(
+        "profiles:\n  local:\n    llm_command: sh\n    prompt_arg_template: \"-c 'cat >/dev/null; echo from-profile'\"\n",
+    );
+    fs::write(&config, content).unwrap();
+    let goals = home.path().join(".config/claw/goals");
+    for (name, receiver) in [
+        (
+            "inline",
+            "{ llm_command: sh, prompt_arg_template: \"-c 'cat >/dev/null; echo from-goal'\" }",
+        ),
+        ("named", "local"),
+    ] {
+        fs::create_dir_all(goals.join(name)).unwrap();
+        fs::write(
+            goals.join(name).join("prompt.yaml"),
+            format!("name: {}\nreceiver: {}\nprompt: Hello\n", name, receiver),
+        )
+        .unwrap();
+    }
+
+    claw_in(&home)
+        .arg("inline")
+        .assert()
+        .success()
+        .stdout("from-goal\n");
+    claw_in(&home)
+        .arg("named")
+        .assert()
+        .success()
+        .stdout("from-profile\n");
+    claw_in(&home)
+        .args(["inline", "--llm", "cat", "--prompt-template", ""])
+        .assert()
+        .success()
+        .stdout("Hello");
+}
+
+#[test]
+fn test_goal_llm_command_overrides_receiver() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo from-global");
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\nllm_command: echo\nprompt_arg_template: \"tool {{prompt}}\"\nprompt: Hello\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .success()
+        .stdout("tool Hello\n");
+    claw_in(&home)
+        .args(["notes", "--llm", "cat", "--prompt-template", ""])
+        .assert()
+        .success()
+        .stdout("Hello");
+}
+
+#[test]
+fn test_post_process_pipes_the_response() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo raw answer");
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\npost_process:\n  - tr a-z A-Z\n  - sed 's/^/> /'\nprompt: Hello\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .success()
+        .stdout("> RAW ANSWER\n");
+
+    let saved = home.path().join("saved.md");
+    claw_in(&home)
+        .args(["notes", "--tee"])
+        .arg(&saved)
+        .assert()
+        .success()
+        .stdout("> RAW ANSWER\n");
+    assert_eq!(fs::read_to_string(&saved).unwrap(), "> RAW ANSWER\n");
+}
+
+#[test]
+fn test_local_post_process_requires_trust() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo answer");
+    let goal = home.path().join(".claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\npost_process:\n  - touch ran.txt\nprompt: Hello\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not trusted"));
+    assert!(!home.path().join("ran.txt").exists());
+
+    claw_in(&home).arg("trust").assert().success();
+    claw_in(&home).arg("notes").assert().success();
+    assert!(home.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_local_goal_llm_command_requires_trust() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo from-global");
+    let goal = home.path().join(".claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\nllm_command: echo\nprompt_arg_template: \"from-goal\"\nprompt: Hello\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not trusted"));
+
+    claw_in(&home).arg("trust").assert().success();
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .success()
+        .stdout("from-goal\n");
+}
+
+#[test]
+fn test_diff_goal_compares_local_with_global() {
+    let home = TempDir::new().unwrap();
+    let global = home.path().join(".config/claw/goals/notes");
+    let local = home.path().join(".claw/goals/notes");
+    fs::create_dir_all(&global).unwrap();
+    fs::create_dir_all(&local).unwrap();
+    fs::write(global.join("prompt.yaml"), "name: Notes\nprompt: Hello\n").unwrap();
+    fs::write(local.join("prompt.yaml"), "name: Notes\nprompt: Hello there\n").unwrap();
+
+    claw_in(&home)
+        .args(["diff-goal", "notes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-prompt: Hello\n"))
+        .stdout(predicate::str::contains("+prompt: Hello there\n"));
+
+    fs::write(local.join("prompt.yaml"), "name: Notes\nprompt: Hello\n").unwrap();
+    claw_in(&home)
+        .args(["diff-goal", "notes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The local goal is the same as the global one."));
+
+    fs::remove_dir_all(&global).unwrap();
+    claw_in(&home)
+        .args(["diff-goal", "notes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("doesn't shadow"));
+}
+
+#[test]
+fn test_docs_writes_a_page_for_the_goal_that_runs() {
+    let home = TempDir::new().unwrap();
+    let global = home.path().join(".config/claw/goals/notes");
+    let local = home.path().join(".claw/goals/notes");
+    fs::create_dir_all(&global).unwrap();
+    fs::create_dir_all(&local).unwrap();
+    fs::write(global.join("prompt.yaml"), "name: Global Notes\nprompt: Hello\n").unwrap();
+    fs::write(
+        local.join("prompt.yaml"),
+        "name: Local Notes\ndescription: Writes notes.\nprompt: Hello there\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["docs", "notes", "-o", "wiki"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 1 goal page(s)"));
+    let page = fs::read_to_string(home.path().join("wiki/notes.md")).unwrap();
+    assert!(page.starts_with("# Local Notes\n\nWrites notes.\n"));
+    assert!(page.contains("```text\nHello there\n```"));
+    let index = fs::read_to_string(home.path().join("wiki/index.md")).unwrap();
+    assert!(index.contains("| [notes](notes.md) | Writes notes. | local |"));
+
+    claw_in(&home)
+        .args(["docs", "missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Goal 'missing' not found"));
+}
+
+#[test]
+fn test_fix_last_triages_the_recorded_failure() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat");
+    let record = home.path().join(".config/claw/cache/last_command");
+    fs::create_dir_all(record.parent().unwrap()).unwrap();
+    fs::write(
+        &record,
+        format!("3\n{}\necho missing-semicolon >&2; exit 3\n", home.path().display()),
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .env("SHELL", "sh")
+        .args(["fix", "--last", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("It exited with status 3."))
+        .stdout(predicate::str::contains("missing-semicolon"));
+
+    claw_in(&home)
+        .args(["fix", "--last", "--no-rerun"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Its output wasn't captured."));
+
+    fs::write(&record, "0\n/tmp\nls\n").unwrap();
+    claw_in(&home)
+        .args(["fix", "--last"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The last command succeeded: ls"));
+}
+
+#[test]
+fn test_fix_hook_is_added_to_the_startup_file_once() {
+    let home = TempDir::new().unwrap();
+    for _ in 0..2 {
+        claw_in(&home)
+            .args(["fix", "--install-hook", "bash"])
+            .assert()
+            .success();
+    }
+    let bashrc = fs::read_to_string(home.path().join(".bashrc")).unwrap();
+    assert_eq!(bashrc.matches("# claw fix hook").count(), 1);
+    assert!(home.path().join(".config/claw/cache/fix-hook.bash").exists());
+
+    claw_in(&home)
+        .args(["fix", "--uninstall-hook", "bash"])
+        .assert()
+        .success();
+    assert!(!fs::read_to_string(home.path().join(".bashrc")).unwrap().contains("claw fix"));
+}
+
+#[test]
+fn test_goal_env_reaches_scripts_and_llm() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat; echo; echo llm=$STAGE $REGION");
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut yaml = fs::read_to_string(&config).unwrap();
+    yaml.push_str("env:\n  REGION: eu\n  STAGE: prod\n");
+    fs::write(&config, yaml).unwrap();
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\n\
+         parameters:\n  - name: stage\n    description: Stage\n    required: false\n    default: dev\n\
+         env:\n  STAGE: \"{{ Args.stage }}-1\"\n\
+         context_scripts:\n  where: \"echo script=$STAGE $REGION\"\n\
+         prompt: \"{{ Context.where }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["notes", "--", "--stage", "qa"])
+        .assert()
+        .success()
+        .stdout("script=qa-1 eu\nllm=qa-1 eu\n");
+}
+
+#[test]
+fn test_goal_working_dir_applies_to_scripts_and_llm() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat; echo; echo llm=$(basename $PWD)");
+    fs::create_dir_all(home.path().join("packages/api")).unwrap();
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\n\
+         parameters:\n  - name: package\n    description: Package\n    required: true\n\
+         working_dir: \"packages/{{ Args.package }}\"\n\
+         context_scripts:\n  where: \"echo script=$(basename $PWD)\"\n\
+         prompt: \"{{ Context.where }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["notes", "--", "--package", "api"])
+        .assert()
+        .success()
+        .stdout("script=api\nllm=api\n");
+    claw_in(&home)
+        .args(["notes", "--", "--package", "web"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't a directory"));
+}
+
+#[test]
+fn test_script_output_is_cached_for_its_ttl() {
+    let home = TempDir::new().unwrap();
+    let goal = home.path().join(".config/claw/goals/slow");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Slow\n\
+         context_scripts:\n  api:\n    run: \"echo ran >> runs.log; echo fetched\"\n    cache_ttl_secs: 3600\n\
+         prompt: \"{{ Context.api }}\"\n",
+    )
+    .unwrap();
+    let dry_run = |extra: &[&str]| {
+        claw_in(&home)
+            .env_remove("XDG_CACHE_HOME")
+            .args(["dry-run", "slow"])
+            .args(extra)
+            .assert()
+            .success()
+    };
+
+    dry_run(&[]).stdout(predicate::str::contains("fetched"));
+    dry_run(&[])
+        .stdout(predicate::str::contains("fetched"))
+        .stderr(predicate::str::contains(
+            "Using the cached output of context script 'api'",
+        ));
+    let runs = || fs::read_to_string(home.path().join("runs.log")).unwrap();
+    assert_eq!(runs(), "ran\n");
+
+    dry_run(&["--no-script-cache"]);
+    assert_eq!(runs(), "ran\nran\n");
+    assert!(home.path().join(".cache/claw/scripts").is_dir());
+}
+
+#[test]
+fn test_prefetched_script_cannot_use_args() {
+    let home = TempDir::new().unwrap();
+    let goal = home.path().join(".config/claw/goals/log");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Log\n\
+         context_scripts:\n  log:\n    run: \"git log {{ Args.branch | default(value='main') }}\"\n    prefetch: true\n\
+         prompt: \"{{ Context.log }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "log", "--", "--branch", "dev"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Context script 'log' is prefetched, but its command uses Args",
+        ));
+}
+
+#[test]
+fn test_no_scripts_renders_placeholders() {
+    let home = TempDir::new().unwrap();
+    let goal = home.path().join(".config/claw/goals/deploy");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Deploy\n\
+         context_scripts:\n  staged_diff: \"touch ran; git diff --staged\"\n\
+         prompt: \"Review:\\n{{ Context.staged_diff }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "deploy", "--no-scripts"])
+        .assert()
+        .success()
+        .stdout("Review:\n<<script: staged_diff>>");
+    assert!(!home.path().join("ran").exists());
+}
+
+#[test]
+fn test_dry_run_context_git() {
+    let home = TempDir::new().unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .current_dir(home.path())
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "{:?}", status);
+    };
+    git(&["init", "-q"]);
+    fs::write(home.path().join("kept.rs"), "fn kept() {}\n").unwrap();
+    fs::write(home.path().join("changed.rs"), "fn old() {}\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+    git(&["checkout", "-q", "-b", "feature"]);
+    fs::write(home.path().join("changed.rs"), "fn new_version() {}\n").unwrap();
+    git(&["commit", "-q", "-am", "change"]);
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-git", "branch:main"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn new_version() {}"))
+        .stdout(predicate::str::contains("fn kept() {}").not());
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-diff", "main..feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "## Git Diff (changes from main to feature)",
+        ))
+        .stdout(predicate::str::contains("### changed.rs (+1 -1)\n\n```diff\n"))
+        .stdout(predicate::str::contains("-fn old() {}\n+fn new_version() {}\n"))
+        .stdout(predicate::str::contains("kept.rs").not());
+
+    // A rename shows as a deletion and an addition, spaces and all
+    fs::create_dir(home.path().join("kept b")).unwrap();
+    git(&["mv", "kept.rs", "kept b/copy.rs"]);
+    fs::write(home.path().join("changed.rs"), "fn newer() {}\n").unwrap();
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-diff", "staged"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("### kept b/copy.rs (+1 -0)"))
+        .stdout(predicate::str::contains("### kept.rs (+0 -1)"))
+        .stdout(predicate::str::contains("changed.rs").not());
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-diff", "working"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3 file(s) changed."))
+        .stdout(predicate::str::contains(
+            "-fn new_version() {}\n+fn newer() {}\n",
+        ));
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "--context-git", "trunk"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid git scope 'trunk'"));
+}
+
+#[test]
+fn test_git_builtins_read_the_repository() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let goal_dir = home.path().join(".config/claw/goals/status");
+    fs::create_dir_all(&goal_dir).unwrap();
+    fs::write(
+        goal_dir.join("prompt.yaml"),
+        "name: Status\ncontext_builtin: [git_status, git_diff_staged]\nprompt: \"{{ Context.git_status }}\\n--\\n{{ Context.git_diff_staged }}\"\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .current_dir(home.path())
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "{:?}", status);
+    };
+    git(&["init", "-q"]);
+    fs::write(home.path().join(".gitignore"), ".config/\n").unwrap();
+    fs::write(home.path().join("kept.rs"), "fn kept() {}\n").unwrap();
+    fs::write(home.path().join("changed.rs"), "fn old() {}\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+    fs::write(home.path().join("changed.rs"), "fn staged() {}\n").unwrap();
+    git(&["add", "changed.rs"]);
+    fs::write(home.path().join("kept.rs"), "fn edited() {}\n").unwrap();
+    fs::create_dir(home.path().join("src")).unwrap();
+    fs::write(home.path().join("src/new.rs"), "fn new() {}\n").unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "## main\nM  changed.rs\n M kept.rs\n?? src/\n--\n",
+        ))
+        .stdout(predicate::str::contains("-fn old() {}\n+fn staged() {}"))
+        .stdout(predicate::str::contains("fn edited() {}").not());
+}
+
+#[test]
+fn test_cite_context_maps_citations_to_files() {
+    let home = TempDir::new().unwrap();
+    fs::write(home.path().join("lock.rs"), "fn lock() {}\n").unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo Locking happens in [1] and [4].")
+        .args(["test_goal", "-c", "lock.rs", "--cite-context", "--tee", "out.md"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Cited sources:"))
+        .stderr(predicate::str::contains("[1] lock.rs"))
+        .stderr(predicate::str::contains("[4] ⚠️  no context file has this number"));
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "-c", "lock.rs", "--cite-context"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("### [1] lock.rs"))
+        .stdout(predicate::str::contains("## Citations"));
+}
+
+#[test]
+fn test_aliases_expand_before_parsing() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let config = home.path().join(".config/claw/claw.yaml");
+    let mut content = fs::read_to_string(&config).unwrap();
+    content.push_str("\naliases:\n  preview: \"dry-run test_goal\"\n  list: \"stats\"\n");
+    fs::write(&config, content).unwrap();
+
+    claw_in(&home)
+        .arg("preview")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("world-class research assistant"));
+
+    claw_in(&home)
+        .arg("alias")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("preview = dry-run test_goal"))
+        .stdout(predicate::str::contains("ignored: a built-in subcommand"));
+}
+
+#[test]
+fn test_failing_tests_builtin() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let config_dir = home.path().join(".config/claw");
+    let mut content = fs::read_to_string(config_dir.join("claw.yaml")).unwrap();
+    content.push_str("\ntest_command: \"sh tests.sh\"\n");
+    fs::write(config_dir.join("claw.yaml"), content).unwrap();
+    fs::write(
+        home.path().join("tests.sh"),
+        "echo '---- math::divides stdout ----'\n\
+         echo 'panicked at src/math.rs:3:5'\n\
+         echo\n\
+         echo 'failures:'\n\
+         exit 101\n",
+    )
+    .unwrap();
+    fs::create_dir_all(config_dir.join("goals/fix-tests")).unwrap();
+    fs::write(
+        config_dir.join("goals/fix-tests/prompt.yaml"),
+        "name: Fix tests\ncontext_builtin: [failing_tests]\nprompt: \"Fix:\\n{{ Context.failing_tests }}\"\n",
+    )
+    .unwrap();
+    fs::create_dir(home.path().join("src")).unwrap();
+    fs::write(home.path().join("src/math.rs"), "fn divide_body() {}\n").unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "fix-tests"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 failing test(s)"))
+        .stdout(predicate::str::contains("### math::divides"))
+        .stdout(predicate::str::contains("### src/math.rs"))
+        .stdout(predicate::str::contains("fn divide_body() {}"));
+}
+
+#[test]
+fn test_daemon_prefetches_context_scripts() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let goal_dir = home.path().join(".config/claw/goals/audit");
+    fs::create_dir_all(&goal_dir).unwrap();
+    fs::write(
+        goal_dir.join("prompt.yaml"),
+        "name: Audit\ncontext_scripts:\n  scan:\n    run: \"cat scan.txt\"\n    prefetch: true\nprompt: \"{{ Context.scan }}\"\n",
+    )
+    .unwrap();
+    fs::write(home.path().join("scan.txt"), "first scan").unwrap();
+
+    claw_in(&home)
+        .args(["daemon", "--once"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Prefetched audit/scan"));
+    fs::write(home.path().join("scan.txt"), "second scan").unwrap();
+
+    // Renders use the prefetched output until it is due again
+    claw_in(&home)
+        .args(["dry-run", "audit"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first scan"))
+        .stderr(predicate::str::contains("Using the prefetched output of 'scan'"));
+    claw_in(&home)
+        .args(["daemon", "--once"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to prefetch"));
+}
+
+#[test]
+fn test_porcelain_stdout_carries_only_the_prompt() {
+    let home = TempDir::new().unwrap();
+    let goal_dir = home.path().join(".claw/goals/hello");
+    fs::create_dir_all(&goal_dir).unwrap();
+    fs::write(goal_dir.join("prompt.yaml"), "name: Hello\nprompt: \"Say hello\"\n").unwrap();
+
+    // The first-run banner goes to stderr, even on the very first run
+    claw_in(&home)
+        .args(["--porcelain", "dry-run", "hello"])
+        .assert()
+        .success()
+        .stdout("Say hello")
+        .stderr(predicate::str::contains("Welcome to claw!"));
+}
+
+#[test]
+fn test_first_run_setup_can_be_skipped() {
+    let home = TempDir::new().unwrap();
+    let goal_dir = home.path().join(".claw/goals/hello");
+    fs::create_dir_all(&goal_dir).unwrap();
+    fs::write(goal_dir.join("prompt.yaml"), "name: Hello\nprompt: \"Say hello\"\n").unwrap();
+    let global = home.path().join(".config/claw");
+
+    claw_in(&home)
+        .args(["--no-setup", "dry-run", "hello"])
+        .assert()
+        .success()
+        .stdout("Say hello")
+        .stderr("");
+    claw_in(&home)
+        .env("CLAW_NO_FIRST_RUN_SETUP", "1")
+        .args(["dry-run", "hello"])
+        .assert()
+        .success()
+        .stderr("");
+    assert!(!global.exists());
+
+    claw_in(&home)
+        .env("CLAW_NO_FIRST_RUN_SETUP", "0")
+        .args(["dry-run", "hello"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Welcome to claw!"));
+    assert!(global.join("claw.yaml").exists());
+}
+
+#[test]
+fn test_dry_run_context_root_options() {
+    let home = TempDir::new().unwrap();
+    fs::create_dir_all(home.path().join("src/deep")).unwrap();
+    fs::create_dir_all(home.path().join("docs")).unwrap();
+    fs::write(home.path().join("src/lib.rs"), "fn shallow() {}\n").unwrap();
+    fs::write(home.path().join("src/deep/inner.rs"), "fn deep() {}\n").unwrap();
+    fs::write(home.path().join("docs/guide.md"), "# Guide\n").unwrap();
+    fs::write(home.path().join("docs/build.sh"), "make docs\n").unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "-c", "src:depth=0", "docs:ext=md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn shallow() {}"))
+        .stdout(predicate::str::contains("# Guide"))
+        .stdout(predicate::str::contains("fn deep() {}").not())
+        .stdout(predicate::str::contains("make docs").not())
+        .stdout(predicate::str::contains("- In docs: only .md files"));
+
+    claw_in(&home)
+        .args(["dry-run", "test_goal", "-c", "src:depth=all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid depth 'all'"));
+}
+
+#[test]
+fn test_long_argument_prompt_falls_back_to_stdin() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let config_path = home.path().join(".config/claw/claw.yaml");
+    fs::write(
+        &config_path,
+        "llm_command: wc\nprompt_arg_template: \"-c {{prompt}}\"\nmax_prompt_arg_kb: 0\nrecord_history: false\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["test_goal", "--tee", "out.txt"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("sending it to 'wc' on stdin instead"));
+    let count = fs::read_to_string(home.path().join("out.txt")).unwrap();
+    assert!(count.trim().parse::<usize>().unwrap() > 0);
+
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str("prompt_arg_overflow: fail\n");
+    fs::write(&config_path, config).unwrap();
+    claw_in(&home)
+        .arg("test_goal")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("too long to pass to 'wc' as an argument"));
+}
+
+#[test]
+fn test_exclusive_goal_is_locked_while_running() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; ls $HOME/.config/claw/locks");
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(goal.join("prompt.yaml"), "name: Notes\nexclusive: true\nprompt: Hello\n").unwrap();
+
+    let output = claw_in(&home).arg("notes").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("notes-"));
+
+    // Released after the run
+    let locks = home.path().join(".config/claw/locks");
+    let lock_files = || {
+        fs::read_dir(&locks)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lock"))
+            .collect::<Vec<_>>()
+    };
+    assert!(lock_files().is_empty());
+
+    // Held by a live process (this test), a second run is refused
+    let lock = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    fs::write(locks.join(&lock), format!("{}\n0\n", std::process::id())).unwrap();
+    claw_in(&home)
+        .arg("notes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Goal 'notes' is exclusive and already running here"));
+    assert_eq!(lock_files().len(), 1);
+}
+
+#[test]
+fn test_save_response_captures_output() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; echo answer")
+        .args(["test_goal", "--save-response", "out.md"])
+        .assert()
+        .success()
+        .stdout("answer\n");
+    assert_eq!(fs::read_to_string(home.path().join("out.md")).unwrap(), "answer\n");
+}
+
+#[test]
+fn test_upgrade_goals_keeps_customized_goals() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    claw_in(&home)
+        .arg("upgrade-goals")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bundled goals are up to date."));
+
+    // One goal is edited, another looks new to this release
+    let config = home.path().join(".config/claw");
+    fs::write(config.join("goals/research/prompt.yaml"), "name: Mine\nprompt: Mine\n").unwrap();
+    fs::remove_file(config.join("goals/pr-notes/prompt.yaml")).unwrap();
+    let manifest_path = config.join(".bundled-goals.json");
+    let mut manifest: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    manifest.remove("goals/pr-notes/prompt.yaml").unwrap();
+    fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+    claw_in(&home)
+        .args(["upgrade-goals", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kept goals/research/prompt.yaml"))
+        .stdout(predicate::str::contains("✓ Added goals/pr-notes/prompt.yaml"));
+    assert_eq!(
+        fs::read_to_string(config.join("goals/research/prompt.yaml")).unwrap(),
+        "name: Mine\nprompt: Mine\n"
+    );
+    assert!(config.join("goals/pr-notes/prompt.yaml").exists());
+}
+
+#[test]
+fn test_context_shell_history_is_redacted() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    fs::write(
+        home.path().join(".bash_history"),
+        "ls\nexport API_TOKEN=abc123\nmake test\nclaw dry-run test_goal\n",
+    )
+    .unwrap();
+
+    let output = claw_in(&home)
+        .env("SHELL", "/bin/bash")
+        .env_remove("HISTFILE")
+        .args(["dry-run", "test_goal", "--context-shell-history", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let prompt = String::from_utf8_lossy(&output.stdout);
+    assert!(prompt.contains("## Recent Shell History (last 2 commands"));
+    assert!(prompt.contains("```sh\nexport API_TOKEN=[REDACTED]\nmake test\n```"));
+    assert!(!prompt.contains("abc123"));
+}
+
+#[test]
+fn test_local_config_shell_requires_trust_for_global_goals() {
+    let home = TempDir::new().unwrap();
+    claw_in(&home).arg("list").assert().success();
+    let goal = home.path().join(".config/claw/goals/status");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Status\ncontext_scripts:\n  files: ls\nprompt: \"Files: {{ Context.files }}\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(home.path().join(".claw")).unwrap();
+    fs::write(
+        home.path().join(".claw/claw.yaml"),
+        "script_shell: 'sh -c \"touch ran.txt\"'\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["dry-run", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not trusted"));
+    assert!(!home.path().join("ran.txt").exists());
+
+    claw_in(&home).arg("trust").assert().success();
+    claw_in(&home).args(["dry-run", "status"]).assert().success();
+    assert!(home.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_local_goal_without_setup_requires_trust() {
+    let home = TempDir::new().unwrap();
+    let goal = home.path().join(".claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\ncontext_scripts:\n  files: touch ran.txt\nprompt: \"{{ Context.files }}\"\n",
+    )
+    .unwrap();
+
+    claw_in(&home)
+        .args(["--no-setup", "dry-run", "notes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not trusted"));
+    assert!(!home.path().join("ran.txt").exists());
+
+    claw_in(&home).args(["--no-setup", "trust"]).assert().success();
+    claw_in(&home)
+        .args(["--no-setup", "dry-run", "notes"])
+        .assert()
+        .success();
+    assert!(home.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_proposed_diff_is_checked_in_the_goal_working_dir() {
+    let home = TempDir::new().unwrap();
+    claw_with_llm_script(&home, "cat >/dev/null; cat answer.diff");
+    let package = home.path().join("packages/api");
+    fs::create_dir_all(&package).unwrap();
+    fs::write(package.join("notes.txt"), "old\n").unwrap();
+    fs::write(
+        package.join("answer.diff"),
+        "--- a/notes.txt\n+++ b/notes.txt\n@@ -1 +1 @@\n-old\n+new\n",
+    )
+    .unwrap();
+    let goal = home.path().join(".config/claw/goals/notes");
+    fs::create_dir_all(&goal).unwrap();
+    fs::write(
+        goal.join("prompt.yaml"),
+        "name: Notes\nexpects: unified-diff\nworking_dir: packages/api\nprompt: Fix it\n",
+    )
+    .unwrap();
+
+    // With no terminal to confirm, the diff is checked but left unapplied
+    claw_in(&home)
+        .args(["notes", "--tee", "answer.txt"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Changes not applied: no terminal"));
+    assert_eq!(fs::read_to_string(package.join("notes.txt")).unwrap(), "old\n");
+}